@@ -0,0 +1,220 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # audit.rs
+//!
+//! Local audit trail of the changes pvenom makes, one JSON line per
+//! mutating request in `$XDG_STATE_HOME/pvenom/audit.log`:
+//!
+//! {"time":1735689600,"local_user":"francesco","api_user":"root@pam",
+//!  "controller":"https://pve:8006","command":"pvenom vm 100 stop",
+//!  "method":"POST","path":"/api2/json/nodes/pve1/qemu/100/status/stop",
+//!  "params":{},"ok":true,"upid":"UPID:pve1:...","error":null}
+//!
+//! The file is only ever appended to. Configured in the config file:
+//!
+//! [audit]
+//! path = "/var/log/pvenom/audit.log"
+//! syslog = true
+//!
+//! Passwords never reach the log, neither in parameters nor in the
+//! recorded command line. Dry runs and refused requests are not logged,
+//! nothing was sent.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{config, Cli};
+use crate::syslog::{self, Severity};
+use crate::vlog_warn;
+
+/// `[audit]` section of the config file
+#[derive(Debug, Deserialize, Clone)]
+pub struct AuditConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Log file, default `$XDG_STATE_HOME/pvenom/audit.log`
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Also send every entry to the local syslog
+    #[serde(default)]
+    pub syslog: bool,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self { enabled: true, path: None, syslog: false }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditEntry<'a> {
+    pub time: u64,
+    pub local_user: String,
+    pub api_user: &'a str,
+    pub controller: &'a str,
+    pub command: String,
+    pub method: &'a str,
+    pub path: &'a str,
+    pub params: BTreeMap<&'a str, &'a str>,
+    pub ok: bool,
+    pub upid: Option<String>,
+    pub error: Option<String>,
+}
+
+pub struct AuditLog {
+    path: Option<PathBuf>,
+    syslog: bool,
+}
+
+impl AuditLog {
+    /// None when auditing is disabled
+    pub fn new(config: &AuditConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        let path = config.path.as_ref().map(PathBuf::from)
+            .or_else(|| config::state_dir().map(|dir| dir.join("audit.log")));
+        Some(Self { path, syslog: config.syslog })
+    }
+
+    /// Append a request and its outcome, warning when the file cannot be
+    /// written: the change itself already happened
+    pub fn record(&self, api_user: &str, controller: &str, method: &str, path: &str,
+                  params: &[(String, String)], outcome: Result<Option<String>, String>) {
+        let entry = AuditEntry {
+            time: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+            local_user: std::env::var("USER").or_else(|_| std::env::var("LOGNAME")).unwrap_or_else(|_| "unknown".to_string()),
+            api_user,
+            controller,
+            command: command_line(),
+            method,
+            path,
+            params: params.iter()
+                .map(|(k, v)| (k.as_str(), if is_secret(k) { "***" } else { v.as_str() }))
+                .collect(),
+            ok: outcome.is_ok(),
+            upid: outcome.as_ref().ok().cloned().flatten(),
+            error: outcome.err(),
+        };
+        let Ok(line) = serde_json::to_string(&entry) else {
+            return;
+        };
+
+        if self.syslog {
+            let severity = if entry.ok { Severity::Notice } else { Severity::Warning };
+            syslog::send(severity, &format!("audit {}", line));
+        }
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).ok();
+        }
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let written = options.open(path).and_then(|mut file| writeln!(file, "{}", line));
+        if let Err(e) = written {
+            vlog_warn!("Failed to write audit log {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn is_secret(key: &str) -> bool {
+    let key = key.to_lowercase();
+    key.contains("password") || key.contains("secret") || key.contains("token")
+}
+
+/// The pvenom command line, with the values of secret options masked
+fn command_line() -> String {
+    masked_command_line(std::env::args(), &secret_flags())
+}
+
+/// `--password`, `--pbs-password`, `-p`...: every option of the CLI
+/// declared with `hide_env_values`
+fn secret_flags() -> Vec<String> {
+    fn collect(command: &clap::Command, flags: &mut Vec<String>) {
+        for arg in command.get_arguments().filter(|a| a.is_hide_env_values_set()) {
+            flags.extend(arg.get_long().map(|long| format!("--{}", long)));
+            flags.extend(arg.get_short().map(|short| format!("-{}", short)));
+        }
+        for subcommand in command.get_subcommands() {
+            collect(subcommand, flags);
+        }
+    }
+    let mut flags = Vec::new();
+    collect(&<Cli as clap::CommandFactory>::command(), &mut flags);
+    flags
+}
+
+/// Mask `--flag value`, `--flag=value` and, for short flags, `-fvalue`
+fn masked_command_line(args: impl Iterator<Item = String>, secrets: &[String]) -> String {
+    let mut words = Vec::new();
+    let mut mask_next = false;
+    for (i, arg) in args.enumerate() {
+        if mask_next {
+            words.push("***".to_string());
+            mask_next = false;
+        } else if i == 0 {
+            words.push("pvenom".to_string());
+        } else if secrets.contains(&arg) {
+            words.push(arg);
+            mask_next = true;
+        } else if let Some(flag) = secrets.iter().find(|flag| {
+            arg.strip_prefix(flag.as_str()).is_some_and(|rest| rest.starts_with('=') || (!flag.starts_with("--") && !rest.is_empty()))
+        }) {
+            words.push(format!("{}{}***", flag, if arg[flag.len()..].starts_with('=') { "=" } else { "" }));
+        } else {
+            words.push(arg);
+        }
+    }
+    words.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_every_secret_option() {
+        let secrets = secret_flags();
+        for flag in ["--password", "-p", "--api-token", "--token", "--netbox-token", "--mqtt-password", "--pbs-password"] {
+            assert!(secrets.iter().any(|s| s == flag), "{} is not a secret option", flag);
+        }
+
+        let args = ["pvenom", "--password", "s1", "-p", "s2", "-ps3", "--api-token=s4", "--token", "s5",
+                    "export", "--netbox-token", "s6", "--mqtt-password=s7", "--pbs-password", "s8", "--node", "pve1"];
+        let line = masked_command_line(args.iter().map(|a| a.to_string()), &secrets);
+        assert_eq!(line, "pvenom --password *** -p *** -p*** --api-token=*** --token *** export --netbox-token *** \
+                          --mqtt-password=*** --pbs-password *** --node pve1");
+        assert!((1..=8).all(|n| !line.contains(&format!("s{}", n))));
+    }
+}
@@ -19,78 +19,197 @@
 //! # client.rs
 //!
 //! The ProxMox client code.
+//!
+//! Tickets of a password login expire after two hours. Long runs like
+//! `serve`, `publish` and the shell renew them ahead of time, and a request
+//! answered 401 logs in again and is retried once, e.g. after pveproxy
+//! restarted.
 
 use anyhow::{Context, Result};
-use reqwest::{Client, ClientBuilder};
-use serde_json::Value;
+use reqwest::{Client, ClientBuilder, RequestBuilder, Response, StatusCode};
+use serde_json::{Map, Value};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::models::{Appliance, CephPool, NodeDisk, GuestFilesystem, HaGroup, HaResource, NodeBridge, NodeCpuInfo, LvmThinPool, MdevType, PciDevice, PciMapping, PruneEntry, BackupJob, ReplicationJob, ReplicationState, AptPackage, AuthTicket, ClusterResource, ClusterStatusEntry, GuestInterface, GuestRrdPoint, GuestSnapshot, GuestStatus, StorageContent, ProxmoxResponse, Node, NodeRrdPoint, PveVersion, Task, TaskStatus, VM, LXC};
+use crate::audit::AuditLog;
+use crate::netbox::encode;
+use crate::{httplog, vlog_debug, vlog_info, vlog_error};
+
+/// Age at which a ticket is renewed, PVE accepts them for two hours
+const TICKET_RENEWAL: Duration = Duration::from_secs(90 * 60);
 
-use crate::models::{AuthTicket, ProxmoxResponse, Node, VM, LXC};
-use crate::{vlog_debug, vlog_info, vlog_error};
+/// Ticket of a password login
+struct Session {
+    ticket: String,           // PVEAuthCookie passed in all requests
+    csrf_token: String,       // CSRFPreventionToken passed in POST/PUT/DELETE
+    issued: Instant,
+}
+
+impl From<AuthTicket> for Session {
+    fn from(auth: AuthTicket) -> Self {
+        Self { ticket: auth.ticket, csrf_token: auth.csrf_token, issued: Instant::now() }
+    }
+}
+
+/// Spacing of the requests of a client, `--max-rps`
+struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Wait for the next free slot, slots are handed out in call order
+    async fn wait(&self) {
+        let slot = {
+            let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(tokio::time::Instant::from_std(slot)).await;
+    }
+}
 
 pub struct ProxmoxClient {
     base_url: String,
+    username: String,
     client: Client,
-    ticket: String,           // PVEAuthCookie passed in all requests
-    csrf_token: String,       // CSRFPreventionToken passed in POST/PUT/DELETE
+    session: RwLock<Session>,
+    password: Option<String>, // kept to log in again when the ticket expires
+    api_token: Option<String>, // `user@realm!id=secret`, replaces the ticket
+    limiter: Option<RateLimiter>, // keeps small nodes' pveproxy responsive
+    dry_run: bool,            // print mutating requests instead of sending them
+    read_only: bool,          // refuse mutating requests altogether
+    audit: Option<AuditLog>,  // trail of the mutating requests sent
 }
 
 impl ProxmoxClient {
     pub async fn new(base_url: &str, username: &str, password: &str, secure: bool) -> Result<Self> {
         vlog_debug!("Creating Proxmox client for {}", base_url);
 
-        // Build HTTP client
-        // When secure=true, verify certs; when secure=false, skip verification
-        let client = ClientBuilder::new()
-            .danger_accept_invalid_certs(!secure)
-            .timeout(std::time::Duration::from_secs(30))
-            .build()
-            .context("Failed to build HTTP client")?;
-
-        // Authenticate and get ticket
-        vlog_debug!("Requesting authentication ticket for user: {}", username);
-        let ticket_url = format!("{}/api2/json/access/ticket", base_url);
-
-        let response = client
-            .post(&ticket_url)
-            .form(&[
-                ("username", username),
-                ("password", password),
-            ])
-            .send()
-            .await
-            .context("Failed to send authentication request")?;
-
-        if !response.status().is_success() {
-            vlog_error!("Authentication failed with status: {}", response.status());
-            anyhow::bail!("Authentication failed: HTTP {}", response.status());
-        }
-
-        let auth_response: ProxmoxResponse<AuthTicket> = response
-            .json()
-            .await
-            .context("Failed to parse authentication response")?;
-
-        vlog_debug!("Received authentication ticket for user: {}", auth_response.data.username);
+        let client = http_client(secure)?;
+        let auth = login(&client, base_url, username, password).await?;
 
         Ok(Self {
             base_url: base_url.to_string(),
+            username: auth.username.clone(),
             client,
-            ticket: auth_response.data.ticket,
-            csrf_token: auth_response.data.csrf_token,
+            session: RwLock::new(Session::from(auth)),
+            password: Some(password.to_string()),
+            api_token: None,
+            limiter: None,
+            dry_run: false,
+            read_only: false,
+            audit: None,
         })
     }
 
+    /// Client authenticating every request with an API token,
+    /// `user@realm!tokenid=secret`, checked against the version endpoint
+    pub async fn with_token(base_url: &str, token: &str, secure: bool) -> Result<Self> {
+        vlog_debug!("Creating Proxmox client for {} with an API token", base_url);
+        let username = token.split('=').next().unwrap_or_default().to_string();
+        let client = Self {
+            base_url: base_url.to_string(),
+            username,
+            client: http_client(secure)?,
+            session: RwLock::new(Session { ticket: String::new(), csrf_token: String::new(), issued: Instant::now() }),
+            password: None,
+            api_token: Some(token.to_string()),
+            limiter: None,
+            dry_run: false,
+            read_only: false,
+            audit: None,
+        };
+        client.get("/api2/json/version").await.context("API token rejected")?;
+        Ok(client)
+    }
+
+    /// Ticket cookie and CSRF token, or the API token header
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.api_token {
+            Some(token) => request.header("Authorization", format!("PVEAPIToken={}", token)),
+            None => {
+                let session = self.session.read().unwrap_or_else(|e| e.into_inner());
+                request
+                    .header("Cookie", format!("PVEAuthCookie={}", session.ticket))
+                    .header("CSRFPreventionToken", &session.csrf_token)
+            }
+        }
+    }
+
+    /// Send an authorized request built by `request`, renewing an old ticket
+    /// first and logging in again once when the cluster answers 401
+    async fn send(&self, request: impl Fn() -> RequestBuilder) -> Result<Response> {
+        if let Some(limiter) = &self.limiter {
+            limiter.wait().await;
+        }
+        let Some(password) = &self.password else {
+            return Ok(httplog::execute(&self.client, self.authorize(request()).build()?).await?);
+        };
+        let issued = self.session.read().unwrap_or_else(|e| e.into_inner()).issued;
+        if issued.elapsed() >= TICKET_RENEWAL {
+            vlog_debug!("Renewing the authentication ticket");
+            self.renew(password).await?;
+        }
+
+        let response = httplog::execute(&self.client, self.authorize(request()).build()?).await?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+        vlog_info!("Session expired, authenticating again");
+        self.renew(password).await?;
+        Ok(httplog::execute(&self.client, self.authorize(request()).build()?).await?)
+    }
+
+    async fn renew(&self, password: &str) -> Result<()> {
+        let auth = login(&self.client, &self.base_url, &self.username, password).await?;
+        *self.session.write().unwrap_or_else(|e| e.into_inner()) = Session::from(auth);
+        Ok(())
+    }
+
+    /// Send at most `max_rps` requests per second, 0 for no limit
+    pub fn set_max_rps(&mut self, max_rps: f64) {
+        self.limiter = (max_rps > 0.0).then(|| RateLimiter {
+            interval: Duration::from_secs_f64(1.0 / max_rps),
+            next: Mutex::new(Instant::now()),
+        });
+    }
+
+    /// Print POST/PUT/DELETE requests on stderr instead of sending them.
+    /// Reads still go to the cluster so commands can plan their actions.
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Refuse every POST/PUT/DELETE, whatever the command asks for
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// The authenticated user, e.g. `root@pam`
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    pub fn set_audit(&mut self, audit: Option<AuditLog>) {
+        self.audit = audit;
+    }
+
     async fn get(&self, path: &str) -> Result<Value> {
         let url = format!("{}{}", self.base_url, path);
         vlog_debug!("GET {}", url);
 
-        // Pass the ticket as cookie
-        let cookie_header = format!("PVEAuthCookie={}", self.ticket);
-
-        let response = self.client
-            .get(&url)
-            .header("Cookie", cookie_header)
-            .send()
+        let response = self.send(|| self.client.get(&url))
             .await
             .context("Failed to send GET request")?;
 
@@ -104,18 +223,69 @@ impl ProxmoxClient {
         Ok(json)
     }
 
+    /// Mutating requests (POST/PUT/DELETE) carry the CSRF token too
+    async fn send_mutating(&self, method: reqwest::Method, path: &str, params: &[(String, String)]) -> Result<Value> {
+        let url = format!("{}{}", self.base_url, path);
+        vlog_debug!("{} {}", method, url);
+
+        if self.read_only {
+            anyhow::bail!("Read-only mode, refusing {} {}", method, path);
+        }
+        if self.dry_run {
+            let params: Vec<String> = params.iter()
+                .map(|(k, v)| format!("{}={}", k, if httplog::is_secret(k) { "<redacted>" } else { v }))
+                .collect();
+            eprintln!("dry-run: {} {} {}", method, path, params.join(" "));
+            return Ok(serde_json::json!({ "data": null }));
+        }
+
+        let result = self.send_unchecked(&method, &url, path, params).await;
+        if let Some(audit) = &self.audit {
+            let outcome = match &result {
+                Ok(json) => Ok(json["data"].as_str().filter(|d| d.starts_with("UPID:")).map(str::to_string)),
+                Err(e) => Err(e.to_string()),
+            };
+            audit.record(&self.username, &self.base_url, method.as_str(), path, params, outcome);
+        }
+        result
+    }
+
+    async fn send_unchecked(&self, method: &reqwest::Method, url: &str, path: &str, params: &[(String, String)]) -> Result<Value> {
+        let response = self.send(|| self.client.request(method.clone(), url).form(params))
+            .await
+            .with_context(|| format!("Failed to send {} request", method))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            // PVE explains parameter errors in the body, keep it for the user
+            let body = response.text().await.unwrap_or_default();
+            vlog_error!("{} {} failed with status: {}", method, path, status);
+            vlog_debug!("Response body: {}", body);
+            anyhow::bail!("Request failed: HTTP {} {}", status, body.trim());
+        }
+
+        let json: Value = response.json().await.context("Failed to parse response")?;
+        Ok(json)
+    }
+
+    async fn post(&self, path: &str, params: &[(String, String)]) -> Result<Value> {
+        self.send_mutating(reqwest::Method::POST, path, params).await
+    }
+
+    async fn put(&self, path: &str, params: &[(String, String)]) -> Result<Value> {
+        self.send_mutating(reqwest::Method::PUT, path, params).await
+    }
+
+    async fn delete(&self, path: &str) -> Result<Value> {
+        self.send_mutating(reqwest::Method::DELETE, path, &[]).await
+    }
+
     /// Get request that doesn't log errors (for optional features like guest agent)
     async fn get_optional(&self, path: &str) -> Result<Value> {
         let url = format!("{}{}", self.base_url, path);
         vlog_debug!("GET {} (optional)", url);
 
-        let cookie_header = format!("PVEAuthCookie={}", self.ticket);
-
-        let response = self.client
-            .get(&url)
-            .header("Cookie", cookie_header)
-            .send()
-            .await?;
+        let response = self.send(|| self.client.get(&url)).await?;
 
         let status = response.status();
         if !status.is_success() {
@@ -133,6 +303,49 @@ impl ProxmoxClient {
         self.get(path).await
     }
 
+    /// Get the API schema behind the cluster's API viewer, the tree of
+    /// every path with its methods. Needs the pve-docs package.
+    pub async fn get_api_schema(&self) -> Result<Value> {
+        let url = format!("{}/pve-docs/api-viewer/apidoc.js", self.base_url);
+        vlog_debug!("GET {}", url);
+
+        let response = self.send(|| self.client.get(&url))
+            .await
+            .context("Failed to send GET request")?;
+        if !response.status().is_success() {
+            anyhow::bail!("Request failed: HTTP {}", response.status());
+        }
+        let script = response.text().await?;
+
+        // `const apiSchema = [...];` followed by the viewer code
+        let start = script.find("apiSchema")
+            .and_then(|i| script[i..].find('[').map(|j| i + j))
+            .context("No apiSchema in apidoc.js")?;
+        let schema = serde_json::Deserializer::from_str(&script[start..])
+            .into_iter::<Value>()
+            .next()
+            .context("No apiSchema in apidoc.js")?
+            .context("Failed to parse API schema")?;
+        Ok(schema)
+    }
+
+    /// Privileges of the authenticated user, by ACL path
+    pub async fn get_permissions(&self) -> Result<Map<String, Value>> {
+        vlog_debug!("Fetching permissions...");
+        let response = self.get("/api2/json/access/permissions").await?;
+
+        let permissions: Map<String, Value> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse permissions response")?;
+        Ok(permissions)
+    }
+
+    /// Clock of a node, seconds since the epoch
+    pub async fn get_node_time(&self, node: &str) -> Result<i64> {
+        vlog_debug!("Fetching time of node '{}'...", node);
+        let response = self.get(&format!("/api2/json/nodes/{}/time", node)).await?;
+        response["data"]["time"].as_i64().context("No time in node time response")
+    }
+
     pub async fn get_nodes(&self) -> Result<Vec<Node>> {
         vlog_info!("Fetching cluster nodes...");
         let response = self.get("/api2/json/nodes").await?;
@@ -144,6 +357,101 @@ impl ProxmoxClient {
         Ok(nodes)
     }
 
+    /// CPU model and flags of a node
+    pub async fn get_node_cpuinfo(&self, node: &str) -> Result<NodeCpuInfo> {
+        vlog_debug!("Fetching CPU info of node '{}'...", node);
+        let response = self.get(&format!("/api2/json/nodes/{}/status", node)).await?;
+
+        let cpuinfo: NodeCpuInfo = serde_json::from_value(response["data"]["cpuinfo"].clone())
+            .context("Failed to parse node CPU info")?;
+        Ok(cpuinfo)
+    }
+
+    /// PCI devices of a node
+    pub async fn get_node_pci(&self, node: &str) -> Result<Vec<PciDevice>> {
+        vlog_debug!("Fetching PCI devices of node '{}'...", node);
+        let response = self.get(&format!("/api2/json/nodes/{}/hardware/pci", node)).await?;
+
+        let devices: Vec<PciDevice> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse PCI devices response")?;
+        Ok(devices)
+    }
+
+    /// Mediated device types a PCI device offers
+    pub async fn get_pci_mdev_types(&self, node: &str, id: &str) -> Result<Vec<MdevType>> {
+        let response = self.get(&format!("/api2/json/nodes/{}/hardware/pci/{}/mdev", node, id)).await?;
+
+        let types: Vec<MdevType> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse mdev types response")?;
+        Ok(types)
+    }
+
+    /// Cluster-wide PCI resource mappings, PVE 8 and later
+    pub async fn get_pci_mappings(&self) -> Result<Vec<PciMapping>> {
+        let response = self.get_optional("/api2/json/cluster/mapping/pci").await?;
+
+        let mappings: Vec<PciMapping> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse PCI mappings response")?;
+        Ok(mappings)
+    }
+
+    /// Linux and OVS bridges of a node
+    pub async fn get_node_bridges(&self, node: &str) -> Result<Vec<NodeBridge>> {
+        vlog_debug!("Fetching bridges of node '{}'...", node);
+        let response = self.get(&format!("/api2/json/nodes/{}/network?type=any_bridge", node)).await?;
+
+        let bridges: Vec<NodeBridge> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse node network response")?;
+        Ok(bridges)
+    }
+
+    /// Physical disks of a node
+    pub async fn get_node_disks(&self, node: &str) -> Result<Vec<NodeDisk>> {
+        vlog_debug!("Fetching disks of node '{}'...", node);
+        let response = self.get(&format!("/api2/json/nodes/{}/disks/list", node)).await?;
+
+        let disks: Vec<NodeDisk> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse node disks response")?;
+        Ok(disks)
+    }
+
+    /// LVM thin pools of a node with data and metadata usage
+    pub async fn get_node_lvmthin(&self, node: &str) -> Result<Vec<LvmThinPool>> {
+        vlog_debug!("Fetching LVM thin pools of node '{}'...", node);
+        let response = self.get(&format!("/api2/json/nodes/{}/disks/lvmthin", node)).await?;
+
+        let pools: Vec<LvmThinPool> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse LVM thin pools response")?;
+        Ok(pools)
+    }
+
+    /// SMART health and attributes of a disk of a node
+    pub async fn get_disk_smart(&self, node: &str, disk: &str) -> Result<Value> {
+        vlog_debug!("Fetching SMART data of {} on node '{}'...", disk, node);
+        let response = self.get(&format!("/api2/json/nodes/{}/disks/smart?disk={}", node, disk)).await?;
+        Ok(response["data"].clone())
+    }
+
+    /// CPU models QEMU offers on a node, custom ones included
+    pub async fn get_cpu_models(&self, node: &str) -> Result<Vec<String>> {
+        vlog_debug!("Fetching CPU models of node '{}'...", node);
+        let response = self.get(&format!("/api2/json/nodes/{}/capabilities/qemu/cpu", node)).await?;
+
+        Ok(response["data"].as_array()
+            .map(|models| models.iter().filter_map(|m| m["name"].as_str().map(str::to_string)).collect())
+            .unwrap_or_default())
+    }
+
+    /// Versioned machine types QEMU offers on a node, e.g. `pc-q35-8.1`
+    pub async fn get_qemu_machines(&self, node: &str) -> Result<Vec<String>> {
+        vlog_debug!("Fetching machine types of node '{}'...", node);
+        let response = self.get(&format!("/api2/json/nodes/{}/capabilities/qemu/machines", node)).await?;
+
+        Ok(response["data"].as_array()
+            .map(|machines| machines.iter().filter_map(|m| m["id"].as_str().map(str::to_string)).collect())
+            .unwrap_or_default())
+    }
+
     pub async fn get_node_status(&self, node: &str) -> Result<Node> {
         vlog_info!("Fetching status for node '{}'...", node);
         let path = format!("/api2/json/nodes/{}/status", node);
@@ -168,6 +476,69 @@ impl ProxmoxClient {
         Ok(node_status)
     }
 
+    /// Get the running kernel release and boot mode of a node, as
+    /// (release, boot mode, secure boot). Boot info needs PVE 8.
+    pub async fn get_node_kernel(&self, node: &str) -> Result<(Option<String>, Option<String>, Option<bool>)> {
+        vlog_debug!("Fetching kernel of node '{}'...", node);
+        let path = format!("/api2/json/nodes/{}/status", node);
+        let response = self.get(&path).await?;
+        let data = &response["data"];
+
+        // `kversion` is "Linux 6.8.12-4-pve #1 SMP ...", `current-kernel` is newer
+        let release = data["current-kernel"]["release"].as_str()
+            .or_else(|| data["kversion"].as_str().and_then(|k| k.split_whitespace().nth(1)))
+            .map(str::to_string);
+        let boot_mode = data["boot-info"]["mode"].as_str().map(str::to_string);
+        let secure_boot = data["boot-info"]["secureboot"].as_u64().map(|s| s == 1);
+        Ok((release, boot_mode, secure_boot))
+    }
+
+    /// Get the versions of the Proxmox related packages of a node
+    pub async fn get_apt_versions(&self, node: &str) -> Result<Vec<AptPackage>> {
+        vlog_debug!("Fetching package versions of node '{}'...", node);
+        let path = format!("/api2/json/nodes/{}/apt/versions", node);
+        let response = self.get(&path).await?;
+
+        let packages: Vec<AptPackage> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse package versions response")?;
+        Ok(packages)
+    }
+
+    /// Get the appliance index of `pveam` as known by a node
+    pub async fn get_appliance_index(&self, node: &str) -> Result<Vec<Appliance>> {
+        vlog_debug!("Fetching appliance index of node '{}'...", node);
+        let path = format!("/api2/json/nodes/{}/aplinfo", node);
+        let response = self.get(&path).await?;
+
+        let appliances: Vec<Appliance> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse appliance index response")?;
+        Ok(appliances)
+    }
+
+    /// Get the node RRD history for a timeframe (hour, day, week, month, year)
+    pub async fn get_node_rrddata(&self, node: &str, timeframe: &str) -> Result<Vec<NodeRrdPoint>> {
+        vlog_debug!("Fetching {} RRD data for node '{}'...", timeframe, node);
+        let path = format!("/api2/json/nodes/{}/rrddata?timeframe={}&cf=AVERAGE", node, timeframe);
+        let response = self.get(&path).await?;
+
+        let points: Vec<NodeRrdPoint> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse RRD response")?;
+
+        vlog_debug!("Found {} RRD sample(s) for node '{}'", points.len(), node);
+        Ok(points)
+    }
+
+    /// Get the RRD history of a guest (`qemu` or `lxc`) for a timeframe
+    pub async fn get_guest_rrddata(&self, node: &str, guest_type: &str, vmid: u32, timeframe: &str) -> Result<Vec<GuestRrdPoint>> {
+        vlog_debug!("Fetching {} RRD data for guest {}...", timeframe, vmid);
+        let path = format!("/api2/json/nodes/{}/{}/{}/rrddata?timeframe={}&cf=AVERAGE", node, guest_type, vmid, timeframe);
+        let response = self.get(&path).await?;
+
+        let points: Vec<GuestRrdPoint> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse guest RRD response")?;
+        Ok(points)
+    }
+
     pub async fn get_node_ip(&self, node: &str) -> Result<Option<String>> {
         vlog_debug!("Fetching IP for node '{}'...", node);
         let path = format!("/api2/json/nodes/{}/network", node);
@@ -248,4 +619,634 @@ impl ProxmoxClient {
         vlog_debug!("Found {} LXC container(s) on node '{}'", lxc.len(), node);
         Ok(lxc)
     }
-}
\ No newline at end of file
+
+    /// Get cluster-wide resources, optionally filtered by type (vm, node, storage)
+    pub async fn get_cluster_resources(&self, kind: Option<&str>) -> Result<Vec<ClusterResource>> {
+        vlog_debug!("Fetching cluster resources (type: {})...", kind.unwrap_or("all"));
+        let path = match kind {
+            Some(k) => format!("/api2/json/cluster/resources?type={}", k),
+            None => "/api2/json/cluster/resources".to_string(),
+        };
+        let response = self.get(&path).await?;
+
+        let resources: Vec<ClusterResource> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse cluster resources response")?;
+
+        vlog_debug!("Found {} cluster resource(s)", resources.len());
+        Ok(resources)
+    }
+
+    /// Get the task history of a node started after `since` (unix epoch)
+    pub async fn get_node_tasks(&self, node: &str, since: u64) -> Result<Vec<Task>> {
+        vlog_debug!("Fetching tasks for node '{}' since {}...", node, since);
+        let path = format!("/api2/json/nodes/{}/tasks?since={}&limit=10000", node, since);
+        let response = self.get(&path).await?;
+
+        let tasks: Vec<Task> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse tasks response")?;
+
+        vlog_debug!("Found {} task(s) on node '{}'", tasks.len(), node);
+        Ok(tasks)
+    }
+
+    /// Get the configuration of a guest, `guest_type` is qemu or lxc
+    pub async fn get_guest_config(&self, node: &str, guest_type: &str, vmid: u32) -> Result<Map<String, Value>> {
+        vlog_debug!("Fetching config for {} {} on node '{}'...", guest_type, vmid, node);
+        let path = format!("/api2/json/nodes/{}/{}/{}/config", node, guest_type, vmid);
+        let response = self.get(&path).await?;
+
+        let config: Map<String, Value> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse guest config response")?;
+        Ok(config)
+    }
+
+    /// Get the cluster-wide configuration of a storage
+    pub async fn get_storage_config(&self, storage: &str) -> Result<Map<String, Value>> {
+        vlog_debug!("Fetching config of storage '{}'...", storage);
+        let response = self.get(&format!("/api2/json/storage/{}", storage)).await?;
+
+        let config: Map<String, Value> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse storage config response")?;
+        Ok(config)
+    }
+
+    /// Get the next free VMID of the cluster
+    pub async fn get_next_vmid(&self) -> Result<u32> {
+        let response = self.get("/api2/json/cluster/nextid").await?;
+
+        // The API returns the id as a string
+        let vmid = match &response["data"] {
+            Value::String(s) => s.parse().context("Failed to parse next VMID")?,
+            other => other.as_u64().context("Failed to parse next VMID")? as u32,
+        };
+        vlog_debug!("Next free VMID is {}", vmid);
+        Ok(vmid)
+    }
+
+    /// Create a guest, returns the UPID of the creation task
+    pub async fn create_guest(&self, node: &str, guest_type: &str, params: &[(String, String)]) -> Result<String> {
+        vlog_info!("Creating {} on node '{}'...", guest_type, node);
+        let path = format!("/api2/json/nodes/{}/{}", node, guest_type);
+        let response = self.post(&path, params).await?;
+
+        Ok(response["data"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Change the power state of a guest: start, stop, shutdown or reboot.
+    /// Returns the UPID of the task.
+    pub async fn set_guest_status(&self, node: &str, guest_type: &str, vmid: u32, action: &str) -> Result<String> {
+        vlog_debug!("Requesting {} of {} {} on node '{}'...", action, guest_type, vmid, node);
+        let path = format!("/api2/json/nodes/{}/{}/{}/status/{}", node, guest_type, vmid, action);
+        let response = self.post(&path, &[]).await?;
+
+        Ok(response["data"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Change options of a guest config
+    pub async fn set_guest_config(&self, node: &str, guest_type: &str, vmid: u32, params: &[(String, String)]) -> Result<()> {
+        vlog_debug!("Updating config of {} {} on node '{}'...", guest_type, vmid, node);
+        let path = format!("/api2/json/nodes/{}/{}/{}/config", node, guest_type, vmid);
+        self.put(&path, params).await?;
+        Ok(())
+    }
+
+    /// Destroy a stopped guest with its disks and every reference to it
+    /// (backup jobs, replication, HA), returns the UPID of the task
+    pub async fn destroy_guest(&self, node: &str, guest_type: &str, vmid: u32) -> Result<String> {
+        vlog_debug!("Destroying {} {} on node '{}'...", guest_type, vmid, node);
+        let path = format!("/api2/json/nodes/{}/{}/{}?purge=1&destroy-unreferenced-disks=1", node, guest_type, vmid);
+        let response = self.delete(&path).await?;
+
+        Ok(response["data"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Start the migration of a guest to another node and return the UPID.
+    /// Running VMs migrate live, running containers in restart mode.
+    pub async fn migrate_guest(&self, node: &str, guest_type: &str, vmid: u32, target: &str, running: bool, with_local_disks: bool) -> Result<String> {
+        vlog_debug!("Migrating {} {} from '{}' to '{}'...", guest_type, vmid, node, target);
+        let path = format!("/api2/json/nodes/{}/{}/{}/migrate", node, guest_type, vmid);
+        let mut params = vec![("target".to_string(), target.to_string())];
+        match (guest_type, running) {
+            ("qemu", true) => params.push(("online".to_string(), "1".to_string())),
+            ("lxc", true) => params.push(("restart".to_string(), "1".to_string())),
+            _ => {}
+        }
+        if with_local_disks && guest_type == "qemu" {
+            params.push(("with-local-disks".to_string(), "1".to_string()));
+        }
+        let response = self.post(&path, &params).await?;
+
+        Ok(response["data"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Move a guest disk to another storage and return the UPID: VM disks
+    /// go through `move_disk`, container volumes through `move_volume`
+    pub async fn move_disk(&self, node: &str, guest_type: &str, vmid: u32, disk: &str, storage: &str, delete_source: bool) -> Result<String> {
+        vlog_debug!("Moving {} of {} {} to storage '{}'...", disk, guest_type, vmid, storage);
+        let (endpoint, key) = if guest_type == "lxc" { ("move_volume", "volume") } else { ("move_disk", "disk") };
+        let path = format!("/api2/json/nodes/{}/{}/{}/{}", node, guest_type, vmid, endpoint);
+        let params = vec![
+            (key.to_string(), disk.to_string()),
+            ("storage".to_string(), storage.to_string()),
+            ("delete".to_string(), if delete_source { "1" } else { "0" }.to_string()),
+        ];
+        let response = self.post(&path, &params).await?;
+
+        Ok(response["data"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Ask the HA manager to migrate a resource (`vm:100`, `ct:101`), the
+    /// move happens asynchronously without a task to follow
+    pub async fn ha_migrate(&self, sid: &str, target: &str) -> Result<()> {
+        vlog_debug!("Requesting HA migration of {} to '{}'...", sid, target);
+        let path = format!("/api2/json/cluster/ha/resources/{}/migrate", sid);
+        self.post(&path, &[("node".to_string(), target.to_string())]).await?;
+        Ok(())
+    }
+
+    /// Get the Ceph pools with usage, as seen from a node
+    pub async fn get_ceph_pools(&self, node: &str) -> Result<Vec<CephPool>> {
+        vlog_debug!("Fetching Ceph pools from node '{}'...", node);
+        let response = self.get(&format!("/api2/json/nodes/{}/ceph/pool", node)).await?;
+
+        let pools: Vec<CephPool> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse Ceph pools response")?;
+        Ok(pools)
+    }
+
+    /// Get the Ceph OSD tree with latencies and usage, as seen from a node
+    pub async fn get_ceph_osd_tree(&self, node: &str) -> Result<Value> {
+        vlog_debug!("Fetching Ceph OSD tree from node '{}'...", node);
+        let response = self.get(&format!("/api2/json/nodes/{}/ceph/osd", node)).await?;
+        Ok(response["data"].clone())
+    }
+
+    /// Storage replication jobs of the cluster
+    pub async fn get_replication_jobs(&self) -> Result<Vec<ReplicationJob>> {
+        vlog_debug!("Fetching replication jobs...");
+        let response = self.get("/api2/json/cluster/replication").await?;
+
+        let jobs: Vec<ReplicationJob> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse replication jobs response")?;
+        Ok(jobs)
+    }
+
+    pub async fn get_backup_jobs(&self) -> Result<Vec<BackupJob>> {
+        vlog_debug!("Fetching backup jobs...");
+        let response = self.get("/api2/json/cluster/backup").await?;
+
+        let jobs: Vec<BackupJob> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse backup jobs response")?;
+        Ok(jobs)
+    }
+
+    /// Backup jobs with every option as stored
+    pub async fn get_backup_job_configs(&self) -> Result<Vec<Map<String, Value>>> {
+        vlog_debug!("Fetching backup job configs...");
+        let response = self.get("/api2/json/cluster/backup").await?;
+
+        let jobs: Vec<Map<String, Value>> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse backup jobs response")?;
+        Ok(jobs)
+    }
+
+    /// vzdump defaults of a node, from its `/etc/vzdump.conf`
+    pub async fn get_vzdump_defaults(&self, node: &str) -> Result<Map<String, Value>> {
+        vlog_debug!("Fetching vzdump defaults of node '{}'...", node);
+        let response = self.get(&format!("/api2/json/nodes/{}/vzdump/defaults", node)).await?;
+
+        let defaults: Map<String, Value> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse vzdump defaults response")?;
+        Ok(defaults)
+    }
+
+    /// Options of a backup job as stored, vzdump parameters included
+    pub async fn get_backup_job(&self, id: &str) -> Result<Map<String, Value>> {
+        vlog_debug!("Fetching backup job '{}'...", id);
+        let response = self.get(&format!("/api2/json/cluster/backup/{}", encode(id))).await?;
+
+        let job: Map<String, Value> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse backup job response")?;
+        Ok(job)
+    }
+
+    /// Start a backup on a node with vzdump parameters and return the UPID
+    pub async fn vzdump(&self, node: &str, params: &[(String, String)]) -> Result<String> {
+        vlog_debug!("Starting vzdump on node '{}'...", node);
+        let path = format!("/api2/json/nodes/{}/vzdump", node);
+        let response = self.post(&path, params).await?;
+
+        Ok(response["data"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Next `iterations` runs of a calendar event from `start` on, as PVE
+    /// computes them, epoch seconds
+    pub async fn analyze_schedule(&self, schedule: &str, start: u64, iterations: u32) -> Result<Vec<u64>> {
+        let path = format!("/api2/json/cluster/jobs/schedule-analyze?schedule={}&starttime={}&iterations={}",
+                           encode(schedule), start, iterations);
+        let response = self.get(&path).await?;
+        let runs = response["data"].as_array()
+            .context("Failed to parse schedule-analyze response")?
+            .iter()
+            .filter_map(|run| run["timestamp"].as_u64())
+            .collect();
+        Ok(runs)
+    }
+
+    /// Last run of the replication jobs whose source is `node`
+    pub async fn get_replication_state(&self, node: &str) -> Result<Vec<ReplicationState>> {
+        vlog_debug!("Fetching replication state of node '{}'...", node);
+        let path = format!("/api2/json/nodes/{}/replication", node);
+        let response = self.get(&path).await?;
+
+        let states: Vec<ReplicationState> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse replication state response")?;
+        Ok(states)
+    }
+
+    /// Run a replication job of `node` as soon as possible, outside its
+    /// schedule; the run has no task, its outcome is in the job state
+    pub async fn schedule_replication(&self, node: &str, id: &str) -> Result<()> {
+        vlog_debug!("Scheduling replication job '{}' on node '{}'...", id, node);
+        let path = format!("/api2/json/nodes/{}/replication/{}/schedule_now", node, id);
+        self.post(&path, &[]).await?;
+        Ok(())
+    }
+
+    /// Get the HA groups, none on clusters using HA rules instead
+    pub async fn get_ha_groups(&self) -> Result<Vec<HaGroup>> {
+        vlog_debug!("Fetching HA groups...");
+        let response = self.get("/api2/json/cluster/ha/groups").await?;
+
+        let groups: Vec<HaGroup> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse HA groups response")?;
+        Ok(groups)
+    }
+
+    /// Get the resources managed by HA
+    pub async fn get_ha_resources(&self) -> Result<Vec<HaResource>> {
+        vlog_debug!("Fetching HA resources...");
+        let response = self.get("/api2/json/cluster/ha/resources").await?;
+
+        let resources: Vec<HaResource> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse HA resources response")?;
+        Ok(resources)
+    }
+
+    /// Get the state of a task, the node is the one in the UPID
+    pub async fn get_task_status(&self, upid: &str) -> Result<TaskStatus> {
+        let node = upid.split(':').nth(1).context("Malformed UPID")?;
+        let path = format!("/api2/json/nodes/{}/tasks/{}/status", node, upid);
+        let response = self.get(&path).await?;
+
+        let status: TaskStatus = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse task status response")?;
+        Ok(status)
+    }
+
+    /// Get the most recent tasks of a node, only the running ones with
+    /// `running`, only those of `user` when given
+    pub async fn get_node_task_list(&self, node: &str, running: bool, user: Option<&str>, limit: usize) -> Result<Vec<Task>> {
+        vlog_debug!("Fetching {} tasks of node '{}'...", if running { "running" } else { "recent" }, node);
+        let mut path = format!("/api2/json/nodes/{}/tasks?source={}&limit={}",
+                               node, if running { "active" } else { "all" }, limit);
+        if let Some(user) = user {
+            path.push_str(&format!("&userfilter={}", user));
+        }
+        let response = self.get(&path).await?;
+
+        let tasks: Vec<Task> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse tasks response")?;
+        Ok(tasks)
+    }
+
+    /// Stop a running task
+    pub async fn stop_task(&self, upid: &str) -> Result<()> {
+        let node = upid.split(':').nth(1).context("Malformed UPID")?;
+        vlog_debug!("Stopping task {}...", upid);
+        let path = format!("/api2/json/nodes/{}/tasks/{}", node, upid);
+        self.delete(&path).await?;
+        Ok(())
+    }
+
+    /// Get the task log lines from line `start` on
+    pub async fn get_task_log(&self, upid: &str, start: usize) -> Result<Vec<String>> {
+        let node = upid.split(':').nth(1).context("Malformed UPID")?;
+        let path = format!("/api2/json/nodes/{}/tasks/{}/log?start={}&limit=500", node, upid, start);
+        let response = self.get(&path).await?;
+
+        Ok(response["data"].as_array()
+            .map(|lines| lines.iter().filter_map(|l| l["t"].as_str().map(str::to_string)).collect())
+            .unwrap_or_default())
+    }
+
+    /// Get the corosync node list of the cluster config, empty on a
+    /// standalone node. Entries keep the raw corosync keys (`link0`,
+    /// `ring0_addr`, `quorum_votes`...).
+    pub async fn get_corosync_nodes(&self) -> Result<Vec<Map<String, Value>>> {
+        vlog_debug!("Fetching corosync nodes...");
+        let response = self.get("/api2/json/cluster/config/nodes").await?;
+
+        let nodes: Vec<Map<String, Value>> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse corosync nodes response")?;
+        Ok(nodes)
+    }
+
+    /// Get the state of the QDevice as reported by corosync-qdevice-tool,
+    /// None when the cluster has no QDevice
+    pub async fn get_qdevice_status(&self) -> Result<Option<Map<String, Value>>> {
+        vlog_debug!("Fetching QDevice status...");
+        let response = match self.get_optional("/api2/json/cluster/config/qdevice").await {
+            Ok(response) => response,
+            Err(_) => return Ok(None),
+        };
+        Ok(response["data"].as_object().cloned())
+    }
+
+    /// Get the cluster status: one `cluster` entry (if clustered) and one per node
+    pub async fn get_cluster_status(&self) -> Result<Vec<ClusterStatusEntry>> {
+        vlog_debug!("Fetching cluster status...");
+        let response = self.get("/api2/json/cluster/status").await?;
+
+        let entries: Vec<ClusterStatusEntry> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse cluster status response")?;
+        Ok(entries)
+    }
+
+    /// Get the runtime state of a guest
+    pub async fn get_guest_status(&self, node: &str, guest_type: &str, vmid: u32) -> Result<GuestStatus> {
+        vlog_debug!("Fetching status of {} {} on node '{}'...", guest_type, vmid, node);
+        let path = format!("/api2/json/nodes/{}/{}/{}/status/current", node, guest_type, vmid);
+        let response = self.get(&path).await?;
+
+        let status: GuestStatus = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse guest status response")?;
+        Ok(status)
+    }
+
+    /// Get the read and write operations of a VM since it started, summed
+    /// over its disks
+    pub async fn get_guest_disk_ops(&self, node: &str, vmid: u32) -> Result<(u64, u64)> {
+        let path = format!("/api2/json/nodes/{}/qemu/{}/status/current", node, vmid);
+        let response = self.get(&path).await?;
+
+        let mut ops = (0, 0);
+        if let Some(devices) = response["data"]["blockstat"].as_object() {
+            for stats in devices.values() {
+                ops.0 += stats["rd_operations"].as_u64().unwrap_or(0);
+                ops.1 += stats["wr_operations"].as_u64().unwrap_or(0);
+            }
+        }
+        Ok(ops)
+    }
+
+    /// Take a snapshot of a guest and return the UPID
+    pub async fn create_snapshot(&self, node: &str, guest_type: &str, vmid: u32, name: &str, description: &str) -> Result<String> {
+        vlog_debug!("Taking snapshot '{}' of {} {} on node '{}'...", name, guest_type, vmid, node);
+        let path = format!("/api2/json/nodes/{}/{}/{}/snapshot", node, guest_type, vmid);
+        let params = vec![
+            ("snapname".to_string(), name.to_string()),
+            ("description".to_string(), description.to_string()),
+        ];
+        let response = self.post(&path, &params).await?;
+
+        Ok(response["data"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Remove a snapshot of a guest and return the UPID
+    pub async fn delete_snapshot(&self, node: &str, guest_type: &str, vmid: u32, name: &str) -> Result<String> {
+        vlog_debug!("Removing snapshot '{}' of {} {} on node '{}'...", name, guest_type, vmid, node);
+        let path = format!("/api2/json/nodes/{}/{}/{}/snapshot/{}", node, guest_type, vmid, name);
+        let response = self.delete(&path).await?;
+
+        Ok(response["data"].as_str().unwrap_or_default().to_string())
+    }
+
+    /// Get the snapshots of a guest, without the `current` pseudo snapshot
+    pub async fn get_guest_snapshots(&self, node: &str, guest_type: &str, vmid: u32) -> Result<Vec<GuestSnapshot>> {
+        vlog_debug!("Fetching snapshots of {} {} on node '{}'...", guest_type, vmid, node);
+        let path = format!("/api2/json/nodes/{}/{}/{}/snapshot", node, guest_type, vmid);
+        let response = self.get(&path).await?;
+
+        let snapshots: Vec<GuestSnapshot> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse snapshots response")?;
+        Ok(snapshots.into_iter().filter(|s| s.name != "current").collect())
+    }
+
+    /// Get the volumes of a storage as seen from a node, optionally only of
+    /// one content type and one guest
+    pub async fn get_storage_content(&self, node: &str, storage: &str, content: Option<&str>, vmid: Option<u32>) -> Result<Vec<StorageContent>> {
+        vlog_debug!("Fetching content of storage '{}' on node '{}'...", storage, node);
+        let mut path = format!("/api2/json/nodes/{}/storage/{}/content", node, storage);
+        let mut filters = Vec::new();
+        if let Some(content) = content {
+            filters.push(format!("content={}", content));
+        }
+        if let Some(vmid) = vmid {
+            filters.push(format!("vmid={}", vmid));
+        }
+        if !filters.is_empty() {
+            path = format!("{}?{}", path, filters.join("&"));
+        }
+        let response = self.get(&path).await?;
+
+        let volumes: Vec<StorageContent> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse storage content response")?;
+        Ok(volumes)
+    }
+
+    /// List the backups a retention policy would keep and remove, without
+    /// removing anything. `retention` is e.g. `keep-last=3,keep-weekly=4`,
+    /// none means the policy configured on the storage.
+    pub async fn get_prune_preview(&self, node: &str, storage: &str, retention: Option<&str>, vmid: Option<u32>) -> Result<Vec<PruneEntry>> {
+        vlog_debug!("Previewing backup pruning on storage '{}'...", storage);
+        let path = format!("/api2/json/nodes/{}/storage/{}/prunebackups{}", node, storage, prune_query(retention, vmid));
+        let response = self.get(&path).await?;
+
+        let entries: Vec<PruneEntry> = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse prune preview response")?;
+        Ok(entries)
+    }
+
+    /// Remove the backups a retention policy does not keep
+    pub async fn prune_backups(&self, node: &str, storage: &str, retention: Option<&str>, vmid: Option<u32>) -> Result<()> {
+        vlog_debug!("Pruning backups on storage '{}'...", storage);
+        let path = format!("/api2/json/nodes/{}/storage/{}/prunebackups{}", node, storage, prune_query(retention, vmid));
+        self.delete(&path).await?;
+        Ok(())
+    }
+
+    /// Run a read-only guest agent command (`info`, `get-osinfo`, ...) and
+    /// return its `result`, None when the agent does not answer
+    pub async fn get_agent_result(&self, node: &str, vmid: u32, command: &str) -> Option<Value> {
+        let path = format!("/api2/json/nodes/{}/qemu/{}/agent/{}", node, vmid, command);
+        match self.get_optional(&path).await {
+            Ok(response) => Some(response["data"]["result"].clone()),
+            Err(e) => {
+                vlog_debug!("Agent command '{}' failed for VM {}: {}", command, vmid, e);
+                None
+            }
+        }
+    }
+
+    /// Mounted filesystems of a VM with their usage, from the guest agent.
+    /// Mount points without usage (pseudo filesystems) are left out.
+    pub async fn get_guest_filesystems(&self, node: &str, vmid: u32) -> Option<Vec<GuestFilesystem>> {
+        let result = self.get_agent_result(node, vmid, "get-fsinfo").await?;
+        let mut filesystems: Vec<GuestFilesystem> = result.as_array()?
+            .iter()
+            .filter_map(|fs| Some(GuestFilesystem {
+                mountpoint: fs["mountpoint"].as_str()?.to_string(),
+                fs_type: fs["type"].as_str().unwrap_or_default().to_string(),
+                used_bytes: fs["used-bytes"].as_u64()?,
+                total_bytes: fs["total-bytes"].as_u64().filter(|t| *t > 0)?,
+            }))
+            .collect();
+        filesystems.sort_by(|a, b| a.mountpoint.cmp(&b.mountpoint));
+        filesystems.dedup_by(|a, b| a.mountpoint == b.mountpoint);
+        Some(filesystems)
+    }
+
+    /// Read a small file inside a VM through the guest agent, None when the
+    /// agent does not answer or the file is missing
+    pub async fn get_agent_file(&self, node: &str, vmid: u32, file: &str) -> Option<String> {
+        let path = format!("/api2/json/nodes/{}/qemu/{}/agent/file-read?file={}", node, vmid, file);
+        match self.get_optional(&path).await {
+            Ok(response) => response["data"]["content"].as_str().map(str::to_string),
+            Err(e) => {
+                vlog_debug!("Agent file-read of {} failed for VM {}: {}", file, vmid, e);
+                None
+            }
+        }
+    }
+
+    /// Get the network interfaces of a guest with MAC and CIDR addresses.
+    /// VMs need a running guest agent, so failures yield an empty list.
+    pub async fn get_guest_interfaces(&self, node: &str, guest_type: &str, vmid: u32) -> Result<Vec<GuestInterface>> {
+        vlog_debug!("Fetching interfaces for {} {} on node '{}'...", guest_type, vmid, node);
+        let mut interfaces = Vec::new();
+
+        if guest_type == "lxc" {
+            let path = format!("/api2/json/nodes/{}/lxc/{}/interfaces", node, vmid);
+            let Ok(response) = self.get_optional(&path).await else {
+                return Ok(interfaces);
+            };
+            for iface in response["data"].as_array().into_iter().flatten() {
+                let name = iface["name"].as_str().unwrap_or_default();
+                if name == "lo" {
+                    continue;
+                }
+                let addresses = ["inet", "inet6"].iter()
+                    .filter_map(|k| iface[*k].as_str())
+                    .filter(|a| !a.starts_with("fe80:"))
+                    .map(str::to_string)
+                    .collect();
+                interfaces.push(GuestInterface {
+                    name: name.to_string(),
+                    hwaddr: iface["hwaddr"].as_str().map(str::to_string),
+                    addresses,
+                });
+            }
+        } else {
+            let path = format!("/api2/json/nodes/{}/qemu/{}/agent/network-get-interfaces", node, vmid);
+            let Ok(response) = self.get_optional(&path).await else {
+                vlog_debug!("Agent not available for {} {}", guest_type, vmid);
+                return Ok(interfaces);
+            };
+            for iface in response["data"]["result"].as_array().into_iter().flatten() {
+                let name = iface["name"].as_str().unwrap_or_default();
+                if name == "lo" {
+                    continue;
+                }
+                let addresses = iface["ip-addresses"].as_array().into_iter().flatten()
+                    .filter_map(|a| {
+                        let ip = a["ip-address"].as_str()?;
+                        let prefix = a["prefix"].as_u64()?;
+                        (!ip.starts_with("127.") && !ip.starts_with("fe80:") && ip != "::1")
+                            .then(|| format!("{}/{}", ip, prefix))
+                    })
+                    .collect();
+                interfaces.push(GuestInterface {
+                    name: name.to_string(),
+                    hwaddr: iface["hardware-address"].as_str().map(str::to_string),
+                    addresses,
+                });
+            }
+        }
+
+        Ok(interfaces)
+    }
+
+    /// Get the Proxmox VE version of the node answering the API
+    pub async fn get_version(&self) -> Result<PveVersion> {
+        vlog_debug!("Fetching Proxmox VE version...");
+        let response = self.get("/api2/json/version").await?;
+
+        let version: PveVersion = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse version response")?;
+        Ok(version)
+    }
+
+    /// Get the Proxmox VE version running on a node
+    pub async fn get_node_version(&self, node: &str) -> Result<PveVersion> {
+        vlog_debug!("Fetching Proxmox VE version of node '{}'...", node);
+        let path = format!("/api2/json/nodes/{}/version", node);
+        let response = self.get(&path).await?;
+
+        let version: PveVersion = serde_json::from_value(response["data"].clone())
+            .context("Failed to parse version response")?;
+        Ok(version)
+    }
+}
+
+/// Query string of the prune endpoints, the retention value needs escaping
+fn prune_query(retention: Option<&str>, vmid: Option<u32>) -> String {
+    let mut params = vec!["type=backup".to_string()];
+    if let Some(retention) = retention {
+        params.push(format!("prune-backups={}", retention.replace('=', "%3D").replace(',', "%2C")));
+    }
+    if let Some(vmid) = vmid {
+        params.push(format!("vmid={}", vmid));
+    }
+    format!("?{}", params.join("&"))
+}
+
+/// Ticket of a password login, `/access/ticket`
+async fn login(client: &Client, base_url: &str, username: &str, password: &str) -> Result<AuthTicket> {
+    vlog_debug!("Requesting authentication ticket for user: {}", username);
+    let ticket_url = format!("{}/api2/json/access/ticket", base_url);
+
+    let request = client
+        .post(&ticket_url)
+        .form(&[
+            ("username", username),
+            ("password", password),
+        ])
+        .build()?;
+    let response = httplog::execute(client, request)
+        .await
+        .context("Failed to send authentication request")?;
+
+    if !response.status().is_success() {
+        vlog_error!("Authentication failed with status: {}", response.status());
+        anyhow::bail!("Authentication failed: HTTP {}", response.status());
+    }
+
+    let auth_response: ProxmoxResponse<AuthTicket> = response
+        .json()
+        .await
+        .context("Failed to parse authentication response")?;
+
+    vlog_debug!("Received authentication ticket for user: {}", auth_response.data.username);
+    Ok(auth_response.data)
+}
+
+/// HTTP client verifying certificates when `secure`, skipping the check
+/// for self-signed clusters otherwise
+fn http_client(secure: bool) -> Result<Client> {
+    ClientBuilder::new()
+        .danger_accept_invalid_certs(!secure)
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .context("Failed to build HTTP client")
+}
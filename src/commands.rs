@@ -34,23 +34,231 @@
 //! └── [102] backup-server (VM) - status:stopped, cpus:2, ram:4.0GB
 //!
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use crate::client::ProxmoxClient;
-use crate::models::{Guest, OutputFormat};
-use crate::{vlog_debug, vlog_success};
+use crate::confirm::ConfirmPolicy;
+use crate::models::{ClusterResource, Guest, GuestJsonInfoV2, Node, NodeBootInfo, NodeJsonInfo, NodeJsonInfoV2, NodeTotals, OutputFormat, OutputVersion, TimeFormat};
+use crate::labels::{self, LabelStore, Selector};
+use crate::mail::SmtpConfig;
+use crate::maintenance::{MaintenanceEntry, Windows};
+use crate::schedule::ScheduleEntry;
+use crate::csv::{self, ToField};
+use crate::{csv_row, pager, timefmt, vlog_debug, vlog_success, vlog_warn};
 use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use std::collections::HashMap;
+
+mod api;
+mod audit;
+mod backups;
+mod ceph;
+mod cluster;
+mod compare;
+mod disk;
+mod dr;
+mod drift;
+mod export;
+mod fanout;
+mod gpus;
+mod grafana;
+mod ha;
+mod ical;
+mod inventory;
+mod jobs;
+mod io;
+mod journal;
+mod label;
+mod maintenance;
+mod migrate;
+mod network;
+mod node;
+mod numa;
+mod pick;
+mod ping;
+mod provision;
+mod publish;
+mod render;
+mod report;
+mod restore;
+mod rightsize;
+mod schedule;
+mod schema;
+mod sensors;
+mod serve;
+mod state;
+mod storage;
+mod summary;
+mod tasks;
+mod templates;
+mod uptime;
+mod vm;
+
+pub use backups::{PruneOptions, VerifyOptions};
+pub use disk::DiskMoveOptions;
+pub use fanout::list_nodes_fanout;
+pub use jobs::JobKind;
+pub use maintenance::{maintenance, MaintenanceChange};
+pub use migrate::MigrateOptions;
+pub use node::DrainOptions;
+pub use provision::ProvisionOptions;
+pub use publish::MqttOptions;
+pub use render::{render, RenderOptions};
+pub use restore::TestRestoreOptions;
+pub use rightsize::RightsizeOptions;
+pub use schema::print_schema;
+pub use serve::ServeOptions;
+pub use storage::EvacuateOptions;
+pub use summary::SummaryStyle;
+
+/// Number of characters of the `--trends` sparklines
+const TREND_WIDTH: usize = 20;
+
+/// Z-score from which `--trends` highlights a node's CPU or RAM usage
+const ANOMALY_Z: f64 = 3.0;
+
+/// Smallest standard deviation of a baseline, as a 0..1 ratio
+const ANOMALY_MIN_STDDEV: f64 = 0.02;
+
+/// Columns `--wide` appends to guest tables, see `wide_guest_cells`
+const WIDE_GUEST_COLUMNS: [&str; 4] = ["Uptime", "Tags", "Pool", "HA State"];
+
+/// `--trends` cells of a node
+struct NodeTrend {
+    cpu: String,
+    ram: String,
+    cpu_anomaly: bool,
+    ram_anomaly: bool,
+}
 
 pub struct Commands {
     client: ProxmoxClient,
     output_format: OutputFormat,
+    output_version: OutputVersion,
+    confirm_policy: ConfirmPolicy,
+    wide: bool,
+    time_format: Option<TimeFormat>,
+    label_filter: Vec<Selector>,
+    schedules: Vec<ScheduleEntry>,
+    maintenance: Vec<MaintenanceEntry>,
+    smtp: Option<SmtpConfig>,
 }
 
 impl Commands {
     pub fn new(client: ProxmoxClient, output_format: OutputFormat) -> Self {
-        Self { client, output_format }
+        Self { client, output_format, output_version: OutputVersion::default(), confirm_policy: ConfirmPolicy::default(), wide: false, time_format: None, label_filter: Vec::new(), schedules: Vec::new(), maintenance: Vec::new(), smtp: None }
+    }
+
+    pub fn output_format(&self) -> OutputFormat {
+        self.output_format
+    }
+
+    /// JSON contract of the outputs that differ between versions, the
+    /// node list and node detail; newer outputs are the same in both
+    pub fn set_output_version(&mut self, version: OutputVersion) {
+        self.output_version = version;
+    }
+
+    pub fn set_confirm_policy(&mut self, policy: ConfirmPolicy) {
+        self.confirm_policy = policy;
+    }
+
+    /// Extra table columns, the default tables stay compact
+    pub fn set_wide(&mut self, wide: bool) {
+        self.wide = wide;
+    }
+
+    pub fn set_time_format(&mut self, format: Option<TimeFormat>) {
+        self.time_format = format;
+    }
+
+    /// `--label` conditions the listed nodes and guests must meet
+    pub fn set_label_filter(&mut self, filter: Vec<Selector>) {
+        self.label_filter = filter;
+    }
+
+    /// `[[schedule]]` entries of the config file
+    pub fn set_schedules(&mut self, schedules: Vec<ScheduleEntry>) {
+        self.schedules = schedules;
+    }
+
+    /// `[[maintenance]]` entries of the config file
+    pub fn set_maintenance(&mut self, entries: Vec<MaintenanceEntry>) {
+        self.maintenance = entries;
+    }
+
+    pub fn maintenance_entries(&self) -> &[MaintenanceEntry] {
+        &self.maintenance
+    }
+
+    /// `[smtp]` section of the config file, for `report --email`
+    pub fn set_smtp(&mut self, smtp: Option<SmtpConfig>) {
+        self.smtp = smtp;
+    }
+
+    /// Maintenance windows for the checks, none when they cannot be read
+    fn maintenance_windows(&self) -> Option<Windows> {
+        Windows::load(&self.maintenance)
+            .inspect_err(|e| vlog_warn!("Maintenance windows not available: {}", e))
+            .ok()
+    }
+
+    /// Epoch seconds in the `--time-format` notation
+    fn timestamp(&self, epoch: u64) -> String {
+        timefmt::timestamp(epoch, self.time_format)
+    }
+
+    /// Seconds since boot in the `--time-format` notation
+    fn uptime(&self, secs: u64) -> String {
+        timefmt::uptime(secs, self.time_format)
+    }
+
+    /// Guests of the cluster by VMID for the `--wide` columns, empty
+    /// without `--wide` so compact tables cost no extra request
+    async fn wide_guests(&self) -> HashMap<u32, ClusterResource> {
+        if !self.wide {
+            return HashMap::new();
+        }
+        match self.client.get_cluster_resources(Some("vm")).await {
+            Ok(resources) => resources.into_iter()
+                .filter_map(|r| r.vmid.map(|vmid| (vmid, r)))
+                .collect(),
+            Err(e) => {
+                vlog_warn!("Cluster resources not available: {}", e);
+                HashMap::new()
+            }
+        }
+    }
+
+    /// Local labels of this cluster, the cluster name is only looked up
+    /// when labels are stored at all
+    async fn labels(&self) -> LabelStore {
+        if !labels::any() {
+            return LabelStore::default();
+        }
+        let store = match self.cluster_name().await {
+            Ok(cluster) => labels::load(&cluster),
+            Err(e) => Err(e),
+        };
+        store.unwrap_or_else(|e| {
+            vlog_warn!("Labels not available: {}", e);
+            LabelStore::default()
+        })
+    }
+
+    /// Ask before a mutating operation as the policy says, dry runs never
+    /// ask since they change nothing. Read-only mode fails here already,
+    /// before anything is planned or written.
+    fn confirm(&self, operation: &str, what: &str) -> Result<()> {
+        if self.client.read_only() {
+            bail!("Read-only mode, refusing to {} {}", operation, what);
+        }
+        if self.client.dry_run() {
+            return Ok(());
+        }
+        self.confirm_policy.check(operation, what)
     }
 
-    pub async fn list_nodes(&self) -> Result<()> {
+    /// Cluster nodes with their IP addresses
+    async fn collect_nodes(&self) -> Result<Vec<Node>> {
         vlog_debug!("Fetching cluster nodes...");
 
         let mut nodes = self.client.get_nodes().await?;
@@ -59,50 +267,65 @@ impl Commands {
         for node in &mut nodes {
             node.ip = self.client.get_node_ip(&node.node).await?;
         }
+        Ok(nodes)
+    }
+
+    /// The node answering the API, flagged `local` in the cluster status
+    async fn root_controller(&self) -> Option<String> {
+        match self.client.get_cluster_status().await {
+            Ok(entries) => entries.into_iter()
+                .find(|e| e.entry_type == "node" && e.local == Some(1))
+                .map(|e| e.name),
+            Err(e) => {
+                vlog_warn!("Cluster status not available: {}", e);
+                None
+            }
+        }
+    }
+
+    pub async fn list_nodes(&self, trends: bool) -> Result<()> {
+        let mut nodes = self.collect_nodes().await?;
+        let wide_guests = self.wide_guests().await;
+        let labels = self.labels().await;
+        nodes.retain(|n| labels::matches(labels.node(&n.node), &self.label_filter));
+        let show_labels = nodes.iter().any(|n| labels.node(&n.node).is_some());
 
         match self.output_format {
+            OutputFormat::Json if self.output_version == OutputVersion::V2 => {
+                let output = crate::models::NodeListOutputV2 {
+                    output_version: 2,
+                    root_controller: self.root_controller().await,
+                    proxmox_version: self.client.get_version().await.ok().map(|v| v.version),
+                    nodes: nodes.iter()
+                        .map(|n| NodeJsonInfoV2 { labels: labels.node(&n.node).cloned().unwrap_or_default(), ..node_json_info_v2(n) })
+                        .collect(),
+                    totals: node_totals(&nodes),
+                };
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            }
             OutputFormat::Json => {
                 // JSON format with custom structure
-                use crate::models::{NodeListOutput, NodeJsonInfo};
+                use crate::models::NodeListOutput;
 
-                // TODO: Fetch actual root_controller and proxmox_version from API
-                // For now, use placeholder values
-                let root_controller = nodes.first()
-                    .map(|n| n.node.clone())
+                let root_controller = self.root_controller().await
                     .unwrap_or_else(|| "unknown".to_string());
-                let proxmox_version = "unknown".to_string();
-
-                let nodes_json: Vec<NodeJsonInfo> = nodes.iter().map(|node| {
-                    let cpu_cores = node.maxcpu.map(|c| c.to_string()).unwrap_or_else(|| "N/A".to_string());
-
-                    let memory_gb = match (node.mem, node.maxmem) {
-                        (Some(m), Some(mm)) => format!("{}/{}",
-                            (m as f64 / 1024.0 / 1024.0 / 1024.0).ceil() as u64,
-                            (mm as f64 / 1024.0 / 1024.0 / 1024.0).ceil() as u64),
-                        _ => "N/A".to_string(),
-                    };
-
-                    let storage_gb = match (node.disk, node.maxdisk) {
-                        (Some(d), Some(md)) => format!("{}/{}",
-                            (d as f64 / 1024.0 / 1024.0 / 1024.0).ceil() as u64,
-                            (md as f64 / 1024.0 / 1024.0 / 1024.0).ceil() as u64),
-                        _ => "N/A".to_string(),
-                    };
-
-                    NodeJsonInfo {
-                        name: node.node.clone(),
-                        cpu: cpu_cores,
-                        memory_gb,
-                        storage_gb,
-                        ipv4: node.ip.clone().unwrap_or_else(|| "N/A".to_string()),
-                        status: node.status.clone(),
+                let proxmox_version = match self.client.get_version().await {
+                    Ok(v) => v.version,
+                    Err(e) => {
+                        vlog_warn!("Proxmox VE version not available: {}", e);
+                        "unknown".to_string()
                     }
-                }).collect();
+                };
+
+                let nodes_json: Vec<NodeJsonInfo> = nodes.iter()
+                    .map(|n| NodeJsonInfo { labels: labels.node(&n.node).cloned().unwrap_or_default(), ..node_json_info(n) })
+                    .collect();
 
                 let output = NodeListOutput {
                     root_controller,
                     proxmox_version,
                     nodes: nodes_json,
+                    totals: node_totals(&nodes),
                 };
 
                 let json_pretty = serde_json::to_string_pretty(&output)?;
@@ -110,10 +333,15 @@ impl Commands {
             }
             OutputFormat::Csv => {
                 // CSV format with header
-                println!("NODE,IP,STATUS,CPU_PERCENT,CPU_CORES,RAM_GB,HDD_GB,UPTIME_DAYS");
+                if show_labels {
+                    csv_row!("NODE", "IP", "STATUS", "CPU_PERCENT", "CPU_CORES", "RAM_GB", "HDD_GB", "UPTIME_DAYS",
+                             "LABELS");
+                } else {
+                    csv_row!("NODE", "IP", "STATUS", "CPU_PERCENT", "CPU_CORES", "RAM_GB", "HDD_GB", "UPTIME_DAYS");
+                }
 
                 for node in &nodes {
-                    let ip = node.ip.as_ref().map(|s| s.as_str()).unwrap_or("N/A");
+                    let ip = node.ip.as_deref().unwrap_or("N/A");
                     let cpu_percent = node.cpu.map(|c| format!("{:.1}", c * 100.0)).unwrap_or_else(|| "N/A".to_string());
                     let cpu_cores = node.maxcpu.map(|c| c.to_string()).unwrap_or_else(|| "N/A".to_string());
                     let uptime_days = node.uptime.map(|u| format!("{:.1}", u as f64 / 86400.0)).unwrap_or_else(|| "N/A".to_string());
@@ -134,16 +362,20 @@ impl Commands {
                         _ => "N/A".to_string(),
                     };
 
-                    println!("{},{},{},{},{},{},{},{}",
-                             node.node,
-                             ip,
-                             node.status,
-                             cpu_percent,
-                             cpu_cores,
-                             ram_gb,
-                             hdd_gb,
-                             uptime_days
-                    );
+                    let mut fields = vec![
+                        node.node.to_field(),
+                        ip.to_field(),
+                        node.status.to_field(),
+                        csv::num(cpu_percent),
+                        cpu_cores.to_field(),
+                        ram_gb.to_field(),
+                        hdd_gb.to_field(),
+                        csv::num(uptime_days),
+                    ];
+                    if show_labels {
+                        fields.push(labels::join(labels.node(&node.node)).to_field());
+                    }
+                    csv::print_row(&fields);
                 }
             }
             OutputFormat::Table => {
@@ -153,21 +385,34 @@ impl Commands {
                      .set_content_arrangement(ContentArrangement::Dynamic);
 
                 // Add header
-                table.set_header(vec![
+                let mut header = vec![
                     Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
                     Cell::new("Status").add_attribute(Attribute::Bold).fg(Color::Cyan),
                     Cell::new("CPU %").add_attribute(Attribute::Bold).fg(Color::Cyan),
                     Cell::new("CPU Cores").add_attribute(Attribute::Bold).fg(Color::Cyan),
                     Cell::new("RAM (GB)").add_attribute(Attribute::Bold).fg(Color::Cyan),
                     Cell::new("HDD (GB)").add_attribute(Attribute::Bold).fg(Color::Cyan),
-                    Cell::new("Uptime (days)").add_attribute(Attribute::Bold).fg(Color::Cyan),
-                ]);
+                    Cell::new("Uptime").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ];
+                if self.wide {
+                    header.insert(1, Cell::new("IP").add_attribute(Attribute::Bold).fg(Color::Cyan));
+                    header.push(Cell::new("Guests").add_attribute(Attribute::Bold).fg(Color::Cyan));
+                    header.push(Cell::new("HA Guests").add_attribute(Attribute::Bold).fg(Color::Cyan));
+                }
+                if show_labels {
+                    header.push(Cell::new("Labels").add_attribute(Attribute::Bold).fg(Color::Cyan));
+                }
+                if trends {
+                    header.push(Cell::new("CPU (1h)").add_attribute(Attribute::Bold).fg(Color::Cyan));
+                    header.push(Cell::new("RAM (1h)").add_attribute(Attribute::Bold).fg(Color::Cyan));
+                }
+                table.set_header(header);
 
                 // Add rows
                 for node in &nodes {
                     let cpu_percent = node.cpu.map(|c| format!("{:.1}", c * 100.0)).unwrap_or_else(|| "N/A".to_string());
                     let cpu_cores = node.maxcpu.map(|c| c.to_string()).unwrap_or_else(|| "N/A".to_string());
-                    let uptime_days = node.uptime.map(|u| format!("{:.1}", u as f64 / 86400.0)).unwrap_or_else(|| "N/A".to_string());
+                    let uptime = node.uptime.map(|u| self.uptime(u)).unwrap_or_else(|| "N/A".to_string());
 
                     // Format RAM as "allocated/total" with ceiling, no decimals (unit in header)
                     let ram = match (node.mem, node.maxmem) {
@@ -185,11 +430,11 @@ impl Commands {
                         _ => "N/A".to_string(),
                     };
 
-                    // Format node name with IP on second line
-                    let node_name_with_ip = if let Some(ip) = &node.ip {
-                        format!("{}\n{}", node.node, ip)
-                    } else {
-                        node.node.clone()
+                    // Format node name with IP on second line, wide tables
+                    // give the IP its own column
+                    let node_name_with_ip = match &node.ip {
+                        Some(ip) if !self.wide => format!("{}\n{}", node.node, ip),
+                        _ => node.node.clone(),
                     };
 
                     let status_cell = if node.status == "online" {
@@ -198,18 +443,69 @@ impl Commands {
                         Cell::new(&node.status).fg(Color::Red)
                     };
 
-                    table.add_row(vec![
+                    // Sparklines of the last hour, offline nodes have no RRD data
+                    let trend = if trends { Some(self.node_trend(node).await) } else { None };
+                    let highlight = |cell: Cell, anomaly: bool| {
+                        if anomaly { cell.fg(Color::Red).add_attribute(Attribute::Bold) } else { cell }
+                    };
+
+                    let mut row = vec![
                         Cell::new(&node_name_with_ip),
                         status_cell,
-                        Cell::new(&cpu_percent),
+                        highlight(Cell::new(&cpu_percent), trend.as_ref().is_some_and(|t| t.cpu_anomaly)),
                         Cell::new(&cpu_cores),
-                        Cell::new(&ram),
+                        highlight(Cell::new(&ram), trend.as_ref().is_some_and(|t| t.ram_anomaly)),
                         Cell::new(&hdd),
-                        Cell::new(&uptime_days),
-                    ]);
+                        Cell::new(&uptime),
+                    ];
+                    if self.wide {
+                        let guests: Vec<&ClusterResource> = wide_guests.values()
+                            .filter(|g| g.is_guest() && g.node.as_deref() == Some(node.node.as_str()))
+                            .collect();
+                        let running = guests.iter().filter(|g| g.status.as_deref() == Some("running")).count();
+                        let ha = guests.iter().filter(|g| g.hastate.is_some()).count();
+                        row.insert(1, Cell::new(node.ip.as_deref().unwrap_or("N/A")));
+                        row.push(Cell::new(format!("{}/{}", running, guests.len())));
+                        row.push(Cell::new(ha));
+                    }
+                    if show_labels {
+                        row.push(Cell::new(labels::join(labels.node(&node.node))).fg(Color::Yellow));
+                    }
+
+                    if let Some(trend) = &trend {
+                        row.push(Cell::new(&trend.cpu).fg(if trend.cpu_anomaly { Color::Red } else { Color::Yellow }));
+                        row.push(Cell::new(&trend.ram).fg(if trend.ram_anomaly { Color::Red } else { Color::Yellow }));
+                    }
+
+                    table.add_row(row);
                 }
 
-                println!("{}", table);
+                // Footer with the size of the whole cluster
+                let totals = node_totals(&nodes);
+                let mut footer = vec![
+                    Cell::new(format!("Total ({})", nodes.len())),
+                    Cell::new(format!("{} online\n{} offline", totals.nodes_online, totals.nodes_offline)),
+                    Cell::new(format!("{:.1}", totals.cpu_percent)),
+                    Cell::new(totals.cpu_cores),
+                    Cell::new(format!("{}/{}", totals.memory_used_gb, totals.memory_total_gb)),
+                    Cell::new(format!("{}/{}", totals.storage_used_gb, totals.storage_total_gb)),
+                    Cell::new(""),
+                ];
+                if self.wide {
+                    footer.insert(1, Cell::new(""));
+                    footer.push(Cell::new(""));
+                    footer.push(Cell::new(""));
+                }
+                if show_labels {
+                    footer.push(Cell::new(""));
+                }
+                if trends {
+                    footer.push(Cell::new(""));
+                    footer.push(Cell::new(""));
+                }
+                table.add_row(footer.into_iter().map(|c| c.add_attribute(Attribute::Bold)));
+
+                pager::print_table(&mut table);
             }
         }
 
@@ -217,12 +513,46 @@ impl Commands {
         Ok(())
     }
 
-    pub async fn show_node_info(&self, node: &str) -> Result<()> {
+    /// Last hour CPU and RAM sparklines of a node, each flagged when its
+    /// latest sample is far off the rest of the hour
+    async fn node_trend(&self, node: &Node) -> NodeTrend {
+        let unknown = NodeTrend { cpu: "N/A".to_string(), ram: "N/A".to_string(), cpu_anomaly: false, ram_anomaly: false };
+        if node.status != "online" {
+            return unknown;
+        }
+        match self.client.get_node_rrddata(&node.node, "hour").await {
+            Ok(points) => {
+                let cpu: Vec<f64> = points.iter().filter_map(|p| p.cpu).collect();
+                let ram: Vec<f64> = points.iter()
+                    .filter_map(|p| match (p.memused, p.memtotal) {
+                        (Some(used), Some(total)) if total > 0.0 => Some(used / total),
+                        _ => None,
+                    })
+                    .collect();
+                NodeTrend {
+                    cpu: sparkline(&cpu, TREND_WIDTH),
+                    ram: sparkline(&ram, TREND_WIDTH),
+                    cpu_anomaly: is_anomaly(&cpu),
+                    ram_anomaly: is_anomaly(&ram),
+                }
+            }
+            Err(e) => {
+                vlog_debug!("No RRD data for node '{}': {}", node.node, e);
+                unknown
+            }
+        }
+    }
+
+    /// Show a node and its guests. With `fail_on_reboot` the command fails
+    /// after printing when the node runs an older kernel than installed,
+    /// `net` adds the current network rates of running guests.
+    pub async fn show_node_info(&self, node: &str, fail_on_reboot: bool, net: bool) -> Result<()> {
         vlog_debug!("Fetching node info and guests for '{}'...", node);
 
         // Fetch node information
         let mut node_info = self.client.get_node_status(node).await?;
         node_info.ip = self.client.get_node_ip(node).await?;
+        let boot = self.node_boot_info(node).await;
 
         // Fetch guests (VMs and LXCs) for this node
         let mut vms = self.client.get_vms(node).await?;
@@ -248,7 +578,26 @@ impl Commands {
         }
         guests.sort_by(|a, b| a.name().cmp(b.name()));
 
+        let labels = self.labels().await;
+        guests.retain(|g| labels::matches(labels.guest(g.vmid()), &self.label_filter));
+        let show_labels = guests.iter().any(|g| labels.guest(g.vmid()).is_some());
+
+        let rates = if net { self.guest_net_rates(node, &guests).await } else { HashMap::new() };
+        let wide_guests = self.wide_guests().await;
+
         match self.output_format {
+            OutputFormat::Json if self.output_version == OutputVersion::V2 => {
+                let output = crate::models::NodeDetailOutputV2 {
+                    output_version: 2,
+                    node: NodeJsonInfoV2 { labels: labels.node(node).cloned().unwrap_or_default(), ..node_json_info_v2(&node_info) },
+                    is_root_controller: self.root_controller().await.as_deref() == Some(node),
+                    boot: boot.clone(),
+                    guests: guests.iter()
+                        .map(|g| GuestJsonInfoV2 { labels: labels.guest(g.vmid()).cloned().unwrap_or_default(), ..guest_json_info_v2(g, rates.get(&g.vmid())) })
+                        .collect(),
+                };
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            }
             OutputFormat::Json => {
                 // JSON format with custom structure (node info + guests)
                 use crate::models::{NodeDetailOutput, GuestJsonInfo};
@@ -269,8 +618,11 @@ impl Commands {
                     _ => "N/A".to_string(),
                 };
 
-                // TODO: Determine if this node is the root controller
-                let is_root_controller = "NO".to_string();
+                let is_root_controller = if self.root_controller().await.as_deref() == Some(node) {
+                    "YES".to_string()
+                } else {
+                    "NO".to_string()
+                };
 
                 let guests_json: Vec<GuestJsonInfo> = guests.iter().map(|guest| {
                     let ip = match guest {
@@ -301,6 +653,9 @@ impl Commands {
                         storage_gb,
                         ipv4: ip,
                         status: guest.status().to_string(),
+                        netin_bps: rates.get(&guest.vmid()).map(|r| r.0),
+                        netout_bps: rates.get(&guest.vmid()).map(|r| r.1),
+                        labels: labels.guest(guest.vmid()).cloned().unwrap_or_default(),
                     }
                 }).collect();
 
@@ -312,6 +667,8 @@ impl Commands {
                     ipv4: node_info.ip.clone().unwrap_or_else(|| "N/A".to_string()),
                     status: node_info.status.clone(),
                     is_root_controller,
+                    boot: boot.clone(),
+                    labels: labels.node(node).cloned().unwrap_or_default(),
                     guests: guests_json,
                 };
 
@@ -320,12 +677,19 @@ impl Commands {
             }
             OutputFormat::Csv => {
                 // CSV format: print ONLY guests (not node info) to keep CSV consistent
-                println!("NAME,STATUS,CPU,RAM_GB,HDD_GB,IPv4");
+                let mut header = vec!["NAME", "STATUS", "CPU", "RAM_GB", "HDD_GB", "IPv4"];
+                if net {
+                    header.extend(["NETIN_BPS", "NETOUT_BPS"]);
+                }
+                if show_labels {
+                    header.push("LABELS");
+                }
+                csv::print_row(&header.iter().map(|h| h.to_field()).collect::<Vec<_>>());
 
                 for guest in &guests {
                     let ip = match guest {
-                        Guest::VM(vm) => vm.ip.as_ref().map(|s| s.as_str()).unwrap_or("N/A"),
-                        Guest::LXC(lxc) => lxc.ip.as_ref().map(|s| s.as_str()).unwrap_or("N/A"),
+                        Guest::VM(vm) => vm.ip.as_deref().unwrap_or("N/A"),
+                        Guest::LXC(lxc) => lxc.ip.as_deref().unwrap_or("N/A"),
                     };
 
                     let ram_gb = match guest {
@@ -343,14 +707,23 @@ impl Commands {
                         Guest::LXC(lxc) => lxc.cpus.map(|c| c.to_string()),
                     }.unwrap_or_else(|| "N/A".to_string());
 
-                    println!("{},{},{},{},{},{}",
-                             guest.name(),
-                             guest.status(),
-                             cpus,
-                             ram_gb,
-                             hdd_gb,
-                             ip
-                    );
+                    let mut fields = vec![
+                        guest.name().to_field(),
+                        guest.status().to_field(),
+                        cpus.to_field(),
+                        csv::num(ram_gb),
+                        csv::num(hdd_gb),
+                        ip.to_field(),
+                    ];
+                    match (net, rates.get(&guest.vmid())) {
+                        (false, _) => {}
+                        (true, Some((netin, netout))) => fields.extend([csv::num(format!("{:.0}", netin)), csv::num(format!("{:.0}", netout))]),
+                        (true, None) => fields.extend(["N/A".to_field(), "N/A".to_field()]),
+                    }
+                    if show_labels {
+                        fields.push(labels::join(labels.guest(guest.vmid())).to_field());
+                    }
+                    csv::print_row(&fields);
                 }
             }
             OutputFormat::Table => {
@@ -398,12 +771,33 @@ impl Commands {
                 }
 
                 if let Some(uptime) = node_info.uptime {
-                    let days = uptime / 86400;
-                    let hours = (uptime % 86400) / 3600;
-                    node_table.add_row(vec!["Uptime", &format!("{}d {}h", days, hours)]);
+                    node_table.add_row(vec!["Uptime", &self.uptime(uptime)]);
                 }
 
-                println!("{}", node_table);
+                if let Some(boot) = &boot {
+                    if let Some(running) = &boot.running_kernel {
+                        node_table.add_row(vec!["Kernel", running]);
+                    }
+                    if let Some(latest) = &boot.latest_kernel {
+                        node_table.add_row(vec!["Newest Kernel", latest]);
+                    }
+                    let reboot_cell = if boot.reboot_required {
+                        Cell::new("YES").fg(Color::Red)
+                    } else {
+                        Cell::new("NO").fg(Color::Green)
+                    };
+                    node_table.add_row(vec![Cell::new("Reboot Needed"), reboot_cell]);
+                    if let Some(mode) = &boot.boot_mode {
+                        let secure = if boot.secure_boot == Some(true) { " (secure boot)" } else { "" };
+                        node_table.add_row(vec!["Boot", &format!("{}{}", mode, secure)]);
+                    }
+                }
+
+                if let Some(node_labels) = labels.node(node) {
+                    node_table.add_row(vec!["Labels", &labels::join(Some(node_labels))]);
+                }
+
+                pager::print_table(&mut node_table);
 
                 // Now show guests in a separate table
                 if !guests.is_empty() {
@@ -413,19 +807,31 @@ impl Commands {
                     guests_table.load_preset(UTF8_FULL)
                          .set_content_arrangement(ContentArrangement::Dynamic);
 
-                    guests_table.set_header(vec![
+                    let mut header = vec![
                         Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
                         Cell::new("IP").add_attribute(Attribute::Bold).fg(Color::Cyan),
                         Cell::new("Type").add_attribute(Attribute::Bold).fg(Color::Cyan),
                         Cell::new("Status").add_attribute(Attribute::Bold).fg(Color::Cyan),
                         Cell::new("CPUs").add_attribute(Attribute::Bold).fg(Color::Cyan),
                         Cell::new("RAM (GB)").add_attribute(Attribute::Bold).fg(Color::Cyan),
-                    ]);
+                    ];
+                    if net {
+                        header.push(Cell::new("Net In").add_attribute(Attribute::Bold).fg(Color::Cyan));
+                        header.push(Cell::new("Net Out").add_attribute(Attribute::Bold).fg(Color::Cyan));
+                    }
+                    if self.wide {
+                        header.extend(WIDE_GUEST_COLUMNS.iter()
+                            .map(|c| Cell::new(c).add_attribute(Attribute::Bold).fg(Color::Cyan)));
+                    }
+                    if show_labels {
+                        header.push(Cell::new("Labels").add_attribute(Attribute::Bold).fg(Color::Cyan));
+                    }
+                    guests_table.set_header(header);
 
                     for guest in &guests {
                         let ip = match guest {
-                            Guest::VM(vm) => vm.ip.as_ref().map(|s| s.as_str()).unwrap_or("N/A"),
-                            Guest::LXC(lxc) => lxc.ip.as_ref().map(|s| s.as_str()).unwrap_or("N/A"),
+                            Guest::VM(vm) => vm.ip.as_deref().unwrap_or("N/A"),
+                            Guest::LXC(lxc) => lxc.ip.as_deref().unwrap_or("N/A"),
                         };
 
                         let ram_gb = match guest {
@@ -450,17 +856,33 @@ impl Commands {
                             Cell::new("LXC").fg(Color::Magenta)
                         };
 
-                        guests_table.add_row(vec![
+                        let mut row = vec![
                             Cell::new(guest.name()),
                             Cell::new(ip),
                             type_cell,
                             status_cell,
                             Cell::new(&cpus),
                             Cell::new(&ram_gb),
-                        ]);
+                        ];
+                        if net {
+                            match rates.get(&guest.vmid()) {
+                                Some((netin, netout)) => {
+                                    row.push(Cell::new(format_rate(*netin)));
+                                    row.push(Cell::new(format_rate(*netout)));
+                                }
+                                None => row.extend([Cell::new("-"), Cell::new("-")]),
+                            }
+                        }
+                        if self.wide {
+                            row.extend(wide_guest_cells(wide_guests.get(&guest.vmid()), self.time_format));
+                        }
+                        if show_labels {
+                            row.push(Cell::new(labels::join(labels.guest(guest.vmid()))).fg(Color::Yellow));
+                        }
+                        guests_table.add_row(row);
                     }
 
-                    println!("{}", guests_table);
+                    pager::print_table(&mut guests_table);
                 } else {
                     println!("\nNo guests on this node.\n");
                 }
@@ -468,174 +890,240 @@ impl Commands {
         }
 
         vlog_success!("Node info and {} guest(s) displayed", guests.len());
-        Ok(())
-    }
 
-    pub async fn list_node_guests(&self, node: &str) -> Result<()> {
-        vlog_debug!("Fetching guests for node '{}'...", node);
-
-        // Fetch both VMs and LXC containers
-        let mut vms = self.client.get_vms(node).await?;
-        let mut lxc = self.client.get_lxc(node).await?;
-
-        // Fetch IP addresses for VMs
-        for vm in &mut vms {
-            vm.ip = self.client.get_guest_ip(node, vm.vmid, "qemu").await?;
-        }
-
-        // Fetch IP addresses for LXC containers
-        for container in &mut lxc {
-            container.ip = self.client.get_guest_ip(node, container.vmid, "lxc").await?;
-        }
-
-        // Combine into Guest enum and sort by name (quicksort, not bogosort! 😄)
-        let mut guests: Vec<Guest> = Vec::new();
-
-        for vm in vms {
-            guests.push(Guest::VM(vm));
-        }
-
-        for container in lxc {
-            guests.push(Guest::LXC(container));
+        if let Some(boot) = boot.filter(|b| fail_on_reboot && b.reboot_required) {
+            let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs();
+            if let Some(until) = self.maintenance_windows().and_then(|w| w.covering(Some(node), None, now)) {
+                vlog_warn!("Node '{}' needs a reboot, in maintenance until {}", node, self.timestamp(until));
+                return Ok(());
+            }
+            bail!("Node '{}' needs a reboot to load kernel {}",
+                  node, boot.latest_kernel.unwrap_or_default());
         }
+        Ok(())
+    }
 
-        guests.sort_by(|a, b| a.name().cmp(b.name()));
-
-        match self.output_format {
-            OutputFormat::Csv => {
-                // CSV format with header
-                println!("NODE,VMID,NAME,IP,TYPE,STATUS,CPUS,RAM_GB");
-
-                for guest in &guests {
-                    let ip = match guest {
-                        Guest::VM(vm) => vm.ip.as_ref().map(|s| s.as_str()).unwrap_or("N/A"),
-                        Guest::LXC(lxc) => lxc.ip.as_ref().map(|s| s.as_str()).unwrap_or("N/A"),
-                    };
-
-                    let ram_gb = match guest {
-                        Guest::VM(vm) => vm.maxmem.map(|m| format!("{:.1}", m as f64 / 1024.0 / 1024.0 / 1024.0)),
-                        Guest::LXC(lxc) => lxc.maxmem.map(|m| format!("{:.1}", m as f64 / 1024.0 / 1024.0 / 1024.0)),
-                    }.unwrap_or_else(|| "N/A".to_string());
-
-                    let cpus = match guest {
-                        Guest::VM(vm) => vm.cpus.map(|c| c.to_string()),
-                        Guest::LXC(lxc) => lxc.cpus.map(|c| c.to_string()),
-                    }.unwrap_or_else(|| "N/A".to_string());
-
-                    println!("{},{},{},{},{},{},{},{}",
-                             node,
-                             guest.vmid(),
-                             guest.name(),
-                             ip,
-                             guest.guest_type(),
-                             guest.status(),
-                             cpus,
-                             ram_gb
-                    );
+    /// Latest network receive and send rates of the running guests, bytes
+    /// per second, from the most recent RRD sample (one minute average)
+    async fn guest_net_rates(&self, node: &str, guests: &[Guest]) -> HashMap<u32, (f64, f64)> {
+        let mut rates = HashMap::new();
+        for guest in guests.iter().filter(|g| g.status() == "running") {
+            let guest_type = match guest {
+                Guest::VM(_) => "qemu",
+                Guest::LXC(_) => "lxc",
+            };
+            let points = match self.client.get_guest_rrddata(node, guest_type, guest.vmid(), "hour").await {
+                Ok(points) => points,
+                Err(e) => {
+                    vlog_warn!("No network rates for guest {}: {}", guest.vmid(), e);
+                    continue;
                 }
+            };
+            // The newest slot is still being filled and often empty
+            if let Some((netin, netout)) = points.iter().rev().find_map(|p| p.netin.zip(p.netout)) {
+                rates.insert(guest.vmid(), (netin, netout));
             }
-            OutputFormat::Table => {
-                // Table format with borders
-                let mut table = Table::new();
-                table.load_preset(UTF8_FULL)
-                     .set_content_arrangement(ContentArrangement::Dynamic);
-
-                table.set_header(vec![
-                    Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
-                    Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
-                    Cell::new("IP").add_attribute(Attribute::Bold).fg(Color::Cyan),
-                    Cell::new("Type").add_attribute(Attribute::Bold).fg(Color::Cyan),
-                    Cell::new("Status").add_attribute(Attribute::Bold).fg(Color::Cyan),
-                    Cell::new("CPUs").add_attribute(Attribute::Bold).fg(Color::Cyan),
-                    Cell::new("RAM (GB)").add_attribute(Attribute::Bold).fg(Color::Cyan),
-                ]);
-
-                for guest in &guests {
-                    let ip = match guest {
-                        Guest::VM(vm) => vm.ip.as_ref().map(|s| s.as_str()).unwrap_or("N/A"),
-                        Guest::LXC(lxc) => lxc.ip.as_ref().map(|s| s.as_str()).unwrap_or("N/A"),
-                    };
-
-                    let ram_gb = match guest {
-                        Guest::VM(vm) => vm.maxmem.map(|m| format!("{:.1}", m as f64 / 1024.0 / 1024.0 / 1024.0)),
-                        Guest::LXC(lxc) => lxc.maxmem.map(|m| format!("{:.1}", m as f64 / 1024.0 / 1024.0 / 1024.0)),
-                    }.unwrap_or_else(|| "N/A".to_string());
-
-                    let cpus = match guest {
-                        Guest::VM(vm) => vm.cpus.map(|c| c.to_string()),
-                        Guest::LXC(lxc) => lxc.cpus.map(|c| c.to_string()),
-                    }.unwrap_or_else(|| "N/A".to_string());
+        }
+        rates
+    }
 
-                    let status_cell = if guest.status() == "running" {
-                        Cell::new(guest.status()).fg(Color::Green)
-                    } else if guest.status() == "stopped" {
-                        Cell::new(guest.status()).fg(Color::Red)
-                    } else {
-                        Cell::new(guest.status()).fg(Color::Yellow)
-                    };
+    /// Running and newest installed kernel of a node. None when the node
+    /// does not answer, e.g. the user lacks Sys.Audit for package versions.
+    async fn node_boot_info(&self, node: &str) -> Option<NodeBootInfo> {
+        let (running, boot_mode, secure_boot) = match self.client.get_node_kernel(node).await {
+            Ok(kernel) => kernel,
+            Err(e) => {
+                vlog_warn!("No kernel info for node '{}': {}", node, e);
+                return None;
+            }
+        };
+        let packages = match self.client.get_apt_versions(node).await {
+            Ok(packages) => packages,
+            Err(e) => {
+                vlog_warn!("No package versions for node '{}': {}", node, e);
+                return None;
+            }
+        };
+
+        // proxmox-kernel-6.8.12-4-pve-signed, pve-kernel-5.15 (meta) etc.,
+        // all carry the kernel version except the helper package
+        let latest = packages.iter()
+            .filter(|p| p.package.starts_with("proxmox-kernel-") || p.package.starts_with("pve-kernel-"))
+            .filter(|p| !p.package.ends_with("-helper"))
+            .filter(|p| p.current_state.as_deref() == Some("Installed"))
+            .filter_map(|p| p.version.as_deref())
+            .max_by_key(|v| kernel_key(v))
+            .map(|v| format!("{}-pve", v));
+
+        let reboot_required = match (&running, &latest) {
+            (Some(running), Some(latest)) => kernel_key(latest) > kernel_key(running),
+            _ => false,
+        };
+        Some(NodeBootInfo { running_kernel: running, latest_kernel: latest, reboot_required, boot_mode, secure_boot })
+    }
+}
 
-                    let type_cell = match guest.guest_type() {
-                        "VM" => Cell::new("VM").fg(Color::Blue),
-                        "LXC" => Cell::new("LXC").fg(Color::Magenta),
-                        _ => Cell::new(guest.guest_type()),
-                    };
+/// Node summary shared by the single and multi-cluster JSON outputs
+fn node_json_info(node: &Node) -> NodeJsonInfo {
+    let cpu_cores = node.maxcpu.map(|c| c.to_string()).unwrap_or_else(|| "N/A".to_string());
+
+    let memory_gb = match (node.mem, node.maxmem) {
+        (Some(m), Some(mm)) => format!("{}/{}",
+            (m as f64 / 1024.0 / 1024.0 / 1024.0).ceil() as u64,
+            (mm as f64 / 1024.0 / 1024.0 / 1024.0).ceil() as u64),
+        _ => "N/A".to_string(),
+    };
+
+    let storage_gb = match (node.disk, node.maxdisk) {
+        (Some(d), Some(md)) => format!("{}/{}",
+            (d as f64 / 1024.0 / 1024.0 / 1024.0).ceil() as u64,
+            (md as f64 / 1024.0 / 1024.0 / 1024.0).ceil() as u64),
+        _ => "N/A".to_string(),
+    };
+
+    NodeJsonInfo {
+        name: node.node.clone(),
+        cpu: cpu_cores,
+        memory_gb,
+        storage_gb,
+        ipv4: node.ip.clone().unwrap_or_else(|| "N/A".to_string()),
+        status: node.status.clone(),
+        labels: Default::default(),
+    }
+}
 
-                    table.add_row(vec![
-                        Cell::new(&guest.vmid().to_string()),
-                        Cell::new(guest.name()),
-                        Cell::new(ip),
-                        type_cell,
-                        status_cell,
-                        Cell::new(&cpus),
-                        Cell::new(&ram_gb),
-                    ]);
-                }
+/// Version 2 of [`node_json_info`], the values as the API reports them
+fn node_json_info_v2(node: &Node) -> NodeJsonInfoV2 {
+    NodeJsonInfoV2 {
+        name: node.node.clone(),
+        status: node.status.clone(),
+        ipv4: node.ip.clone(),
+        cpu_cores: node.maxcpu,
+        cpu_usage: node.cpu,
+        memory_used_bytes: node.mem,
+        memory_total_bytes: node.maxmem,
+        storage_used_bytes: node.disk,
+        storage_total_bytes: node.maxdisk,
+        uptime_secs: node.uptime,
+        labels: Default::default(),
+    }
+}
 
-                println!("Node: {}", node);
-                println!("{}", table);
-            }
-            OutputFormat::Json => {
-                // JSON format: list of guests
-                use crate::models::GuestJsonInfo;
+/// Guest in the version 2 JSON output, `rate` is its network (in, out)
+/// rate when measured
+fn guest_json_info_v2(guest: &Guest, rate: Option<&(f64, f64)>) -> GuestJsonInfoV2 {
+    let (guest_type, ip, cpus, maxmem, maxdisk, uptime) = match guest {
+        Guest::VM(vm) => ("qemu", &vm.ip, vm.cpus, vm.maxmem, vm.maxdisk, vm.uptime),
+        Guest::LXC(lxc) => ("lxc", &lxc.ip, lxc.cpus, lxc.maxmem, lxc.maxdisk, lxc.uptime),
+    };
+    GuestJsonInfoV2 {
+        vmid: guest.vmid(),
+        name: guest.name().to_string(),
+        guest_type: guest_type.to_string(),
+        status: guest.status().to_string(),
+        ipv4: ip.clone(),
+        cpu_cores: cpus,
+        memory_total_bytes: maxmem,
+        storage_total_bytes: maxdisk,
+        uptime_secs: uptime,
+        netin_bps: rate.map(|r| r.0),
+        netout_bps: rate.map(|r| r.1),
+        labels: Default::default(),
+    }
+}
 
-                let guests_json: Vec<GuestJsonInfo> = guests.iter().map(|guest| {
-                    let ip = match guest {
-                        Guest::VM(vm) => vm.ip.clone().unwrap_or_else(|| "N/A".to_string()),
-                        Guest::LXC(lxc) => lxc.ip.clone().unwrap_or_else(|| "N/A".to_string()),
-                    };
+/// Sum cores, memory and disk of the nodes, offline nodes count only in
+/// `nodes_offline` since they report no usage
+fn node_totals(nodes: &[Node]) -> NodeTotals {
+    const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+    let online: Vec<&Node> = nodes.iter().filter(|n| n.status == "online").collect();
+
+    let cpu_cores: u32 = online.iter().filter_map(|n| n.maxcpu).sum();
+    let busy_cores: f64 = online.iter()
+        .filter_map(|n| Some(n.cpu? * n.maxcpu? as f64))
+        .sum();
+    let sum = |f: fn(&Node) -> Option<u64>| online.iter().filter_map(|n| f(n)).sum::<u64>();
+
+    NodeTotals {
+        nodes_online: online.len(),
+        nodes_offline: nodes.len() - online.len(),
+        cpu_cores,
+        cpu_percent: if cpu_cores > 0 { busy_cores * 100.0 / cpu_cores as f64 } else { 0.0 },
+        memory_used_gb: (sum(|n| n.mem) as f64 / GB).ceil() as u64,
+        memory_total_gb: (sum(|n| n.maxmem) as f64 / GB).ceil() as u64,
+        storage_used_gb: (sum(|n| n.disk) as f64 / GB).ceil() as u64,
+        storage_total_gb: (sum(|n| n.maxdisk) as f64 / GB).ceil() as u64,
+    }
+}
 
-                    let cpu_cores = match guest {
-                        Guest::VM(vm) => vm.cpus.map(|c| c.to_string()),
-                        Guest::LXC(lxc) => lxc.cpus.map(|c| c.to_string()),
-                    }.unwrap_or_else(|| "N/A".to_string());
+/// Bytes per second in the largest fitting unit, `1.2 MB/s`
+fn format_rate(bytes: f64) -> String {
+    const UNITS: [&str; 4] = ["B/s", "KB/s", "MB/s", "GB/s"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
 
-                    let memory_gb = match guest {
-                        Guest::VM(vm) => vm.maxmem.map(|m| format!("{:.1}", m as f64 / 1024.0 / 1024.0 / 1024.0)),
-                        Guest::LXC(lxc) => lxc.maxmem.map(|m| format!("{:.1}", m as f64 / 1024.0 / 1024.0 / 1024.0)),
-                    }.unwrap_or_else(|| "N/A".to_string());
+/// Cells of `WIDE_GUEST_COLUMNS` for a guest, dashes when the cluster
+/// resources do not list it
+fn wide_guest_cells(resource: Option<&ClusterResource>, time_format: Option<TimeFormat>) -> Vec<Cell> {
+    let field = |f: &dyn Fn(&ClusterResource) -> Option<String>| {
+        Cell::new(resource.and_then(f).filter(|v| !v.is_empty()).unwrap_or_else(|| "-".to_string()))
+    };
+    vec![
+        field(&|r| r.uptime.filter(|u| *u > 0).map(|u| timefmt::uptime(u, time_format))),
+        field(&|r| r.tags.as_ref().map(|t| t.replace(';', ", "))),
+        field(&|r| r.pool.clone()),
+        field(&|r| r.hastate.clone()),
+    ]
+}
 
-                    let storage_gb = match guest {
-                        Guest::VM(vm) => vm.maxdisk.map(|d| format!("{:.1}", d as f64 / 1024.0 / 1024.0 / 1024.0)),
-                        Guest::LXC(lxc) => lxc.maxdisk.map(|d| format!("{:.1}", d as f64 / 1024.0 / 1024.0 / 1024.0)),
-                    }.unwrap_or_else(|| "N/A".to_string());
+/// Numeric parts of a kernel version, "6.8.12-4-pve" is [6, 8, 12, 4]
+fn kernel_key(version: &str) -> Vec<u64> {
+    version.split(|c: char| !c.is_ascii_digit())
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
 
-                    GuestJsonInfo {
-                        name: guest.name().to_string(),
-                        guest_type: guest.guest_type().to_string(),
-                        cpu: cpu_cores,
-                        memory_gb,
-                        storage_gb,
-                        ipv4: ip,
-                        status: guest.status().to_string(),
-                    }
-                }).collect();
+/// Latest sample more than `ANOMALY_Z` standard deviations away from the
+/// mean of the samples before it. Nearly flat histories use
+/// `ANOMALY_MIN_STDDEV` instead, so an idle node going to 3% is no alarm.
+fn is_anomaly(values: &[f64]) -> bool {
+    let Some((latest, baseline)) = values.split_last() else {
+        return false;
+    };
+    if baseline.len() < 10 {
+        return false;
+    }
+    let mean = baseline.iter().sum::<f64>() / baseline.len() as f64;
+    let variance = baseline.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / baseline.len() as f64;
+    let stddev = variance.sqrt().max(ANOMALY_MIN_STDDEV);
+    ((latest - mean) / stddev).abs() > ANOMALY_Z
+}
 
-                let json_pretty = serde_json::to_string_pretty(&guests_json)?;
-                println!("{}", json_pretty);
-            }
-        }
+/// Render ratios in range 0.0..=1.0 as a unicode sparkline of at most
+/// `width` characters, averaging samples into buckets when there are more.
+/// Values are not rescaled, so a flat line at the bottom means an idle node.
+fn sparkline(values: &[f64], width: usize) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
 
-        vlog_success!("Listed {} guest(s) on node '{}'", guests.len(), node);
-        Ok(())
+    if values.is_empty() || width == 0 {
+        return "N/A".to_string();
     }
-}
\ No newline at end of file
+
+    let buckets = values.len().min(width);
+    (0..buckets)
+        .map(|i| {
+            let start = i * values.len() / buckets;
+            let end = ((i + 1) * values.len() / buckets).max(start + 1);
+            let slice = &values[start..end];
+            let avg = slice.iter().sum::<f64>() / slice.len() as f64;
+            let idx = (avg.clamp(0.0, 1.0) * (BARS.len() - 1) as f64).round() as usize;
+            BARS[idx]
+        })
+        .collect()
+}
@@ -0,0 +1,141 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # api.rs
+//!
+//! Raw API passthrough, `pvenom api <path>`, for what pvenom has no command
+//! for yet. Only GET requests are sent and the `data` of the response is
+//! printed as JSON.
+//!
+//! Paths are checked against the API schema the cluster ships for its API
+//! viewer (`/pve-docs/api-viewer/apidoc.js`), so a typo fails with a hint
+//! instead of a bare HTTP 501, and `--ls` lists the child paths of a path
+//! with their methods, like `pvesh ls`. Without the pve-docs package the
+//! path goes through unchecked.
+
+use anyhow::{bail, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use serde_json::Value;
+
+use super::Commands;
+use crate::models::{ApiChild, OutputFormat};
+use crate::{csv_row, pager, vlog_debug, vlog_success, vlog_warn};
+
+impl Commands {
+    pub async fn api(&self, path: &str, ls: bool) -> Result<()> {
+        let path = format!("/{}", path.trim_start_matches("/api2/json").trim_matches('/'));
+        // The schema is the list of top level entries, give it a root
+        let schema = match self.client.get_api_schema().await {
+            Ok(schema) => Some(serde_json::json!({ "path": "/", "children": schema })),
+            Err(e) => {
+                vlog_warn!("No API schema, the path is not checked: {}", e);
+                None
+            }
+        };
+        let entry = match &schema {
+            Some(schema) => Some(schema_entry(schema, &path)
+                .ok_or_else(|| anyhow::anyhow!("Unknown API path '{}', see `pvenom api {} --ls`", path, parent(&path)))?),
+            None => None,
+        };
+
+        if !ls {
+            if let Some(entry) = entry {
+                if entry["info"].is_object() && entry["info"]["GET"].is_null() {
+                    bail!("'{}' has no GET method, it accepts {}", path, methods(entry).join(", "));
+                }
+            }
+            let response = self.client.get_raw_json(&format!("/api2/json{}", path)).await?;
+            println!("{}", serde_json::to_string_pretty(&response["data"])?);
+            return Ok(());
+        }
+
+        let Some(entry) = entry else {
+            bail!("Listing child paths needs the API schema");
+        };
+        let children: Vec<ApiChild> = entry["children"].as_array().into_iter().flatten()
+            .map(|child| {
+                let name = child["path"].as_str().unwrap_or_default().rsplit('/').next().unwrap_or_default();
+                ApiChild {
+                    path: format!("{}/{}", path.trim_end_matches('/'), name),
+                    methods: methods(child),
+                    description: child["info"]["GET"]["description"].as_str()
+                        .or_else(|| child["info"].as_object().and_then(|i| i.values().next()).and_then(|m| m["description"].as_str()))
+                        .and_then(|d| d.lines().next())
+                        .map(str::to_string),
+                }
+            })
+            .collect();
+        vlog_debug!("{} child path(s) under '{}'", children.len(), path);
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&children)?),
+            OutputFormat::Csv => {
+                csv_row!("PATH", "METHODS", "DESCRIPTION");
+                for child in &children {
+                    csv_row!(child.path, child.methods.join(" "), child.description.as_deref().unwrap_or(""));
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Path").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Methods").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Description").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for child in &children {
+                    table.add_row(vec![
+                        Cell::new(&child.path),
+                        Cell::new(child.methods.join(" ")),
+                        Cell::new(child.description.as_deref().unwrap_or("")),
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        vlog_success!("Listed {} path(s) under '{}'", children.len(), path);
+        Ok(())
+    }
+}
+
+/// Schema entry of a concrete path, walking down from `root`. Literal
+/// segments win over `{param}` ones so `/nodes/localhost` is not mistaken
+/// for another node.
+fn schema_entry<'a>(root: &'a Value, path: &str) -> Option<&'a Value> {
+    let name = |child: &Value| child["path"].as_str().unwrap_or_default().rsplit('/').next().unwrap_or_default().to_string();
+    let mut entry = root;
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        let children = entry["children"].as_array()?;
+        entry = children.iter().find(|c| name(c) == segment)
+            .or_else(|| children.iter().find(|c| name(c).starts_with('{')))?;
+    }
+    Some(entry)
+}
+
+fn methods(entry: &Value) -> Vec<String> {
+    entry["info"].as_object().map(|i| i.keys().cloned().collect()).unwrap_or_default()
+}
+
+fn parent(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(0) | None => "/",
+        Some(i) => &path[..i],
+    }
+}
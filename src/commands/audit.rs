@@ -0,0 +1,441 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # audit.rs
+//!
+//! Configuration audits of the guests, `pvenom audit <what>`, each a table
+//! of the guests concerned and what is wrong with them.
+//!
+//! `audit hookscripts` lists the `hookscript` of every guest and whether
+//! its snippet volume exists on the guest's node. A missing one makes
+//! starts, backups and migrations fail, so the command fails too. Snippets
+//! on storage that is not shared must also exist on every node the guest
+//! may migrate to, they are marked as local.
+//!
+//! `audit firmware` lists the firmware of every VM: OVMF with its EFI vars
+//! disk, secure boot (Microsoft keys pre-enrolled in the EFI disk) and the
+//! TPM state device, for Windows 11 and measured boot rollouts. A VM is
+//! Windows 11 ready with OVMF, secure boot and a v2.0 TPM; Windows 11
+//! guests lacking any of them are flagged.
+//!
+//! `audit machine-types` lists the QEMU machine of every VM. Unpinned
+//! machines (`q35`, or none for i440fx) follow the newest version at each
+//! cold start; pinned ones (`pc-q35-6.2`) stay on theirs and miss the
+//! devices and fixes of newer QEMU. A pin older than the newest major
+//! version the node offers is outdated, with the way up: a newer pin for
+//! Windows, which may see new hardware when its machine changes, the
+//! unversioned type for the others.
+//!
+//! `audit spice-usb` lists the VMs with SPICE displays, audio or USB
+//! redirection, which need a SPICE client, and host USB devices, which
+//! prevent live migration. The guest detail view shows the same devices.
+
+use anyhow::{bail, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use std::collections::{BTreeMap, HashMap};
+
+use super::vm::client_devices;
+use super::Commands;
+use crate::models::{ClientDevicesInfo, FirmwareInfo, HookscriptInfo, MachineTypeInfo, OutputFormat};
+use crate::{csv_row, pager, vlog_debug, vlog_info, vlog_success, vlog_warn};
+
+impl Commands {
+    pub async fn audit_hookscripts(&self) -> Result<()> {
+        vlog_info!("Collecting guest hookscripts...");
+        let shared: HashMap<String, bool> = self.client.get_cluster_resources(Some("storage")).await?
+            .into_iter()
+            .filter_map(|s| Some((s.storage?, s.shared == Some(1))))
+            .collect();
+
+        // Snippets of each storage seen from each node, listed once
+        let mut snippets: HashMap<(String, String), std::result::Result<Vec<String>, String>> = HashMap::new();
+        let mut hooks = Vec::new();
+        for (guest, config) in self.guests_with_config().await? {
+            let (Some(node), Some(vmid)) = (guest.node.clone(), guest.vmid) else { continue };
+            let Some(hookscript) = config.get("hookscript").and_then(|v| v.as_str()) else { continue };
+            let (storage, exists, error) = match hookscript.split_once(':') {
+                Some((storage, _)) => {
+                    let listing = match snippets.get(&(node.clone(), storage.to_string())) {
+                        Some(listing) => listing.clone(),
+                        None => {
+                            let listing = self.client.get_storage_content(&node, storage, Some("snippets"), None).await
+                                .map(|volumes| volumes.into_iter().map(|v| v.volid).collect::<Vec<_>>())
+                                .map_err(|e| e.to_string());
+                            snippets.insert((node.clone(), storage.to_string()), listing.clone());
+                            listing
+                        }
+                    };
+                    match listing {
+                        Ok(volids) => (Some(storage.to_string()), Some(volids.iter().any(|v| v == hookscript)), None),
+                        Err(e) => {
+                            vlog_debug!("No snippets of '{}' on '{}': {}", storage, node, e);
+                            (Some(storage.to_string()), Some(false), Some(format!("storage {} not readable on {}", storage, node)))
+                        }
+                    }
+                }
+                None => (None, None, Some("not a storage volume".to_string())),
+            };
+            hooks.push(HookscriptInfo {
+                vmid,
+                name: guest.name.clone().unwrap_or_default(),
+                guest_type: guest.resource_type.clone(),
+                node,
+                hookscript: hookscript.to_string(),
+                shared: storage.and_then(|s| shared.get(&s).copied()),
+                exists,
+                error,
+            });
+        }
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&hooks)?),
+            OutputFormat::Csv => {
+                csv_row!("VMID", "NAME", "TYPE", "NODE", "HOOKSCRIPT", "SHARED", "EXISTS", "ERROR");
+                for h in &hooks {
+                    csv_row!(h.vmid, h.name, h.guest_type, h.node, h.hookscript,
+                             h.shared.map(|s| s.to_string()).unwrap_or_default(),
+                             h.exists.map(|e| e.to_string()).unwrap_or_default(), h.error.as_deref().unwrap_or(""));
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Type").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Hookscript").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("State").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for h in &hooks {
+                    let state = match (h.exists, h.shared, &h.error) {
+                        (_, _, Some(error)) => Cell::new(error).fg(Color::Red),
+                        (Some(false), _, _) => Cell::new("missing").fg(Color::Red),
+                        (_, Some(false), _) => Cell::new("ok, local storage").fg(Color::Yellow),
+                        _ => Cell::new("ok").fg(Color::Green),
+                    };
+                    table.add_row(vec![
+                        Cell::new(h.vmid),
+                        Cell::new(&h.name),
+                        Cell::new(&h.guest_type),
+                        Cell::new(&h.node),
+                        Cell::new(&h.hookscript),
+                        state,
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        let broken = hooks.iter().filter(|h| h.exists != Some(true)).count();
+        if broken > 0 {
+            bail!("{} of {} hookscript(s) missing or unreadable", broken, hooks.len());
+        }
+        vlog_success!("{} hookscript(s), all present", hooks.len());
+        Ok(())
+    }
+
+    pub async fn audit_firmware(&self) -> Result<()> {
+        vlog_info!("Collecting VM firmware settings...");
+        let mut vms = Vec::new();
+        for (guest, config) in self.guests_with_config().await? {
+            let (Some(node), Some(vmid)) = (guest.node.clone(), guest.vmid) else { continue };
+            if guest.resource_type != "qemu" {
+                continue;
+            }
+            let text = |key: &str| config.get(key).and_then(|v| v.as_str()).map(str::to_string);
+            let ovmf = text("bios").as_deref() == Some("ovmf");
+            let efidisk = text("efidisk0");
+            let option = |value: &Option<String>, key: &str| value.as_deref()
+                .and_then(|v| v.split(',').find_map(|kv| kv.strip_prefix(key)?.strip_prefix('=').map(str::to_string)));
+            let tpmstate = text("tpmstate0");
+            let secure_boot = ovmf && option(&efidisk, "pre-enrolled-keys").as_deref() == Some("1");
+            let tpm = tpmstate.as_ref().map(|_| option(&tpmstate, "version").unwrap_or_else(|| "v1.2".to_string()));
+            vms.push(FirmwareInfo {
+                vmid,
+                name: guest.name.clone().unwrap_or_default(),
+                node,
+                ostype: text("ostype"),
+                firmware: if ovmf { "ovmf" } else { "seabios" }.to_string(),
+                efi_disk: efidisk.as_deref().map(|v| v.split(',').next().unwrap_or_default().to_string()),
+                efitype: option(&efidisk, "efitype"),
+                secure_boot,
+                win11_ready: ovmf && secure_boot && tpm.as_deref() == Some("v2.0"),
+                tpm,
+            });
+        }
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&vms)?),
+            OutputFormat::Csv => {
+                csv_row!("VMID", "NAME", "NODE", "OSTYPE", "FIRMWARE", "EFI_DISK", "EFITYPE", "SECURE_BOOT", "TPM",
+                         "WIN11_READY");
+                for v in &vms {
+                    csv_row!(v.vmid, v.name, v.node, v.ostype.as_deref().unwrap_or(""), v.firmware,
+                             v.efi_disk.as_deref().unwrap_or(""), v.efitype.as_deref().unwrap_or(""), v.secure_boot,
+                             v.tpm.as_deref().unwrap_or(""), v.win11_ready);
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("OS type").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Firmware").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("EFI disk").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Secure boot").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("TPM").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Win 11").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for v in &vms {
+                    let ready = match (v.win11_ready, v.ostype.as_deref() == Some("win11")) {
+                        (true, _) => Cell::new("ready").fg(Color::Green),
+                        (false, true) => Cell::new("NOT READY").fg(Color::Red),
+                        (false, false) => Cell::new("no"),
+                    };
+                    table.add_row(vec![
+                        Cell::new(v.vmid),
+                        Cell::new(&v.name),
+                        Cell::new(v.ostype.as_deref().unwrap_or("-")),
+                        Cell::new(&v.firmware),
+                        Cell::new(match (&v.efi_disk, &v.efitype) {
+                            (Some(disk), Some(efitype)) => format!("{} ({})", disk, efitype),
+                            (Some(disk), None) => disk.clone(),
+                            (None, _) => "-".to_string(),
+                        }),
+                        Cell::new(if v.secure_boot { "yes" } else { "no" }),
+                        Cell::new(v.tpm.as_deref().unwrap_or("-")),
+                        ready,
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        for v in vms.iter().filter(|v| v.ostype.as_deref() == Some("win11") && !v.win11_ready) {
+            vlog_warn!("VM {} runs Windows 11 without OVMF, secure boot and a v2.0 TPM", v.vmid);
+        }
+        vlog_info!("{} of {} VM(s) with OVMF, {} with secure boot, {} with a TPM",
+                   vms.iter().filter(|v| v.firmware == "ovmf").count(), vms.len(),
+                   vms.iter().filter(|v| v.secure_boot).count(),
+                   vms.iter().filter(|v| v.tpm.is_some()).count());
+        Ok(())
+    }
+
+    pub async fn audit_machine_types(&self) -> Result<()> {
+        vlog_info!("Collecting VM machine types...");
+        let guests = self.guests_with_config().await?;
+        // Newest version of each machine type by node
+        let mut latest: BTreeMap<String, HashMap<String, String>> = BTreeMap::new();
+        for node in guests.iter().filter_map(|(g, _)| g.node.clone()) {
+            if latest.contains_key(&node) {
+                continue;
+            }
+            let mut newest: HashMap<String, String> = HashMap::new();
+            match self.client.get_qemu_machines(&node).await {
+                Ok(machines) => {
+                    for machine in &machines {
+                        let (machine_type, Some(version)) = parse_machine(machine) else { continue };
+                        let current = newest.entry(machine_type).or_insert_with(|| version.clone());
+                        if version_key(&version) > version_key(current) {
+                            *current = version;
+                        }
+                    }
+                }
+                Err(e) => vlog_warn!("No machine types of node '{}': {}", node, e),
+            }
+            latest.insert(node, newest);
+        }
+
+        let mut vms = Vec::new();
+        for (guest, config) in &guests {
+            let (Some(node), Some(vmid)) = (guest.node.clone(), guest.vmid) else { continue };
+            if guest.resource_type != "qemu" {
+                continue;
+            }
+            let machine = config.get("machine").and_then(|v| v.as_str()).map(|m| {
+                let base = m.split(',').next().unwrap_or_default();
+                base.strip_prefix("type=").unwrap_or(base).to_string()
+            });
+            let (machine_type, pinned) = parse_machine(machine.as_deref().unwrap_or("i440fx"));
+            let newest = latest.get(&node).and_then(|l| l.get(&machine_type)).cloned();
+            let ostype = config.get("ostype").and_then(|v| v.as_str()).map(str::to_string);
+            let outdated = match (&pinned, &newest) {
+                (Some(pinned), Some(newest)) => version_key(pinned).0 < version_key(newest).0,
+                _ => false,
+            };
+            let suggestion = outdated.then(|| match ostype.as_deref() {
+                Some(os) if os.starts_with('w') => format!("pin pc-{}-{}, snapshot first, Windows may see new devices",
+                                                           machine_type, newest.as_deref().unwrap_or_default()),
+                _ => format!("unpin to {}, the newest version at each cold start", machine_type),
+            });
+            vms.push(MachineTypeInfo {
+                vmid,
+                name: guest.name.clone().unwrap_or_default(),
+                node,
+                ostype,
+                machine: machine.unwrap_or_else(|| "default".to_string()),
+                machine_type,
+                pinned_version: pinned,
+                latest_version: newest,
+                outdated,
+                suggestion,
+            });
+        }
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&vms)?),
+            OutputFormat::Csv => {
+                csv_row!("VMID", "NAME", "NODE", "OSTYPE", "MACHINE", "TYPE", "PINNED", "LATEST", "OUTDATED",
+                         "SUGGESTION");
+                for v in &vms {
+                    csv_row!(v.vmid, v.name, v.node, v.ostype.as_deref().unwrap_or(""), v.machine, v.machine_type,
+                             v.pinned_version.as_deref().unwrap_or(""), v.latest_version.as_deref().unwrap_or(""),
+                             v.outdated, v.suggestion.as_deref().unwrap_or(""));
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Machine").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Newest").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Suggestion").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for v in &vms {
+                    let machine = match (&v.pinned_version, v.outdated) {
+                        (Some(_), true) => Cell::new(&v.machine).fg(Color::Red),
+                        (Some(_), false) => Cell::new(&v.machine),
+                        (None, _) => Cell::new(format!("{} (unpinned)", v.machine)).fg(Color::Green),
+                    };
+                    table.add_row(vec![
+                        Cell::new(v.vmid),
+                        Cell::new(&v.name),
+                        Cell::new(&v.node),
+                        machine,
+                        Cell::new(v.latest_version.as_deref().unwrap_or("-")),
+                        Cell::new(v.suggestion.as_deref().unwrap_or("")),
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        let outdated = vms.iter().filter(|v| v.outdated).count();
+        if outdated > 0 {
+            vlog_warn!("{} of {} VM(s) pinned to an outdated machine version", outdated, vms.len());
+        } else {
+            vlog_success!("No VM pinned to an outdated machine version");
+        }
+        Ok(())
+    }
+
+    pub async fn audit_spice_usb(&self) -> Result<()> {
+        vlog_info!("Collecting SPICE and USB devices...");
+        let mut vms = Vec::new();
+        for (guest, config) in self.guests_with_config().await? {
+            let (Some(node), Some(vmid)) = (guest.node.clone(), guest.vmid) else { continue };
+            let devices = client_devices(&config);
+            if guest.resource_type != "qemu" || devices.is_empty() {
+                continue;
+            }
+            vms.push(ClientDevicesInfo {
+                vmid,
+                name: guest.name.clone().unwrap_or_default(),
+                node,
+                status: guest.status.clone().unwrap_or_default(),
+                devices,
+            });
+        }
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&vms)?),
+            OutputFormat::Csv => {
+                csv_row!("VMID", "NAME", "NODE", "STATUS", "KEY", "KIND", "DETAIL", "BLOCKS_MIGRATION");
+                for v in &vms {
+                    for d in &v.devices {
+                        csv_row!(v.vmid, v.name, v.node, v.status, d.key, d.kind, d.detail, d.blocks_migration);
+                    }
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Devices").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Live migration").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for v in &vms {
+                    let devices: Vec<String> = v.devices.iter().map(|d| format!("{}: {}", d.key, d.detail)).collect();
+                    table.add_row(vec![
+                        Cell::new(v.vmid),
+                        Cell::new(&v.name),
+                        Cell::new(&v.node),
+                        Cell::new(devices.join("\n")),
+                        if v.devices.iter().any(|d| d.blocks_migration) {
+                            Cell::new("blocked").fg(Color::Red)
+                        } else {
+                            Cell::new("ok").fg(Color::Green)
+                        },
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        vlog_info!("{} VM(s) with SPICE or USB devices, {} pinned to their node by host USB",
+                   vms.len(), vms.iter().filter(|v| v.devices.iter().any(|d| d.blocks_migration)).count());
+        Ok(())
+    }
+}
+
+/// Machine type and pinned version of a `machine` value: `pc-q35-8.1+pve0`
+/// is q35 8.1+pve0, `q35` unpinned q35, `pc` and `pc-6.2` are i440fx
+fn parse_machine(machine: &str) -> (String, Option<String>) {
+    if let Some(version) = machine.strip_prefix("pc-q35-") {
+        return ("q35".to_string(), Some(version.to_string()));
+    }
+    if let Some(version) = machine.strip_prefix("pc-i440fx-") {
+        return ("i440fx".to_string(), Some(version.to_string()));
+    }
+    match machine {
+        "pc" | "i440fx" => ("i440fx".to_string(), None),
+        m if m.starts_with("pc-") && m[3..].starts_with(|c: char| c.is_ascii_digit()) => ("i440fx".to_string(), Some(m[3..].to_string())),
+        m => (m.to_string(), None),
+    }
+}
+
+/// Major and minor of a machine version, `8.1+pve0` is (8, 1)
+fn version_key(version: &str) -> (u32, u32) {
+    let numbers = version.split('+').next().unwrap_or_default();
+    let (major, minor) = numbers.split_once('.').unwrap_or((numbers, "0"));
+    (major.parse().unwrap_or(0), minor.parse().unwrap_or(0))
+}
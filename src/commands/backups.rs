@@ -0,0 +1,605 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # backups.rs
+//!
+//! Backup housekeeping, `pvenom backups ...`.
+//!
+//! `backups growth` sums the backups of every guest and compares its
+//! latest backup with the newest one taken at least a week earlier,
+//! biggest growth first, to spot guests whose backups are ballooning.
+//!
+//! `backups prune-preview --storage nas --vmid 100 --keep-last 3` shows
+//! which backups a retention policy would keep and remove, using the same
+//! endpoint as the storage prune job so the answer is Proxmox's own.
+//! Without `--keep-*` options the retention configured on the storage is
+//! used. Nothing is removed until `--apply` is given.
+//!
+//! `backups verify <volid>` or `backups verify --all` starts verification
+//! tasks on the Proxmox Backup Server behind PBS storages (see pbs.rs) and
+//! reports, per storage, how many backups passed, failed or were never
+//! verified. `--all` starts one task per datastore or namespace rather than
+//! one per snapshot. The counts are the state before the new tasks, which
+//! run on the PBS in the background.
+//!
+//! `backups config` shows the backup jobs of the cluster next to the
+//! vzdump defaults of every node (bwlimit, tmpdir, compress...), with
+//! defaults differing between nodes highlighted. Its JSON output has
+//! sorted keys, to diff two clusters or two points in time.
+//!
+//! Local storages exist once per node, each node's copy is pruned on its
+//! own; shared storages are asked once.
+
+use anyhow::{bail, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use super::jobs::param_value;
+use super::Commands;
+use crate::models::{BackupConfigOutput, BackupGrowth, BackupVerifyOutput, NodeVzdumpDefaults, OutputFormat, PruneEntry, PrunePreviewOutput, StorageContent};
+use crate::pbs::{self, PbsClient};
+use crate::{csv, csv_row, pager, vlog_debug, vlog_info, vlog_success, vlog_warn};
+
+/// Settings of `pvenom backups prune-preview`
+pub struct PruneOptions {
+    pub storage: String,
+    pub vmid: Option<u32>,
+    /// Retention options such as ("keep-last", 3)
+    pub keep: Vec<(&'static str, u32)>,
+    pub apply: bool,
+}
+
+/// Settings of `pvenom backups verify`
+pub struct VerifyOptions {
+    /// One backup volume, None for all of them
+    pub volid: Option<String>,
+    pub storage: Option<String>,
+    pub pbs_password: Option<String>,
+    /// Verify the PBS certificate
+    pub secure: bool,
+}
+
+/// Seconds in a week, the comparison window of `backups growth`
+const WEEK: u64 = 604_800;
+
+/// Growth above this percentage is highlighted
+const GROWTH_WARN_PERCENT: f64 = 20.0;
+
+/// Backup job keys computed by PVE, they change without a config change
+const VOLATILE_JOB_KEYS: &[&str] = &["next-run"];
+
+/// Backup job keys with their own column in `backups config`
+const JOB_COLUMN_KEYS: &[&str] = &[
+    "id", "type", "schedule", "enabled", "node", "all", "exclude", "pool", "vmid", "storage", "mode",
+];
+
+impl Commands {
+    pub async fn backup_growth(&self, storage: Option<&str>) -> Result<()> {
+        let backups: Vec<StorageContent> = self.storage_volumes("backup", storage).await?
+            .into_iter()
+            .map(|(_, volume)| volume)
+            .collect();
+        let names: HashMap<u32, String> = self.client.get_cluster_resources(Some("vm")).await?
+            .into_iter()
+            .filter_map(|r| Some((r.vmid?, r.name?)))
+            .collect();
+
+        let mut by_guest: BTreeMap<u32, Vec<&StorageContent>> = BTreeMap::new();
+        for backup in &backups {
+            if let (Some(vmid), Some(_)) = (backup.vmid, backup.ctime) {
+                by_guest.entry(vmid).or_default().push(backup);
+            }
+        }
+
+        let gb = |bytes: u64| (bytes as f64 / 1024.0 / 1024.0 / 1024.0 * 100.0).round() / 100.0;
+        let mut rows: Vec<BackupGrowth> = by_guest.into_iter()
+            .filter_map(|(vmid, mut chain)| {
+                chain.sort_by_key(|b| b.ctime);
+                let latest = *chain.last()?;
+                let latest_time = latest.ctime.unwrap_or_default();
+                let week_ago = chain.iter().rev()
+                    .find(|b| b.ctime.unwrap_or_default() + WEEK <= latest_time)
+                    .and_then(|b| b.size);
+                let latest_size = latest.size.unwrap_or(0);
+                Some(BackupGrowth {
+                    vmid,
+                    name: names.get(&vmid).cloned().unwrap_or_else(|| "N/A".to_string()),
+                    backups: chain.len(),
+                    chain_gb: gb(chain.iter().filter_map(|b| b.size).sum()),
+                    latest_gb: gb(latest_size),
+                    week_ago_gb: week_ago.map(gb),
+                    growth_percent: week_ago.filter(|w| *w > 0)
+                        .map(|w| ((latest_size as f64 - w as f64) / w as f64 * 1000.0).round() / 10.0),
+                    latest: latest_time,
+                })
+            })
+            .collect();
+        rows.sort_by(|a, b| {
+            b.growth_percent.unwrap_or(f64::MIN).total_cmp(&a.growth_percent.unwrap_or(f64::MIN))
+                .then(a.vmid.cmp(&b.vmid))
+        });
+
+        let percent = |p: Option<f64>| p.map(|p| format!("{:+.1}%", p)).unwrap_or_else(|| "N/A".to_string());
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+            OutputFormat::Csv => {
+                csv_row!("VMID", "NAME", "BACKUPS", "CHAIN_GB", "LATEST_GB", "WEEK_AGO_GB", "GROWTH_PERCENT", "LATEST");
+                for r in &rows {
+                    csv_row!(r.vmid, r.name, r.backups, csv::num(format!("{:.2}", r.chain_gb)),
+                             csv::num(format!("{:.2}", r.latest_gb)),
+                             csv::num(r.week_ago_gb.map(|w| format!("{:.2}", w)).unwrap_or_default()),
+                             csv::num(r.growth_percent.map(|p| format!("{:.1}", p)).unwrap_or_default()),
+                             self.timestamp(r.latest));
+                }
+            }
+            OutputFormat::Table => {
+                if rows.is_empty() {
+                    println!("No backups found.");
+                    return Ok(());
+                }
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Backups").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Chain (GB)").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Latest (GB)").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Week Ago (GB)").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Growth").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Latest Backup").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for r in &rows {
+                    let growth = match r.growth_percent {
+                        Some(p) if p > GROWTH_WARN_PERCENT => Cell::new(percent(Some(p))).fg(Color::Red),
+                        other => Cell::new(percent(other)),
+                    };
+                    table.add_row(vec![
+                        Cell::new(r.vmid),
+                        Cell::new(&r.name),
+                        Cell::new(r.backups),
+                        Cell::new(format!("{:.2}", r.chain_gb)),
+                        Cell::new(format!("{:.2}", r.latest_gb)),
+                        Cell::new(r.week_ago_gb.map(|w| format!("{:.2}", w)).unwrap_or_else(|| "N/A".to_string())),
+                        growth,
+                        Cell::new(self.timestamp(r.latest)),
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        vlog_success!("Backup growth of {} guest(s) displayed", rows.len());
+        Ok(())
+    }
+
+    /// Backup jobs and the vzdump defaults of every online node
+    pub async fn backup_config(&self) -> Result<()> {
+        let mut jobs: Vec<BTreeMap<String, Value>> = self.client.get_backup_job_configs().await?
+            .into_iter()
+            .map(|job| job.into_iter().filter(|(key, _)| !VOLATILE_JOB_KEYS.contains(&key.as_str())).collect())
+            .collect();
+        jobs.sort_by_key(|job| job.get("id").map(param_value));
+
+        let mut nodes = Vec::new();
+        for node in self.client.get_nodes().await? {
+            if node.status != "online" {
+                vlog_warn!("Node '{}' is {}, its vzdump defaults are not shown", node.node, node.status);
+                continue;
+            }
+            match self.client.get_vzdump_defaults(&node.node).await {
+                Ok(defaults) => nodes.push(NodeVzdumpDefaults { node: node.node, defaults: defaults.into_iter().collect() }),
+                Err(e) => vlog_warn!("No vzdump defaults of node '{}': {}", node.node, e),
+            }
+        }
+        nodes.sort_by(|a, b| a.node.cmp(&b.node));
+        let output = BackupConfigOutput { jobs, nodes };
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&output)?),
+            OutputFormat::Csv => {
+                csv_row!("SCOPE", "NAME", "OPTION", "VALUE");
+                for job in &output.jobs {
+                    let id = job.get("id").map(param_value).unwrap_or_default();
+                    for (key, value) in job.iter().filter(|(key, _)| *key != "id") {
+                        csv_row!("job", id, key, param_value(value));
+                    }
+                }
+                for node in &output.nodes {
+                    for (key, value) in &node.defaults {
+                        csv_row!("node", node.node, key, param_value(value));
+                    }
+                }
+            }
+            OutputFormat::Table => {
+                println!("\n=== Backup jobs ===\n");
+                if output.jobs.is_empty() {
+                    println!("No backup jobs.");
+                } else {
+                    let mut table = Table::new();
+                    table.load_preset(UTF8_FULL)
+                         .set_content_arrangement(ContentArrangement::Dynamic);
+                    table.set_header(vec![
+                        Cell::new("ID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Schedule").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Enabled").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Guests").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Storage").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Mode").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Options").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    ]);
+                    for job in &output.jobs {
+                        let text = |key: &str| job.get(key).map(param_value);
+                        let guests = match (text("all").as_deref(), text("pool"), text("vmid")) {
+                            (Some("1"), _, _) => match text("exclude") {
+                                Some(exclude) => format!("all except {}", exclude),
+                                None => "all".to_string(),
+                            },
+                            (_, Some(pool), _) => format!("pool {}", pool),
+                            (_, _, Some(vmid)) => vmid,
+                            _ => "-".to_string(),
+                        };
+                        let enabled = text("enabled").is_none_or(|e| e != "0");
+                        let options: Vec<String> = job.iter()
+                            .filter(|(key, _)| !JOB_COLUMN_KEYS.contains(&key.as_str()))
+                            .map(|(key, value)| format!("{}={}", key, param_value(value)))
+                            .collect();
+                        table.add_row(vec![
+                            Cell::new(text("id").unwrap_or_default()),
+                            Cell::new(text("schedule").unwrap_or_else(|| "-".to_string())),
+                            if enabled { Cell::new("yes").fg(Color::Green) } else { Cell::new("no").fg(Color::Yellow) },
+                            Cell::new(text("node").unwrap_or_else(|| "all".to_string())),
+                            Cell::new(guests),
+                            Cell::new(text("storage").unwrap_or_else(|| "-".to_string())),
+                            Cell::new(text("mode").unwrap_or_else(|| "-".to_string())),
+                            Cell::new(options.join("\n")),
+                        ]);
+                    }
+                    pager::print_table(&mut table);
+                }
+
+                // One column per node, options set differently across nodes in yellow
+                println!("\n=== vzdump defaults ===\n");
+                let keys: BTreeSet<&String> = output.nodes.iter().flat_map(|n| n.defaults.keys()).collect();
+                if keys.is_empty() {
+                    println!("No vzdump defaults set.");
+                } else {
+                    let mut table = Table::new();
+                    table.load_preset(UTF8_FULL)
+                         .set_content_arrangement(ContentArrangement::Dynamic);
+                    let mut header = vec![Cell::new("Option").add_attribute(Attribute::Bold).fg(Color::Cyan)];
+                    header.extend(output.nodes.iter().map(|n| Cell::new(&n.node).add_attribute(Attribute::Bold).fg(Color::Cyan)));
+                    table.set_header(header);
+                    for key in keys {
+                        let values: Vec<Option<String>> = output.nodes.iter()
+                            .map(|n| n.defaults.get(key).map(param_value))
+                            .collect();
+                        let differs = values.iter().any(|v| *v != values[0]);
+                        let mut row = vec![Cell::new(key)];
+                        row.extend(values.into_iter().map(|value| {
+                            let cell = Cell::new(value.unwrap_or_else(|| "-".to_string()));
+                            if differs { cell.fg(Color::Yellow) } else { cell }
+                        }));
+                        table.add_row(row);
+                    }
+                    pager::print_table(&mut table);
+                }
+            }
+        }
+
+        vlog_success!("Listed {} backup job(s) and the vzdump defaults of {} node(s)", output.jobs.len(), output.nodes.len());
+        Ok(())
+    }
+
+    pub async fn verify_backups(&self, options: &VerifyOptions) -> Result<()> {
+        let storage = options.storage.as_deref()
+            .or_else(|| options.volid.as_deref().and_then(|v| v.split_once(':')).map(|(s, _)| s));
+
+        // PBS storages are shared, any node with it online can list it
+        let mut storages: BTreeMap<String, String> = BTreeMap::new();
+        for entry in self.client.get_cluster_resources(Some("storage")).await? {
+            let (Some(name), Some(node)) = (entry.storage, entry.node) else {
+                continue;
+            };
+            if entry.plugintype.as_deref() == Some("pbs") && entry.status.as_deref() == Some("available")
+                && storage.is_none_or(|s| s == name) {
+                storages.entry(name).or_insert(node);
+            }
+        }
+        if storages.is_empty() {
+            match storage {
+                Some(storage) => bail!("Storage '{}' is not an available PBS storage", storage),
+                None => bail!("No available PBS storage in the cluster"),
+            }
+        }
+
+        let mut rows = Vec::new();
+        for (name, node) in &storages {
+            let backups = self.client.get_storage_content(node, name, Some("backup"), None).await?;
+            let targets: Vec<&StorageContent> = backups.iter()
+                .filter(|b| options.volid.as_ref().is_none_or(|v| *v == b.volid))
+                .collect();
+            if let Some(volid) = options.volid.as_deref().filter(|_| targets.is_empty()) {
+                bail!("Backup '{}' not found on storage '{}'", volid, name);
+            }
+
+            let state = |s: &str| targets.iter()
+                .filter(|b| b.verification.as_ref().is_some_and(|v| v.state == s))
+                .count();
+            let started = self.start_verification(name, &targets, options).await?;
+            rows.push(BackupVerifyOutput {
+                storage: name.clone(),
+                backups: targets.len(),
+                verified_ok: state("ok"),
+                verify_failed: state("failed"),
+                never_verified: targets.iter().filter(|b| b.verification.is_none()).count(),
+                started,
+            });
+        }
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+            OutputFormat::Csv => {
+                csv_row!("STORAGE", "BACKUPS", "VERIFIED_OK", "VERIFY_FAILED", "NEVER_VERIFIED", "STARTED");
+                for r in &rows {
+                    csv_row!(r.storage, r.backups, r.verified_ok, r.verify_failed, r.never_verified, r.started.len());
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Storage").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Backups").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Verified OK").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Failed").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Never Verified").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Started").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for r in &rows {
+                    let failed = match r.verify_failed {
+                        0 => Cell::new(0).fg(Color::Green),
+                        n => Cell::new(n).fg(Color::Red),
+                    };
+                    let never = match r.never_verified {
+                        0 => Cell::new(0),
+                        n => Cell::new(n).fg(Color::Yellow),
+                    };
+                    table.add_row(vec![
+                        Cell::new(&r.storage),
+                        Cell::new(r.backups),
+                        Cell::new(r.verified_ok),
+                        failed,
+                        never,
+                        Cell::new(r.started.len()),
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        let started: usize = rows.iter().map(|r| r.started.len()).sum();
+        vlog_success!("Started {} verification task(s) on {} PBS storage(s)", started, rows.len());
+        Ok(())
+    }
+
+    /// Ask the PBS behind `storage` to verify the given backups, returns the
+    /// UPIDs of the started tasks
+    async fn start_verification(&self, storage: &str, backups: &[&StorageContent], options: &VerifyOptions) -> Result<Vec<String>> {
+        if backups.is_empty() {
+            return Ok(Vec::new());
+        }
+        if self.client.read_only() {
+            bail!("Read-only mode, refusing to start verification on storage '{}'", storage);
+        }
+
+        let config = self.client.get_storage_config(storage).await?;
+        let field = |key: &str| config.get(key).and_then(|v| v.as_str());
+        let (Some(server), Some(datastore), Some(username)) = (field("server"), field("datastore"), field("username")) else {
+            bail!("Storage '{}' has no PBS server, datastore or username configured", storage);
+        };
+        let port = config.get("port").and_then(|v| v.as_u64()).map_or(pbs::DEFAULT_PORT, |p| p as u16);
+        let namespace = field("namespace");
+
+        let pbs = if self.client.dry_run() {
+            None
+        } else {
+            let Some(password) = options.pbs_password.as_deref() else {
+                bail!("Starting verification needs the password of '{}' on {}: --pbs-password or PVENOM_PBS_PASSWORD",
+                      username, server);
+            };
+            Some(PbsClient::new(server, port, username, password, options.secure).await?)
+        };
+
+        // One task walks the whole datastore or namespace, rather than one
+        // task per snapshot all running at once on the PBS
+        if options.volid.is_none() {
+            vlog_info!("Starting verification of datastore {} on {} ({} backup(s))...", datastore, server, backups.len());
+            let Some(pbs) = &pbs else {
+                eprintln!("dry-run: POST {}:{}/admin/datastore/{}/verify{}", server, port, datastore,
+                          namespace.map(|ns| format!(" ns={}", ns)).unwrap_or_default());
+                return Ok(Vec::new());
+            };
+            let upid = pbs.verify(datastore, namespace, None).await?;
+            vlog_debug!("Verification of datastore {} started: {}", datastore, upid);
+            return Ok(vec![upid]);
+        }
+
+        vlog_info!("Starting verification of {} backup(s) on {} ({})...", backups.len(), server, datastore);
+        let mut started = Vec::new();
+        for backup in backups {
+            // backup/<type>/<id>/<time>, the time comes from ctime as epoch
+            let snapshot = backup.volid.split_once(":backup/").map(|(_, s)| s).unwrap_or_default();
+            let mut parts = snapshot.split('/');
+            let (Some(backup_type), Some(backup_id), Some(backup_time)) = (parts.next(), parts.next(), backup.ctime) else {
+                vlog_warn!("Skipping '{}', not a PBS snapshot volume", backup.volid);
+                continue;
+            };
+            let Some(pbs) = &pbs else {
+                eprintln!("dry-run: POST {}:{}/admin/datastore/{}/verify backup-type={} backup-id={} backup-time={}",
+                          server, port, datastore, backup_type, backup_id, backup_time);
+                continue;
+            };
+            match pbs.verify(datastore, namespace, Some((backup_type, backup_id, backup_time))).await {
+                Ok(upid) => {
+                    vlog_debug!("Verification of '{}' started: {}", backup.volid, upid);
+                    started.push(upid);
+                }
+                Err(e) => vlog_warn!("Verification of '{}' not started: {}", backup.volid, e),
+            }
+        }
+        Ok(started)
+    }
+
+    /// Every volume of a content type (`backup`, `vztmpl`, ...) on the
+    /// storages holding it, or on `storage` only, with the node it was
+    /// listed from. Shared storages are listed once.
+    pub(super) async fn storage_volumes(&self, content: &str, storage: Option<&str>) -> Result<Vec<(String, StorageContent)>> {
+        let storages = self.client.get_cluster_resources(Some("storage")).await?;
+        let mut seen = HashSet::new();
+        let mut volumes = Vec::new();
+
+        for entry in storages {
+            let (Some(name), Some(node)) = (entry.storage.as_deref(), entry.node.as_deref()) else {
+                continue;
+            };
+            let holds = entry.content.as_deref().is_some_and(|c| c.split(',').any(|c| c == content));
+            if !holds || entry.status.as_deref() != Some("available") || storage.is_some_and(|s| s != name) {
+                continue;
+            }
+            let shared = entry.shared == Some(1);
+            if !seen.insert((name.to_string(), if shared { String::new() } else { node.to_string() })) {
+                continue;
+            }
+            match self.client.get_storage_content(node, name, Some(content), None).await {
+                Ok(found) => volumes.extend(found.into_iter().map(|v| (node.to_string(), v))),
+                Err(e) => vlog_warn!("No {} list from storage '{}' on '{}': {}", content, name, node, e),
+            }
+        }
+        if let Some(storage) = storage.filter(|_| seen.is_empty()) {
+            bail!("Storage '{}' not found or holds no {} volumes", storage, content);
+        }
+        Ok(volumes)
+    }
+
+    pub async fn prune_preview(&self, options: &PruneOptions) -> Result<()> {
+        let retention = (!options.keep.is_empty()).then(|| {
+            options.keep.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join(",")
+        });
+
+        let nodes = self.storage_nodes(&options.storage).await?;
+        let mut volumes: Vec<(String, PruneEntry)> = Vec::new();
+        for node in &nodes {
+            let entries = self.client.get_prune_preview(node, &options.storage, retention.as_deref(), options.vmid).await?;
+            volumes.extend(entries.into_iter().map(|e| (node.clone(), e)));
+        }
+        volumes.sort_by(|a, b| a.1.vmid.cmp(&b.1.vmid).then(b.1.ctime.cmp(&a.1.ctime)));
+
+        let total = volumes.len();
+        let removed = volumes.iter().filter(|(_, v)| v.mark == "remove").count();
+        if options.apply && removed > 0 {
+            let what = format!("{} backup(s) on storage '{}'", removed, options.storage);
+            self.confirm("prune", &what)?;
+            for node in &nodes {
+                vlog_info!("Pruning storage '{}' on node '{}'...", options.storage, node);
+                self.client.prune_backups(node, &options.storage, retention.as_deref(), options.vmid).await?;
+            }
+        }
+
+        match self.output_format {
+            OutputFormat::Json => {
+                let output = PrunePreviewOutput {
+                    storage: options.storage.clone(),
+                    retention,
+                    applied: options.apply,
+                    volumes: volumes.into_iter().map(|(_, v)| v).collect(),
+                };
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            }
+            OutputFormat::Csv => {
+                csv_row!("NODE", "VOLID", "VMID", "CREATED", "MARK");
+                for (node, v) in &volumes {
+                    csv_row!(node, v.volid, v.vmid.map(|id| id.to_string()).unwrap_or_default(),
+                             v.ctime.map(|t| self.timestamp(t)).unwrap_or_default(), v.mark);
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Created").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Volume").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new(if options.apply { "Result" } else { "Action" }).add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for (node, v) in &volumes {
+                    let mark = match (v.mark.as_str(), options.apply) {
+                        ("remove", true) => Cell::new("removed").fg(Color::Red),
+                        ("remove", false) => Cell::new("remove").fg(Color::Red),
+                        ("keep", _) => Cell::new("keep").fg(Color::Green),
+                        (other, _) => Cell::new(other).fg(Color::Yellow),
+                    };
+                    table.add_row(vec![
+                        Cell::new(v.vmid.map(|id| id.to_string()).unwrap_or_default()),
+                        Cell::new(v.ctime.map(|t| self.timestamp(t)).unwrap_or_default()),
+                        Cell::new(&v.volid),
+                        Cell::new(node),
+                        mark,
+                    ]);
+                }
+                pager::print_table(&mut table);
+                if !options.apply && removed > 0 {
+                    println!("{} backup(s) would be removed, run again with --apply to remove them.", removed);
+                }
+            }
+        }
+
+        if options.apply {
+            vlog_success!("Pruned {} backup(s) from storage '{}'", removed, options.storage);
+        } else {
+            vlog_success!("{} of {} backup(s) would be pruned", removed, total);
+        }
+        Ok(())
+    }
+
+    /// Nodes to ask about a storage: one for shared storages, every node
+    /// holding it otherwise
+    pub(super) async fn storage_nodes(&self, storage: &str) -> Result<Vec<String>> {
+        let resources = self.client.get_cluster_resources(Some("storage")).await?;
+        let entries: Vec<_> = resources.iter()
+            .filter(|r| r.storage.as_deref() == Some(storage) && r.status.as_deref() == Some("available"))
+            .collect();
+        if entries.is_empty() {
+            bail!("Storage '{}' not found or not available on any node", storage);
+        }
+
+        let mut nodes: Vec<String> = entries.iter().filter_map(|r| r.node.clone()).collect();
+        nodes.sort();
+        if entries.iter().any(|r| r.shared == Some(1)) {
+            nodes.truncate(1);
+        }
+        vlog_debug!("Storage '{}' queried on {}", storage, nodes.join(", "));
+        Ok(nodes)
+    }
+}
@@ -0,0 +1,220 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # ceph.rs
+//!
+//! Ceph as managed by Proxmox, `pvenom ceph ...`, read from the first
+//! online node that answers the Ceph endpoints.
+//!
+//! `ceph pools` shows usage, replication (size/min_size) and placement
+//! groups of every pool.
+//!
+//! `ceph osd perf` lists the OSDs slowest first by commit and apply
+//! latency, with their fill level: OSDs above the Ceph `nearfull` ratio
+//! (85%) or slower than 100 ms are highlighted. This answers "which OSD is
+//! slow" without `ssh node ceph osd perf`.
+
+use anyhow::{bail, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use serde_json::Value;
+
+use super::Commands;
+use crate::models::{CephOsd, CephPool, OutputFormat};
+use crate::{csv, csv_row, pager, vlog_debug, vlog_success};
+
+/// Default Ceph `nearfull` ratio, in percent
+const NEARFULL_PERCENT: f64 = 85.0;
+
+/// Commit or apply latency worth a look
+const SLOW_LATENCY_MS: u64 = 100;
+
+impl Commands {
+    pub async fn ceph_pools(&self) -> Result<()> {
+        let node = self.ceph_node().await?;
+        let mut pools = self.client.get_ceph_pools(&node).await?;
+        pools.sort_by(|a, b| a.pool_name.cmp(&b.pool_name));
+
+        let gb = |bytes: Option<u64>| bytes.map(|b| format!("{:.1}", b as f64 / 1024.0 / 1024.0 / 1024.0));
+        let percent = |p: &CephPool| p.percent_used.map(|u| u * 100.0);
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&pools)?),
+            OutputFormat::Csv => {
+                csv_row!("POOL", "TYPE", "SIZE", "MIN_SIZE", "PG_NUM", "AUTOSCALE", "USED_GB", "USED_PERCENT",
+                         "CRUSH_RULE");
+                for p in &pools {
+                    csv_row!(p.pool_name, p.pool_type.as_deref().unwrap_or(""),
+                             p.size.map(|s| s.to_string()).unwrap_or_default(),
+                             p.min_size.map(|s| s.to_string()).unwrap_or_default(),
+                             p.pg_num.map(|s| s.to_string()).unwrap_or_default(),
+                             p.pg_autoscale_mode.as_deref().unwrap_or(""),
+                             csv::num(gb(p.bytes_used).unwrap_or_default()),
+                             csv::num(percent(p).map(|u| format!("{:.1}", u)).unwrap_or_default()),
+                             p.crush_rule_name.as_deref().unwrap_or(""));
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Pool").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Size/Min").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("PGs").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Autoscale").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Used (GB)").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Used").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("CRUSH Rule").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for p in &pools {
+                    let replication = match (p.size, p.min_size) {
+                        (Some(size), Some(min)) => format!("{}/{}", size, min),
+                        _ => "N/A".to_string(),
+                    };
+                    // Pools that stop at the first lost copy
+                    let replication = match p.min_size.zip(p.size) {
+                        Some((min, size)) if min >= size || size < 3 => Cell::new(replication).fg(Color::Yellow),
+                        _ => Cell::new(replication),
+                    };
+                    let used = match percent(p) {
+                        Some(u) if u >= NEARFULL_PERCENT => Cell::new(format!("{:.1}%", u)).fg(Color::Red),
+                        Some(u) => Cell::new(format!("{:.1}%", u)),
+                        None => Cell::new("N/A"),
+                    };
+                    table.add_row(vec![
+                        Cell::new(&p.pool_name),
+                        replication,
+                        Cell::new(p.pg_num.map(|n| n.to_string()).unwrap_or_else(|| "N/A".to_string())),
+                        Cell::new(p.pg_autoscale_mode.as_deref().unwrap_or("-")),
+                        Cell::new(gb(p.bytes_used).unwrap_or_else(|| "N/A".to_string())),
+                        used,
+                        Cell::new(p.crush_rule_name.as_deref().unwrap_or("-")),
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        vlog_success!("Listed {} Ceph pool(s)", pools.len());
+        Ok(())
+    }
+
+    pub async fn ceph_osd_perf(&self) -> Result<()> {
+        let node = self.ceph_node().await?;
+        let tree = self.client.get_ceph_osd_tree(&node).await?;
+        let mut osds = Vec::new();
+        collect_osds(&tree["root"], None, &mut osds);
+        let latency = |o: &CephOsd| o.commit_latency_ms.unwrap_or(0).max(o.apply_latency_ms.unwrap_or(0));
+        osds.sort_by(|a, b| latency(b).cmp(&latency(a)).then(a.id.cmp(&b.id)));
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&osds)?),
+            OutputFormat::Csv => {
+                csv_row!("OSD", "HOST", "CLASS", "STATUS", "IN", "COMMIT_MS", "APPLY_MS", "USED_PERCENT");
+                for o in &osds {
+                    csv_row!(o.name, o.host.as_deref().unwrap_or(""), o.device_class.as_deref().unwrap_or(""),
+                             o.status, o.in_cluster, o.commit_latency_ms.map(|l| l.to_string()).unwrap_or_default(),
+                             o.apply_latency_ms.map(|l| l.to_string()).unwrap_or_default(),
+                             csv::num(o.percent_used.map(|u| format!("{:.1}", u)).unwrap_or_default()));
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("OSD").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Host").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Class").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Status").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Commit (ms)").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Apply (ms)").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Used").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                let ms = |l: Option<u64>| match l {
+                    Some(l) if l >= SLOW_LATENCY_MS => Cell::new(l).fg(Color::Red),
+                    Some(l) => Cell::new(l),
+                    None => Cell::new("-"),
+                };
+                for o in &osds {
+                    let state = format!("{}/{}", o.status, if o.in_cluster { "in" } else { "out" });
+                    let status = match (o.status.as_str(), o.in_cluster) {
+                        ("up", true) => Cell::new(state).fg(Color::Green),
+                        _ => Cell::new(state).fg(Color::Red),
+                    };
+                    let used = match o.percent_used {
+                        Some(u) if u >= NEARFULL_PERCENT => Cell::new(format!("{:.1}%", u)).fg(Color::Red),
+                        Some(u) => Cell::new(format!("{:.1}%", u)),
+                        None => Cell::new("-"),
+                    };
+                    table.add_row(vec![
+                        Cell::new(&o.name),
+                        Cell::new(o.host.as_deref().unwrap_or("-")),
+                        Cell::new(o.device_class.as_deref().unwrap_or("-")),
+                        status,
+                        ms(o.commit_latency_ms),
+                        ms(o.apply_latency_ms),
+                        used,
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        vlog_success!("Listed {} OSD(s)", osds.len());
+        Ok(())
+    }
+
+    /// First online node answering the Ceph status endpoint
+    async fn ceph_node(&self) -> Result<String> {
+        for node in self.client.get_nodes().await? {
+            if node.status != "online" {
+                continue;
+            }
+            match self.client.get_ceph_pools(&node.node).await {
+                Ok(_) => return Ok(node.node),
+                Err(e) => vlog_debug!("No Ceph on node '{}': {}", node.node, e),
+            }
+        }
+        bail!("No online node answers for Ceph, is it installed?")
+    }
+}
+
+/// OSD leaves of `/nodes/{node}/ceph/osd`, the host is the nearest
+/// `host` bucket above them
+fn collect_osds(item: &Value, host: Option<&str>, osds: &mut Vec<CephOsd>) {
+    let host = match item["type"].as_str() {
+        Some("host") => item["name"].as_str().or(host),
+        _ => host,
+    };
+    if item["type"].as_str() == Some("osd") {
+        osds.push(CephOsd {
+            id: item["id"].as_i64().unwrap_or_default(),
+            name: item["name"].as_str().unwrap_or_default().to_string(),
+            host: item["host"].as_str().or(host).map(str::to_string),
+            device_class: item["device_class"].as_str().map(str::to_string),
+            status: item["status"].as_str().unwrap_or("unknown").to_string(),
+            in_cluster: item["in"].as_u64() == Some(1),
+            commit_latency_ms: item["commit_latency_ms"].as_u64(),
+            apply_latency_ms: item["apply_latency_ms"].as_u64(),
+            percent_used: item["percent_used"].as_f64(),
+        });
+    }
+    for child in item["children"].as_array().into_iter().flatten() {
+        collect_osds(child, host, osds);
+    }
+}
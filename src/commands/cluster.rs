@@ -0,0 +1,361 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # cluster.rs
+//!
+//! Cluster-wide checks, `pvenom cluster ...`.
+//!
+//! `cluster cpu-matrix` lines up the CPU model and flags of every online
+//! node. Each node lists the flags other nodes have and it lacks, then the
+//! VMs with CPU type `host` are listed with the nodes they cannot live
+//! migrate to: a `host` guest sees every flag of its node, and a target
+//! missing one of them refuses the migration.
+//!
+//! `cluster status` shows name, quorum, node and vote counts and the
+//! QDevice, if any. A two-node cluster needs the QDevice vote to survive
+//! losing a node, so one without it, or with a QDevice not casting its
+//! vote, gets a warning.
+//!
+//! `cluster links` lists the corosync links (`link0`..`link7`, formerly
+//! `ring0_addr`...) configured for every node. A node with a single link
+//! loses quorum as soon as that one network hiccups, e.g. during switch
+//! maintenance, and is shown in red. The API does not expose knet link
+//! state, so health here is the configuration plus the node being online;
+//! `corosync-cfgtool -s` on the node shows the live state.
+
+use anyhow::{bail, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::migrate::cpu_type;
+use super::Commands;
+use crate::models::{ClusterStatusOutput, CorosyncLink, CorosyncNode, QDeviceStatus, CpuMatrixOutput, HostCpuGuest, NodeCpu, OutputFormat};
+use crate::{csv_row, pager, vlog_info, vlog_success, vlog_warn};
+
+/// Missing flags named in the table, the rest is counted
+const FLAGS_SHOWN: usize = 6;
+
+/// Links corosync supports per node
+const MAX_LINKS: usize = 8;
+
+impl Commands {
+    pub async fn cluster_status(&self) -> Result<()> {
+        let status = self.client.get_cluster_status().await?;
+        let Some(cluster) = status.iter().find(|e| e.entry_type == "cluster") else {
+            bail!("Standalone node, not part of a cluster");
+        };
+        let members: Vec<_> = status.iter().filter(|e| e.entry_type == "node").collect();
+        let expected_votes: u32 = self.client.get_corosync_nodes().await?
+            .iter()
+            .map(|n| n.get("quorum_votes").and_then(|v| v.as_str().and_then(|s| s.parse().ok()).or(v.as_u64().map(|i| i as u32))).unwrap_or(1))
+            .sum();
+
+        let qdevice = self.client.get_qdevice_status().await?
+            .filter(|q| !q.is_empty())
+            .map(|q| {
+                let find = |needle: &str| q.iter()
+                    .find(|(k, _)| k.to_lowercase().contains(needle))
+                    .and_then(|(_, v)| v.as_str().map(str::to_string));
+                QDeviceStatus {
+                    state: find("state"),
+                    vote: find("vote"),
+                    host: find("host"),
+                }
+            });
+
+        let mut warnings = Vec::new();
+        if members.len() == 2 {
+            match &qdevice {
+                None => warnings.push("Two-node cluster without QDevice: losing either node loses quorum".to_string()),
+                Some(q) if !q.vote.as_deref().unwrap_or_default().to_lowercase().contains("ack") => {
+                    warnings.push(format!("QDevice not casting its vote ({})", q.vote.as_deref().unwrap_or("unknown")));
+                }
+                Some(_) => {}
+            }
+        }
+
+        let output = ClusterStatusOutput {
+            name: cluster.name.clone(),
+            quorate: cluster.quorate == Some(1),
+            nodes: members.len(),
+            online: members.iter().filter(|m| m.online == Some(1)).count(),
+            expected_votes: expected_votes + qdevice.is_some() as u32,
+            qdevice,
+            warnings,
+        };
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&output)?),
+            _ => {
+                let mut rows: Vec<(&str, String)> = vec![
+                    ("Cluster", output.name.clone()),
+                    ("Quorate", if output.quorate { "yes" } else { "NO" }.to_string()),
+                    ("Nodes", format!("{}/{} online", output.online, output.nodes)),
+                    ("Expected votes", output.expected_votes.to_string()),
+                ];
+                match &output.qdevice {
+                    Some(q) => {
+                        rows.push(("QDevice", q.host.clone().unwrap_or_else(|| "configured".to_string())));
+                        rows.push(("QDevice state", q.state.clone().unwrap_or_else(|| "unknown".to_string())));
+                        rows.push(("QDevice vote", q.vote.clone().unwrap_or_else(|| "unknown".to_string())));
+                    }
+                    None => rows.push(("QDevice", "none".to_string())),
+                }
+
+                if self.output_format == OutputFormat::Csv {
+                    csv_row!("PROPERTY", "VALUE");
+                    for (property, value) in &rows {
+                        csv_row!(property, value);
+                    }
+                } else {
+                    let mut table = Table::new();
+                    table.load_preset(UTF8_FULL)
+                         .set_content_arrangement(ContentArrangement::Dynamic);
+                    table.set_header(vec![
+                        Cell::new("Property").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Value").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    ]);
+                    for (property, value) in &rows {
+                        let cell = match (*property, value.as_str()) {
+                            ("Quorate", "yes") => Cell::new(value).fg(Color::Green),
+                            ("Quorate", _) => Cell::new(value).fg(Color::Red),
+                            _ => Cell::new(value),
+                        };
+                        table.add_row(vec![Cell::new(property), cell]);
+                    }
+                    pager::print_table(&mut table);
+                }
+            }
+        }
+
+        for warning in &output.warnings {
+            vlog_warn!("{}", warning);
+        }
+        Ok(())
+    }
+
+    pub async fn cluster_links(&self) -> Result<()> {
+        let status = self.client.get_cluster_status().await?;
+        if !status.iter().any(|e| e.entry_type == "cluster") {
+            bail!("Standalone node, there are no corosync links");
+        }
+        let online: BTreeMap<String, bool> = status
+            .into_iter()
+            .filter(|e| e.entry_type == "node")
+            .map(|e| (e.name, e.online == Some(1)))
+            .collect();
+        let mut nodes: Vec<CorosyncNode> = self.client.get_corosync_nodes().await?
+            .into_iter()
+            .map(|n| {
+                let links = (0..MAX_LINKS)
+                    .filter_map(|i| {
+                        let address = n.get(&format!("link{}", i)).or_else(|| n.get(&format!("ring{}_addr", i)))?;
+                        Some(CorosyncLink { link: i as u8, address: address.as_str()?.to_string() })
+                    })
+                    .collect();
+                let name = n.get("name").or_else(|| n.get("node")).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+                CorosyncNode {
+                    online: online.get(&name).copied().unwrap_or(false),
+                    nodeid: n.get("nodeid").and_then(|v| v.as_str().and_then(|s| s.parse().ok()).or(v.as_u64().map(|i| i as u32))),
+                    node: name,
+                    links,
+                }
+            })
+            .collect();
+        nodes.sort_by(|a, b| a.node.cmp(&b.node));
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&nodes)?),
+            OutputFormat::Csv => {
+                csv_row!("NODE", "NODEID", "ONLINE", "LINKS");
+                for n in &nodes {
+                    let links: Vec<String> = n.links.iter().map(|l| format!("link{}={}", l.link, l.address)).collect();
+                    csv_row!(n.node, n.nodeid.map(|i| i.to_string()).unwrap_or_default(), n.online, links.join(" "));
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Node ID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Status").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Links").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Redundancy").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for n in &nodes {
+                    let status = match n.online {
+                        true => Cell::new("online").fg(Color::Green),
+                        false => Cell::new("offline").fg(Color::Red),
+                    };
+                    let redundancy = match n.links.len() {
+                        0 | 1 => Cell::new("single link").fg(Color::Red),
+                        count => Cell::new(format!("{} links", count)).fg(Color::Green),
+                    };
+                    let links: Vec<String> = n.links.iter().map(|l| format!("link{} {}", l.link, l.address)).collect();
+                    table.add_row(vec![
+                        Cell::new(&n.node),
+                        Cell::new(n.nodeid.map(|i| i.to_string()).unwrap_or_else(|| "-".to_string())),
+                        status,
+                        Cell::new(links.join("\n")),
+                        redundancy,
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        let single = nodes.iter().filter(|n| n.links.len() < 2).count();
+        if single > 0 {
+            vlog_warn!("{} node(s) with a single corosync link", single);
+        }
+        vlog_success!("Listed corosync links of {} node(s)", nodes.len());
+        Ok(())
+    }
+
+    pub async fn cpu_matrix(&self) -> Result<()> {
+        vlog_info!("Comparing node CPUs...");
+        let mut flags: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        let mut nodes = Vec::new();
+        for node in self.client.get_nodes().await? {
+            if node.status != "online" {
+                vlog_warn!("Node '{}' is {}, its CPU is not compared", node.node, node.status);
+                continue;
+            }
+            let cpu = self.client.get_node_cpuinfo(&node.node).await?;
+            flags.insert(node.node.clone(), cpu.flags.as_deref().unwrap_or_default().split_whitespace().map(str::to_string).collect());
+            nodes.push(NodeCpu { node: node.node, model: cpu.model, cpus: cpu.cpus, flags: 0, missing_flags: Vec::new() });
+        }
+
+        let all: BTreeSet<&String> = flags.values().flatten().collect();
+        for node in &mut nodes {
+            let own = &flags[&node.node];
+            node.flags = own.len();
+            node.missing_flags = all.iter().filter(|f| !own.contains(**f)).map(|f| f.to_string()).collect();
+        }
+
+        // Nodes lacking at least one flag of the guest's node
+        let mut host_guests = Vec::new();
+        for guest in self.client.get_cluster_resources(Some("vm")).await? {
+            let (Some(vmid), Some(node)) = (guest.vmid, guest.node.as_deref()) else {
+                continue;
+            };
+            if guest.resource_type != "qemu" || !flags.contains_key(node) {
+                continue;
+            }
+            let config = self.client.get_guest_config(node, "qemu", vmid).await?;
+            if cpu_type(&config) != Some("host") {
+                continue;
+            }
+            let source = &flags[node];
+            let blocked_targets: Vec<String> = flags.iter()
+                .filter(|(other, other_flags)| *other != node && !source.is_subset(other_flags))
+                .map(|(other, _)| other.clone())
+                .collect();
+            host_guests.push(HostCpuGuest {
+                vmid,
+                name: guest.name.clone().unwrap_or_else(|| "N/A".to_string()),
+                node: node.to_string(),
+                blocked_targets,
+            });
+        }
+        host_guests.sort_by_key(|g| g.vmid);
+
+        let output = CpuMatrixOutput {
+            homogeneous: nodes.iter().all(|n| n.missing_flags.is_empty()),
+            nodes,
+            host_guests,
+        };
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&output)?),
+            OutputFormat::Csv => {
+                csv_row!("NODE", "MODEL", "CPUS", "FLAGS", "MISSING_FLAGS");
+                for n in &output.nodes {
+                    csv_row!(n.node, n.model, n.cpus.map(|c| c.to_string()).unwrap_or_default(), n.flags,
+                             n.missing_flags.join(" "));
+                }
+                println!();
+                csv_row!("VMID", "NAME", "NODE", "BLOCKED_TARGETS");
+                for g in &output.host_guests {
+                    csv_row!(g.vmid, g.name, g.node, g.blocked_targets.join(" "));
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("CPU Model").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("CPUs").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Flags").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Lacks").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for n in &output.nodes {
+                    let lacks = match n.missing_flags.len() {
+                        0 => Cell::new("-").fg(Color::Green),
+                        count => {
+                            let mut shown = n.missing_flags.iter().take(FLAGS_SHOWN).cloned().collect::<Vec<_>>().join(" ");
+                            if count > FLAGS_SHOWN {
+                                shown = format!("{} (+{} more)", shown, count - FLAGS_SHOWN);
+                            }
+                            Cell::new(shown).fg(Color::Yellow)
+                        }
+                    };
+                    table.add_row(vec![
+                        Cell::new(&n.node),
+                        Cell::new(&n.model),
+                        Cell::new(n.cpus.map(|c| c.to_string()).unwrap_or_else(|| "N/A".to_string())),
+                        Cell::new(n.flags),
+                        lacks,
+                    ]);
+                }
+                pager::print_table(&mut table);
+
+                let blocked: Vec<&HostCpuGuest> = output.host_guests.iter().filter(|g| !g.blocked_targets.is_empty()).collect();
+                if blocked.is_empty() {
+                    println!("No guest with CPU type host is kept from live migrating.");
+                } else {
+                    let mut table = Table::new();
+                    table.load_preset(UTF8_FULL)
+                         .set_content_arrangement(ContentArrangement::Dynamic);
+                    table.set_header(vec![
+                        Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Cannot Live Migrate To").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    ]);
+                    for g in blocked {
+                        table.add_row(vec![
+                            Cell::new(g.vmid),
+                            Cell::new(&g.name),
+                            Cell::new(&g.node),
+                            Cell::new(g.blocked_targets.join(", ")).fg(Color::Red),
+                        ]);
+                    }
+                    pager::print_table(&mut table);
+                }
+            }
+        }
+
+        vlog_success!("Compared the CPUs of {} node(s), {} guest(s) with CPU type host",
+                      output.nodes.len(), output.host_guests.len());
+        Ok(())
+    }
+}
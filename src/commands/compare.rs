@@ -0,0 +1,208 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # compare.rs
+//!
+//! Diff the guests of two clusters, e.g. the primary and its DR replica:
+//! `pvenom compare --profile prod --profile dr`.
+//!
+//! Guests are paired by name, VMIDs may differ between the sites. Reported
+//! are guests missing on the replica, guests only on the replica, and pairs
+//! whose type or configuration differ. Settings that are unique by design,
+//! like the config digest or the SMBIOS UUID, are not compared, and the
+//! VMID inside volume names and the MAC addresses of NICs are masked.
+
+use anyhow::Result;
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::Commands;
+use crate::models::{ClusterResource, CompareOutput, GuestComparison, OutputFormat};
+use crate::{csv_row, pager, vlog_info, vlog_success, vlog_warn};
+
+/// Config keys differing between any two copies of a guest
+const UNIQUE_KEYS: [&str; 7] = ["digest", "lock", "meta", "parent", "smbios1", "vmgenid", "vmstate"];
+
+type GuestConfig = (ClusterResource, Map<String, Value>);
+
+impl Commands {
+    /// Compare the guests of this cluster, `primary`, with `replica`
+    pub async fn compare(&self, replica: &Commands, primary_name: &str, replica_name: &str) -> Result<()> {
+        vlog_info!("Comparing guests of '{}' and '{}'...", primary_name, replica_name);
+        let (primary_guests, replica_guests) = tokio::try_join!(self.guests_with_config(), replica.guests_with_config())?;
+        let primary_guests = by_name(primary_guests, primary_name);
+        let mut replica_guests = by_name(replica_guests, replica_name);
+
+        let mut identical = 0;
+        let mut guests = Vec::new();
+        for (name, (resource, config)) in &primary_guests {
+            let Some((replica_resource, replica_config)) = replica_guests.remove(name) else {
+                guests.push(GuestComparison {
+                    name: name.clone(),
+                    primary_vmid: resource.vmid,
+                    replica_vmid: None,
+                    state: "missing".to_string(),
+                    differences: Vec::new(),
+                });
+                continue;
+            };
+            let mut differences = Vec::new();
+            if resource.resource_type != replica_resource.resource_type {
+                differences.push(format!("type: {} -> {}", resource.resource_type, replica_resource.resource_type));
+            }
+            differences.extend(config_differences((resource.vmid, config), (replica_resource.vmid, &replica_config)));
+            if differences.is_empty() {
+                identical += 1;
+                continue;
+            }
+            guests.push(GuestComparison {
+                name: name.clone(),
+                primary_vmid: resource.vmid,
+                replica_vmid: replica_resource.vmid,
+                state: "divergent".to_string(),
+                differences,
+            });
+        }
+        for (name, (resource, _)) in replica_guests {
+            guests.push(GuestComparison {
+                name,
+                primary_vmid: None,
+                replica_vmid: resource.vmid,
+                state: "extra".to_string(),
+                differences: Vec::new(),
+            });
+        }
+
+        let output = CompareOutput {
+            primary: primary_name.to_string(),
+            replica: replica_name.to_string(),
+            identical,
+            guests,
+        };
+        let vmid = |v: Option<u32>| v.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string());
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&output)?),
+            OutputFormat::Csv => {
+                csv_row!("NAME", "PRIMARY_VMID", "REPLICA_VMID", "STATE", "DIFFERENCES");
+                for g in &output.guests {
+                    csv_row!(g.name, vmid(g.primary_vmid), vmid(g.replica_vmid), g.state, g.differences.join(" | "));
+                }
+            }
+            OutputFormat::Table => {
+                if output.guests.is_empty() {
+                    println!("All {} guest(s) of '{}' match '{}'.", identical, primary_name, replica_name);
+                } else {
+                    let mut table = Table::new();
+                    table.load_preset(UTF8_FULL)
+                         .set_content_arrangement(ContentArrangement::Dynamic);
+                    table.set_header(vec![
+                        Cell::new("Guest").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new(format!("VMID ({})", primary_name)).add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new(format!("VMID ({})", replica_name)).add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("State").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Differences").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    ]);
+                    for g in &output.guests {
+                        let state = match g.state.as_str() {
+                            "missing" => Cell::new(&g.state).fg(Color::Red),
+                            "divergent" => Cell::new(&g.state).fg(Color::Yellow),
+                            _ => Cell::new(&g.state),
+                        };
+                        table.add_row(vec![
+                            Cell::new(&g.name),
+                            Cell::new(vmid(g.primary_vmid)),
+                            Cell::new(vmid(g.replica_vmid)),
+                            state,
+                            Cell::new(g.differences.join("\n")),
+                        ]);
+                    }
+                    pager::print_table(&mut table);
+                }
+            }
+        }
+
+        let missing = output.guests.iter().filter(|g| g.state == "missing").count();
+        let divergent = output.guests.iter().filter(|g| g.state == "divergent").count();
+        vlog_success!("{} identical, {} missing and {} divergent on '{}'", identical, missing, divergent, replica_name);
+        Ok(())
+    }
+}
+
+/// Guests by name, unnamed ones by VMID. Of guests sharing a name only
+/// the first is compared
+fn by_name(guests: Vec<GuestConfig>, cluster: &str) -> BTreeMap<String, GuestConfig> {
+    let mut named: BTreeMap<String, GuestConfig> = BTreeMap::new();
+    for (resource, config) in guests {
+        let name = resource.name.clone()
+            .unwrap_or_else(|| format!("vmid {}", resource.vmid.unwrap_or_default()));
+        match named.get(&name) {
+            Some((first, _)) => vlog_warn!("Guests {} and {} on '{}' are both named '{}', comparing only {}",
+                                           first.vmid.unwrap_or_default(), resource.vmid.unwrap_or_default(),
+                                           cluster, name, first.vmid.unwrap_or_default()),
+            None => {
+                named.insert(name, (resource, config));
+            }
+        }
+    }
+    named
+}
+
+/// `key: primary -> replica` for every setting that differs
+fn config_differences(primary: (Option<u32>, &Map<String, Value>), replica: (Option<u32>, &Map<String, Value>)) -> Vec<String> {
+    let keys: BTreeSet<&String> = primary.1.keys().chain(replica.1.keys())
+        .filter(|k| !UNIQUE_KEYS.contains(&k.as_str()))
+        .collect();
+    let text = |key: &str, (vmid, config): (Option<u32>, &Map<String, Value>)| match config.get(key) {
+        None => "(none)".to_string(),
+        Some(Value::String(s)) => normalize(key, s, vmid),
+        Some(other) => other.to_string(),
+    };
+    keys.into_iter()
+        .filter_map(|key| {
+            let (a, b) = (text(key, primary), text(key, replica));
+            (a != b).then(|| format!("{}: {} -> {}", key, a, b))
+        })
+        .collect()
+}
+
+/// Mask what differs between copies of the same disk or NIC: the VMID in
+/// `vm-100-disk-0` or `local:100/...` and the MAC address of `net*`
+fn normalize(key: &str, value: &str, vmid: Option<u32>) -> String {
+    let mut value = value.to_string();
+    if let Some(vmid) = vmid {
+        value = value.replace(&format!("-{}-", vmid), "-{vmid}-").replace(&format!(":{}/", vmid), ":{vmid}/");
+    }
+    if key.starts_with("net") {
+        value = value.split(',')
+            .map(|option| match option.split_once('=') {
+                Some((name, mac)) if is_mac(mac) => format!("{}={{mac}}", name),
+                _ => option.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+    }
+    value
+}
+
+/// `BC:24:11:AA:BB:CC`
+fn is_mac(value: &str) -> bool {
+    let octets: Vec<&str> = value.split(':').collect();
+    octets.len() == 6 && octets.iter().all(|o| o.len() == 2 && o.bytes().all(|b| b.is_ascii_hexdigit()))
+}
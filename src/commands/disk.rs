@@ -0,0 +1,108 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+
+//! # disk.rs
+//!
+//! Guest disks changing storage, `pvenom vm <vmid> disk move scsi0
+//! --storage <storage> [--delete-source]`.
+//!
+//! VMs move their disks while running, containers have to be stopped.
+//! Without `--delete-source` PVE keeps the source volume as an `unusedN`
+//! disk of the guest, to remove once the copy is trusted.
+
+use anyhow::{bail, Context, Result};
+
+use super::vm::is_disk_key;
+use super::Commands;
+use crate::models::ClusterResource;
+use crate::{vlog_info, vlog_success};
+
+/// Settings of `pvenom vm <vmid> disk move`
+pub struct DiskMoveOptions {
+    /// Config key of the disk, e.g. `scsi0` or `mp0`
+    pub disk: String,
+    pub storage: String,
+    pub delete_source: bool,
+    pub timeout: u64,
+}
+
+impl Commands {
+    pub async fn move_guest_disk(&self, vmid: u32, options: &DiskMoveOptions) -> Result<()> {
+        let guest = self.locate_guest(vmid).await?;
+        let node = guest.node.clone().context("Guest has no node")?;
+        let disk = options.disk.as_str();
+        let volume = self.check_disk_move(&guest, disk, &options.storage).await?;
+        let source = volume.split_once(':').map(|(s, _)| s).unwrap_or_default();
+        if source == options.storage {
+            bail!("'{}' of guest {} is already on '{}'", disk, vmid, source);
+        }
+
+        let what = format!("{} of guest {} ({}) from '{}' to '{}'", disk, vmid, guest.name.as_deref().unwrap_or("-"), source, options.storage);
+        self.confirm("move", &what)?;
+
+        vlog_info!("Moving {}...", what);
+        let upid = self.client.move_disk(&node, &guest.resource_type, vmid, disk, &options.storage, options.delete_source).await?;
+        self.wait_for_task(&upid, options.timeout).await?;
+        if self.client.dry_run() {
+            return Ok(());
+        }
+        vlog_success!("{} of guest {} moved to '{}'", disk, vmid, options.storage);
+        if !options.delete_source {
+            vlog_info!("The source volume {} stays as an unused disk, --delete-source removes it", volume);
+        }
+        Ok(())
+    }
+
+    /// Check that `disk` of `guest` can move to `storage` and return its
+    /// current volume
+    pub(super) async fn check_disk_move(&self, guest: &ClusterResource, disk: &str, storage: &str) -> Result<String> {
+        let vmid = guest.vmid.context("Guest has no VMID")?;
+        let node = guest.node.clone().context("Guest has no node")?;
+        if !is_disk_key(disk) && !disk.starts_with("unused") {
+            bail!("'{}' is not a disk key, e.g. scsi0, virtio1, rootfs or mp0", disk);
+        }
+        if guest.resource_type == "lxc" && guest.status.as_deref() == Some("running") {
+            bail!("Container {} is running, stop it before moving '{}'", vmid, disk);
+        }
+
+        let config = self.client.get_guest_config(&node, &guest.resource_type, vmid).await?;
+        let value = config.get(disk).and_then(|v| v.as_str())
+            .with_context(|| format!("Guest {} has no disk '{}'", vmid, disk))?;
+        if value.split(',').any(|o| o == "media=cdrom") {
+            bail!("'{}' of guest {} is a CD-ROM drive", disk, vmid);
+        }
+        let volume = value.split(',').next().unwrap_or_default();
+        if !volume.contains(':') {
+            bail!("'{}' of guest {} is not a storage volume: {}", disk, vmid, volume);
+        }
+
+        let content = if guest.resource_type == "lxc" { "rootdir" } else { "images" };
+        let target = self.client.get_cluster_resources(Some("storage")).await?
+            .into_iter()
+            .find(|s| s.storage.as_deref() == Some(storage) && s.node.as_deref() == Some(node.as_str()))
+            .with_context(|| format!("Storage '{}' not found on node '{}'", storage, node))?;
+        if target.status.as_deref() != Some("available") {
+            bail!("Storage '{}' is not available on node '{}'", storage, node);
+        }
+        if !target.content.as_deref().unwrap_or_default().split(',').any(|c| c == content) {
+            bail!("Storage '{}' does not hold {} content", storage, content);
+        }
+        Ok(volume.to_string())
+    }
+}
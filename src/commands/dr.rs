@@ -0,0 +1,312 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # dr.rs
+//!
+//! Disaster recovery runbook, `pvenom dr-plan > DR.md`.
+//!
+//! A markdown document generated from the live cluster, so it is never
+//! stale: regenerate it after changes instead of editing it. For the loss
+//! of each node it lists, step by step, the guests HA restarts by itself
+//! and where, the guests to start by hand on a surviving node from shared
+//! storage or a replica, and the guests only a backup brings back because
+//! their disks sit on local storage without replication. Replication jobs,
+//! HA groups and the storage layout follow as reference.
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::ha::group_members;
+use super::vm::is_disk_key;
+use super::Commands;
+use crate::models::{ClusterResource, ReplicationJob, ReplicationState};
+use crate::{vlog_info, vlog_success, vlog_warn};
+
+/// Storage of the cluster, merged over the nodes seeing it
+struct StorageInfo {
+    plugintype: String,
+    shared: bool,
+    nodes: Vec<String>,
+}
+
+/// Everything deciding how a guest comes back
+struct GuestPlan<'a> {
+    resource: &'a ClusterResource,
+    /// HA group, `Some("")` for HA resources without a group
+    ha_group: Option<String>,
+    replicas: Vec<&'a ReplicationJob>,
+    /// Storages of the disks
+    storages: Vec<String>,
+}
+
+impl Commands {
+    pub async fn dr_plan(&self) -> Result<()> {
+        vlog_info!("Collecting cluster layout for the DR plan...");
+        let cluster = self.cluster_name().await?;
+        let resources = self.client.get_cluster_resources(None).await?;
+        let guests = self.guests_with_config().await?;
+        let ha_resources = self.client.get_ha_resources().await.unwrap_or_else(|e| {
+            vlog_warn!("HA resources not available: {}", e);
+            Vec::new()
+        });
+        let ha_groups = self.client.get_ha_groups().await.unwrap_or_else(|e| {
+            vlog_warn!("HA groups not available: {}", e);
+            Vec::new()
+        });
+        let jobs = self.client.get_replication_jobs().await.unwrap_or_else(|e| {
+            vlog_warn!("Replication jobs not available: {}", e);
+            Vec::new()
+        });
+
+        let mut nodes: Vec<&ClusterResource> = resources.iter().filter(|r| r.resource_type == "node").collect();
+        nodes.sort_by(|a, b| a.node.cmp(&b.node));
+        let online: Vec<&str> = nodes.iter()
+            .filter(|n| n.status.as_deref() == Some("online"))
+            .filter_map(|n| n.node.as_deref())
+            .collect();
+
+        let mut states: HashMap<String, ReplicationState> = HashMap::new();
+        for node in &online {
+            match self.client.get_replication_state(node).await {
+                Ok(list) => states.extend(list.into_iter().map(|s| (s.id.clone(), s))),
+                Err(e) => vlog_warn!("No replication state for node '{}': {}", node, e),
+            }
+        }
+
+        let mut storages: BTreeMap<String, StorageInfo> = BTreeMap::new();
+        for r in resources.iter().filter(|r| r.resource_type == "storage") {
+            let (Some(name), Some(node)) = (&r.storage, &r.node) else { continue };
+            let info = storages.entry(name.clone()).or_insert_with(|| StorageInfo {
+                plugintype: r.plugintype.clone().unwrap_or_default(),
+                shared: r.shared == Some(1),
+                nodes: Vec::new(),
+            });
+            info.nodes.push(node.clone());
+        }
+
+        let plans: Vec<GuestPlan> = guests.iter()
+            .map(|(resource, config)| {
+                let vmid = resource.vmid.unwrap_or_default();
+                let prefix = if resource.resource_type == "lxc" { "ct" } else { "vm" };
+                GuestPlan {
+                    resource,
+                    ha_group: ha_resources.iter()
+                        .find(|h| h.sid == format!("{}:{}", prefix, vmid))
+                        .map(|h| h.group.clone().unwrap_or_default()),
+                    replicas: jobs.iter().filter(|j| j.guest == vmid && j.disable != Some(1)).collect(),
+                    storages: disk_storages(config),
+                }
+            })
+            .collect();
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        println!("# Disaster recovery plan: {}", cluster);
+        println!();
+        println!("Generated by pvenom {} on {} from the live cluster. Regenerate it with", env!("CARGO_PKG_VERSION"), self.timestamp(now));
+        println!("`pvenom dr-plan` after changes instead of editing it.");
+        println!();
+        let ha_count = plans.iter().filter(|p| p.ha_group.is_some()).count();
+        let replicated = plans.iter().filter(|p| !p.replicas.is_empty()).count();
+        println!("{} node(s), {} guest(s), {} under HA, {} replicated.", nodes.len(), plans.len(), ha_count, replicated);
+
+        println!();
+        println!("## Nodes");
+        println!();
+        println!("| Node | Status | Guests |");
+        println!("|---|---|---|");
+        for node in &nodes {
+            let name = node.node.as_deref().unwrap_or_default();
+            let count = plans.iter().filter(|p| p.resource.node.as_deref() == Some(name)).count();
+            println!("| {} | {} | {} |", name, node.status.as_deref().unwrap_or("unknown"), count);
+        }
+
+        for node in nodes.iter().filter_map(|n| n.node.as_deref()) {
+            let survivors: Vec<&str> = nodes.iter()
+                .filter_map(|n| n.node.as_deref())
+                .filter(|n| *n != node)
+                .collect();
+            let local: Vec<&GuestPlan> = plans.iter().filter(|p| p.resource.node.as_deref() == Some(node)).collect();
+            println!();
+            println!("## If {} fails", node);
+            println!();
+            if local.is_empty() {
+                println!("No guests run on {}, nothing to recover.", node);
+                continue;
+            }
+
+            let mut automatic = Vec::new();
+            let mut manual = Vec::new();
+            let mut restore = Vec::new();
+            for plan in local {
+                let lost: Vec<&String> = plan.storages.iter()
+                    .filter(|s| storages.get(*s).is_none_or(|i| !i.shared))
+                    .collect();
+                let replica = plan.replicas.iter().copied().find(|j| survivors.contains(&j.target.as_str()));
+                match (&plan.ha_group, lost.is_empty(), replica) {
+                    (_, false, None) => restore.push((plan, lost)),
+                    (Some(group), _, _) => automatic.push((plan, group, replica)),
+                    (None, _, _) => manual.push((plan, replica)),
+                }
+            }
+
+            let mut step = 1;
+            println!("{}. Make sure {} is really down, or power it off: a guest running twice corrupts its disks.", step, node);
+            if !automatic.is_empty() {
+                step += 1;
+                println!("{}. HA restarts these guests by itself once {} is fenced, after about two minutes:", step, node);
+                println!();
+                println!("   | Guest | Restarts on | Data |");
+                println!("   |---|---|---|");
+                for (plan, group, replica) in &automatic {
+                    let targets: Vec<String> = ha_groups.iter()
+                        .find(|g| g.group == **group)
+                        .map(|g| group_members(&g.nodes).into_iter().map(|m| m.node).filter(|n| n != node).collect())
+                        .unwrap_or_default();
+                    let targets = if targets.is_empty() { "any online node".to_string() } else { targets.join(", ") };
+                    println!("   | {} | {} | {} |", guest_label(plan.resource), targets, data_source(*replica, &states, self));
+                }
+                println!();
+            }
+            if !manual.is_empty() {
+                step += 1;
+                println!("{}. Start these guests by hand: move the config to the target node and start it there.", step);
+                println!();
+                println!("   | Guest | Target | Data | On the target node |");
+                println!("   |---|---|---|---|");
+                for (plan, replica) in &manual {
+                    let target = match replica {
+                        Some(job) => Some(job.target.as_str()),
+                        None => survivors.iter().copied().find(|n| {
+                            plan.storages.iter().all(|s| storages.get(s).is_some_and(|i| i.nodes.iter().any(|sn| sn == n)))
+                        }),
+                    };
+                    let Some(target) = target else {
+                        println!("   | {} | none | no surviving node sees its storage | restore from backup |", guest_label(plan.resource));
+                        continue;
+                    };
+                    let vmid = plan.resource.vmid.unwrap_or_default();
+                    let (dir, start) = if plan.resource.resource_type == "lxc" { ("lxc", "pct") } else { ("qemu-server", "qm") };
+                    println!("   | {} | {} | {} | `mv /etc/pve/nodes/{}/{}/{}.conf /etc/pve/nodes/{}/{}/ && {} start {}` |",
+                             guest_label(plan.resource), target, data_source(*replica, &states, self),
+                             node, dir, vmid, target, dir, start, vmid);
+                }
+                println!();
+            }
+            if !restore.is_empty() {
+                step += 1;
+                println!("{}. Restore these guests from backup, their disks are on storage of {} without replication:", step, node);
+                println!();
+                println!("   | Guest | Lost storage |");
+                println!("   |---|---|");
+                for (plan, lost) in &restore {
+                    let lost: Vec<&str> = lost.iter().map(|s| s.as_str()).collect();
+                    println!("   | {} | {} |", guest_label(plan.resource), lost.join(", "));
+                }
+                println!();
+            }
+            step += 1;
+            println!("{}. When {} is back, check that it does not start the moved guests again before rejoining.", step, node);
+        }
+
+        println!();
+        println!("## Replication jobs");
+        println!();
+        if jobs.is_empty() {
+            println!("No replication jobs.");
+        } else {
+            println!("| Job | Guest | Target | Schedule | Last sync |");
+            println!("|---|---|---|---|---|");
+            for job in &jobs {
+                let guest = plans.iter()
+                    .find(|p| p.resource.vmid == Some(job.guest))
+                    .map(|p| guest_label(p.resource))
+                    .unwrap_or_else(|| job.guest.to_string());
+                let schedule = if job.disable == Some(1) { "disabled".to_string() } else { job.schedule.clone().unwrap_or_else(|| "*/15".to_string()) };
+                println!("| {} | {} | {} | {} | {} |", job.id, guest, job.target, schedule, data_source(Some(job), &states, self));
+            }
+        }
+
+        println!();
+        println!("## HA groups");
+        println!();
+        if ha_groups.is_empty() {
+            println!("No HA groups.");
+        } else {
+            println!("| Group | Nodes by priority | Restricted | Guests |");
+            println!("|---|---|---|---|");
+            for group in &ha_groups {
+                let members: Vec<String> = group_members(&group.nodes).into_iter()
+                    .map(|m| format!("{} ({})", m.node, m.priority))
+                    .collect();
+                let guests: Vec<String> = plans.iter()
+                    .filter(|p| p.ha_group.as_deref() == Some(group.group.as_str()))
+                    .map(|p| guest_label(p.resource))
+                    .collect();
+                println!("| {} | {} | {} | {} |", group.group, members.join(", "),
+                         if group.restricted == Some(1) { "yes" } else { "no" }, guests.join(", "));
+            }
+        }
+
+        println!();
+        println!("## Storage");
+        println!();
+        println!("| Storage | Type | Shared | Nodes |");
+        println!("|---|---|---|---|");
+        for (name, info) in &storages {
+            println!("| {} | {} | {} | {} |", name, info.plugintype, if info.shared { "yes" } else { "no" }, info.nodes.join(", "));
+        }
+
+        vlog_success!("DR plan written for {} guest(s) on {} node(s)", plans.len(), nodes.len());
+        Ok(())
+    }
+}
+
+/// `web01 (101)`
+fn guest_label(resource: &ClusterResource) -> String {
+    format!("{} ({})", resource.name.as_deref().unwrap_or("unnamed"), resource.vmid.unwrap_or_default())
+}
+
+/// Storages of the disks of a guest config, cdroms excluded
+fn disk_storages(config: &Map<String, Value>) -> Vec<String> {
+    let mut storages: Vec<String> = config.iter()
+        .filter(|(key, _)| is_disk_key(key))
+        .filter_map(|(_, value)| value.as_str())
+        .filter(|value| !value.contains("media=cdrom"))
+        .filter_map(|value| value.split_once(':').map(|(storage, _)| storage.to_string()))
+        .filter(|storage| !storage.starts_with('/'))
+        .collect();
+    storages.sort();
+    storages.dedup();
+    storages
+}
+
+/// Where the data comes from, a replica with its age or shared storage
+fn data_source(replica: Option<&ReplicationJob>, states: &HashMap<String, ReplicationState>, commands: &Commands) -> String {
+    let Some(job) = replica else {
+        return "shared storage".to_string();
+    };
+    match states.get(&job.id) {
+        Some(state) if state.fail_count.unwrap_or(0) > 0 => {
+            format!("replica, failing: {}", state.error.as_deref().unwrap_or("unknown error"))
+        }
+        Some(ReplicationState { last_sync: Some(sync), .. }) if *sync > 0 => format!("replica of {}", commands.timestamp(*sync)),
+        _ => "replica, never synced".to_string(),
+    }
+}
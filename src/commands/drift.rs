@@ -0,0 +1,246 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # drift.rs
+//!
+//! `pvenom drift --expected inventory.yaml`: the guests an operator (or
+//! their IaC repository) says the cluster runs, compared with what it
+//! really runs. The inventory is YAML, JSON or CSV, every field but one of
+//! `name` and `vmid` optional:
+//!
+//! guests:
+//!   - name: web01
+//!     vmid: 101
+//!     node: pve1
+//!     type: qemu        # or lxc
+//!     cores: 2          # vCPUs
+//!     memory_gb: 4
+//!     disk_gb: 32       # all disks, EFI and TPM state excluded
+//!
+//! CSV files have a header with the same field names. Guests match by
+//! VMID when given, by name otherwise; guests of the cluster the inventory
+//! does not list are unmanaged. The command fails when anything drifted,
+//! so it can gate a CI pipeline.
+
+use anyhow::{bail, Context, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+use super::vm::{is_disk_key, size_in_gib};
+use super::Commands;
+use crate::models::{ClusterResource, DriftOutput, GuestDrift, OutputFormat};
+use crate::{csv_row, pager, vlog_info, vlog_success};
+
+/// Memory sizes closer than this are the same, GiB
+const MEMORY_TOLERANCE: f64 = 0.05;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExpectedInventory {
+    guests: Vec<ExpectedGuest>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExpectedGuest {
+    name: Option<String>,
+    vmid: Option<u32>,
+    node: Option<String>,
+    #[serde(rename = "type")]
+    guest_type: Option<String>,
+    cores: Option<u32>,
+    memory_gb: Option<f64>,
+    disk_gb: Option<u64>,
+}
+
+impl Commands {
+    /// Compare the guests with the inventory in `expected`, failing on
+    /// drift. `ignore_unmanaged` leaves guests missing from the inventory
+    /// out, for clusters only partly managed as code.
+    pub async fn drift(&self, expected: &str, ignore_unmanaged: bool) -> Result<()> {
+        let inventory = load_inventory(expected)?;
+        vlog_info!("Comparing {} expected guest(s) with the cluster...", inventory.len());
+        let guests = self.guests_with_config().await?;
+
+        let mut matched = HashSet::new();
+        let mut drifted = Vec::new();
+        let mut in_sync = 0;
+        for wanted in &inventory {
+            let found = match (wanted.vmid, &wanted.name) {
+                (Some(vmid), _) => guests.iter().find(|(r, _)| r.vmid == Some(vmid)),
+                (None, Some(name)) => guests.iter().find(|(r, _)| r.name.as_deref() == Some(name.as_str())),
+                (None, None) => bail!("Every guest of {} needs a name or a vmid", expected),
+            };
+            let label = wanted.name.clone().unwrap_or_else(|| format!("vmid {}", wanted.vmid.unwrap_or_default()));
+            let Some((resource, config)) = found else {
+                drifted.push(GuestDrift { name: label, vmid: wanted.vmid, state: "missing".to_string(), differences: Vec::new() });
+                continue;
+            };
+            matched.insert(resource.vmid);
+            let differences = differences(wanted, resource, config);
+            if differences.is_empty() {
+                in_sync += 1;
+            } else {
+                drifted.push(GuestDrift { name: label, vmid: resource.vmid, state: "drifted".to_string(), differences });
+            }
+        }
+        if !ignore_unmanaged {
+            for (resource, _) in guests.iter().filter(|(r, _)| !matched.contains(&r.vmid)) {
+                drifted.push(GuestDrift {
+                    name: resource.name.clone().unwrap_or_default(),
+                    vmid: resource.vmid,
+                    state: "unmanaged".to_string(),
+                    differences: Vec::new(),
+                });
+            }
+        }
+
+        let output = DriftOutput { expected: inventory.len(), in_sync, guests: drifted };
+        let vmid = |v: Option<u32>| v.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string());
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&output)?),
+            OutputFormat::Csv => {
+                csv_row!("NAME", "VMID", "STATE", "DIFFERENCES");
+                for g in &output.guests {
+                    csv_row!(g.name, vmid(g.vmid), g.state, g.differences.join(" | "));
+                }
+            }
+            OutputFormat::Table => {
+                if output.guests.is_empty() {
+                    println!("All {} expected guest(s) match the cluster.", in_sync);
+                } else {
+                    let mut table = Table::new();
+                    table.load_preset(UTF8_FULL)
+                         .set_content_arrangement(ContentArrangement::Dynamic);
+                    table.set_header(vec![
+                        Cell::new("Guest").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("State").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Differences").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    ]);
+                    for g in &output.guests {
+                        let state = match g.state.as_str() {
+                            "missing" => Cell::new(&g.state).fg(Color::Red),
+                            "drifted" => Cell::new(&g.state).fg(Color::Yellow),
+                            _ => Cell::new(&g.state),
+                        };
+                        table.add_row(vec![Cell::new(&g.name), Cell::new(vmid(g.vmid)), state, Cell::new(g.differences.join("\n"))]);
+                    }
+                    pager::print_table(&mut table);
+                }
+            }
+        }
+
+        if !output.guests.is_empty() {
+            bail!("{} guest(s) drifted from {}", output.guests.len(), expected);
+        }
+        vlog_success!("{} guest(s) match {}", in_sync, expected);
+        Ok(())
+    }
+}
+
+/// `field: expected X, found Y` for every field the inventory sets
+fn differences(wanted: &ExpectedGuest, resource: &ClusterResource, config: &Map<String, Value>) -> Vec<String> {
+    let mut differences = Vec::new();
+    let mut check = |field: &str, expected: String, found: String| {
+        if expected != found {
+            differences.push(format!("{}: expected {}, found {}", field, expected, found));
+        }
+    };
+    let text = |value: Option<String>| value.unwrap_or_else(|| "none".to_string());
+
+    if let Some(name) = &wanted.name {
+        check("name", name.clone(), text(resource.name.clone()));
+    }
+    if let Some(vmid) = wanted.vmid {
+        check("vmid", vmid.to_string(), text(resource.vmid.map(|v| v.to_string())));
+    }
+    if let Some(node) = &wanted.node {
+        check("node", node.clone(), text(resource.node.clone()));
+    }
+    if let Some(guest_type) = &wanted.guest_type {
+        check("type", guest_type.clone(), resource.resource_type.clone());
+    }
+    if let Some(cores) = wanted.cores {
+        check("cores", cores.to_string(), text(resource.maxcpu.map(|c| format!("{}", c as u32))));
+    }
+    if let Some(memory) = wanted.memory_gb {
+        let found = resource.maxmem.map(|m| m as f64 / 1024.0 / 1024.0 / 1024.0);
+        if found.is_none_or(|f| (f - memory).abs() >= MEMORY_TOLERANCE) {
+            check("memory_gb", format!("{}", memory), text(found.map(|f| format!("{:.1}", f))));
+        }
+    }
+    if let Some(disk) = wanted.disk_gb {
+        check("disk_gb", disk.to_string(), disk_gib(config).to_string());
+    }
+    differences
+}
+
+/// Size of the data disks, EFI and TPM state and CD-ROMs left out
+fn disk_gib(config: &Map<String, Value>) -> u64 {
+    config.iter()
+        .filter(|(key, _)| is_disk_key(key) && !key.starts_with("efidisk") && !key.starts_with("tpmstate"))
+        .filter_map(|(_, value)| value.as_str())
+        .filter(|value| !value.contains("media=cdrom"))
+        .filter_map(|value| value.split(',').find_map(|option| option.strip_prefix("size=")))
+        .map(size_in_gib)
+        .sum()
+}
+
+/// Read the inventory, its format from the extension: `.json`, `.csv`,
+/// YAML otherwise
+fn load_inventory(path: &str) -> Result<Vec<ExpectedGuest>> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+    let inventory: ExpectedInventory = if path.ends_with(".json") {
+        serde_json::from_str(&content).with_context(|| format!("Invalid inventory {}", path))?
+    } else if path.ends_with(".csv") {
+        ExpectedInventory { guests: csv_guests(&content).with_context(|| format!("Invalid inventory {}", path))? }
+    } else {
+        serde_norway::from_str(&content).with_context(|| format!("Invalid inventory {}", path))?
+    };
+    Ok(inventory.guests)
+}
+
+/// CSV rows with a header of field names, `;` or `,` separated, empty
+/// cells unset
+fn csv_guests(content: &str) -> Result<Vec<ExpectedGuest>> {
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+    let header = lines.next().context("Missing header line")?;
+    let delimiter = if header.contains(';') { ';' } else { ',' };
+    let fields: Vec<&str> = header.split(delimiter).map(str::trim).collect();
+
+    lines.enumerate()
+        .map(|(index, line)| {
+            let mut guest = Map::new();
+            for (field, cell) in fields.iter().zip(line.split(delimiter).map(str::trim)) {
+                if cell.is_empty() {
+                    continue;
+                }
+                let value = match *field {
+                    "name" | "node" | "type" => Value::String(cell.to_string()),
+                    _ => serde_json::from_str(&cell.replace(',', ".")).unwrap_or_else(|_| Value::String(cell.to_string())),
+                };
+                guest.insert(field.to_string(), value);
+            }
+            serde_json::from_value(Value::Object(guest)).with_context(|| format!("Row {}", index + 2))
+        })
+        .collect()
+}
@@ -0,0 +1,497 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # export.rs
+//!
+//! Export the cluster inventory to third-party tools, `pvenom export ...`.
+//!
+//! Exports are written to stdout in the target tool's own format, ready to
+//! be redirected to a file: `pvenom export terraform > imports.tf`.
+//!
+//! `export dns --zone lab.example.com` writes A/AAAA records of the running
+//! guests, named after the guest, as a BIND zone snippet to `$INCLUDE`, or
+//! as dnsmasq `host-record` lines with `--style dnsmasq`.
+//!
+//! `export known-hosts` reads the SSH host public keys of the running VMs
+//! through the guest agent (`file-read`, nothing is executed) and writes
+//! them as known_hosts lines for the guest name and its addresses.
+//! Containers have no agent and are skipped.
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::IpAddr;
+
+use super::Commands;
+use super::vm::{is_disk_key, size_in_gib};
+use crate::models::{ClusterResource, NetboxCluster, NetboxExport, NetboxInterface, NetboxInterfaceRef,
+                    NetboxIpAddress, NetboxRef, NetboxVirtualMachine, ZabbixDiscovery};
+use crate::netbox::NetboxClient;
+use crate::{vlog_debug, vlog_info, vlog_success, vlog_warn};
+
+/// SSH host key files read by `export known-hosts`
+const SSH_HOST_KEYS: [&str; 3] = [
+    "/etc/ssh/ssh_host_ed25519_key.pub",
+    "/etc/ssh/ssh_host_ecdsa_key.pub",
+    "/etc/ssh/ssh_host_rsa_key.pub",
+];
+
+impl Commands {
+    /// Fetch all guests with their configs, sorted by VMID
+    pub(super) async fn guests_with_config(&self) -> Result<Vec<(ClusterResource, Map<String, Value>)>> {
+        let mut guests: Vec<_> = self.client.get_cluster_resources(Some("vm")).await?
+            .into_iter()
+            .filter(|r| r.is_guest())
+            .collect();
+        guests.sort_by_key(|g| g.vmid);
+
+        let mut result = Vec::new();
+        for guest in guests {
+            let (Some(node), Some(vmid)) = (guest.node.as_deref(), guest.vmid) else {
+                continue;
+            };
+            let config = self.client.get_guest_config(node, &guest.resource_type, vmid).await?;
+            result.push((guest, config));
+        }
+        Ok(result)
+    }
+
+    /// Terraform/OpenTofu import blocks and resource skeletons for the
+    /// bpg/proxmox provider
+    pub async fn export_terraform(&self) -> Result<()> {
+        vlog_info!("Exporting guests as Terraform resources...");
+        let guests = self.guests_with_config().await?;
+
+        println!("# Generated by pvenom {}, review before applying", env!("CARGO_PKG_VERSION"));
+        for (guest, config) in &guests {
+            let (Some(node), Some(vmid)) = (guest.node.as_deref(), guest.vmid) else {
+                continue;
+            };
+            let name = guest.name.as_deref().unwrap_or_default();
+            let resource = if guest.resource_type == "qemu" {
+                "proxmox_virtual_environment_vm"
+            } else {
+                "proxmox_virtual_environment_container"
+            };
+            let label = terraform_label(name, vmid);
+
+            println!();
+            println!("import {{");
+            println!("  to = {}.{}", resource, label);
+            println!("  id = \"{}/{}\"", node, vmid);
+            println!("}}");
+            println!();
+            println!("resource \"{}\" \"{}\" {{", resource, label);
+            println!("  node_name = \"{}\"", node);
+            println!("  vm_id     = {}", vmid);
+            if guest.resource_type == "qemu" {
+                println!("  name      = \"{}\"", name);
+            }
+            if let Some(Value::String(tags)) = config.get("tags") {
+                let tags: Vec<String> = tags.split([';', ',']).map(|t| format!("\"{}\"", t)).collect();
+                println!("  tags      = [{}]", tags.join(", "));
+            }
+
+            if let Some(cores) = config.get("cores") {
+                println!();
+                println!("  cpu {{");
+                println!("    cores = {}", config_str(cores));
+                println!("  }}");
+            }
+            if let Some(memory) = config.get("memory") {
+                println!();
+                println!("  memory {{");
+                println!("    dedicated = {}", config_str(memory));
+                println!("  }}");
+            }
+
+            if guest.resource_type == "lxc" {
+                println!();
+                println!("  initialization {{");
+                println!("    hostname = \"{}\"", name);
+                println!("  }}");
+            }
+
+            for (key, value) in config {
+                let Value::String(value) = value else { continue };
+                if is_disk_key(key) && !value.contains("media=cdrom") {
+                    print_disk_block(guest, key, value);
+                } else if key.starts_with("net") {
+                    print_network_block(guest, value);
+                }
+            }
+
+            println!("}}");
+        }
+
+        vlog_success!("Exported {} guest(s) as Terraform resources", guests.len());
+        Ok(())
+    }
+
+    /// Name of the cluster, standalone nodes have no cluster entry and use
+    /// their own node name
+    pub(super) async fn cluster_name(&self) -> Result<String> {
+        let status = self.client.get_cluster_status().await?;
+        let name = status.iter()
+            .find(|e| e.entry_type == "cluster")
+            .or_else(|| status.iter().find(|e| e.entry_type == "node"))
+            .map(|e| e.name.clone())
+            .unwrap_or_else(|| "proxmox".to_string());
+        Ok(name)
+    }
+
+    /// NetBox virtualization objects (clusters, VMs, interfaces, IPs), printed
+    /// as JSON or pushed to NetBox when a client is given
+    pub async fn export_netbox(&self, push: Option<NetboxClient>) -> Result<()> {
+        vlog_info!("Exporting inventory for NetBox...");
+        let cluster = NetboxRef { name: self.cluster_name().await? };
+        let guests = self.guests_with_config().await?;
+
+        let mut export = NetboxExport {
+            clusters: vec![NetboxCluster {
+                name: cluster.name.clone(),
+                cluster_type: NetboxRef { name: "Proxmox VE".to_string() },
+                status: "active".to_string(),
+            }],
+            virtual_machines: Vec::new(),
+            interfaces: Vec::new(),
+            ip_addresses: Vec::new(),
+        };
+
+        for (guest, config) in &guests {
+            let (Some(node), Some(vmid)) = (guest.node.as_deref(), guest.vmid) else {
+                continue;
+            };
+            let name = guest.name.clone().unwrap_or_else(|| vmid.to_string());
+            let running = guest.status.as_deref() == Some("running");
+            let vm_ref = NetboxRef { name: name.clone() };
+
+            export.virtual_machines.push(NetboxVirtualMachine {
+                name: name.clone(),
+                cluster: cluster.clone(),
+                status: if running { "active" } else { "offline" }.to_string(),
+                vcpus: guest.maxcpu,
+                memory: guest.maxmem.map(|m| m / 1024 / 1024),
+                disk: guest.maxdisk.map(|d| d / 1024 / 1024),
+                comments: format!("Proxmox {} {} on node {}", guest.guest_type(), vmid, node),
+            });
+
+            // Addresses are only known for running guests (agent or LXC runtime)
+            let runtime = if running {
+                self.client.get_guest_interfaces(node, &guest.resource_type, vmid).await?
+            } else {
+                Vec::new()
+            };
+
+            let mut nics: Vec<(&String, &Value)> = config.iter()
+                .filter(|(k, _)| k.starts_with("net") && k[3..].chars().all(|c| c.is_ascii_digit()))
+                .collect();
+            nics.sort_by_key(|(k, _)| k[3..].parse::<u32>().unwrap_or(0));
+
+            for (key, value) in nics {
+                let mac = config_mac(&config_str(value));
+                let matched = mac.as_ref().and_then(|mac| {
+                    runtime.iter().find(|i| i.hwaddr.as_deref().is_some_and(|h| h.eq_ignore_ascii_case(mac)))
+                });
+                let iface_name = matched.map(|i| i.name.clone()).unwrap_or_else(|| key.clone());
+                vlog_debug!("Guest {} {} maps to interface {}", vmid, key, iface_name);
+
+                export.interfaces.push(NetboxInterface {
+                    virtual_machine: vm_ref.clone(),
+                    name: iface_name.clone(),
+                    mac_address: mac,
+                    enabled: !config_str(value).contains("link_down=1"),
+                });
+
+                for address in matched.map(|i| i.addresses.as_slice()).unwrap_or_default() {
+                    export.ip_addresses.push(NetboxIpAddress {
+                        address: address.clone(),
+                        status: "active".to_string(),
+                        assigned_object_type: "virtualization.vminterface".to_string(),
+                        assigned_object: NetboxInterfaceRef {
+                            virtual_machine: vm_ref.clone(),
+                            name: iface_name.clone(),
+                        },
+                    });
+                }
+            }
+        }
+
+        match push {
+            Some(netbox) => netbox.sync(&export).await?,
+            None => println!("{}", serde_json::to_string_pretty(&export)?),
+        }
+
+        vlog_success!("Exported {} guest(s), {} interface(s), {} address(es) for NetBox",
+                      export.virtual_machines.len(), export.interfaces.len(), export.ip_addresses.len());
+        Ok(())
+    }
+
+    /// DNS records of the running guests' addresses, `style` is bind or
+    /// dnsmasq
+    pub async fn export_dns(&self, zone: &str, style: &str) -> Result<()> {
+        vlog_info!("Exporting guest DNS records for {}...", zone);
+        let zone = zone.trim_end_matches('.');
+        let mut records: BTreeMap<String, BTreeSet<IpAddr>> = BTreeMap::new();
+
+        let mut guests: Vec<_> = self.client.get_cluster_resources(Some("vm")).await?
+            .into_iter()
+            .filter(|r| r.is_guest() && r.status.as_deref() == Some("running"))
+            .collect();
+        guests.sort_by_key(|g| g.vmid);
+        for guest in guests {
+            let (Some(node), Some(vmid)) = (guest.node.as_deref(), guest.vmid) else {
+                continue;
+            };
+            let Some(label) = guest.name.as_deref().and_then(dns_label) else {
+                vlog_warn!("Guest {} has no name usable in DNS, skipped", vmid);
+                continue;
+            };
+            let addresses: BTreeSet<IpAddr> = self.client.get_guest_interfaces(node, &guest.resource_type, vmid).await?
+                .iter()
+                .flat_map(|i| i.addresses.iter())
+                .filter_map(|a| a.split('/').next()?.parse().ok())
+                .collect();
+            if addresses.is_empty() {
+                vlog_debug!("No address known for guest {} ({})", vmid, label);
+                continue;
+            }
+            if records.contains_key(&label) {
+                vlog_warn!("Name '{}' is used by more than one guest, its records are merged", label);
+            }
+            records.entry(label).or_default().extend(addresses);
+        }
+
+        match style {
+            "dnsmasq" => println!("# Generated by pvenom {} for {}", env!("CARGO_PKG_VERSION"), zone),
+            _ => {
+                println!("; Generated by pvenom {}", env!("CARGO_PKG_VERSION"));
+                println!("$ORIGIN {}.", zone);
+            }
+        }
+        for (label, addresses) in &records {
+            match style {
+                "dnsmasq" => {
+                    let addresses: Vec<String> = addresses.iter().map(|a| a.to_string()).collect();
+                    println!("host-record={}.{},{}", label, zone, addresses.join(","));
+                }
+                _ => {
+                    for address in addresses {
+                        let kind = if address.is_ipv4() { "A" } else { "AAAA" };
+                        println!("{:<24} IN {:<4} {}", label, kind, address);
+                    }
+                }
+            }
+        }
+
+        vlog_success!("Exported DNS records of {} guest name(s)", records.len());
+        Ok(())
+    }
+
+    /// known_hosts lines of the running VMs, keys read through the agent
+    pub async fn export_known_hosts(&self) -> Result<()> {
+        vlog_info!("Collecting SSH host keys through the guest agent...");
+        let mut guests: Vec<_> = self.client.get_cluster_resources(Some("vm")).await?
+            .into_iter()
+            .filter(|r| r.resource_type == "qemu" && r.template != Some(1) && r.status.as_deref() == Some("running"))
+            .collect();
+        guests.sort_by_key(|g| g.vmid);
+
+        println!("# Generated by pvenom {}", env!("CARGO_PKG_VERSION"));
+        let mut exported = 0;
+        for guest in guests {
+            let (Some(node), Some(vmid)) = (guest.node.as_deref(), guest.vmid) else {
+                continue;
+            };
+            let mut keys = Vec::new();
+            for file in SSH_HOST_KEYS {
+                if let Some(content) = self.client.get_agent_file(node, vmid, file).await {
+                    keys.extend(content.lines()
+                        .filter_map(|l| {
+                            let mut fields = l.split_whitespace();
+                            Some(format!("{} {}", fields.next()?, fields.next()?))
+                        }));
+                }
+            }
+            if keys.is_empty() {
+                vlog_warn!("No SSH host key read from guest {}, agent missing or no sshd", vmid);
+                continue;
+            }
+
+            let mut hosts: Vec<String> = guest.name.iter().cloned().collect();
+            hosts.extend(self.client.get_guest_interfaces(node, "qemu", vmid).await?
+                .iter()
+                .flat_map(|i| i.addresses.iter())
+                .filter_map(|a| a.split('/').next().map(str::to_string)));
+            if hosts.is_empty() {
+                vlog_warn!("Guest {} has neither name nor address, skipped", vmid);
+                continue;
+            }
+            for key in keys {
+                println!("{} {}", hosts.join(","), key);
+            }
+            exported += 1;
+        }
+
+        vlog_success!("Exported SSH host keys of {} guest(s)", exported);
+        Ok(())
+    }
+
+    /// Zabbix LLD JSON for nodes and/or guests, compact on a single line as
+    /// expected from an external check
+    pub async fn export_zabbix_lld(&self, nodes: bool, guests: bool) -> Result<()> {
+        vlog_info!("Exporting Zabbix low-level discovery data...");
+        let mut discovery = ZabbixDiscovery { data: Vec::new() };
+
+        if nodes {
+            for node in self.client.get_nodes().await? {
+                discovery.data.push(BTreeMap::from([
+                    ("{#TYPE}".to_string(), "node".to_string()),
+                    ("{#NODE}".to_string(), node.node),
+                    ("{#STATUS}".to_string(), node.status),
+                ]));
+            }
+        }
+
+        if guests {
+            let mut resources: Vec<_> = self.client.get_cluster_resources(Some("vm")).await?
+                .into_iter()
+                .filter(|r| r.is_guest())
+                .collect();
+            resources.sort_by_key(|r| r.vmid);
+
+            for guest in resources {
+                discovery.data.push(BTreeMap::from([
+                    ("{#TYPE}".to_string(), guest.resource_type.clone()),
+                    ("{#NODE}".to_string(), guest.node.clone().unwrap_or_default()),
+                    ("{#VMID}".to_string(), guest.vmid.map(|v| v.to_string()).unwrap_or_default()),
+                    ("{#NAME}".to_string(), guest.name.clone().unwrap_or_default()),
+                    ("{#STATUS}".to_string(), guest.status.clone().unwrap_or_default()),
+                ]));
+            }
+        }
+
+        println!("{}", serde_json::to_string(&discovery)?);
+        vlog_success!("Exported {} discovery entr(ies) for Zabbix", discovery.data.len());
+        Ok(())
+    }
+}
+
+/// MAC address of a `netX` config entry, `virtio=MAC` (VM) or `hwaddr=MAC` (LXC)
+fn config_mac(value: &str) -> Option<String> {
+    value.split(',')
+        .filter_map(|o| o.split_once('='))
+        .find(|(k, v)| *k == "hwaddr" || (v.len() == 17 && v.matches(':').count() == 5))
+        .map(|(_, v)| v.to_uppercase())
+}
+
+fn config_str(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Guest name as a DNS label: lowercase letters, digits and inner `-`
+fn dns_label(name: &str) -> Option<String> {
+    let label: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .take(63)
+        .collect();
+    let label = label.trim_matches('-');
+    (!label.is_empty()).then(|| label.to_string())
+}
+
+/// Terraform labels allow letters, digits, `_` and `-` and must not start
+/// with a digit; the VMID suffix keeps duplicated guest names apart
+fn terraform_label(name: &str, vmid: u32) -> String {
+    let clean: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if clean.is_empty() {
+        format!("guest_{}", vmid)
+    } else if clean.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("guest_{}_{}", clean, vmid)
+    } else {
+        format!("{}_{}", clean, vmid)
+    }
+}
+
+fn print_disk_block(guest: &ClusterResource, key: &str, value: &str) {
+    let volume = value.split(',').next().unwrap_or_default();
+    let Some((storage, _)) = volume.split_once(':') else {
+        return;
+    };
+    let size = value.split(',')
+        .find_map(|o| o.strip_prefix("size="))
+        .map(size_in_gib);
+
+    println!();
+    if guest.resource_type == "qemu" {
+        if key.starts_with("efidisk") || key.starts_with("tpmstate") {
+            println!("  # {} = \"{}\" needs an efi_disk/tpm_state block", key, value);
+            return;
+        }
+        println!("  disk {{");
+        println!("    datastore_id = \"{}\"", storage);
+        println!("    interface    = \"{}\"", key);
+        if let Some(size) = size {
+            println!("    size         = {}", size);
+        }
+        println!("  }}");
+    } else if key == "rootfs" {
+        println!("  disk {{");
+        println!("    datastore_id = \"{}\"", storage);
+        if let Some(size) = size {
+            println!("    size         = {}", size);
+        }
+        println!("  }}");
+    } else {
+        let path = value.split(',').find_map(|o| o.strip_prefix("mp=")).unwrap_or_default();
+        println!("  mount_point {{");
+        println!("    volume = \"{}\"", storage);
+        println!("    path   = \"{}\"", path);
+        if let Some(size) = size {
+            println!("    size   = \"{}G\"", size);
+        }
+        println!("  }}");
+    }
+}
+
+fn print_network_block(guest: &ClusterResource, value: &str) {
+    let option = |name: &str| {
+        value.split(',').find_map(|o| o.strip_prefix(name).and_then(|v| v.strip_prefix('=')))
+    };
+
+    println!();
+    if guest.resource_type == "qemu" {
+        println!("  network_device {{");
+    } else {
+        println!("  network_interface {{");
+        if let Some(name) = option("name") {
+            println!("    name   = \"{}\"", name);
+        }
+    }
+    if let Some(bridge) = option("bridge") {
+        println!("    bridge = \"{}\"", bridge);
+    }
+    if let Some(tag) = option("tag") {
+        println!("    vlan_id = {}", tag);
+    }
+    println!("  }}");
+}
@@ -0,0 +1,184 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # fanout.rs
+//!
+//! The nodes listing run against several clusters at once,
+//! `pvenom --profile all` or `pvenom --clusters prod,lab`. Other commands
+//! are rejected when the arguments are parsed, they act on one cluster.
+//!
+//! Every cluster is queried concurrently, results are merged with a
+//! leading `cluster` column. A cluster that cannot be reached does not hide
+//! the others, it is reported on stderr and pvenom exits with an error.
+//!
+//! CLUSTER,NODE,IP,STATUS,CPU_PERCENT,CPU_CORES,RAM_GB,HDD_GB,UPTIME_DAYS
+//! prod,tatooine,10.0.0.11,online,15.3,8,13/32,46/500,15.2
+//! lab,hoth,192.168.54.10,online,8.7,4,7/16,24/250,10.5
+
+use anyhow::{bail, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use std::collections::BTreeMap;
+use tokio::task::JoinSet;
+
+use super::{node_json_info, node_json_info_v2, Commands};
+use crate::client::ProxmoxClient;
+use crate::models::{ClusterNodeJsonInfo, ClusterNodeJsonInfoV2, MultiClusterNodeListOutput, MultiClusterNodeListOutputV2, Node, OutputFormat, OutputVersion, TimeFormat};
+use crate::{csv, csv_row, pager, timefmt, vlog_debug, vlog_error, vlog_success};
+
+/// List the nodes of every cluster, `clusters` pairs a profile name with its
+/// authenticated client or the reason it could not connect
+pub async fn list_nodes_fanout(clusters: Vec<(String, Result<ProxmoxClient>)>, output_format: OutputFormat, output_version: OutputVersion, time_format: Option<TimeFormat>) -> Result<()> {
+    let names: Vec<String> = clusters.iter().map(|(name, _)| name.clone()).collect();
+    let mut failed: BTreeMap<String, String> = BTreeMap::new();
+    let mut tasks = JoinSet::new();
+
+    for (name, client) in clusters {
+        match client {
+            Ok(client) => {
+                let commands = Commands::new(client, output_format);
+                tasks.spawn(async move {
+                    let nodes = commands.collect_nodes().await;
+                    (name, nodes)
+                });
+            }
+            Err(e) => {
+                failed.insert(name, e.to_string());
+            }
+        }
+    }
+
+    let mut rows: Vec<(String, Node)> = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined? {
+            (name, Ok(nodes)) => {
+                vlog_debug!("Cluster '{}' returned {} node(s)", name, nodes.len());
+                rows.extend(nodes.into_iter().map(|node| (name.clone(), node)));
+            }
+            (name, Err(e)) => {
+                failed.insert(name, e.to_string());
+            }
+        }
+    }
+
+    // Keep the order of the profiles, nodes sorted by name within a cluster
+    rows.sort_by(|(ca, a), (cb, b)| {
+        let pos = |c: &String| names.iter().position(|n| n == c);
+        pos(ca).cmp(&pos(cb)).then(a.node.cmp(&b.node))
+    });
+
+    match output_format {
+        OutputFormat::Json if output_version == OutputVersion::V2 => {
+            let output = MultiClusterNodeListOutputV2 {
+                output_version: 2,
+                clusters: names.clone(),
+                failed: failed.clone(),
+                nodes: rows.iter()
+                    .map(|(cluster, node)| ClusterNodeJsonInfoV2 { cluster: cluster.clone(), node: node_json_info_v2(node) })
+                    .collect(),
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        OutputFormat::Json => {
+            let output = MultiClusterNodeListOutput {
+                clusters: names.clone(),
+                failed: failed.clone(),
+                nodes: rows.iter()
+                    .map(|(cluster, node)| ClusterNodeJsonInfo { cluster: cluster.clone(), node: node_json_info(node) })
+                    .collect(),
+            };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        OutputFormat::Csv => {
+            csv_row!("CLUSTER", "NODE", "IP", "STATUS", "CPU_PERCENT", "CPU_CORES", "RAM_GB", "HDD_GB", "UPTIME_DAYS");
+            for (cluster, node) in &rows {
+                let info = node_json_info(node);
+                csv_row!(cluster, node.node, info.ipv4, node.status, csv::num(cpu_percent(node)), info.cpu,
+                         info.memory_gb, info.storage_gb, csv::num(uptime_days(node)));
+            }
+        }
+        OutputFormat::Table => {
+            let mut table = Table::new();
+            table.load_preset(UTF8_FULL)
+                 .set_content_arrangement(ContentArrangement::Dynamic);
+
+            table.set_header(vec![
+                Cell::new("Cluster").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                Cell::new("Status").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                Cell::new("CPU %").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                Cell::new("CPU Cores").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                Cell::new("RAM (GB)").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                Cell::new("HDD (GB)").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                Cell::new("Uptime").add_attribute(Attribute::Bold).fg(Color::Cyan),
+            ]);
+
+            for (cluster, node) in &rows {
+                let info = node_json_info(node);
+                let node_name_with_ip = match &node.ip {
+                    Some(ip) => format!("{}\n{}", node.node, ip),
+                    None => node.node.clone(),
+                };
+                let status_cell = if node.status == "online" {
+                    Cell::new(&node.status).fg(Color::Green)
+                } else {
+                    Cell::new(&node.status).fg(Color::Red)
+                };
+
+                table.add_row(vec![
+                    Cell::new(cluster).fg(Color::Blue),
+                    Cell::new(&node_name_with_ip),
+                    status_cell,
+                    Cell::new(cpu_percent(node)),
+                    Cell::new(&info.cpu),
+                    Cell::new(&info.memory_gb),
+                    Cell::new(&info.storage_gb),
+                    Cell::new(node.uptime.map(|u| timefmt::uptime(u, time_format)).unwrap_or_else(|| "N/A".to_string())),
+                ]);
+            }
+
+            // Unreachable clusters stay visible in the aggregate view
+            for name in failed.keys() {
+                table.add_row(vec![
+                    Cell::new(name).fg(Color::Blue),
+                    Cell::new("-"),
+                    Cell::new("unreachable").fg(Color::Red),
+                ]);
+            }
+
+            pager::print_table(&mut table);
+        }
+    }
+
+    for (name, reason) in &failed {
+        vlog_error!("Cluster '{}' failed: {}", name, reason);
+    }
+    if !failed.is_empty() {
+        bail!("{} of {} cluster(s) could not be listed", failed.len(), names.len());
+    }
+
+    vlog_success!("Listed {} node(s) across {} cluster(s)", rows.len(), names.len());
+    Ok(())
+}
+
+fn cpu_percent(node: &Node) -> String {
+    node.cpu.map(|c| format!("{:.1}", c * 100.0)).unwrap_or_else(|| "N/A".to_string())
+}
+
+fn uptime_days(node: &Node) -> String {
+    node.uptime.map(|u| format!("{:.1}", u as f64 / 86400.0)).unwrap_or_else(|| "N/A".to_string())
+}
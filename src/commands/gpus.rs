@@ -0,0 +1,223 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # gpus.rs
+//!
+//! GPUs of the cluster and the guests claiming them, `pvenom gpus`.
+//!
+//! Every display controller of every online node is listed with the
+//! mediated device types it offers and the VMs whose `hostpciN` points at
+//! it, by address or through a cluster PCI mapping. A device passed through
+//! whole to more than one VM of its node is a conflict: whichever starts
+//! second fails. Mediated devices are shared by design and only conflict
+//! with a whole passthrough. The check covers any PCI device, not just
+//! GPUs, since a doubly assigned NIC blocks a start just as well.
+
+use anyhow::Result;
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use std::collections::BTreeSet;
+
+use super::Commands;
+use crate::models::{GpusOutput, HostGpu, OutputFormat, PciMapping};
+use crate::{csv_row, pager, vlog_info, vlog_success, vlog_warn};
+
+/// A `hostpciN` entry of a VM, one per address it names
+struct Claim {
+    node: String,
+    vmid: u32,
+    /// `0000:01:00.0`, or `0000:01:00` for all functions
+    address: String,
+    mdev: bool,
+}
+
+impl Commands {
+    pub async fn gpus(&self) -> Result<()> {
+        vlog_info!("Collecting PCI devices and guest passthroughs...");
+        let mappings = self.client.get_pci_mappings().await.unwrap_or_else(|e| {
+            vlog_warn!("PCI mappings not available: {}", e);
+            Vec::new()
+        });
+        let mut claims = Vec::new();
+        for (guest, config) in self.guests_with_config().await? {
+            let (Some(node), Some(vmid)) = (guest.node, guest.vmid) else { continue };
+            if guest.resource_type != "qemu" {
+                continue;
+            }
+            for (_, value) in config.iter().filter(|(k, _)| k.starts_with("hostpci")) {
+                let Some(value) = value.as_str() else { continue };
+                claims.extend(parse_hostpci(value, &node, &mappings).into_iter().map(|(address, mdev)| Claim {
+                    node: node.clone(),
+                    vmid,
+                    address,
+                    mdev,
+                }));
+            }
+        }
+
+        let mut nodes: Vec<String> = self.client.get_cluster_resources(Some("node")).await?
+            .into_iter()
+            .filter(|n| n.status.as_deref() == Some("online"))
+            .filter_map(|n| n.node)
+            .collect();
+        nodes.sort();
+
+        let mut gpus = Vec::new();
+        let mut conflicts = Vec::new();
+        for node in &nodes {
+            let devices = match self.client.get_node_pci(node).await {
+                Ok(devices) => devices,
+                Err(e) => {
+                    vlog_warn!("No PCI devices of node '{}': {}", node, e);
+                    continue;
+                }
+            };
+            for device in devices {
+                let holders: Vec<&Claim> = claims.iter()
+                    .filter(|c| &c.node == node && claims_device(&c.address, &device.id))
+                    .collect();
+                let guests: Vec<u32> = holders.iter().map(|c| c.vmid).collect::<BTreeSet<_>>().into_iter().collect();
+                if guests.len() > 1 && holders.iter().any(|c| !c.mdev) {
+                    let vmids: Vec<String> = guests.iter().map(|v| v.to_string()).collect();
+                    conflicts.push(format!("{} on '{}' ({}) passed through to VMs {}", device.id, node,
+                                           device.device_name.as_deref().unwrap_or("unknown device"), vmids.join(", ")));
+                }
+                if !device.class.starts_with("0x03") {
+                    continue;
+                }
+                let mdev = device.mdev == Some(1);
+                let mdev_types = if mdev {
+                    match self.client.get_pci_mdev_types(node, &device.id).await {
+                        Ok(types) => types.into_iter()
+                            .map(|t| match t.name {
+                                Some(name) => format!("{} ({}), {} available", t.mdev_type, name, t.available.unwrap_or(0)),
+                                None => format!("{}, {} available", t.mdev_type, t.available.unwrap_or(0)),
+                            })
+                            .collect(),
+                        Err(e) => {
+                            vlog_warn!("No mdev types of {} on '{}': {}", device.id, node, e);
+                            Vec::new()
+                        }
+                    }
+                } else {
+                    Vec::new()
+                };
+                gpus.push(HostGpu {
+                    node: node.clone(),
+                    id: device.id,
+                    vendor: device.vendor_name,
+                    device: device.device_name,
+                    mdev,
+                    mdev_types,
+                    guests,
+                });
+            }
+        }
+
+        let output = GpusOutput { gpus, conflicts };
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&output)?),
+            OutputFormat::Csv => {
+                csv_row!("NODE", "ID", "VENDOR", "DEVICE", "MDEV", "MDEV_TYPES", "GUESTS");
+                for g in &output.gpus {
+                    let guests: Vec<String> = g.guests.iter().map(|v| v.to_string()).collect();
+                    csv_row!(g.node, g.id, g.vendor.as_deref().unwrap_or(""), g.device.as_deref().unwrap_or(""),
+                             g.mdev, g.mdev_types.join(" | "), guests.join(" "));
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("PCI ID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Device").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Mdev types").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Guests").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for g in &output.gpus {
+                    let device = match (&g.vendor, &g.device) {
+                        (Some(vendor), Some(device)) => format!("{} {}", vendor, device),
+                        (vendor, device) => vendor.clone().or(device.clone()).unwrap_or_else(|| "unknown".to_string()),
+                    };
+                    let guests: Vec<String> = g.guests.iter().map(|v| v.to_string()).collect();
+                    let conflicting = output.conflicts.iter().any(|c| c.starts_with(&format!("{} on '{}'", g.id, g.node)));
+                    table.add_row(vec![
+                        Cell::new(&g.node),
+                        Cell::new(&g.id),
+                        Cell::new(device),
+                        Cell::new(if g.mdev { g.mdev_types.join("\n") } else { "-".to_string() }),
+                        match (guests.is_empty(), conflicting) {
+                            (true, _) => Cell::new("free").fg(Color::Green),
+                            (false, true) => Cell::new(guests.join(", ")).fg(Color::Red),
+                            (false, false) => Cell::new(guests.join(", ")),
+                        },
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        if output.conflicts.is_empty() {
+            vlog_success!("No PCI device assigned twice");
+        }
+        for conflict in &output.conflicts {
+            vlog_warn!("{}", conflict);
+        }
+        Ok(())
+    }
+}
+
+/// Addresses of a `hostpciN` value on `node`, each with whether it is a
+/// mediated device: `0000:01:00,pcie=1`, `host=01:00.0;02:00.0,mdev=...`
+/// or `mapping=gpu,...`
+fn parse_hostpci(value: &str, node: &str, mappings: &[PciMapping]) -> Vec<(String, bool)> {
+    let mut hosts = Vec::new();
+    let mut mdev = false;
+    for (i, part) in value.split(',').enumerate() {
+        match part.split_once('=') {
+            Some(("host", host)) => hosts.push(host.to_string()),
+            Some(("mdev", _)) => mdev = true,
+            Some(("mapping", name)) => {
+                let paths = mappings.iter()
+                    .filter(|m| m.id == name)
+                    .flat_map(|m| m.map.iter())
+                    .find(|entry| entry.split(',').any(|kv| kv == format!("node={}", node)))
+                    .and_then(|entry| entry.split(',').find_map(|kv| kv.strip_prefix("path=")));
+                match paths {
+                    Some(paths) => hosts.push(paths.to_string()),
+                    None => vlog_warn!("PCI mapping '{}' has no device on '{}'", name, node),
+                }
+            }
+            None if i == 0 => hosts.push(part.to_string()),
+            _ => {}
+        }
+    }
+    hosts.iter()
+        .flat_map(|h| h.split(';'))
+        .map(|address| match address.matches(':').count() {
+            1 => (format!("0000:{}", address), mdev),
+            _ => (address.to_string(), mdev),
+        })
+        .collect()
+}
+
+/// `claim` names `device`, or all the functions of its slot
+fn claims_device(claim: &str, device: &str) -> bool {
+    claim == device || (!claim.contains('.') && device.strip_prefix(claim).is_some_and(|f| f.starts_with('.')))
+}
@@ -0,0 +1,204 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # grafana.rs
+//!
+//! Grafana simple-json datasource endpoints of `pvenom serve`, point the
+//! datasource URL to `http://<host>:8088/grafana`.
+//!
+//! GET  /grafana              connection test
+//! POST /grafana/search       metric names
+//! POST /grafana/query        time series kept by serve, or tables
+//! POST /grafana/annotations  always empty
+//!
+//! Metric names:
+//!
+//! node.<name>.cpu | node.<name>.mem | node.<name>.disk      percent
+//! guest.<vmid>.cpu | guest.<vmid>.mem                       percent
+//! cluster.nodes_online | cluster.guests_running             counts
+//! nodes | guests | storage                                  tables
+//!
+//! The Infinity datasource needs nothing special: it reads /api/v1/* as is.
+
+use serde_json::{json, Value};
+
+use super::serve::{ServeSnapshot, ServeState};
+use crate::http::HttpRequest;
+use crate::models::ClusterResource;
+
+const TABLES: [&str; 3] = ["nodes", "guests", "storage"];
+
+pub(super) fn route(request: &HttpRequest, state: &ServeState) -> (u16, Value) {
+    let path = request.path.trim_end_matches('/');
+    match (request.method.as_str(), path) {
+        ("GET", "/grafana") => (200, json!({ "status": "ok" })),
+        ("POST", "/grafana/search") => (200, json!(search(state.current.as_ref()))),
+        ("POST", "/grafana/annotations") => (200, json!([])),
+        ("POST", "/grafana/query") => match serde_json::from_slice::<Value>(&request.body) {
+            Ok(body) => (200, query(&body, state)),
+            Err(_) => (400, json!({ "error": "invalid query body" })),
+        },
+        (_, "/grafana" | "/grafana/search" | "/grafana/annotations" | "/grafana/query") => {
+            (405, json!({ "error": "method not allowed" }))
+        }
+        _ => (404, json!({ "error": "not found" })),
+    }
+}
+
+fn search(current: Option<&ServeSnapshot>) -> Vec<String> {
+    let mut metrics: Vec<String> = vec!["cluster.nodes_online".to_string(), "cluster.guests_running".to_string()];
+    if let Some(snapshot) = current {
+        for node in snapshot.nodes.iter().filter_map(|n| n.node.as_ref()) {
+            for metric in ["cpu", "mem", "disk"] {
+                metrics.push(format!("node.{}.{}", node, metric));
+            }
+        }
+        for vmid in snapshot.guests.iter().filter_map(|g| g.vmid) {
+            for metric in ["cpu", "mem"] {
+                metrics.push(format!("guest.{}.{}", vmid, metric));
+            }
+        }
+    }
+    metrics.extend(TABLES.iter().map(|t| t.to_string()));
+    metrics
+}
+
+fn query(body: &Value, state: &ServeState) -> Value {
+    let from = parse_time_ms(&body["range"]["from"]).unwrap_or(0);
+    let to = parse_time_ms(&body["range"]["to"]).unwrap_or(u64::MAX);
+
+    let results: Vec<Value> = body["targets"].as_array().into_iter().flatten()
+        .filter_map(|t| t["target"].as_str())
+        .map(|target| {
+            if TABLES.contains(&target) {
+                table(target, state.current.as_ref())
+            } else {
+                let datapoints: Vec<Value> = state.series.get(target).into_iter().flatten()
+                    .filter(|(updated, _)| (from..=to).contains(&(updated * 1000)))
+                    .map(|(updated, value)| json!([value, updated * 1000]))
+                    .collect();
+                json!({ "target": target, "datapoints": datapoints })
+            }
+        })
+        .collect();
+
+    json!(results)
+}
+
+/// Grafana sends RFC 3339 times (`2025-01-01T10:00:00.000Z`), or epoch ms
+fn parse_time_ms(value: &Value) -> Option<u64> {
+    if let Some(ms) = value.as_u64() {
+        return Some(ms);
+    }
+    let s = value.as_str()?;
+    let (date, time) = s.trim_end_matches('Z').split_once('T')?;
+    let mut d = date.split('-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (d.next()??, d.next()??, d.next()??);
+    let mut t = time.split(':');
+    let hour: i64 = t.next()?.parse().ok()?;
+    let minute: i64 = t.next()?.parse().ok()?;
+    let seconds: f64 = t.next().unwrap_or("0").parse().ok()?;
+
+    // Days from civil date, proleptic Gregorian calendar
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    let secs = days * 86_400 + hour * 3_600 + minute * 60;
+    Some((secs as f64 * 1000.0 + seconds * 1000.0) as u64)
+}
+
+fn percent(used: Option<u64>, total: Option<u64>) -> Option<f64> {
+    match (used, total) {
+        (Some(u), Some(t)) if t > 0 => Some(u as f64 * 100.0 / t as f64),
+        _ => None,
+    }
+}
+
+/// Every time series metric of a snapshot, what serve keeps of it
+pub(super) fn metrics(snapshot: &ServeSnapshot) -> Vec<(String, f64)> {
+    let mut metrics = vec![
+        ("cluster.nodes_online".to_string(),
+         snapshot.nodes.iter().filter(|n| n.status.as_deref() == Some("online")).count() as f64),
+        ("cluster.guests_running".to_string(),
+         snapshot.guests.iter().filter(|g| g.status.as_deref() == Some("running")).count() as f64),
+    ];
+    for node in &snapshot.nodes {
+        let Some(name) = node.node.as_deref() else { continue };
+        for metric in ["cpu", "mem", "disk"] {
+            metrics.extend(resource_metric(node, metric).map(|v| (format!("node.{}.{}", name, metric), v)));
+        }
+    }
+    for guest in &snapshot.guests {
+        let Some(vmid) = guest.vmid else { continue };
+        for metric in ["cpu", "mem"] {
+            metrics.extend(resource_metric(guest, metric).map(|v| (format!("guest.{}.{}", vmid, metric), v)));
+        }
+    }
+    metrics
+}
+
+fn resource_metric(resource: &ClusterResource, metric: &str) -> Option<f64> {
+    match metric {
+        "cpu" => resource.cpu.map(|c| c * 100.0),
+        "mem" => percent(resource.mem, resource.maxmem),
+        "disk" => percent(resource.disk, resource.maxdisk),
+        _ => None,
+    }
+}
+
+fn table(target: &str, snapshot: Option<&ServeSnapshot>) -> Value {
+    let resources: &[ClusterResource] = match (target, snapshot) {
+        ("nodes", Some(s)) => &s.nodes,
+        ("guests", Some(s)) => &s.guests,
+        ("storage", Some(s)) => &s.storage,
+        _ => &[],
+    };
+
+    let rows: Vec<Value> = resources.iter()
+        .map(|r| json!([
+            r.name.as_deref().or(r.storage.as_deref()).or(r.node.as_deref()),
+            r.node,
+            r.vmid,
+            r.resource_type,
+            r.status,
+            r.cpu.map(|c| c * 100.0),
+            percent(r.mem, r.maxmem),
+            percent(r.disk, r.maxdisk),
+        ]))
+        .collect();
+
+    json!({
+        "type": "table",
+        "columns": [
+            { "text": "Name", "type": "string" },
+            { "text": "Node", "type": "string" },
+            { "text": "VMID", "type": "number" },
+            { "text": "Type", "type": "string" },
+            { "text": "Status", "type": "string" },
+            { "text": "CPU %", "type": "number" },
+            { "text": "RAM %", "type": "number" },
+            { "text": "Disk %", "type": "number" },
+        ],
+        "rows": rows,
+    })
+}
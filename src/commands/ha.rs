@@ -0,0 +1,140 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # ha.rs
+//!
+//! High availability, `pvenom ha ...`.
+//!
+//! `ha groups` lists every HA group with its nodes by priority, highest
+//! first, and the resources bound to it, so where a guest fails over to can
+//! be reviewed before a node goes down. Restricted groups never run their
+//! resources outside the listed nodes; resources without a group may land
+//! on any node and are listed on their own row.
+
+use anyhow::Result;
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+
+use super::Commands;
+use crate::models::{HaGroupMember, HaGroupOutput, OutputFormat};
+use crate::{csv_row, pager, vlog_success};
+
+/// Group name of the resources without a group
+const NO_GROUP: &str = "(none)";
+
+impl Commands {
+    pub async fn ha_groups(&self) -> Result<()> {
+        let resources = self.client.get_ha_resources().await?;
+        let mut groups: Vec<HaGroupOutput> = self.client.get_ha_groups().await?
+            .into_iter()
+            .map(|g| {
+                let nodes = group_members(&g.nodes);
+                let mut members: Vec<String> = resources.iter()
+                    .filter(|r| r.group.as_deref() == Some(g.group.as_str()))
+                    .map(|r| r.sid.clone())
+                    .collect();
+                members.sort();
+                HaGroupOutput {
+                    group: g.group,
+                    nodes,
+                    restricted: g.restricted == Some(1),
+                    nofailback: g.nofailback == Some(1),
+                    resources: members,
+                    comment: g.comment,
+                }
+            })
+            .collect();
+        groups.sort_by(|a, b| a.group.cmp(&b.group));
+
+        let mut ungrouped: Vec<String> = resources.iter()
+            .filter(|r| r.group.is_none())
+            .map(|r| r.sid.clone())
+            .collect();
+        ungrouped.sort();
+        if !ungrouped.is_empty() {
+            groups.push(HaGroupOutput {
+                group: NO_GROUP.to_string(),
+                nodes: Vec::new(),
+                restricted: false,
+                nofailback: false,
+                resources: ungrouped,
+                comment: None,
+            });
+        }
+
+        let nodes = |g: &HaGroupOutput| g.nodes.iter()
+            .map(|n| format!("{}:{}", n.node, n.priority))
+            .collect::<Vec<_>>();
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&groups)?),
+            OutputFormat::Csv => {
+                csv_row!("GROUP", "NODES", "RESTRICTED", "NOFAILBACK", "RESOURCES");
+                for g in &groups {
+                    csv_row!(g.group, nodes(g).join(" "), g.restricted, g.nofailback, g.resources.join(" "));
+                }
+            }
+            OutputFormat::Table => {
+                if groups.is_empty() {
+                    println!("No HA groups and no HA resources.");
+                    return Ok(());
+                }
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Group").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Nodes (priority)").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Restricted").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("No Failback").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Resources").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for g in &groups {
+                    let resources = match g.resources.is_empty() {
+                        true => Cell::new("-").fg(Color::Yellow),
+                        false => Cell::new(g.resources.join(", ")),
+                    };
+                    let yes_no = |b: bool| if b { "yes" } else { "no" };
+                    table.add_row(vec![
+                        Cell::new(&g.group),
+                        Cell::new(if g.nodes.is_empty() { "any".to_string() } else { nodes(g).join(", ") }),
+                        Cell::new(yes_no(g.restricted)),
+                        Cell::new(yes_no(g.nofailback)),
+                        resources,
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        vlog_success!("Listed {} HA group(s), {} HA resource(s)", groups.iter().filter(|g| g.group != NO_GROUP).count(), resources.len());
+        Ok(())
+    }
+}
+
+/// Nodes of a group, `pve1:2,pve2`, highest priority first; priority 0
+/// when omitted
+pub(super) fn group_members(nodes: &str) -> Vec<HaGroupMember> {
+    let mut members: Vec<HaGroupMember> = nodes.split(',')
+        .filter(|n| !n.is_empty())
+        .map(|n| match n.split_once(':') {
+            Some((node, priority)) => HaGroupMember { node: node.to_string(), priority: priority.parse().unwrap_or(0) },
+            None => HaGroupMember { node: n.to_string(), priority: 0 },
+        })
+        .collect();
+    members.sort_by(|a, b| b.priority.cmp(&a.priority).then(a.node.cmp(&b.node)));
+    members
+}
@@ -0,0 +1,168 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # ical.rs
+//!
+//! `pvenom export ical > pve.ics`: the backup jobs of the cluster and the
+//! recurring maintenance windows of the config file as an iCalendar feed,
+//! for the team calendar.
+//!
+//! Backup calendar events are expanded by PVE itself (`schedule-analyze`),
+//! so every systemd-style spec the cluster accepts comes out right. Each
+//! run becomes an event of `--duration`, backup jobs having no planned
+//! length; maintenance windows keep their own. Events cover the next
+//! `--days`, at most 100 per job. Replication jobs, running every few
+//! minutes, would drown the calendar and are left out.
+
+use anyhow::Result;
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::Commands;
+use crate::models::BackupJob;
+use crate::timefmt;
+use crate::{vlog_info, vlog_warn};
+
+/// Runs of a job asked to PVE, its upper limit
+const MAX_RUNS: u32 = 100;
+
+/// Lines longer than this many octets are folded, RFC 5545
+const FOLD_AT: usize = 75;
+
+struct Event {
+    uid: String,
+    start: u64,
+    end: u64,
+    summary: String,
+    description: String,
+}
+
+impl Commands {
+    /// Print the feed of the next `days` days, backup runs lasting `duration` seconds
+    pub async fn export_ical(&self, days: u64, duration: u64) -> Result<()> {
+        vlog_info!("Collecting backup jobs and maintenance windows...");
+        let cluster = self.cluster_name().await?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let horizon = now + days * 86_400;
+        let mut events = Vec::new();
+
+        let jobs = self.client.get_backup_jobs().await?;
+        for job in jobs.iter().filter(|j| j.enabled != Some(0)) {
+            let Some(schedule) = &job.schedule else { continue };
+            let runs = match self.client.analyze_schedule(schedule, now, MAX_RUNS).await {
+                Ok(runs) => runs,
+                Err(e) => {
+                    vlog_warn!("Cannot expand the schedule '{}' of backup job {}: {}", schedule, job.id, e);
+                    continue;
+                }
+            };
+            for start in runs.into_iter().filter(|t| *t < horizon) {
+                events.push(Event {
+                    uid: format!("backup-{}-{}@{}.pvenom", job.id, start, cluster),
+                    start,
+                    end: start + duration,
+                    summary: format!("Backup {}", job.comment.as_deref().unwrap_or(&job.id)),
+                    description: backup_description(job, schedule),
+                });
+            }
+        }
+
+        match self.maintenance_windows() {
+            Some(windows) => {
+                for (target, schedule, length) in windows.recurring() {
+                    let mut after = now;
+                    for _ in 0..MAX_RUNS {
+                        let Some(start) = schedule.next_after(after).filter(|t| *t < horizon) else { break };
+                        events.push(Event {
+                            uid: format!("maintenance-{}-{}@{}.pvenom", target.to_string().replace(' ', "-"), start, cluster),
+                            start,
+                            end: start + length,
+                            summary: format!("Maintenance {}", target),
+                            description: format!("Cron: {}", schedule.cron()),
+                        });
+                        after = start;
+                    }
+                }
+            }
+            None => vlog_warn!("Maintenance windows left out of the feed"),
+        }
+        events.sort_by_key(|e| e.start);
+
+        let stamp = ical_time(now);
+        let mut ics = String::new();
+        let mut line = |text: String| fold(&mut ics, &text);
+        line("BEGIN:VCALENDAR".to_string());
+        line("VERSION:2.0".to_string());
+        line(format!("PRODID:-//pvenom//pvenom {}//EN", env!("CARGO_PKG_VERSION")));
+        line(format!("X-WR-CALNAME:{}", escape(&format!("Proxmox {}", cluster))));
+        for event in &events {
+            line("BEGIN:VEVENT".to_string());
+            line(format!("UID:{}", event.uid));
+            line(format!("DTSTAMP:{}", stamp));
+            line(format!("DTSTART:{}", ical_time(event.start)));
+            line(format!("DTEND:{}", ical_time(event.end)));
+            line(format!("SUMMARY:{}", escape(&event.summary)));
+            line(format!("DESCRIPTION:{}", escape(&event.description)));
+            line("END:VEVENT".to_string());
+        }
+        line("END:VCALENDAR".to_string());
+        print!("{}", ics);
+        vlog_info!("{} event(s) in the next {} day(s)", events.len(), days);
+        Ok(())
+    }
+}
+
+fn backup_description(job: &BackupJob, schedule: &str) -> String {
+    let guests = match (job.all, &job.vmid, &job.pool) {
+        (Some(1), Some(excluded), _) => format!("all guests but {}", excluded),
+        (Some(1), None, _) => "all guests".to_string(),
+        (_, _, Some(pool)) => format!("pool {}", pool),
+        (_, Some(vmids), _) => format!("guests {}", vmids),
+        _ => "no guests".to_string(),
+    };
+    let mut text = format!("Job {}: {} to {}, schedule {}", job.id, guests, job.storage.as_deref().unwrap_or("default storage"), schedule);
+    if let Some(node) = &job.node {
+        text.push_str(&format!(", node {} only", node));
+    }
+    text
+}
+
+/// UTC date-time form, `20250101T013000Z`
+fn ical_time(epoch: u64) -> String {
+    let (year, month, day, hour, minute, second) = timefmt::civil(epoch);
+    format!("{:04}{:02}{:02}T{:02}{:02}{:02}Z", year, month, day, hour, minute, second)
+}
+
+/// TEXT value escaping
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+/// Append a content line, CRLF terminated and folded at 75 octets
+fn fold(out: &mut String, text: &str) {
+    let mut width = 0;
+    for c in text.chars() {
+        if width + c.len_utf8() > FOLD_AT {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    let _ = write!(out, "\r\n");
+}
@@ -0,0 +1,139 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # inventory.rs
+//!
+//! What runs inside the guests, `pvenom inventory ...`.
+//!
+//! `inventory os` asks the guest agent of every running VM for its OS
+//! release, kernel, hostname and filesystems, sorted by OS and version so
+//! the guests to patch together end up next to each other. VMs without a
+//! responding agent are listed too, as unknown, so nothing silently falls
+//! out of the patch plan. Containers have no agent and are not listed.
+
+use anyhow::Result;
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+
+use super::{wide_guest_cells, Commands, WIDE_GUEST_COLUMNS};
+use crate::models::{GuestOs, OutputFormat};
+use crate::{csv_row, pager, vlog_info, vlog_success};
+
+impl Commands {
+    pub async fn inventory_os(&self) -> Result<()> {
+        vlog_info!("Querying the guest agents of the running VMs...");
+        let mut guests: Vec<_> = self.client.get_cluster_resources(Some("vm")).await?
+            .into_iter()
+            .filter(|r| r.resource_type == "qemu" && r.status.as_deref() == Some("running"))
+            .collect();
+        guests.sort_by_key(|g| g.vmid);
+
+        let mut rows = Vec::new();
+        for guest in guests {
+            let (Some(node), Some(vmid)) = (guest.node.as_deref(), guest.vmid) else {
+                continue;
+            };
+            let os = self.client.get_agent_result(node, vmid, "get-osinfo").await;
+            let host = self.client.get_agent_result(node, vmid, "get-host-name").await;
+            let field = |key: &str| os.as_ref().and_then(|o| o[key].as_str()).map(str::to_string);
+            rows.push(GuestOs {
+                vmid,
+                name: guest.name.clone().unwrap_or_else(|| "N/A".to_string()),
+                node: node.to_string(),
+                agent: os.is_some(),
+                hostname: host.and_then(|h| h["host-name"].as_str().map(str::to_string)),
+                os_id: field("id"),
+                os: field("pretty-name").or_else(|| field("name")),
+                version: field("version-id").or_else(|| field("version")),
+                kernel: field("kernel-release"),
+                machine: field("machine"),
+                filesystems: self.client.get_guest_filesystems(node, vmid).await.unwrap_or_default(),
+            });
+        }
+        rows.sort_by(|a, b| {
+            (a.os.is_none(), &a.os_id, &a.version, a.vmid).cmp(&(b.os.is_none(), &b.os_id, &b.version, b.vmid))
+        });
+
+        let gb = |bytes: u64| bytes as f64 / 1024.0 / 1024.0 / 1024.0;
+        let root = |r: &GuestOs| r.filesystems.iter()
+            .find(|f| f.mountpoint == "/" || f.mountpoint == "C:\\")
+            .map(|f| format!("{:.1}/{:.1} GB", gb(f.used_bytes), gb(f.total_bytes)));
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+            OutputFormat::Csv => {
+                csv_row!("VMID", "NAME", "NODE", "HOSTNAME", "OS_ID", "OS", "VERSION", "KERNEL", "MACHINE", "ROOT_FS");
+                for r in &rows {
+                    csv_row!(r.vmid, r.name, r.node, r.hostname.as_deref().unwrap_or(""),
+                             r.os_id.as_deref().unwrap_or(""), r.os.as_deref().unwrap_or(""),
+                             r.version.as_deref().unwrap_or(""), r.kernel.as_deref().unwrap_or(""),
+                             r.machine.as_deref().unwrap_or(""), root(r).unwrap_or_default());
+                }
+            }
+            OutputFormat::Table => {
+                if rows.is_empty() {
+                    println!("No running VMs.");
+                    return Ok(());
+                }
+                let wide_guests = self.wide_guests().await;
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                let mut header = vec![
+                    Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Hostname").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("OS").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Version").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Kernel").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Root FS").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ];
+                if self.wide {
+                    header.extend(WIDE_GUEST_COLUMNS.iter()
+                        .map(|c| Cell::new(c).add_attribute(Attribute::Bold).fg(Color::Cyan)));
+                }
+                table.set_header(header);
+                for r in &rows {
+                    let os = match &r.os {
+                        Some(os) => Cell::new(os),
+                        None => Cell::new("unknown, no agent").fg(Color::Yellow),
+                    };
+                    let mut row = vec![
+                        Cell::new(r.vmid),
+                        Cell::new(&r.name),
+                        Cell::new(&r.node),
+                        Cell::new(r.hostname.as_deref().unwrap_or("-")),
+                        os,
+                        Cell::new(r.version.as_deref().unwrap_or("-")),
+                        Cell::new(r.kernel.as_deref().unwrap_or("-")),
+                        Cell::new(root(r).unwrap_or_else(|| "-".to_string())),
+                    ];
+                    if self.wide {
+                        row.extend(wide_guest_cells(wide_guests.get(&r.vmid), self.time_format));
+                    }
+                    table.add_row(row);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        let unknown = rows.iter().filter(|r| !r.agent).count();
+        vlog_success!("Inventoried {} VM(s), {} without agent", rows.len(), unknown);
+        Ok(())
+    }
+}
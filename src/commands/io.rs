@@ -0,0 +1,258 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # io.rs
+//!
+//! Storage I/O pressure of a node, `pvenom node <name> io`.
+//!
+//! Running guests are listed busiest first with their disk throughput (the
+//! last one minute RRD average) and, for VMs, their IOPS measured over one
+//! second from the QEMU block counters. Containers have no such counters.
+//! Below them the storages of the node and how full they are, and on top
+//! the node iowait, to tell a slow disk from a noisy guest.
+//!
+//! LVM thin storages also show the data and metadata usage of their pool:
+//! a pool out of metadata goes read-only long before its data is full.
+
+use anyhow::Result;
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use super::{format_rate, Commands};
+use crate::models::{GuestIo, NodeIoOutput, OutputFormat, StorageIo};
+use crate::{csv_row, pager, vlog_debug, vlog_success, vlog_warn};
+
+/// Seconds between the two block counter samples
+const IOPS_SAMPLE_SECS: u64 = 1;
+
+/// Thin pool usage shown as a warning, then as critical, percent
+const THIN_WARN_PERCENT: f64 = 80.0;
+const THIN_CRIT_PERCENT: f64 = 90.0;
+
+impl Commands {
+    pub async fn show_node_io(&self, node: &str) -> Result<()> {
+        let resources = self.client.get_cluster_resources(None).await?;
+        let running: Vec<_> = resources.iter()
+            .filter(|r| r.is_guest() && r.node.as_deref() == Some(node) && r.status.as_deref() == Some("running"))
+            .collect();
+        vlog_debug!("Sampling I/O of {} running guest(s) on '{}'", running.len(), node);
+
+        // Block counters of the VMs, twice, to turn them into rates. Each VM
+        // is sampled at its own time, the rate uses its own interval
+        let mut first_ops = HashMap::new();
+        for guest in running.iter().filter(|g| g.resource_type == "qemu") {
+            let vmid = guest.vmid.unwrap_or_default();
+            match self.client.get_guest_disk_ops(node, vmid).await {
+                Ok(ops) => { first_ops.insert(vmid, (ops, Instant::now())); }
+                Err(e) => vlog_warn!("No block counters for VM {}: {}", vmid, e),
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(IOPS_SAMPLE_SECS)).await;
+
+        let mut iops = HashMap::new();
+        for (vmid, ((read, write), sampled)) in &first_ops {
+            if let Ok((read2, write2)) = self.client.get_guest_disk_ops(node, *vmid).await {
+                let secs = sampled.elapsed().as_secs_f64();
+                iops.insert(*vmid, (read2.saturating_sub(*read) as f64 / secs, write2.saturating_sub(*write) as f64 / secs));
+            }
+        }
+
+        let mut guests = Vec::new();
+        for guest in &running {
+            let vmid = guest.vmid.unwrap_or_default();
+            let throughput = match self.client.get_guest_rrddata(node, &guest.resource_type, vmid, "hour").await {
+                Ok(points) => points.iter().rev().find_map(|p| p.diskread.zip(p.diskwrite)),
+                Err(e) => {
+                    vlog_warn!("No RRD data for guest {}: {}", vmid, e);
+                    None
+                }
+            };
+            guests.push(GuestIo {
+                vmid,
+                name: guest.name.clone().unwrap_or_else(|| "N/A".to_string()),
+                guest_type: guest.guest_type().to_string(),
+                read_bps: throughput.map(|t| t.0),
+                write_bps: throughput.map(|t| t.1),
+                read_iops: iops.get(&vmid).map(|i| i.0),
+                write_iops: iops.get(&vmid).map(|i| i.1),
+            });
+        }
+        let total = |a: Option<f64>, b: Option<f64>| a.unwrap_or(0.0) + b.unwrap_or(0.0);
+        guests.sort_by(|a, b| {
+            total(b.read_iops, b.write_iops).total_cmp(&total(a.read_iops, a.write_iops))
+                .then(total(b.read_bps, b.write_bps).total_cmp(&total(a.read_bps, a.write_bps)))
+        });
+
+        let gb = |bytes: Option<u64>| (bytes.unwrap_or(0) as f64 / 1024.0 / 1024.0 / 1024.0 * 10.0).round() / 10.0;
+        let mut storages: Vec<StorageIo> = resources.iter()
+            .filter(|r| r.resource_type == "storage" && r.node.as_deref() == Some(node))
+            .map(|r| StorageIo {
+                storage: r.storage.clone().unwrap_or_default(),
+                storage_type: r.plugintype.clone().unwrap_or_else(|| "N/A".to_string()),
+                status: r.status.clone().unwrap_or_else(|| "unknown".to_string()),
+                shared: r.shared == Some(1),
+                used_gb: gb(r.disk),
+                total_gb: gb(r.maxdisk),
+                thin_data_percent: None,
+                thin_metadata_percent: None,
+            })
+            .collect();
+        storages.sort_by(|a, b| a.storage.cmp(&b.storage));
+
+        // Thin pools behind the lvmthin storages
+        if storages.iter().any(|s| s.storage_type == "lvmthin" && s.status == "available") {
+            match self.client.get_node_lvmthin(node).await {
+                Ok(pools) => {
+                    let percent = |used: u64, size: u64| (size > 0).then(|| (used as f64 / size as f64 * 1000.0).round() / 10.0);
+                    for storage in storages.iter_mut().filter(|s| s.storage_type == "lvmthin") {
+                        let config = match self.client.get_storage_config(&storage.storage).await {
+                            Ok(config) => config,
+                            Err(e) => {
+                                vlog_warn!("No config for storage '{}': {}", storage.storage, e);
+                                continue;
+                            }
+                        };
+                        let vgname = config.get("vgname").and_then(|v| v.as_str());
+                        let thinpool = config.get("thinpool").and_then(|v| v.as_str());
+                        let Some(pool) = pools.iter().find(|p| Some(p.lv.as_str()) == thinpool
+                            && p.vg.as_deref().is_none_or(|vg| Some(vg) == vgname)) else {
+                            continue;
+                        };
+                        storage.thin_data_percent = percent(pool.used, pool.lv_size);
+                        storage.thin_metadata_percent = percent(pool.metadata_used, pool.metadata_size);
+                        for (what, value) in [("data", storage.thin_data_percent), ("metadata", storage.thin_metadata_percent)] {
+                            if let Some(value) = value.filter(|v| *v >= THIN_WARN_PERCENT) {
+                                vlog_warn!("Thin pool of storage '{}' has {:.1}% of its {} used", storage.storage, value, what);
+                            }
+                        }
+                    }
+                }
+                Err(e) => vlog_warn!("No LVM thin pools of node '{}': {}", node, e),
+            }
+        }
+
+        let iowait_percent = match self.client.get_node_rrddata(node, "hour").await {
+            Ok(points) => points.iter().rev().find_map(|p| p.iowait).map(|w| (w * 1000.0).round() / 10.0),
+            Err(e) => {
+                vlog_warn!("No RRD data for node '{}': {}", node, e);
+                None
+            }
+        };
+
+        let output = NodeIoOutput { node: node.to_string(), iowait_percent, guests, storages };
+        let rate = |bytes: Option<f64>| bytes.map(format_rate).unwrap_or_else(|| "-".to_string());
+        let ops = |ops: Option<f64>| ops.map(|o| format!("{:.0}", o)).unwrap_or_else(|| "-".to_string());
+
+        match self.output_format {
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            }
+            OutputFormat::Csv => {
+                // Guests only, like the node detail view
+                csv_row!("VMID", "NAME", "TYPE", "READ_BPS", "WRITE_BPS", "READ_IOPS", "WRITE_IOPS");
+                let number = |value: Option<f64>| value.map(|v| format!("{:.0}", v)).unwrap_or_else(|| "N/A".to_string());
+                for guest in &output.guests {
+                    csv_row!(guest.vmid, guest.name, guest.guest_type, number(guest.read_bps),
+                             number(guest.write_bps), number(guest.read_iops), number(guest.write_iops));
+                }
+            }
+            OutputFormat::Table => {
+                match output.iowait_percent {
+                    Some(iowait) => println!("\n=== I/O of {} (iowait {:.1}%) ===\n", node, iowait),
+                    None => println!("\n=== I/O of {} ===\n", node),
+                }
+
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Type").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Read").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Write").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Read IOPS").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Write IOPS").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for guest in &output.guests {
+                    table.add_row(vec![
+                        Cell::new(guest.vmid),
+                        Cell::new(&guest.name),
+                        Cell::new(&guest.guest_type),
+                        Cell::new(rate(guest.read_bps)),
+                        Cell::new(rate(guest.write_bps)),
+                        Cell::new(ops(guest.read_iops)),
+                        Cell::new(ops(guest.write_iops)),
+                    ]);
+                }
+                pager::print_table(&mut table);
+
+                println!("\n=== Storages ===\n");
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Storage").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Type").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Status").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Shared").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Used (GB)").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Thin data/meta").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for storage in &output.storages {
+                    let status = if storage.status == "available" {
+                        Cell::new(&storage.status).fg(Color::Green)
+                    } else {
+                        Cell::new(&storage.status).fg(Color::Red)
+                    };
+                    let used = if storage.total_gb > 0.0 {
+                        format!("{:.1}/{:.1} ({:.0}%)", storage.used_gb, storage.total_gb, storage.used_gb / storage.total_gb * 100.0)
+                    } else {
+                        "N/A".to_string()
+                    };
+                    table.add_row(vec![
+                        Cell::new(&storage.storage),
+                        Cell::new(&storage.storage_type),
+                        status,
+                        Cell::new(if storage.shared { "yes" } else { "no" }),
+                        Cell::new(used),
+                        thin_cell(storage.thin_data_percent, storage.thin_metadata_percent),
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        vlog_success!("I/O of {} guest(s) on '{}' displayed", output.guests.len(), node);
+        Ok(())
+    }
+}
+
+/// `data%/metadata%` of a thin pool, colored by the fuller of the two
+fn thin_cell(data: Option<f64>, metadata: Option<f64>) -> Cell {
+    let (Some(data), Some(metadata)) = (data, metadata) else {
+        return Cell::new("-");
+    };
+    let cell = Cell::new(format!("{:.0}%/{:.0}%", data, metadata));
+    match data.max(metadata) {
+        p if p >= THIN_CRIT_PERCENT => cell.fg(Color::Red),
+        p if p >= THIN_WARN_PERCENT => cell.fg(Color::Yellow),
+        _ => cell,
+    }
+}
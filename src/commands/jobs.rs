@@ -0,0 +1,381 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+
+//! # jobs.rs
+//!
+//! Second chance for the scheduled jobs whose last run failed, `pvenom
+//! jobs retry-failed [--type replication|backup]`.
+//!
+//! Replication jobs keep their failures in the job state: they are run
+//! again with `schedule_now` and followed until the new attempt ends.
+//! Backup jobs keep no state, so the vzdump task logs of the last days
+//! tell which guests failed their latest backup; every job covering some
+//! of them runs again on the guests' node, limited to those guests.
+
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use super::Commands;
+use crate::models::{BackupJob, ClusterResource, JobRetry, OutputFormat, ReplicationState};
+use crate::{csv_row, pager, vlog_debug, vlog_info, vlog_success, vlog_warn};
+
+/// History searched for failed backups, a weekly job and a day of slack
+const BACKUP_LOOKBACK_SECS: u64 = 8 * 86400;
+
+/// Pause between two looks at a replication job
+const REPLICATION_POLL: Duration = Duration::from_secs(5);
+
+/// Backup job options choosing the guests and the schedule, not passed
+/// on to vzdump
+const BACKUP_JOB_KEYS: &[&str] = &[
+    "id", "type", "schedule", "starttime", "dow", "enabled", "comment", "node",
+    "all", "exclude", "pool", "vmid", "next-run", "repeat-missed",
+];
+
+/// Kind of job of `jobs retry-failed --type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum JobKind {
+    Replication,
+    Backup,
+}
+
+impl JobKind {
+    fn name(self) -> &'static str {
+        match self {
+            JobKind::Replication => "replication",
+            JobKind::Backup => "backup",
+        }
+    }
+}
+
+/// Job whose last run failed
+struct FailedJob {
+    kind: JobKind,
+    job: String,
+    node: String,
+    guests: Vec<u32>,
+    error: Option<String>,
+}
+
+impl Commands {
+    /// Run again the replication and backup jobs whose last run failed,
+    /// one at a time, and report how each retry went
+    pub async fn retry_failed_jobs(&self, kind: Option<JobKind>, timeout: u64) -> Result<()> {
+        let resources = self.client.get_cluster_resources(None).await?;
+        let online: Vec<String> = resources.iter()
+            .filter(|r| r.resource_type == "node" && r.status.as_deref() == Some("online"))
+            .filter_map(|r| r.node.clone())
+            .collect();
+
+        let mut failed = Vec::new();
+        if kind != Some(JobKind::Backup) {
+            failed.extend(self.failed_replications(&online).await?);
+        }
+        if kind != Some(JobKind::Replication) {
+            failed.extend(self.failed_backups(&online, &resources).await?);
+        }
+        if !failed.is_empty() {
+            self.confirm("retry", &format!("{} failed job(s)", failed.len()))?;
+        }
+
+        let mut retries = Vec::new();
+        for job in failed {
+            vlog_info!("Retrying {} job '{}' on node '{}'...", job.kind.name(), job.job, job.node);
+            let (upid, outcome) = match job.kind {
+                JobKind::Replication => (None, self.retry_replication(&job.node, &job.job, timeout).await),
+                JobKind::Backup => match self.retry_backup(&job).await {
+                    Ok(upid) => {
+                        let outcome = self.wait_for_task(&upid, timeout).await;
+                        (Some(upid).filter(|u| !u.is_empty()), outcome)
+                    }
+                    Err(e) => (None, Err(e)),
+                },
+            };
+            if let Err(e) = &outcome {
+                vlog_warn!("{} job '{}' failed again: {}", job.kind.name(), job.job, e);
+            }
+            retries.push(JobRetry {
+                job_type: job.kind.name().to_string(),
+                job: job.job,
+                node: job.node,
+                guests: job.guests,
+                previous_error: job.error,
+                upid,
+                ok: outcome.is_ok(),
+                error: outcome.err().map(|e| e.to_string()),
+            });
+        }
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&retries)?),
+            OutputFormat::Csv => {
+                csv_row!("TYPE", "JOB", "NODE", "GUESTS", "PREVIOUS_ERROR", "OK", "ERROR");
+                for r in &retries {
+                    let guests: Vec<String> = r.guests.iter().map(u32::to_string).collect();
+                    csv_row!(r.job_type, r.job, r.node, guests.join(" "), r.previous_error.as_deref().unwrap_or(""),
+                             r.ok, r.error.as_deref().unwrap_or(""));
+                }
+            }
+            OutputFormat::Table => {
+                if retries.is_empty() {
+                    println!("No failed jobs.");
+                    return Ok(());
+                }
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Type").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Job").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Guests").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Previous error").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Retry").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for r in &retries {
+                    let guests: Vec<String> = r.guests.iter().map(u32::to_string).collect();
+                    let result = match &r.error {
+                        None => Cell::new("OK").fg(Color::Green),
+                        Some(error) => Cell::new(error).fg(Color::Red),
+                    };
+                    table.add_row(vec![
+                        Cell::new(&r.job_type),
+                        Cell::new(&r.job),
+                        Cell::new(&r.node),
+                        Cell::new(guests.join(", ")),
+                        Cell::new(r.previous_error.as_deref().unwrap_or("")),
+                        result,
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        let still_failing = retries.iter().filter(|r| !r.ok).count();
+        if still_failing > 0 {
+            bail!("{} of {} retried job(s) still failing", still_failing, retries.len());
+        }
+        if !retries.is_empty() {
+            vlog_success!("Retried {} job(s)", retries.len());
+        }
+        Ok(())
+    }
+
+    /// Enabled replication jobs with failed attempts since the last sync
+    async fn failed_replications(&self, online: &[String]) -> Result<Vec<FailedJob>> {
+        let disabled: Vec<String> = self.client.get_replication_jobs().await?
+            .into_iter()
+            .filter(|j| j.disable == Some(1))
+            .map(|j| j.id)
+            .collect();
+
+        let mut failed = Vec::new();
+        for node in online {
+            let states = match self.client.get_replication_state(node).await {
+                Ok(states) => states,
+                Err(e) => {
+                    vlog_warn!("No replication state for node '{}': {}", node, e);
+                    continue;
+                }
+            };
+            for state in states {
+                if disabled.contains(&state.id) || (state.fail_count.unwrap_or(0) == 0 && state.error.is_none()) {
+                    continue;
+                }
+                failed.push(FailedJob {
+                    kind: JobKind::Replication,
+                    guests: state.id.split('-').next().and_then(|id| id.parse().ok()).into_iter().collect(),
+                    job: state.id,
+                    node: node.clone(),
+                    error: state.error,
+                });
+            }
+        }
+        Ok(failed)
+    }
+
+    /// Backup jobs covering guests whose latest backup failed, one per job
+    /// and node
+    async fn failed_backups(&self, online: &[String], resources: &[ClusterResource]) -> Result<Vec<FailedJob>> {
+        let since = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs().saturating_sub(BACKUP_LOOKBACK_SECS);
+
+        // Outcome of the latest backup of each guest: start, error
+        let mut latest: HashMap<u32, (u64, Option<String>)> = HashMap::new();
+        for node in online {
+            let tasks = match self.client.get_node_tasks(node, since).await {
+                Ok(tasks) => tasks,
+                Err(e) => {
+                    vlog_warn!("No task history for node '{}': {}", node, e);
+                    continue;
+                }
+            };
+            for task in tasks.iter().filter(|t| t.task_type == "vzdump" && t.endtime.is_some()) {
+                for (vmid, error) in self.backup_outcomes(&task.upid).await? {
+                    if latest.get(&vmid).is_none_or(|(start, _)| task.starttime >= *start) {
+                        latest.insert(vmid, (task.starttime, error));
+                    }
+                }
+            }
+        }
+
+        let jobs = self.client.get_backup_jobs().await?;
+        let mut failed: BTreeMap<(String, String), FailedJob> = BTreeMap::new();
+        let mut guests: Vec<(u32, String)> = latest.into_iter()
+            .filter_map(|(vmid, (_, error))| error.map(|e| (vmid, e)))
+            .collect();
+        guests.sort();
+        for (vmid, error) in guests {
+            let Some(guest) = resources.iter().find(|r| r.vmid == Some(vmid) && matches!(r.resource_type.as_str(), "qemu" | "lxc")) else {
+                vlog_debug!("Guest {} failed its backup but no longer exists", vmid);
+                continue;
+            };
+            let node = guest.node.clone().unwrap_or_default();
+            if !online.contains(&node) {
+                vlog_warn!("Guest {} failed its backup, its node '{}' is not online", vmid, node);
+                continue;
+            }
+            let Some(job) = jobs.iter().find(|j| j.enabled != Some(0) && job_covers(j, guest)) else {
+                vlog_debug!("Guest {} failed a backup outside the scheduled jobs", vmid);
+                continue;
+            };
+            failed.entry((job.id.clone(), node.clone()))
+                .or_insert_with(|| FailedJob {
+                    kind: JobKind::Backup,
+                    job: job.id.clone(),
+                    node,
+                    guests: Vec::new(),
+                    error: Some(error),
+                })
+                .guests.push(vmid);
+        }
+        Ok(failed.into_values().collect())
+    }
+
+    /// Guests backed up by a vzdump task, with the error of the failed ones
+    async fn backup_outcomes(&self, upid: &str) -> Result<Vec<(u32, Option<String>)>> {
+        let mut lines = Vec::new();
+        loop {
+            let page = self.client.get_task_log(upid, lines.len()).await?;
+            if page.is_empty() {
+                break;
+            }
+            lines.extend(page);
+        }
+
+        let mut outcomes: Vec<(u32, Option<String>)> = Vec::new();
+        for line in &lines {
+            let (vmid, outcome) = if let Some(vmid) = vmid_after(line, "Starting Backup of VM ") {
+                (vmid, Some("backup did not finish".to_string()))
+            } else if let Some(vmid) = vmid_after(line, "Finished Backup of VM ") {
+                (vmid, None)
+            } else if let Some(vmid) = vmid_after(line, "ERROR: Backup of VM ") {
+                let error = line.split_once(" failed - ").map_or(line.as_str(), |(_, e)| e);
+                (vmid, Some(error.trim().to_string()))
+            } else {
+                continue;
+            };
+            match outcomes.iter_mut().find(|(id, _)| *id == vmid) {
+                Some(entry) => entry.1 = outcome,
+                None => outcomes.push((vmid, outcome)),
+            }
+        }
+        Ok(outcomes)
+    }
+
+    /// Run replication job `id` of `node` now and wait for the attempt
+    async fn retry_replication(&self, node: &str, id: &str, timeout: u64) -> Result<()> {
+        let state = |states: Vec<ReplicationState>| {
+            states.into_iter().find(|s| s.id == id).context("Replication job no longer exists")
+        };
+        let before = state(self.client.get_replication_state(node).await?)?;
+        self.client.schedule_replication(node, id).await?;
+        if self.client.dry_run() {
+            return Ok(());
+        }
+
+        let started = Instant::now();
+        loop {
+            tokio::time::sleep(REPLICATION_POLL).await;
+            let now = state(self.client.get_replication_state(node).await?)?;
+            if now.last_sync > before.last_sync {
+                return Ok(());
+            }
+            if now.fail_count > before.fail_count {
+                bail!("{}", now.error.unwrap_or_else(|| "replication failed".to_string()));
+            }
+            if started.elapsed().as_secs() > timeout {
+                bail!("Replication still running after {}s", timeout);
+            }
+            vlog_debug!("Replication job '{}' still running", id);
+        }
+    }
+
+    /// Start the vzdump of a failed backup job limited to its failed guests,
+    /// returns the UPID
+    async fn retry_backup(&self, job: &FailedJob) -> Result<String> {
+        let config = self.client.get_backup_job(&job.job).await?;
+        let mut params: Vec<(String, String)> = config.iter()
+            .filter(|(key, _)| !BACKUP_JOB_KEYS.contains(&key.as_str()))
+            .map(|(key, value)| (key.clone(), param_value(value)))
+            .collect();
+        let guests: Vec<String> = job.guests.iter().map(u32::to_string).collect();
+        params.push(("vmid".to_string(), guests.join(",")));
+        self.client.vzdump(&job.node, &params).await
+    }
+}
+
+/// Whether a backup job includes a guest, the way vzdump selects them
+fn job_covers(job: &BackupJob, guest: &ClusterResource) -> bool {
+    let vmid = guest.vmid.unwrap_or_default();
+    let listed = |ids: &Option<String>| ids.as_deref()
+        .is_some_and(|ids| ids.split(',').any(|id| id.trim().parse() == Ok(vmid)));
+    if job.node.as_ref().is_some_and(|node| guest.node.as_ref() != Some(node)) {
+        return false;
+    }
+    if job.all == Some(1) {
+        return !listed(&job.exclude);
+    }
+    if let Some(pool) = &job.pool {
+        return guest.pool.as_ref() == Some(pool);
+    }
+    listed(&job.vmid)
+}
+
+/// VMID following `marker` in a vzdump log line
+fn vmid_after(line: &str, marker: &str) -> Option<u32> {
+    let rest = &line[line.find(marker)? + marker.len()..];
+    let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    rest[..digits].parse().ok()
+}
+
+/// Job option as a vzdump parameter, property strings flattened
+pub(super) fn param_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Object(map) => map.iter()
+            .map(|(key, value)| format!("{}={}", key, param_value(value)))
+            .collect::<Vec<_>>()
+            .join(","),
+        other => other.to_string(),
+    }
+}
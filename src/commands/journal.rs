@@ -0,0 +1,87 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # journal.rs
+//!
+//! Operation journals of batch commands such as `node <name> drain` and
+//! `storage <id> evacuate`.
+//!
+//! Before touching anything a batch command writes every intended action
+//! to `$XDG_STATE_HOME/pvenom/journal/<operation>-<node>-<epoch>.json`,
+//! then records the UPID and the outcome of each action as it goes. When
+//! some actions fail, the same command with `--resume <journal>` retries
+//! only the items not done yet.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config;
+use crate::models::{Journal, JournalItem};
+use crate::vlog_debug;
+
+/// Start a journal for `items`, written to disk and its path returned
+/// unless `persist` is false (dry runs)
+pub(super) fn create_journal(operation: &str, node: &str, items: Vec<JournalItem>, persist: bool) -> Result<(Journal, Option<PathBuf>)> {
+    let started_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let journal = Journal { operation: operation.to_string(), node: node.to_string(), started_at, items };
+    if !persist {
+        return Ok((journal, None));
+    }
+
+    let dir = config::state_dir().context("Cannot locate the state directory, HOME is not set")?;
+    let path = dir.join("journal").join(format!("{}-{}-{}.json", operation, node, started_at));
+    save_journal(&journal, Some(&path))?;
+    Ok((journal, Some(path)))
+}
+
+/// Display name of a journal path in messages
+pub(super) fn journal_name(path: &Option<PathBuf>) -> String {
+    match path {
+        Some(path) => path.display().to_string(),
+        None => "not written (dry run)".to_string(),
+    }
+}
+
+/// Read a journal back, refusing one written by another operation or for
+/// another node or storage
+pub(super) fn load_journal(path: &str, operation: &str, node: &str) -> Result<(Journal, Option<PathBuf>)> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read journal {}", path))?;
+    let journal: Journal = serde_json::from_str(&content).with_context(|| format!("Invalid journal {}", path))?;
+    if journal.operation != operation || journal.node != node {
+        bail!("Journal {} belongs to `{}` of '{}'", path, journal.operation, journal.node);
+    }
+    Ok((journal, Some(PathBuf::from(path))))
+}
+
+/// Replace the journal file, through a temporary file so a crash never
+/// leaves half a journal behind. Without a path there is nothing to do.
+pub(super) fn save_journal(journal: &Journal, path: Option<&Path>) -> Result<()> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let partial = path.with_extension("json.partial");
+    std::fs::write(&partial, serde_json::to_vec_pretty(journal)?)
+        .with_context(|| format!("Failed to write {}", partial.display()))?;
+    std::fs::rename(&partial, path).with_context(|| format!("Failed to write {}", path.display()))?;
+    vlog_debug!("Journal saved to {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,140 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # label.rs
+//!
+//! `pvenom label`: set, remove and show the local labels of nodes and
+//! guests, stored by [`crate::labels`].
+
+use anyhow::{bail, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+
+use super::Commands;
+use crate::labels::{self, LabelKind, Labels};
+use crate::models::OutputFormat;
+use crate::{csv_row, pager, vlog_debug, vlog_success};
+
+impl Commands {
+    /// Apply `changes` to the labels of a node or guest, print them when
+    /// there are no changes, print all labels of the cluster without target
+    pub async fn label(&self, target: Option<(LabelKind, String)>, changes: &[String]) -> Result<()> {
+        let cluster = self.cluster_name().await?;
+        let mut store = labels::load(&cluster)?;
+
+        let Some((kind, name)) = target else {
+            return self.print_all_labels(&store);
+        };
+
+        let labels = match kind {
+            LabelKind::Node => {
+                let nodes = self.client.get_nodes().await?;
+                if !nodes.iter().any(|n| n.node == name) {
+                    bail!("Node '{}' not found in the cluster", name);
+                }
+                store.nodes.entry(name.clone()).or_default()
+            }
+            LabelKind::Vm => {
+                let vmid = self.resolve_guest(&name).await?;
+                self.locate_guest(vmid).await?;
+                store.guests.entry(vmid).or_default()
+            }
+        };
+
+        if changes.is_empty() {
+            let labels = labels.clone();
+            return self.print_labels(&labels);
+        }
+
+        labels::apply(labels, changes)?;
+        let count = labels.len();
+        store.nodes.retain(|_, l| !l.is_empty());
+        store.guests.retain(|_, l| !l.is_empty());
+        labels::save(&cluster, &store)?;
+        vlog_success!("{} label(s) on {} {}", count, if kind == LabelKind::Node { "node" } else { "guest" }, name);
+        Ok(())
+    }
+
+    fn print_labels(&self, labels: &Labels) -> Result<()> {
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(labels)?),
+            OutputFormat::Csv => {
+                csv_row!("KEY", "VALUE");
+                for (key, value) in labels {
+                    csv_row!(key, value);
+                }
+            }
+            OutputFormat::Table => {
+                if labels.is_empty() {
+                    println!("No labels.");
+                    return Ok(());
+                }
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Key").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Value").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for (key, value) in labels {
+                    table.add_row(vec![Cell::new(key), Cell::new(value)]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+        Ok(())
+    }
+
+    fn print_all_labels(&self, store: &labels::LabelStore) -> Result<()> {
+        vlog_debug!("Listing labels of {} node(s) and {} guest(s)", store.nodes.len(), store.guests.len());
+        let rows: Vec<(&str, String, &Labels)> = store.nodes.iter()
+            .map(|(node, l)| ("node", node.clone(), l))
+            .chain(store.guests.iter().map(|(vmid, l)| ("vm", vmid.to_string(), l)))
+            .collect();
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(store)?),
+            OutputFormat::Csv => {
+                csv_row!("KIND", "TARGET", "KEY", "VALUE");
+                for (kind, target, labels) in &rows {
+                    for (key, value) in *labels {
+                        csv_row!(kind, target, key, value);
+                    }
+                }
+            }
+            OutputFormat::Table => {
+                if rows.is_empty() {
+                    println!("No labels, add some with `pvenom label vm <guest> KEY=VALUE`.");
+                    return Ok(());
+                }
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Kind").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Target").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Labels").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for (kind, target, labels) in &rows {
+                    table.add_row(vec![Cell::new(kind), Cell::new(target), Cell::new(labels::join(Some(labels))).fg(Color::Yellow)]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # maintenance.rs
+//!
+//! `pvenom maintenance` lists the maintenance windows, `maintenance start
+//! pve2 --for 2h` and `maintenance stop pve2` open and close ad hoc ones
+//! (`--tag dev` for guests). No cluster is needed, a node about to be
+//! worked on may already be down. See [`crate::maintenance`].
+
+use anyhow::Result;
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::maintenance::{self, AdHocWindow, MaintenanceEntry, Target, Windows};
+use crate::models::{MaintenanceInfo, OutputFormat};
+use crate::{csv_row, pager, timefmt, vlog_success};
+
+pub enum MaintenanceChange {
+    Start { target: Target, duration: u64, reason: Option<String> },
+    Stop(Target),
+}
+
+/// Apply `change` or, without one, list the windows
+pub fn maintenance(change: Option<MaintenanceChange>, entries: &[MaintenanceEntry], output_format: OutputFormat) -> Result<()> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    match change {
+        Some(MaintenanceChange::Start { target, duration, reason }) => {
+            let mut windows = maintenance::load_ad_hoc()?;
+            let (node, tag) = match &target {
+                Target::Node(node) => (Some(node.clone()), None),
+                Target::Tag(tag) => (None, Some(tag.clone())),
+            };
+            windows.retain(|w| w.node != node || w.tag != tag);
+            windows.push(AdHocWindow { node, tag, until: now + duration, reason });
+            maintenance::save_ad_hoc(&windows, now)?;
+            vlog_success!("Maintenance of {} until {}", target, timefmt::timestamp(now + duration, None));
+            Ok(())
+        }
+        Some(MaintenanceChange::Stop(target)) => {
+            let mut windows = maintenance::load_ad_hoc()?;
+            let before = windows.len();
+            windows.retain(|w| match &target {
+                Target::Node(node) => w.node.as_ref() != Some(node),
+                Target::Tag(tag) => w.tag.as_ref() != Some(tag),
+            });
+            maintenance::save_ad_hoc(&windows, now)?;
+            vlog_success!("{} ad hoc window(s) of {} closed", before - windows.len(), target);
+            Ok(())
+        }
+        None => list(&Windows::load(entries)?, now, output_format),
+    }
+}
+
+fn list(windows: &Windows, now: u64, output_format: OutputFormat) -> Result<()> {
+    let mut infos: Vec<MaintenanceInfo> = windows.ad_hoc().iter()
+        .filter(|w| w.until > now)
+        .map(|w| MaintenanceInfo {
+            target: w.node.clone().map(Target::Node).or(w.tag.clone().map(Target::Tag)).map(|t| t.to_string()).unwrap_or_default(),
+            cron: None,
+            duration_secs: None,
+            active_until: Some(w.until),
+            next_start: None,
+            reason: w.reason.clone(),
+        })
+        .collect();
+    for (target, schedule, duration) in windows.recurring() {
+        let (node, tag) = match target {
+            Target::Node(node) => (Some(node.as_str()), None),
+            Target::Tag(tag) => (None, Some(tag.as_str())),
+        };
+        infos.push(MaintenanceInfo {
+            target: target.to_string(),
+            cron: Some(schedule.cron().to_string()),
+            duration_secs: Some(duration),
+            active_until: windows.covering(node, tag, now),
+            next_start: schedule.next_after(now),
+            reason: None,
+        });
+    }
+
+    match output_format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&infos)?),
+        OutputFormat::Csv => {
+            csv_row!("TARGET", "CRON", "DURATION_SECS", "ACTIVE_UNTIL", "NEXT_START", "REASON");
+            let value = |v: Option<u64>| v.map(|v| v.to_string()).unwrap_or_default();
+            for i in &infos {
+                csv_row!(i.target, i.cron.as_deref().unwrap_or_default(), value(i.duration_secs),
+                         value(i.active_until), value(i.next_start), i.reason.as_deref().unwrap_or_default());
+            }
+        }
+        OutputFormat::Table => {
+            if infos.is_empty() {
+                println!("No maintenance windows.");
+                return Ok(());
+            }
+            let mut table = Table::new();
+            table.load_preset(UTF8_FULL)
+                 .set_content_arrangement(ContentArrangement::Dynamic);
+            table.set_header(vec![
+                Cell::new("Target").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                Cell::new("When").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                Cell::new("State").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                Cell::new("Reason").add_attribute(Attribute::Bold).fg(Color::Cyan),
+            ]);
+            for i in &infos {
+                let when = match (&i.cron, i.duration_secs) {
+                    (Some(cron), Some(duration)) => format!("{} for {}m", cron, duration / 60),
+                    _ => "ad hoc".to_string(),
+                };
+                let state = match (i.active_until, i.next_start) {
+                    (Some(until), _) => Cell::new(format!("active until {}", timefmt::timestamp(until, None))).fg(Color::Yellow),
+                    (None, Some(next)) => Cell::new(format!("next {}", timefmt::timestamp(next, None))),
+                    (None, None) => Cell::new("never"),
+                };
+                table.add_row(vec![Cell::new(&i.target), Cell::new(when), state, Cell::new(i.reason.as_deref().unwrap_or_default())]);
+            }
+            pager::print_table(&mut table);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,253 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # migrate.rs
+//!
+//! Single guest migration, `pvenom vm <vmid> migrate --target <node>`.
+//!
+//! The guest config is checked against the target first: disks on local
+//! storages, storages and bridges missing on the target, passthrough
+//! devices and bind mounts, a CPU type the target cannot offer. With
+//! `--check` the readiness report is all that happens; otherwise the
+//! migration starts only when nothing blocks it.
+//!
+//! Local disks are a blocker unless `--with-local-disks` is given, then
+//! they only warn since copying them takes time. Guests with CPU type
+//! `host` need every CPU flag of the source node on the target.
+
+use anyhow::{bail, Context, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use serde_json::{Map, Value};
+use std::collections::{BTreeSet, HashSet};
+
+use super::vm::is_disk_key;
+use super::Commands;
+use crate::models::{CheckLevel, MigrationCheck, MigrationCheckOutput, OutputFormat};
+use crate::{csv_row, pager, vlog_debug, vlog_info, vlog_success};
+
+/// Settings of `pvenom vm <vmid> migrate`
+pub struct MigrateOptions {
+    pub target: String,
+    pub check: bool,
+    pub with_local_disks: bool,
+    pub timeout: u64,
+}
+
+impl Commands {
+    pub async fn migrate_guest(&self, vmid: u32, options: &MigrateOptions) -> Result<()> {
+        let guest = self.locate_guest(vmid).await?;
+        let node = guest.node.clone().context("Guest has no node")?;
+        let report = self.migration_check(vmid, &node, &guest.resource_type, options).await?;
+
+        if options.check || !report.ready {
+            self.print_migration_check(&report)?;
+            if !report.ready && !options.check {
+                bail!("Guest {} cannot migrate to '{}', see the blockers above", vmid, options.target);
+            }
+            return Ok(());
+        }
+
+        let running = guest.status.as_deref() == Some("running");
+        let what = format!("guest {} ({}) from '{}' to '{}'", vmid, guest.name.as_deref().unwrap_or("-"), node, options.target);
+        self.confirm("migrate", &what)?;
+
+        vlog_info!("Migrating {}...", what);
+        let upid = self.client.migrate_guest(&node, &guest.resource_type, vmid, &options.target, running, options.with_local_disks).await?;
+        self.wait_for_task(&upid, options.timeout).await?;
+        if self.client.dry_run() {
+            return Ok(());
+        }
+        vlog_success!("Guest {} migrated to '{}'", vmid, options.target);
+        Ok(())
+    }
+
+    async fn migration_check(&self, vmid: u32, node: &str, guest_type: &str, options: &MigrateOptions) -> Result<MigrationCheckOutput> {
+        let target = options.target.as_str();
+        let mut checks = Vec::new();
+        let mut check = |name: &str, level: CheckLevel, detail: String| {
+            checks.push(MigrationCheck { check: name.to_string(), level, detail });
+        };
+
+        if target == node {
+            bail!("Guest {} already runs on '{}'", vmid, target);
+        }
+        let target_online = self.client.get_nodes().await?
+            .iter()
+            .any(|n| n.node == target && n.status == "online");
+        if !target_online {
+            bail!("Target node '{}' not found or offline", target);
+        }
+
+        let config = self.client.get_guest_config(node, guest_type, vmid).await?;
+        let storages = self.client.get_cluster_resources(Some("storage")).await?;
+        let shared: HashSet<&str> = storages.iter()
+            .filter(|s| s.shared == Some(1))
+            .filter_map(|s| s.storage.as_deref())
+            .collect();
+        let on_target: HashSet<&str> = storages.iter()
+            .filter(|s| s.node.as_deref() == Some(target) && s.status.as_deref() == Some("available"))
+            .filter_map(|s| s.storage.as_deref())
+            .collect();
+
+        // Disks and their storages
+        let mut used_storages = BTreeSet::new();
+        let mut local = Vec::new();
+        for (key, value) in config.iter().filter(|(k, _)| is_disk_key(k)) {
+            let Some(volume) = value.as_str().and_then(|v| v.split(',').next()) else {
+                continue;
+            };
+            let Some((storage, _)) = volume.split_once(':') else {
+                continue;
+            };
+            used_storages.insert(storage);
+            if !shared.contains(storage) {
+                local.push(format!("{} ({})", key, volume));
+            }
+        }
+        match (local.is_empty(), options.with_local_disks) {
+            (true, _) => check("local disks", CheckLevel::Ok, "none".to_string()),
+            (false, true) => check("local disks", CheckLevel::Warning, format!("copied: {}", local.join(", "))),
+            (false, false) => check("local disks", CheckLevel::Blocker,
+                                    format!("{}, use --with-local-disks", local.join(", "))),
+        }
+        let missing: Vec<&str> = used_storages.iter().copied().filter(|s| !on_target.contains(s)).collect();
+        match missing.is_empty() {
+            true => check("storages", CheckLevel::Ok, used_storages.into_iter().collect::<Vec<_>>().join(", ")),
+            false => check("storages", CheckLevel::Blocker, format!("not available on target: {}", missing.join(", "))),
+        }
+
+        // Bridges
+        let target_bridges = self.client.get_node_bridges(target).await?;
+        let bridges: BTreeSet<&str> = config.iter()
+            .filter(|(k, _)| k.strip_prefix("net").is_some_and(|n| n.parse::<u32>().is_ok()))
+            .filter_map(|(_, v)| v.as_str())
+            .filter_map(|v| v.split(',').find_map(|o| o.strip_prefix("bridge=")))
+            .collect();
+        let missing: Vec<&str> = bridges.iter().copied().filter(|b| !target_bridges.iter().any(|t| t.iface == *b)).collect();
+        match missing.is_empty() {
+            true => check("bridges", CheckLevel::Ok, bridges.into_iter().collect::<Vec<_>>().join(", ")),
+            false => check("bridges", CheckLevel::Blocker, format!("missing on target: {}", missing.join(", "))),
+        }
+
+        // Host devices
+        let devices = host_devices(&config);
+        match devices.is_empty() {
+            true => check("host devices", CheckLevel::Ok, "none".to_string()),
+            false => check("host devices", CheckLevel::Blocker, devices.join(", ")),
+        }
+
+        // CPU
+        if guest_type == "qemu" {
+            let cpu_type = cpu_type(&config).unwrap_or("default");
+            if cpu_type == "host" {
+                let source_cpu = self.client.get_node_cpuinfo(node).await?;
+                let target_cpu = self.client.get_node_cpuinfo(target).await?;
+                let target_flags: HashSet<&str> = target_cpu.flags.as_deref().unwrap_or_default().split_whitespace().collect();
+                let lacking: Vec<&str> = source_cpu.flags.as_deref().unwrap_or_default()
+                    .split_whitespace()
+                    .filter(|f| !target_flags.contains(f))
+                    .collect();
+                if lacking.is_empty() {
+                    check("cpu", CheckLevel::Ok, format!("host, {} on target", target_cpu.model));
+                } else {
+                    check("cpu", CheckLevel::Blocker, format!("host, target lacks flags: {}", lacking.join(" ")));
+                }
+            } else if cpu_type != "default" {
+                let models = self.client.get_cpu_models(target).await?;
+                match models.iter().any(|m| m == cpu_type) {
+                    true => check("cpu", CheckLevel::Ok, cpu_type.to_string()),
+                    false => check("cpu", CheckLevel::Blocker, format!("{} not offered by target", cpu_type)),
+                }
+            } else {
+                check("cpu", CheckLevel::Ok, "default model".to_string());
+            }
+        }
+
+        let ready = checks.iter().all(|c| c.level != CheckLevel::Blocker);
+        vlog_debug!("Migration check of {} to '{}': {} check(s), ready {}", vmid, target, checks.len(), ready);
+        Ok(MigrationCheckOutput {
+            vmid,
+            source: node.to_string(),
+            target: target.to_string(),
+            ready,
+            checks,
+        })
+    }
+
+    fn print_migration_check(&self, report: &MigrationCheckOutput) -> Result<()> {
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+            OutputFormat::Csv => {
+                csv_row!("CHECK", "LEVEL", "DETAIL");
+                for c in &report.checks {
+                    csv_row!(c.check, c.level.as_str(), c.detail);
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Check").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Result").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Detail").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for c in &report.checks {
+                    let color = match c.level {
+                        CheckLevel::Ok => Color::Green,
+                        CheckLevel::Warning => Color::Yellow,
+                        CheckLevel::Blocker => Color::Red,
+                    };
+                    table.add_row(vec![
+                        Cell::new(&c.check),
+                        Cell::new(c.level.as_str()).fg(color),
+                        Cell::new(&c.detail),
+                    ]);
+                }
+                pager::print_table(&mut table);
+                println!("Guest {} from '{}' to '{}': {}", report.vmid, report.source, report.target,
+                         if report.ready { "ready" } else { "blocked" });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// CPU type of a VM config, `host` in `cpu: host,flags=+aes`
+pub(super) fn cpu_type(config: &Map<String, Value>) -> Option<&str> {
+    config.get("cpu")
+        .and_then(Value::as_str)
+        .and_then(|c| c.split(',').find_map(|o| if o.contains('=') { o.strip_prefix("cputype=") } else { Some(o) }))
+}
+
+/// Passthrough devices and host paths tying a guest to its node
+fn host_devices(config: &Map<String, Value>) -> Vec<String> {
+    let indexed = |key: &str, prefix: &str| key.strip_prefix(prefix).is_some_and(|n| n.parse::<u32>().is_ok());
+    config.iter()
+        .filter_map(|(key, value)| {
+            let value = value.as_str()?;
+            let first = value.split(',').next().unwrap_or_default();
+            let bound = indexed(key, "hostpci")
+                || indexed(key, "dev")
+                || (indexed(key, "usb") && value.split(',').any(|o| o.starts_with("host=") && o != "host=spice"))
+                || ((indexed(key, "serial") || indexed(key, "parallel")) && first.starts_with("/dev/"))
+                || (indexed(key, "mp") && first.starts_with('/') && !value.split(',').any(|o| o == "shared=1"));
+            bound.then(|| format!("{} ({})", key, first))
+        })
+        .collect()
+}
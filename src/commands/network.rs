@@ -0,0 +1,309 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # network.rs
+//!
+//! Cluster networking, `pvenom network ...`.
+//!
+//! `network bridges` lists every bridge of every online node with the
+//! guest NICs attached to it. A bridge missing on some nodes is shown in
+//! red: guests attached to it fail to migrate or start there. Bridges
+//! VLAN-aware on some nodes only are shown in yellow.
+//!
+//! `ipam` lists the addresses of the running guests, as reported by the
+//! guest agent or the container runtime, sorted by address. Addresses used
+//! twice are shown in red. `--cidr 192.168.10.0/24` keeps the addresses of
+//! one network and, for IPv4, lists its free ranges too; stopped guests
+//! and VMs without agent are not seen, so free means free right now.
+
+use anyhow::{anyhow, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::{IpAddr, Ipv4Addr};
+
+use super::Commands;
+use crate::models::{BridgeInventory, BridgeNic, FreeRange, IpamEntry, IpamOutput, OutputFormat};
+use crate::{csv_row, pager, vlog_info, vlog_success, vlog_warn};
+
+impl Commands {
+    pub async fn ipam(&self, cidr: Option<&str>) -> Result<()> {
+        let network = cidr.map(parse_cidr).transpose()?;
+
+        vlog_info!("Collecting guest addresses...");
+        let mut addresses = Vec::new();
+        for guest in self.client.get_cluster_resources(Some("vm")).await? {
+            let (Some(vmid), Some(node)) = (guest.vmid, guest.node.as_deref()) else {
+                continue;
+            };
+            if !guest.is_guest() || guest.status.as_deref() != Some("running") {
+                continue;
+            }
+            for iface in self.client.get_guest_interfaces(node, &guest.resource_type, vmid).await? {
+                for cidr in &iface.addresses {
+                    let Ok((address, prefix)) = parse_cidr(cidr) else {
+                        continue;
+                    };
+                    if network.is_some_and(|(net, bits)| !contains(net, bits, address)) {
+                        continue;
+                    }
+                    addresses.push(IpamEntry {
+                        address,
+                        prefix,
+                        vmid,
+                        name: guest.name.clone().unwrap_or_else(|| "N/A".to_string()),
+                        node: node.to_string(),
+                        interface: iface.name.clone(),
+                        mac: iface.hwaddr.clone(),
+                    });
+                }
+            }
+        }
+        addresses.sort_by(|a, b| a.address.cmp(&b.address).then(a.vmid.cmp(&b.vmid)));
+
+        let free = match network {
+            Some((IpAddr::V4(net), bits)) => free_ranges(net, bits, &addresses),
+            _ => Vec::new(),
+        };
+        let mut uses: HashMap<IpAddr, usize> = HashMap::new();
+        for entry in &addresses {
+            *uses.entry(entry.address).or_default() += 1;
+        }
+
+        let output = IpamOutput { cidr: cidr.map(str::to_string), addresses, free };
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&output)?),
+            OutputFormat::Csv => {
+                csv_row!("ADDRESS", "PREFIX", "VMID", "NAME", "NODE", "INTERFACE", "MAC");
+                for e in &output.addresses {
+                    csv_row!(e.address, e.prefix, e.vmid, e.name, e.node, e.interface, e.mac.as_deref().unwrap_or(""));
+                }
+            }
+            OutputFormat::Table => {
+                if output.addresses.is_empty() {
+                    println!("No guest addresses found{}.", cidr.map(|c| format!(" in {}", c)).unwrap_or_default());
+                } else {
+                    let mut table = Table::new();
+                    table.load_preset(UTF8_FULL)
+                         .set_content_arrangement(ContentArrangement::Dynamic);
+                    table.set_header(vec![
+                        Cell::new("Address").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Interface").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("MAC").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    ]);
+                    for e in &output.addresses {
+                        let address = format!("{}/{}", e.address, e.prefix);
+                        let address = match uses[&e.address] {
+                            1 => Cell::new(address),
+                            _ => Cell::new(address).fg(Color::Red),
+                        };
+                        table.add_row(vec![
+                            address,
+                            Cell::new(e.vmid),
+                            Cell::new(&e.name),
+                            Cell::new(&e.node),
+                            Cell::new(&e.interface),
+                            Cell::new(e.mac.as_deref().unwrap_or("")),
+                        ]);
+                    }
+                    pager::print_table(&mut table);
+                }
+                if !output.free.is_empty() {
+                    println!("Free in {}:", cidr.unwrap_or_default());
+                    for range in &output.free {
+                        match range.count {
+                            1 => println!("  {}", range.first),
+                            n => println!("  {} - {} ({})", range.first, range.last, n),
+                        }
+                    }
+                }
+            }
+        }
+
+        let duplicates = uses.values().filter(|n| **n > 1).count();
+        if duplicates > 0 {
+            vlog_warn!("{} address(es) used by more than one interface", duplicates);
+        }
+        vlog_success!("Listed {} guest address(es)", output.addresses.len());
+        Ok(())
+    }
+
+    pub async fn list_bridges(&self) -> Result<()> {
+        vlog_info!("Collecting bridges of every node...");
+        let mut online = Vec::new();
+        let mut bridges: BTreeMap<String, BridgeInventory> = BTreeMap::new();
+        for node in self.client.get_nodes().await? {
+            if node.status != "online" {
+                vlog_warn!("Node '{}' is {}, its bridges are not listed", node.node, node.status);
+                continue;
+            }
+            for bridge in self.client.get_node_bridges(&node.node).await? {
+                let entry = bridges.entry(bridge.iface.clone()).or_insert_with(|| BridgeInventory::new(&bridge.iface));
+                entry.nodes.push(node.node.clone());
+                if bridge.bridge_vlan_aware == Some(1) || bridge.bridge_type == "OVSBridge" {
+                    entry.vlan_aware_on.push(node.node.clone());
+                }
+            }
+            online.push(node.node);
+        }
+
+        for guest in self.client.get_cluster_resources(Some("vm")).await? {
+            let (Some(vmid), Some(node)) = (guest.vmid, guest.node.as_deref()) else {
+                continue;
+            };
+            if !guest.is_guest() || !online.iter().any(|n| n == node) {
+                continue;
+            }
+            let config = self.client.get_guest_config(node, &guest.resource_type, vmid).await?;
+            for (key, value) in &config {
+                let Some(value) = value.as_str().filter(|_| key.strip_prefix("net").is_some_and(|n| n.parse::<u32>().is_ok())) else {
+                    continue;
+                };
+                let option = |name: &str| value.split(',').find_map(|o| o.strip_prefix(name)?.strip_prefix('='));
+                let Some(bridge) = option("bridge") else {
+                    continue;
+                };
+                let entry = bridges.entry(bridge.to_string()).or_insert_with(|| BridgeInventory::new(bridge));
+                entry.guests.push(BridgeNic {
+                    vmid,
+                    name: guest.name.clone().unwrap_or_else(|| "N/A".to_string()),
+                    node: node.to_string(),
+                    nic: key.clone(),
+                    tag: option("tag").and_then(|t| t.parse().ok()),
+                });
+            }
+        }
+
+        let bridges: Vec<BridgeInventory> = bridges.into_values()
+            .map(|mut b| {
+                b.missing_on = online.iter().filter(|n| !b.nodes.contains(n)).cloned().collect();
+                b.vlans = b.guests.iter().filter_map(|g| g.tag).collect::<BTreeSet<_>>().into_iter().collect();
+                b.guests.sort_by(|x, y| x.vmid.cmp(&y.vmid).then(x.nic.cmp(&y.nic)));
+                b
+            })
+            .collect();
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&bridges)?),
+            OutputFormat::Csv => {
+                csv_row!("BRIDGE", "NODES", "MISSING_ON", "VLAN_AWARE_ON", "VLANS", "GUEST_NICS");
+                for b in &bridges {
+                    csv_row!(b.bridge, b.nodes.join(" "), b.missing_on.join(" "), b.vlan_aware_on.join(" "),
+                             b.vlans.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(" "), b.guests.len());
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Bridge").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Nodes").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Missing On").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("VLAN Aware").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("VLANs").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Guest NICs").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for b in &bridges {
+                    let missing = match b.missing_on.is_empty() {
+                        true => Cell::new("-").fg(Color::Green),
+                        false => Cell::new(b.missing_on.join(", ")).fg(Color::Red),
+                    };
+                    let vlan_aware = match b.vlan_aware_on.len() {
+                        0 => Cell::new("no"),
+                        n if n == b.nodes.len() => Cell::new("yes"),
+                        _ => Cell::new(format!("only {}", b.vlan_aware_on.join(", "))).fg(Color::Yellow),
+                    };
+                    let nics: Vec<String> = b.guests.iter().map(|g| format!("{}/{}", g.vmid, g.nic)).collect();
+                    table.add_row(vec![
+                        Cell::new(&b.bridge),
+                        Cell::new(b.nodes.join(", ")),
+                        missing,
+                        vlan_aware,
+                        Cell::new(b.vlans.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ")),
+                        Cell::new(nics.join(", ")),
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        let partial = bridges.iter().filter(|b| !b.missing_on.is_empty()).count();
+        vlog_success!("Listed {} bridge(s), {} missing on some node", bridges.len(), partial);
+        Ok(())
+    }
+}
+
+/// `192.168.10.0/24` or a bare address, taken as a host route
+fn parse_cidr(cidr: &str) -> Result<(IpAddr, u8)> {
+    let (address, prefix) = cidr.split_once('/').unwrap_or((cidr, ""));
+    let address: IpAddr = address.parse().map_err(|_| anyhow!("Invalid address '{}'", cidr))?;
+    let max = if address.is_ipv4() { 32 } else { 128 };
+    let prefix = match prefix {
+        "" => max,
+        p => p.parse().ok().filter(|p| *p <= max).ok_or_else(|| anyhow!("Invalid prefix in '{}'", cidr))?,
+    };
+    Ok((address, prefix))
+}
+
+fn contains(network: IpAddr, prefix: u8, address: IpAddr) -> bool {
+    match (network, address) {
+        (IpAddr::V4(net), IpAddr::V4(addr)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(net) & mask == u32::from(addr) & mask
+        }
+        (IpAddr::V6(net), IpAddr::V6(addr)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(net) & mask == u128::from(addr) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Unused host addresses of an IPv4 network, network and broadcast
+/// addresses excluded from /30 and larger networks
+fn free_ranges(network: Ipv4Addr, prefix: u8, used: &[IpamEntry]) -> Vec<FreeRange> {
+    let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+    let (mut first, mut last) = (u32::from(network) & mask, u32::from(network) | !mask);
+    if prefix <= 30 {
+        first += 1;
+        last -= 1;
+    }
+    let taken: BTreeSet<u32> = used.iter()
+        .filter_map(|e| match e.address {
+            IpAddr::V4(a) => Some(u32::from(a)),
+            IpAddr::V6(_) => None,
+        })
+        .collect();
+
+    let mut ranges = Vec::new();
+    let mut start = first as u64;
+    for address in taken.range(first..=last).map(|a| *a as u64).chain([last as u64 + 1]) {
+        if address > start {
+            ranges.push(FreeRange {
+                first: Ipv4Addr::from(start as u32),
+                last: Ipv4Addr::from((address - 1) as u32),
+                count: address - start,
+            });
+        }
+        start = address + 1;
+    }
+    ranges
+}
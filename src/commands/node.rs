@@ -0,0 +1,457 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # node.rs
+//!
+//! Node maintenance, `pvenom node <name> drain --target <node>|auto`.
+//!
+//! Drain migrates every running guest off a node, one at a time: VMs live,
+//! containers in restart mode. HA-managed guests are moved through the HA
+//! manager so it does not fight the migration back. With `--target auto`
+//! each guest goes to the online node with the most free memory, counting
+//! the guests already sent there.
+//!
+//! Successful migrations are recorded in
+//! `$XDG_STATE_HOME/pvenom/placement/<cluster>/<node>.json`, and
+//! `pvenom node <name> restore-placement` moves those guests back once the
+//! node is up again. Guests already back home are skipped, the record is
+//! removed when every guest is home.
+//!
+//! Both write an operation journal first (see journal.rs) and name it when
+//! a migration fails; `--resume <journal>` retries the failed guests only.
+//!
+//! Stopped guests stay where they are. Put the node in HA maintenance
+//! (`ha-manager crm-command node-maintenance enable`) beforehand if HA
+//! should not place new guests on it.
+
+use anyhow::{bail, Context, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::journal::{create_journal, journal_name, load_journal, save_journal};
+use super::Commands;
+use crate::config;
+use crate::models::{ClusterResource, DrainOutput, Journal, JournalItem, JournalState, MigrationResult, OutputFormat, PlacementEntry, PlacementRecord};
+use crate::progress::{self, Event};
+use crate::{csv_row, pager, vlog_debug, vlog_error, vlog_info, vlog_success, vlog_warn};
+
+/// Seconds between two placement checks of HA migrations
+const HA_POLL_SECS: u64 = 5;
+
+/// Settings of `pvenom node <name> drain`
+pub struct DrainOptions {
+    /// Target node name, or `auto`
+    pub target: String,
+    pub with_local_disks: bool,
+    /// Seconds allowed for each migration
+    pub timeout: u64,
+    /// Journal of an earlier drain to retry instead of planning a new one
+    pub resume: Option<String>,
+}
+
+impl Commands {
+    pub async fn drain_node(&self, node: &str, options: &DrainOptions) -> Result<()> {
+        let resources = self.client.get_cluster_resources(None).await?;
+        let dry_run = self.client.dry_run();
+        let (mut journal, path) = match &options.resume {
+            Some(path) => load_journal(path, "drain", node)?,
+            None => {
+                let items = plan_drain(&resources, node, &options.target)?;
+                self.confirm("drain", &format!("node '{}' ({} running guest(s))", node, items.len()))?;
+                create_journal("drain", node, items, !dry_run)?
+            }
+        };
+        let path = path.filter(|_| !dry_run);
+        vlog_info!("Draining {} running guest(s) from node '{}', journal {}", journal.items.len(), node, journal_name(&path));
+
+        self.run_migrations(&mut journal, path.as_deref(), &resources, options.with_local_disks, options.timeout).await?;
+        if dry_run {
+            let output = DrainOutput { node: node.to_string(), guests: migration_results(&journal) };
+            return self.print_migrations(&output.guests, &output);
+        }
+
+        // Remember where guests came from, merging with an earlier drain
+        let cluster = self.cluster_name().await?;
+        let mut record = load_placement(&cluster, node)?.unwrap_or_default();
+        record.node = node.to_string();
+        record.drained_at = journal.started_at;
+        for item in journal.items.iter().filter(|i| i.state == JournalState::Done) {
+            record.guests.retain(|g| g.vmid != item.vmid);
+            record.guests.push(PlacementEntry {
+                vmid: item.vmid,
+                name: item.name.clone(),
+                guest_type: item.guest_type.clone(),
+                moved_to: item.target.clone(),
+            });
+        }
+        if !record.guests.is_empty() {
+            save_placement(&cluster, &record)?;
+        }
+
+        let output = DrainOutput { node: node.to_string(), guests: migration_results(&journal) };
+        self.print_migrations(&output.guests, &output)?;
+
+        let failed = output.guests.iter().filter(|r| !r.ok).count();
+        if failed > 0 {
+            bail!("{} of {} migration(s) failed, node '{}' is not empty, retry with --resume {}",
+                  failed, output.guests.len(), node, journal_name(&path));
+        }
+        vlog_success!("Node '{}' drained, {} guest(s) migrated", node, output.guests.len());
+        Ok(())
+    }
+
+    /// Migrate the guests recorded by a drain back to `node`
+    pub async fn restore_placement(&self, node: &str, with_local_disks: bool, timeout: u64, resume: Option<&str>) -> Result<()> {
+        let cluster = self.cluster_name().await?;
+        let Some(mut record) = load_placement(&cluster, node)? else {
+            bail!("No placement record for node '{}', nothing was drained from it", node);
+        };
+
+        let resources = self.client.get_cluster_resources(None).await?;
+        let online = resources.iter()
+            .any(|r| r.resource_type == "node" && r.node.as_deref() == Some(node) && r.status.as_deref() == Some("online"));
+        if !online {
+            bail!("Node '{}' is not online", node);
+        }
+
+        let dry_run = self.client.dry_run();
+        let (mut journal, path) = match resume {
+            Some(path) => load_journal(path, "restore-placement", node)?,
+            None => {
+                let items = record.guests.iter()
+                    .filter_map(|entry| match resources.iter().find(|r| r.vmid == Some(entry.vmid)) {
+                        Some(guest) => Some(journal_item(guest, node)),
+                        None => {
+                            vlog_error!("Guest {} no longer exists, dropping it", entry.vmid);
+                            None
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                self.confirm("restore-placement", &format!("node '{}' ({} guest(s))", node, items.len()))?;
+                create_journal("restore-placement", node, items, !dry_run)?
+            }
+        };
+        let path = path.filter(|_| !dry_run);
+        vlog_info!("Restoring {} guest(s) to node '{}', journal {}", journal.items.len(), node, journal_name(&path));
+
+        self.run_migrations(&mut journal, path.as_deref(), &resources, with_local_disks, timeout).await?;
+        if dry_run {
+            let output = DrainOutput { node: node.to_string(), guests: migration_results(&journal) };
+            return self.print_migrations(&output.guests, &output);
+        }
+
+        // Keep only the guests still away from home
+        record.guests.retain(|g| {
+            resources.iter().any(|r| r.vmid == Some(g.vmid))
+                && !journal.items.iter().any(|i| i.vmid == g.vmid && i.state == JournalState::Done)
+        });
+        if record.guests.is_empty() {
+            remove_placement(&cluster, node)?;
+        } else {
+            save_placement(&cluster, &record)?;
+        }
+
+        let output = DrainOutput { node: node.to_string(), guests: migration_results(&journal) };
+        self.print_migrations(&output.guests, &output)?;
+
+        if !record.guests.is_empty() {
+            bail!("{} guest(s) could not be moved back to '{}', retry with --resume {}",
+                  record.guests.len(), node, journal_name(&path));
+        }
+        vlog_success!("Placement of node '{}' restored", node);
+        Ok(())
+    }
+
+    /// Migrate every journal item not done yet, saving the journal after
+    /// each step. Guests already on their target count as done.
+    async fn run_migrations(&self, journal: &mut Journal, path: Option<&Path>, resources: &[ClusterResource],
+                            with_local_disks: bool, timeout: u64) -> Result<()> {
+        let total = journal.items.len();
+        progress::emit(&Event::BatchStarted { operation: &journal.operation, total });
+        for i in 0..total {
+            let item = journal.items[i].clone();
+            if item.state == JournalState::Done {
+                continue;
+            }
+            progress::emit(&Event::ItemStarted { index: i + 1, total, vmid: item.vmid, target: &item.target });
+            let Some(guest) = resources.iter().find(|r| r.vmid == Some(item.vmid)) else {
+                vlog_error!("Guest {} no longer exists", item.vmid);
+                journal.items[i].state = JournalState::Failed;
+                journal.items[i].error = Some("Guest no longer exists".to_string());
+                save_journal(journal, path)?;
+                progress::emit(&Event::ItemFinished { index: i + 1, total, vmid: item.vmid, ok: false });
+                continue;
+            };
+            let current = guest.node.clone().unwrap_or_default();
+            if current == item.target {
+                vlog_debug!("Guest {} is already on '{}'", item.vmid, item.target);
+                journal.items[i].state = JournalState::Done;
+                journal.items[i].error = None;
+                save_journal(journal, path)?;
+                progress::emit(&Event::ItemFinished { index: i + 1, total, vmid: item.vmid, ok: true });
+                continue;
+            }
+
+            // An interrupted run may have left its migration task running,
+            // wait for it rather than starting a second one
+            let started = Instant::now();
+            let resumed = match item.upid.as_deref().filter(|_| item.state == JournalState::Running) {
+                Some(upid) => {
+                    vlog_info!("Waiting for the earlier migration of guest {}: {}", item.vmid, upid);
+                    match self.wait_for_task(upid, timeout).await {
+                        Ok(()) => Some(Ok(())),
+                        Err(e) => {
+                            vlog_warn!("Earlier migration of guest {} did not succeed ({}), migrating again", item.vmid, e);
+                            None
+                        }
+                    }
+                }
+                None => None,
+            };
+            if resumed.is_none() {
+                journal.items[i].state = JournalState::Running;
+                journal.items[i].upid = None;
+                journal.items[i].error = None;
+                save_journal(journal, path)?;
+                vlog_info!("Migrating {} {} from '{}' to '{}'{}...",
+                           guest.guest_type(), item.vmid, current, item.target, if item.ha { " via HA" } else { "" });
+            }
+            let outcome = if let Some(outcome) = resumed {
+                outcome
+            } else if item.ha {
+                self.ha_migrate_and_wait(guest, &item.target, timeout).await
+            } else {
+                let running = guest.status.as_deref() == Some("running");
+                match self.client.migrate_guest(&current, &guest.resource_type, item.vmid, &item.target, running, with_local_disks).await {
+                    Ok(upid) => {
+                        journal.items[i].upid = Some(upid.clone());
+                        save_journal(journal, path)?;
+                        self.wait_for_task(&upid, timeout).await
+                    }
+                    Err(e) => Err(e),
+                }
+            };
+
+            match &outcome {
+                Ok(()) => vlog_success!("Guest {} migrated to '{}'", item.vmid, item.target),
+                Err(e) => vlog_error!("Migration of guest {} failed: {}", item.vmid, e),
+            }
+            progress::emit(&Event::ItemFinished { index: i + 1, total, vmid: item.vmid, ok: outcome.is_ok() });
+            let entry = &mut journal.items[i];
+            entry.state = if outcome.is_ok() { JournalState::Done } else { JournalState::Failed };
+            entry.error = outcome.err().map(|e| e.to_string());
+            entry.seconds = started.elapsed().as_secs();
+            save_journal(journal, path)?;
+        }
+
+        let done = journal.items.iter().filter(|i| i.state == JournalState::Done).count();
+        progress::emit(&Event::BatchFinished { operation: &journal.operation, done, failed: total - done });
+        Ok(())
+    }
+
+    /// Hand the guest to the HA manager and wait until it runs on `target`
+    async fn ha_migrate_and_wait(&self, guest: &ClusterResource, target: &str, timeout: u64) -> Result<()> {
+        let vmid = guest.vmid.unwrap_or_default();
+        let sid = format!("{}:{}", if guest.resource_type == "qemu" { "vm" } else { "ct" }, vmid);
+        self.client.ha_migrate(&sid, target).await?;
+        if self.client.dry_run() {
+            return Ok(());
+        }
+
+        let started = Instant::now();
+        loop {
+            tokio::time::sleep(Duration::from_secs(HA_POLL_SECS)).await;
+            let current = self.client.get_cluster_resources(Some("vm")).await?
+                .into_iter()
+                .find(|r| r.vmid == Some(vmid));
+            if let Some(current) = current {
+                vlog_debug!("{} is on '{}', HA state {:?}", sid, current.node.as_deref().unwrap_or("-"), current.hastate);
+                if current.node.as_deref() == Some(target) && current.status.as_deref() == Some("running") {
+                    return Ok(());
+                }
+            }
+            if started.elapsed().as_secs() > timeout {
+                bail!("{} not running on '{}' after {}s", sid, target, timeout);
+            }
+        }
+    }
+
+    /// Summary of a batch of migrations, `document` is the JSON output
+    fn print_migrations<T: Serialize>(&self, results: &[MigrationResult], document: &T) -> Result<()> {
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(document)?),
+            OutputFormat::Csv => {
+                csv_row!("VMID", "NAME", "TYPE", "TARGET", "HA", "RESULT", "SECONDS");
+                for r in results {
+                    csv_row!(r.vmid, r.name, r.guest_type, r.target, r.ha, r.error.as_deref().unwrap_or("OK"),
+                             r.seconds);
+                }
+            }
+            OutputFormat::Table => {
+                if results.is_empty() {
+                    println!("No running guests to migrate.");
+                    return Ok(());
+                }
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Type").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Target").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("HA").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Result").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Time (s)").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for r in results {
+                    let result = match &r.error {
+                        None => Cell::new("OK").fg(Color::Green),
+                        Some(e) => Cell::new(e).fg(Color::Red),
+                    };
+                    table.add_row(vec![
+                        Cell::new(r.vmid),
+                        Cell::new(&r.name),
+                        Cell::new(&r.guest_type),
+                        Cell::new(&r.target),
+                        Cell::new(if r.ha { "yes" } else { "no" }),
+                        result,
+                        Cell::new(r.seconds),
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One migration per running guest of `node`, to `target` or, with `auto`,
+/// to the online node with the most free memory left
+fn plan_drain(resources: &[ClusterResource], node: &str, target: &str) -> Result<Vec<JournalItem>> {
+    let nodes: Vec<&ClusterResource> = resources.iter().filter(|r| r.resource_type == "node").collect();
+    if !nodes.iter().any(|n| n.node.as_deref() == Some(node)) {
+        bail!("Node '{}' not found in the cluster", node);
+    }
+
+    // Free memory of every possible target, reduced as guests are assigned
+    let mut free: HashMap<String, i64> = nodes.iter()
+        .filter(|n| n.status.as_deref() == Some("online") && n.node.as_deref() != Some(node))
+        .filter_map(|n| Some((n.node.clone()?, n.maxmem? as i64 - n.mem.unwrap_or(0) as i64)))
+        .collect();
+    if target != "auto" && !free.contains_key(target) {
+        bail!("Target '{}' is not an online node other than '{}'", target, node);
+    }
+    if free.is_empty() {
+        bail!("No other online node to drain '{}' to", node);
+    }
+
+    let mut guests: Vec<&ClusterResource> = resources.iter()
+        .filter(|r| r.is_guest() && r.node.as_deref() == Some(node) && r.status.as_deref() == Some("running"))
+        .collect();
+    guests.sort_by_key(|g| g.vmid);
+
+    Ok(guests.into_iter()
+        .map(|guest| {
+            let destination = if target == "auto" {
+                free.iter().max_by_key(|(_, f)| **f).map(|(n, _)| n.clone()).unwrap_or_default()
+            } else {
+                target.to_string()
+            };
+            if let Some(f) = free.get_mut(&destination) {
+                *f -= guest.maxmem.unwrap_or(0) as i64;
+            }
+            journal_item(guest, &destination)
+        })
+        .collect())
+}
+
+fn journal_item(guest: &ClusterResource, target: &str) -> JournalItem {
+    JournalItem {
+        vmid: guest.vmid.unwrap_or_default(),
+        name: guest.name.clone().unwrap_or_else(|| "N/A".to_string()),
+        guest_type: guest.resource_type.clone(),
+        action: "migrate".to_string(),
+        disk: None,
+        target: target.to_string(),
+        ha: guest.hastate.as_deref().is_some_and(|s| s != "ignored"),
+        upid: None,
+        state: JournalState::Pending,
+        error: None,
+        seconds: 0,
+    }
+}
+
+fn migration_results(journal: &Journal) -> Vec<MigrationResult> {
+    journal.items.iter()
+        .map(|item| MigrationResult {
+            vmid: item.vmid,
+            name: item.name.clone(),
+            guest_type: if item.guest_type == "qemu" { "VM" } else { "LXC" }.to_string(),
+            target: item.target.clone(),
+            ha: item.ha,
+            ok: item.state == JournalState::Done,
+            error: match item.state {
+                JournalState::Done => None,
+                JournalState::Failed => item.error.clone(),
+                _ => Some("Not attempted".to_string()),
+            },
+            seconds: item.seconds,
+        })
+        .collect()
+}
+
+/// Node names repeat across clusters, records are kept per cluster like labels
+fn placement_path(cluster: &str, node: &str) -> Result<PathBuf> {
+    let dir = config::state_dir().context("Cannot locate the state directory, HOME is not set")?;
+    Ok(dir.join("placement").join(cluster).join(format!("{}.json", node)))
+}
+
+fn load_placement(cluster: &str, node: &str) -> Result<Option<PlacementRecord>> {
+    let path = placement_path(cluster, node)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let record = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid placement record {}", path.display()))?;
+    Ok(Some(record))
+}
+
+fn save_placement(cluster: &str, record: &PlacementRecord) -> Result<()> {
+    let path = placement_path(cluster, &record.node)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    std::fs::write(&path, serde_json::to_vec_pretty(record)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    vlog_debug!("Placement record saved to {}", path.display());
+    Ok(())
+}
+
+fn remove_placement(cluster: &str, node: &str) -> Result<()> {
+    let path = placement_path(cluster, node)?;
+    if path.exists() {
+        std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    }
+    Ok(())
+}
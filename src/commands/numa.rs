@@ -0,0 +1,233 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # numa.rs
+//!
+//! vCPU pinning and NUMA layout of the VMs of a node, `pvenom node <name>
+//! numa`, for tuning latency-sensitive guests.
+//!
+//! Every VM is listed with its vCPUs, its `affinity` host CPUs, its `numa`
+//! and `numaN` settings and `hugepages`. Conflicts are flagged: host CPUs
+//! pinned by several VMs, more vCPUs than pinned CPUs, affinity outside the
+//! host, hugepages without NUMA or with memory not a multiple of the page,
+//! and guest NUMA nodes beyond the host ones.
+//!
+//! The API has no host NUMA topology, so each socket is taken as a NUMA
+//! node, with the usual Linux numbering: the first thread of every core,
+//! socket after socket, then their siblings in the same order. Affinity
+//! spanning sockets is reported on that basis.
+
+use anyhow::Result;
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::Commands;
+use crate::models::{GuestNuma, NodeNumaOutput, OutputFormat};
+use crate::{csv_row, pager, vlog_success, vlog_warn};
+
+impl Commands {
+    pub async fn show_node_numa(&self, node: &str) -> Result<()> {
+        let cpuinfo = self.client.get_node_cpuinfo(node).await?;
+        let host_cpus = cpuinfo.cpus.unwrap_or(0);
+        let host_sockets = cpuinfo.sockets.unwrap_or(1).max(1);
+        let cores_per_socket = cpuinfo.cores.unwrap_or(host_cpus / host_sockets).max(1);
+
+        let mut vms = self.client.get_vms(node).await?;
+        vms.sort_by_key(|vm| vm.vmid);
+        let mut guests = Vec::new();
+        for vm in vms {
+            let config = match self.client.get_guest_config(node, "qemu", vm.vmid).await {
+                Ok(config) => config,
+                Err(e) => {
+                    vlog_warn!("No config for VM {}: {}", vm.vmid, e);
+                    continue;
+                }
+            };
+            guests.push(guest_numa(vm.vmid, vm.name, vm.status, &config));
+        }
+
+        let mut conflicts = Vec::new();
+        let mut flagged = BTreeSet::new();
+        let socket_of = |cpu: u32| (cpu % (cores_per_socket * host_sockets)) / cores_per_socket;
+        let mut pinned_by: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        for g in &guests {
+            for cpu in &g.pinned_cpus {
+                pinned_by.entry(*cpu).or_default().push(g.vmid);
+            }
+            if let Some(affinity) = &g.affinity {
+                if host_cpus > 0 && g.pinned_cpus.iter().any(|c| *c >= host_cpus) {
+                    flagged.insert(g.vmid);
+                    conflicts.push(format!("VM {}: affinity {} beyond the {} host CPUs", g.vmid, affinity, host_cpus));
+                }
+                if g.vcpus > g.pinned_cpus.len() as u32 {
+                    flagged.insert(g.vmid);
+                    conflicts.push(format!("VM {}: {} vCPUs pinned to {} host CPU(s)", g.vmid, g.vcpus, g.pinned_cpus.len()));
+                }
+                let mut sockets: Vec<u32> = g.pinned_cpus.iter().map(|c| socket_of(*c)).collect();
+                sockets.sort_unstable();
+                sockets.dedup();
+                if host_sockets > 1 && sockets.len() > 1 {
+                    flagged.insert(g.vmid);
+                    conflicts.push(format!("VM {}: affinity {} spans {} sockets", g.vmid, affinity, sockets.len()));
+                }
+            }
+            if g.hugepages.is_some() && !g.numa {
+                flagged.insert(g.vmid);
+                conflicts.push(format!("VM {}: hugepages need numa enabled", g.vmid));
+            }
+            if g.hugepages.as_deref() == Some("1024") && g.memory_mb % 1024 != 0 {
+                flagged.insert(g.vmid);
+                conflicts.push(format!("VM {}: {} MiB memory is not a multiple of 1 GiB hugepages", g.vmid, g.memory_mb));
+            }
+            if g.numa && g.sockets > host_sockets {
+                flagged.insert(g.vmid);
+                conflicts.push(format!("VM {}: {} virtual sockets on a {}-socket host", g.vmid, g.sockets, host_sockets));
+            }
+            for hostnode in g.numa_nodes.iter().filter_map(|n| option(n, "hostnodes")) {
+                if parse_cpus(&hostnode).iter().any(|n| *n >= host_sockets) {
+                    flagged.insert(g.vmid);
+                    conflicts.push(format!("VM {}: hostnodes {} beyond the {} host NUMA node(s)", g.vmid, hostnode, host_sockets));
+                }
+            }
+        }
+        let mut shared: BTreeMap<Vec<u32>, Vec<u32>> = BTreeMap::new();
+        for (cpu, vmids) in pinned_by.into_iter().filter(|(_, v)| v.len() > 1) {
+            shared.entry(vmids).or_default().push(cpu);
+        }
+        for (vmids, cpus) in shared {
+            flagged.extend(vmids.iter().copied());
+            let vmids: Vec<String> = vmids.iter().map(|v| v.to_string()).collect();
+            conflicts.push(format!("Host CPU(s) {} pinned by VMs {}", format_cpus(&cpus), vmids.join(", ")));
+        }
+
+        let output = NodeNumaOutput { node: node.to_string(), host_cpus, host_sockets, guests, conflicts };
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&output)?),
+            OutputFormat::Csv => {
+                csv_row!("VMID", "NAME", "STATUS", "VCPUS", "AFFINITY", "NUMA", "NUMA_NODES", "HUGEPAGES", "MEMORY_MB");
+                for g in &output.guests {
+                    csv_row!(g.vmid, g.name, g.status, g.vcpus, g.affinity.as_deref().unwrap_or(""), g.numa,
+                             g.numa_nodes.join(" "), g.hugepages.as_deref().unwrap_or(""), g.memory_mb);
+                }
+            }
+            OutputFormat::Table => {
+                println!("\n=== NUMA of {} ({} CPUs, {} socket(s)) ===\n", node, host_cpus, host_sockets);
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Status").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("vCPUs").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Affinity").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("NUMA").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Hugepages").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for g in &output.guests {
+                    let numa = match (g.numa, g.numa_nodes.is_empty()) {
+                        (false, _) => "off".to_string(),
+                        (true, true) => "on".to_string(),
+                        (true, false) => g.numa_nodes.join("\n"),
+                    };
+                    table.add_row(vec![
+                        if flagged.contains(&g.vmid) { Cell::new(g.vmid).fg(Color::Red) } else { Cell::new(g.vmid) },
+                        Cell::new(&g.name),
+                        Cell::new(&g.status),
+                        Cell::new(g.vcpus),
+                        Cell::new(g.affinity.as_deref().unwrap_or("-")),
+                        Cell::new(numa),
+                        Cell::new(g.hugepages.as_deref().unwrap_or("-")),
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        if output.conflicts.is_empty() {
+            vlog_success!("No pinning or NUMA conflicts on '{}'", node);
+        }
+        for conflict in &output.conflicts {
+            vlog_warn!("{}", conflict);
+        }
+        Ok(())
+    }
+}
+
+fn guest_numa(vmid: u32, name: String, status: String, config: &Map<String, Value>) -> GuestNuma {
+    let number = |key: &str| config.get(key).and_then(|v| v.as_u64().or_else(|| v.as_str().and_then(|s| s.parse().ok())));
+    let text = |key: &str| config.get(key).and_then(|v| v.as_str().map(str::to_string).or_else(|| v.as_u64().map(|n| n.to_string())));
+    let sockets = number("sockets").unwrap_or(1) as u32;
+    let vcpus = number("vcpus").unwrap_or(sockets as u64 * number("cores").unwrap_or(1)) as u32;
+    let affinity = text("affinity");
+    let mut numa_nodes: Vec<String> = config.iter()
+        .filter(|(k, _)| k.strip_prefix("numa").is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit())))
+        .filter_map(|(k, v)| v.as_str().map(|v| format!("{}: {}", k, v)))
+        .collect();
+    numa_nodes.sort();
+    GuestNuma {
+        vmid,
+        name,
+        status,
+        vcpus,
+        sockets,
+        pinned_cpus: affinity.as_deref().map(parse_cpus).unwrap_or_default(),
+        affinity,
+        numa: number("numa") == Some(1),
+        numa_nodes,
+        hugepages: text("hugepages"),
+        memory_mb: number("memory").unwrap_or(512),
+    }
+}
+
+/// Value of `key=` in a `numaN` entry such as `numa0: cpus=0-3;8,hostnodes=0`,
+/// lists go on with `;`
+fn option(entry: &str, key: &str) -> Option<String> {
+    let (_, settings) = entry.split_once(": ")?;
+    settings.split(',').find_map(|s| s.strip_prefix(key)?.strip_prefix('=').map(str::to_string))
+}
+
+/// `0-3,8` or `0-3;8` as the CPUs or nodes it names, sorted
+fn parse_cpus(list: &str) -> Vec<u32> {
+    let mut cpus: Vec<u32> = list.split([',', ';'])
+        .filter_map(|part| match part.trim().split_once('-') {
+            Some((from, to)) => Some((from.parse().ok()?..=to.parse().ok()?).collect::<Vec<u32>>()),
+            None => part.trim().parse().ok().map(|c| vec![c]),
+        })
+        .flatten()
+        .collect();
+    cpus.sort_unstable();
+    cpus.dedup();
+    cpus
+}
+
+/// Sorted CPUs back to ranges, `0-3,8`
+fn format_cpus(cpus: &[u32]) -> String {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for cpu in cpus {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == *cpu => *end = *cpu,
+            _ => ranges.push((*cpu, *cpu)),
+        }
+    }
+    ranges.iter()
+        .map(|(from, to)| if from == to { from.to_string() } else { format!("{}-{}", from, to) })
+        .collect::<Vec<_>>()
+        .join(",")
+}
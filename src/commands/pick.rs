@@ -0,0 +1,119 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # pick.rs
+//!
+//! Interactive fuzzy pickers for commands that need a node or a guest and
+//! did not get one, e.g. `pvenom -n` or `pvenom vm export-config web.toml`.
+//!
+//! The picker only shows up when both stdin and stderr are terminals, so
+//! scripts and pipes keep getting a plain error instead of a hanging prompt.
+
+use anyhow::{bail, Result};
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::FuzzySelect;
+use std::io::IsTerminal;
+
+use super::Commands;
+use crate::vlog_debug;
+
+impl Commands {
+    /// The VMID of the given guest (VMID or name), or one picked among the
+    /// cluster guests
+    pub async fn guest_or_pick(&self, guest: Option<&str>) -> Result<u32> {
+        if let Some(guest) = guest {
+            return self.resolve_guest(guest).await;
+        }
+        if !interactive() {
+            bail!("This action needs a guest VMID or name: pvenom vm <guest> ...");
+        }
+
+        let mut guests: Vec<_> = self.client.get_cluster_resources(Some("vm")).await?
+            .into_iter()
+            .filter(|r| r.is_guest())
+            .collect();
+        guests.sort_by_key(|g| g.vmid);
+
+        let items: Vec<String> = guests.iter()
+            .map(|g| format!("[{}] {} ({}) on {} - {}",
+                             g.vmid.unwrap_or_default(),
+                             g.name.as_deref().unwrap_or("-"),
+                             g.guest_type(),
+                             g.node.as_deref().unwrap_or("-"),
+                             g.status.as_deref().unwrap_or("unknown")))
+            .collect();
+
+        let index = pick("Guest", &items)?;
+        let vmid = guests[index].vmid.unwrap_or_default();
+        vlog_debug!("Picked guest {}", vmid);
+        Ok(vmid)
+    }
+
+    /// The given node name, or one picked among the cluster nodes
+    pub async fn node_or_pick(&self, node: Option<&str>) -> Result<String> {
+        if let Some(node) = node {
+            return Ok(node.to_string());
+        }
+        if !interactive() {
+            bail!("This action needs a node name: pvenom --node <name> ...");
+        }
+
+        let mut nodes = self.client.get_nodes().await?;
+        nodes.sort_by(|a, b| a.node.cmp(&b.node));
+        let items: Vec<String> = nodes.iter()
+            .map(|n| format!("{} ({})", n.node, n.status))
+            .collect();
+
+        let index = pick("Node", &items)?;
+        vlog_debug!("Picked node {}", nodes[index].node);
+        Ok(nodes[index].node.clone())
+    }
+
+    /// Node names, guest VMIDs and guest names, the dynamic words of shell
+    /// completion
+    pub async fn node_and_guest_names(&self) -> Result<Vec<String>> {
+        let resources = self.client.get_cluster_resources(None).await?;
+        Ok(resources.iter()
+            .flat_map(|r| match r.resource_type.as_str() {
+                "node" => vec![r.node.clone()],
+                _ if r.is_guest() => vec![r.vmid.map(|v| v.to_string()), r.name.clone()],
+                _ => Vec::new(),
+            })
+            .flatten()
+            .collect())
+    }
+}
+
+fn interactive() -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+fn pick(prompt: &str, items: &[String]) -> Result<usize> {
+    if items.is_empty() {
+        bail!("Nothing to pick from, the cluster returned no {}s", prompt.to_lowercase());
+    }
+    match FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .items(items)
+        .default(0)
+        .interact_opt()?
+    {
+        Some(index) => Ok(index),
+        None => bail!("Selection cancelled"),
+    }
+}
@@ -0,0 +1,168 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # ping.rs
+//!
+//! Reachability of the running guests from the operator's machine,
+//! `pvenom ping-sweep [--port 22]`.
+//!
+//! Every running guest with an address known to the API (guest agent or
+//! container runtime) is probed at once: with `ping` by default, with a
+//! TCP connection to `--port` otherwise, for networks dropping ICMP. A
+//! guest Proxmox says is running but which does not answer is reported in
+//! red and makes the command fail, so it can run from cron or a monitor,
+//! unless its node or one of its tags is in a maintenance window.
+
+use anyhow::{bail, Context, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use std::net::IpAddr;
+use std::process::Stdio;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::net::TcpStream;
+use tokio::task::JoinSet;
+
+use super::Commands;
+use crate::models::{GuestReachability, OutputFormat, PingSweepOutput};
+use crate::{csv, csv_row, pager, vlog_debug, vlog_info, vlog_success};
+
+impl Commands {
+    /// Probe the running guests with ICMP, or TCP on `port`, each allowed
+    /// `timeout` seconds
+    pub async fn ping_sweep(&self, port: Option<u16>, timeout: u64) -> Result<()> {
+        vlog_info!("Collecting guest addresses...");
+        let mut targets = Vec::new();
+        let mut without_address = 0;
+        for guest in self.client.get_cluster_resources(Some("vm")).await? {
+            let (Some(vmid), Some(node)) = (guest.vmid, guest.node.clone()) else {
+                continue;
+            };
+            if !guest.is_guest() || guest.status.as_deref() != Some("running") {
+                continue;
+            }
+            match self.client.get_guest_ip(&node, vmid, &guest.resource_type).await? {
+                Some(ip) => targets.push((node, vmid, guest.name.clone().unwrap_or_default(), guest.tags.clone(), ip)),
+                None => without_address += 1,
+            }
+        }
+        if without_address > 0 {
+            vlog_info!("{} running guest(s) without a known address skipped", without_address);
+        }
+
+        let method = port.map(|p| format!("tcp/{}", p)).unwrap_or_else(|| "icmp".to_string());
+        vlog_info!("Probing {} guest(s) with {}...", targets.len(), method);
+        let mut probes = JoinSet::new();
+        for (node, vmid, name, tags, ip) in targets {
+            probes.spawn(async move {
+                let started = Instant::now();
+                let result = probe(&ip, port, Duration::from_secs(timeout)).await;
+                (node, vmid, name, tags, ip, result.map(|_| started.elapsed()))
+            });
+        }
+
+        let windows = self.maintenance_windows();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let mut guests = Vec::new();
+        while let Some(joined) = probes.join_next().await {
+            let (node, vmid, name, tags, ip, result) = joined?;
+            vlog_debug!("Guest {} at {}: {:?}", vmid, ip, result);
+            let maintenance = result.is_err()
+                && windows.as_ref().is_some_and(|w| w.covering(Some(&node), tags.as_deref(), now).is_some());
+            guests.push(GuestReachability {
+                node,
+                vmid,
+                name,
+                ip,
+                reachable: result.is_ok(),
+                maintenance,
+                latency_ms: result.as_ref().ok().map(|d| (d.as_secs_f64() * 10_000.0).round() / 10.0),
+                error: result.err().map(|e| e.to_string()),
+            });
+        }
+        guests.sort_by(|a, b| a.node.cmp(&b.node).then(a.vmid.cmp(&b.vmid)));
+        let unreachable = guests.iter().filter(|g| !g.reachable && !g.maintenance).count();
+
+        match self.output_format {
+            OutputFormat::Json => {
+                let output = PingSweepOutput { method, guests };
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            }
+            OutputFormat::Csv => {
+                csv_row!("NODE", "VMID", "NAME", "IP", "REACHABLE", "MAINTENANCE", "LATENCY_MS", "ERROR");
+                for g in &guests {
+                    csv_row!(g.node, g.vmid, g.name, g.ip, g.reachable, g.maintenance,
+                             csv::num(g.latency_ms.map(|l| format!("{:.1}", l)).unwrap_or_default()),
+                             g.error.as_deref().unwrap_or_default());
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("IP").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new(format!("Reachable ({})", method)).add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for g in &guests {
+                    let reachable = match (&g.latency_ms, &g.error) {
+                        (Some(latency), _) => Cell::new(format!("yes, {:.1} ms", latency)).fg(Color::Green),
+                        (None, _) if g.maintenance => Cell::new("no, in maintenance").fg(Color::Yellow),
+                        (None, error) => Cell::new(format!("NO: {}", error.as_deref().unwrap_or("no answer"))).fg(Color::Red),
+                    };
+                    table.add_row(vec![Cell::new(&g.node), Cell::new(g.vmid), Cell::new(&g.name), Cell::new(&g.ip), reachable]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        if unreachable > 0 {
+            bail!("{} running guest(s) unreachable", unreachable);
+        }
+        vlog_success!("All probed guests reachable");
+        Ok(())
+    }
+}
+
+/// One ICMP echo with the system `ping`, needing no privileges, or a TCP
+/// connection to `port`
+pub(super) async fn probe(ip: &str, port: Option<u16>, timeout: Duration) -> Result<()> {
+    let address: IpAddr = ip.parse().with_context(|| format!("Invalid address '{}'", ip))?;
+    match port {
+        Some(port) => {
+            tokio::time::timeout(timeout, TcpStream::connect((address, port))).await
+                .map_err(|_| anyhow::anyhow!("timed out"))??;
+            Ok(())
+        }
+        None => {
+            let status = tokio::process::Command::new("ping")
+                .args(["-c", "1", "-W", &timeout.as_secs().max(1).to_string(), ip])
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .await
+                .context("Cannot run ping, use --port for TCP checks")?;
+            if !status.success() {
+                bail!("no echo reply");
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,131 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # provision.rs
+//!
+//! First boot of a new guest up to the configuration management handoff,
+//! `pvenom vm <vmid> provision --wait-ip --wait-ssh --then 'ansible-playbook
+//! -i {ip}, site.yml'`.
+//!
+//! The guest is started unless already running, then pvenom waits for an
+//! address reported by the guest agent (or the container runtime), with
+//! `--wait-ssh` for the SSH port to accept connections, and runs the
+//! `--then` command with `sh -c`. `{ip}` in it is replaced by the address, which is also in
+//! `PVENOM_IP` next to `PVENOM_VMID` and `PVENOM_NAME`. Without `--then`
+//! the address is printed, for scripts.
+
+use anyhow::{bail, Context, Result};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+use super::ping::probe;
+use super::vm::guest_address;
+use super::Commands;
+use crate::{vlog_debug, vlog_info, vlog_success};
+
+/// Pause between two looks at the guest
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Time allowed to each SSH connection attempt
+const SSH_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Settings of `pvenom vm <vmid> provision`
+pub struct ProvisionOptions {
+    pub wait_ip: bool,
+    pub wait_ssh: bool,
+    pub ssh_port: u16,
+    /// Local command run once the guest is ready, `{ip}` substituted
+    pub then: Option<String>,
+    /// Seconds allowed to the whole provisioning
+    pub timeout: u64,
+}
+
+impl Commands {
+    pub async fn provision_guest(&self, vmid: u32, options: &ProvisionOptions) -> Result<()> {
+        let started = Instant::now();
+        let guest = self.locate_guest(vmid).await?;
+        let node = guest.node.clone().context("Guest has no node")?;
+        let name = guest.name.clone().unwrap_or_default();
+
+        if guest.status.as_deref() == Some("running") {
+            vlog_info!("Guest {} already running", vmid);
+        } else {
+            self.confirm("start", &format!("guest {} ({}) on '{}'", vmid, name, node))?;
+            vlog_info!("Starting guest {} on node '{}'...", vmid, node);
+            let upid = self.client.set_guest_status(&node, &guest.resource_type, vmid, "start").await?;
+            self.wait_for_task(&upid, options.timeout).await.context("Start failed")?;
+        }
+        if self.client.dry_run() {
+            return Ok(());
+        }
+        let remaining = || options.timeout.saturating_sub(started.elapsed().as_secs());
+
+        let needs_ip = options.wait_ip || options.wait_ssh || options.then.is_some();
+        if !needs_ip {
+            vlog_success!("Guest {} running", vmid);
+            return Ok(());
+        }
+        vlog_info!("Waiting for an address of guest {}...", vmid);
+        let ip = loop {
+            let interfaces = self.client.get_guest_interfaces(&node, &guest.resource_type, vmid).await.unwrap_or_default();
+            if let Some(ip) = guest_address(&interfaces) {
+                break ip;
+            }
+            if remaining() == 0 {
+                bail!("Guest {} has no address after {}s, is the guest agent running?", vmid, options.timeout);
+            }
+            vlog_debug!("No address of guest {} yet", vmid);
+            tokio::time::sleep(POLL_INTERVAL).await;
+        };
+        vlog_info!("Guest {} is at {}", vmid, ip);
+
+        if options.wait_ssh {
+            vlog_info!("Waiting for SSH on {}:{}...", ip, options.ssh_port);
+            loop {
+                match probe(&ip, Some(options.ssh_port), SSH_PROBE_TIMEOUT).await {
+                    Ok(()) => break,
+                    Err(e) if remaining() == 0 => bail!("No SSH on {}:{} after {}s: {}", ip, options.ssh_port, options.timeout, e),
+                    Err(e) => vlog_debug!("SSH not up yet: {}", e),
+                }
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+
+        let Some(then) = &options.then else {
+            println!("{}", ip);
+            vlog_success!("Guest {} ready in {}s", vmid, started.elapsed().as_secs());
+            return Ok(());
+        };
+        let command = then.replace("{ip}", &ip);
+        vlog_info!("Handing over: {}", command);
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .env("PVENOM_IP", &ip)
+            .env("PVENOM_VMID", vmid.to_string())
+            .env("PVENOM_NAME", &name)
+            .status()
+            .await
+            .with_context(|| format!("Failed to run '{}'", command))?;
+        if !status.success() {
+            bail!("Handoff command failed: {}", status);
+        }
+        vlog_success!("Guest {} provisioned in {}s", vmid, started.elapsed().as_secs());
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # publish.rs
+//!
+//! Publish cluster state to external systems, `pvenom publish ...`.
+//!
+//! MQTT topics, with the default prefixes:
+//!
+//! pvenom/<cluster>/node/<node>/state          JSON state of a node
+//! pvenom/<cluster>/guest/<vmid>/state         JSON state of a guest
+//! homeassistant/<component>/<id>/config       Home Assistant discovery
+//!
+//! All messages are retained, so Home Assistant picks the last state up
+//! even when it restarts between two publications.
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use super::Commands;
+use crate::models::ClusterResource;
+use crate::mqtt::MqttPublisher;
+use crate::{vlog_error, vlog_info, vlog_success};
+
+/// Settings of `pvenom publish mqtt`
+pub struct MqttOptions {
+    pub broker: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub topic_prefix: String,
+    pub discovery_prefix: String,
+    pub discovery: bool,
+    /// Publish every `interval` seconds instead of once
+    pub interval: Option<u64>,
+}
+
+impl Commands {
+    pub async fn publish_mqtt(&self, options: &MqttOptions) -> Result<()> {
+        let cluster = topic_safe(&self.cluster_name().await?);
+
+        loop {
+            match self.publish_mqtt_once(&cluster, options).await {
+                Ok(count) => vlog_success!("Published state of {} resource(s) to {}", count, options.broker),
+                // In daemon mode a broker hiccup must not end the run
+                Err(e) if options.interval.is_some() => vlog_error!("MQTT publication failed: {}", e),
+                Err(e) => return Err(e),
+            }
+
+            let Some(interval) = options.interval else {
+                return Ok(());
+            };
+            tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+        }
+    }
+
+    async fn publish_mqtt_once(&self, cluster: &str, options: &MqttOptions) -> Result<usize> {
+        vlog_info!("Publishing cluster state to MQTT broker {}...", options.broker);
+        let resources = self.client.get_cluster_resources(None).await?;
+
+        let client_id = format!("pvenom-{}-{}", cluster, std::process::id());
+        let mut mqtt = MqttPublisher::connect(
+            &options.broker, &client_id, options.username.as_deref(), options.password.as_deref()).await?;
+
+        let mut count = 0;
+        for resource in &resources {
+            if resource.resource_type == "node" {
+                let Some(node) = resource.node.as_deref() else { continue };
+                let id = format!("{}_{}", cluster, topic_safe(node));
+                let state_topic = format!("{}/{}/node/{}/state", options.topic_prefix, cluster, topic_safe(node));
+                mqtt.publish(&state_topic, &state_payload(resource).to_string(), true).await?;
+
+                if options.discovery {
+                    let device = json!({
+                        "identifiers": [format!("pvenom_{}", id)],
+                        "name": format!("Proxmox node {}", node),
+                        "manufacturer": "Proxmox",
+                        "model": "PVE node",
+                    });
+                    let entities = [
+                        ("binary_sensor", "online", "Online", "{{ 'ON' if value_json.status == 'online' else 'OFF' }}", None),
+                        ("sensor", "cpu", "CPU", "{{ value_json.cpu_percent }}", Some("%")),
+                        ("sensor", "memory", "Memory", "{{ value_json.mem_percent }}", Some("%")),
+                        ("sensor", "disk", "Disk", "{{ value_json.disk_percent }}", Some("%")),
+                    ];
+                    for (component, key, name, template, unit) in entities {
+                        let unique_id = format!("pvenom_{}_{}", id, key);
+                        let topic = format!("{}/{}/{}/config", options.discovery_prefix, component, unique_id);
+                        let config = discovery_config(&unique_id, name, &state_topic, template, unit, &device);
+                        mqtt.publish(&topic, &config.to_string(), true).await?;
+                    }
+                }
+                count += 1;
+            } else if resource.is_guest() {
+                let Some(vmid) = resource.vmid else { continue };
+                let name = resource.name.clone().unwrap_or_else(|| vmid.to_string());
+                let id = format!("{}_{}", cluster, vmid);
+                let state_topic = format!("{}/{}/guest/{}/state", options.topic_prefix, cluster, vmid);
+                mqtt.publish(&state_topic, &state_payload(resource).to_string(), true).await?;
+
+                if options.discovery {
+                    let device = json!({
+                        "identifiers": [format!("pvenom_{}", id)],
+                        "name": format!("{} ({})", name, vmid),
+                        "manufacturer": "Proxmox",
+                        "model": format!("PVE {}", resource.guest_type()),
+                    });
+                    let entities = [
+                        ("binary_sensor", "running", "Running", "{{ 'ON' if value_json.status == 'running' else 'OFF' }}", None),
+                        ("sensor", "cpu", "CPU", "{{ value_json.cpu_percent }}", Some("%")),
+                        ("sensor", "memory", "Memory", "{{ value_json.mem_percent }}", Some("%")),
+                    ];
+                    for (component, key, label, template, unit) in entities {
+                        let unique_id = format!("pvenom_{}_{}", id, key);
+                        let topic = format!("{}/{}/{}/config", options.discovery_prefix, component, unique_id);
+                        let config = discovery_config(&unique_id, label, &state_topic, template, unit, &device);
+                        mqtt.publish(&topic, &config.to_string(), true).await?;
+                    }
+                }
+                count += 1;
+            }
+        }
+
+        mqtt.disconnect().await?;
+        Ok(count)
+    }
+}
+
+/// MQTT topic levels must not contain wildcards or separators
+fn topic_safe(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+fn percent(used: Option<u64>, total: Option<u64>) -> Option<f64> {
+    match (used, total) {
+        (Some(u), Some(t)) if t > 0 => Some((u as f64 * 1000.0 / t as f64).round() / 10.0),
+        _ => None,
+    }
+}
+
+fn state_payload(resource: &ClusterResource) -> Value {
+    json!({
+        "name": resource.name.as_deref().or(resource.node.as_deref()),
+        "node": resource.node,
+        "type": resource.resource_type,
+        "status": resource.status,
+        "cpu_percent": resource.cpu.map(|c| (c * 1000.0).round() / 10.0),
+        "mem_percent": percent(resource.mem, resource.maxmem),
+        "disk_percent": percent(resource.disk, resource.maxdisk),
+        "uptime": resource.uptime,
+    })
+}
+
+fn discovery_config(unique_id: &str, name: &str, state_topic: &str, template: &str, unit: Option<&str>, device: &Value) -> Value {
+    let mut config = json!({
+        "name": name,
+        "unique_id": unique_id,
+        "state_topic": state_topic,
+        "value_template": template,
+        "device": device,
+    });
+    if let Some(unit) = unit {
+        config["unit_of_measurement"] = json!(unit);
+        config["state_class"] = json!("measurement");
+    }
+    config
+}
@@ -0,0 +1,245 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # render.rs
+//!
+//! Print captured JSON again as tables or CSV, without a cluster:
+//! `pvenom render --input cluster.json.gz --filter type=qemu`.
+//!
+//! Any JSON output of pvenom works, `snapshot-state` files included. The
+//! scalar fields of an object, nested ones with dotted names, make a
+//! property table; each list of objects gets a table of its own, with a
+//! column per field that has a value in some row. Nested values show as a
+//! count, `--columns config.memory` reaches into them.
+//!
+//! `--filter` and `--columns` apply to the lists having the named field, so
+//! filtering guests leaves the nodes of a state file alone.
+
+use anyhow::{bail, Context, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use flate2::read::GzDecoder;
+use serde_json::{Map, Value};
+use std::io::Read;
+
+use crate::csv::{self, ToField};
+use crate::models::OutputFormat;
+use crate::{csv_row, pager, vlog_debug, vlog_success};
+
+pub struct RenderOptions {
+    /// JSON file, `.gz` compressed or `-` for stdin
+    pub input: String,
+    /// `FIELD=VALUE` or `FIELD!=VALUE`, all must hold
+    pub filters: Vec<String>,
+    /// Fields shown in the tables, dotted for nested ones
+    pub columns: Vec<String>,
+}
+
+/// One list of objects of the document, `name` is empty for a top level list
+struct Section<'a> {
+    name: String,
+    rows: Vec<&'a Map<String, Value>>,
+}
+
+pub fn render(options: &RenderOptions, output_format: OutputFormat) -> Result<()> {
+    let document = read(&options.input)?;
+    let filters = options.filters.iter().map(|f| parse_filter(f)).collect::<Result<Vec<_>>>()?;
+
+    let mut properties = Vec::new();
+    let mut sections = Vec::new();
+    match &document {
+        Value::Array(items) => sections.push(Section { name: String::new(), rows: objects(items) }),
+        Value::Object(fields) => collect(fields, "", &mut properties, &mut sections),
+        other => properties.push((String::new(), cell_text(other))),
+    }
+    for section in &mut sections {
+        for (field, expected, equal) in &filters {
+            if section.rows.iter().any(|row| lookup(row, field).is_some()) {
+                section.rows.retain(|row| (lookup(row, field).map(cell_text).as_deref() == Some(expected.as_str())) == *equal);
+            }
+        }
+    }
+    vlog_debug!("Rendering {} properties and {} list(s) from {}", properties.len(), sections.len(), options.input);
+
+    match output_format {
+        OutputFormat::Json => {
+            // The document with the filtered lists
+            let mut document = document.clone();
+            for section in &sections {
+                let rows = Value::Array(section.rows.iter().map(|r| Value::Object((*r).clone())).collect());
+                if section.name.is_empty() {
+                    document = rows;
+                } else if let Some(slot) = document.pointer_mut(&format!("/{}", section.name.replace('.', "/"))) {
+                    *slot = rows;
+                }
+            }
+            println!("{}", serde_json::to_string_pretty(&document)?);
+        }
+        OutputFormat::Csv => {
+            if !properties.is_empty() {
+                csv_row!("PROPERTY", "VALUE");
+                for (property, value) in &properties {
+                    csv_row!(property, value);
+                }
+            }
+            for (i, section) in sections.iter().enumerate() {
+                if i > 0 || !properties.is_empty() {
+                    println!();
+                }
+                let columns = columns(section, &options.columns);
+                csv::print_row(&columns.iter().map(|c| c.to_uppercase().replace('.', "_").to_field()).collect::<Vec<_>>());
+                for row in &section.rows {
+                    csv::print_row(&columns.iter()
+                        .map(|c| match lookup(row, c) {
+                            Some(value) if value.is_number() => csv::num(value),
+                            value => value.map(cell_text).unwrap_or_default().to_field(),
+                        })
+                        .collect::<Vec<_>>());
+                }
+            }
+        }
+        OutputFormat::Table => {
+            if !properties.is_empty() {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Property").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Value").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for (property, value) in &properties {
+                    table.add_row(vec![property, value]);
+                }
+                pager::print_table(&mut table);
+            }
+            for section in &sections {
+                if !section.name.is_empty() {
+                    println!("\n=== {} ({}) ===\n", section.name, section.rows.len());
+                }
+                if section.rows.is_empty() {
+                    println!("Nothing to show.");
+                    continue;
+                }
+                let columns = columns(section, &options.columns);
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(columns.iter().map(|c| Cell::new(c).add_attribute(Attribute::Bold).fg(Color::Cyan)));
+                for row in &section.rows {
+                    table.add_row(columns.iter().map(|c| Cell::new(lookup(row, c).map(cell_text).unwrap_or_else(|| "-".to_string()))));
+                }
+                pager::print_table(&mut table);
+            }
+        }
+    }
+
+    let rows: usize = sections.iter().map(|s| s.rows.len()).sum();
+    vlog_success!("Rendered {} row(s) in {} list(s)", rows, sections.len());
+    Ok(())
+}
+
+fn read(input: &str) -> Result<Value> {
+    let mut raw = Vec::new();
+    if input == "-" {
+        std::io::stdin().read_to_end(&mut raw)?;
+    } else {
+        raw = std::fs::read(input).with_context(|| format!("Failed to read {}", input))?;
+    }
+    if input.ends_with(".gz") {
+        let mut json = Vec::new();
+        GzDecoder::new(raw.as_slice()).read_to_end(&mut json)
+            .with_context(|| format!("Failed to decompress {}", input))?;
+        raw = json;
+    }
+    serde_json::from_slice(&raw).with_context(|| format!("{} is not JSON", input))
+}
+
+/// `FIELD=VALUE` as (field, value, true), `FIELD!=VALUE` as (field, value, false)
+fn parse_filter(filter: &str) -> Result<(String, String, bool)> {
+    if let Some((field, value)) = filter.split_once("!=") {
+        return Ok((field.to_string(), value.to_string(), false));
+    }
+    match filter.split_once('=') {
+        Some((field, value)) => Ok((field.to_string(), value.to_string(), true)),
+        None => bail!("Invalid filter '{}', expected FIELD=VALUE or FIELD!=VALUE", filter),
+    }
+}
+
+/// Scalars of `fields` as dotted properties, lists of objects as sections
+fn collect<'a>(fields: &'a Map<String, Value>, prefix: &str, properties: &mut Vec<(String, String)>, sections: &mut Vec<Section<'a>>) {
+    for (key, value) in fields {
+        let name = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        match value {
+            Value::Object(nested) => collect(nested, &name, properties, sections),
+            Value::Array(items) if items.iter().all(Value::is_object) => {
+                sections.push(Section { name, rows: objects(items) });
+            }
+            other => properties.push((name, cell_text(other))),
+        }
+    }
+}
+
+fn objects(items: &[Value]) -> Vec<&Map<String, Value>> {
+    items.iter().filter_map(Value::as_object).collect()
+}
+
+/// Requested columns found in the section, or every field with a value
+fn columns(section: &Section, requested: &[String]) -> Vec<String> {
+    let found: Vec<String> = requested.iter()
+        .filter(|c| section.rows.iter().any(|row| lookup(row, c).is_some()))
+        .cloned()
+        .collect();
+    if !found.is_empty() {
+        return found;
+    }
+    let mut columns: Vec<String> = Vec::new();
+    for row in &section.rows {
+        for (key, value) in row.iter() {
+            if !value.is_null() && !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+    columns
+}
+
+/// Field of a row by dotted path, list items by index
+fn lookup<'a>(row: &'a Map<String, Value>, path: &str) -> Option<&'a Value> {
+    let mut parts = path.split('.');
+    let mut value = row.get(parts.next()?)?;
+    for part in parts {
+        value = match value {
+            Value::Object(fields) => fields.get(part)?,
+            Value::Array(items) => items.get(part.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    (!value.is_null()).then_some(value)
+}
+
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::String(s) => s.clone(),
+        Value::Object(fields) => format!("{{{} fields}}", fields.len()),
+        Value::Array(items) if items.iter().all(|i| !i.is_object() && !i.is_array()) => {
+            items.iter().map(cell_text).collect::<Vec<_>>().join(", ")
+        }
+        Value::Array(items) => format!("[{} items]", items.len()),
+        other => other.to_string(),
+    }
+}
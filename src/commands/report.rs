@@ -0,0 +1,128 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # report.rs
+//!
+//! Markdown report of the cluster, `pvenom report > report.md`: the health
+//! of `summary` followed by tables of the nodes, storages and guests.
+//! `--email ops@example.com` sends it through the `[smtp]` server of the
+//! config file instead of printing it, for a daily mail from cron.
+
+use anyhow::Result;
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::Commands;
+use crate::{mail, vlog_info, vlog_success};
+
+impl Commands {
+    /// Print the report, or send it to the `email` addresses
+    pub async fn report(&self, email: &[String]) -> Result<()> {
+        vlog_info!("Collecting cluster state for the report...");
+        let health = self.health().await?;
+        let mut resources = self.client.get_cluster_resources(None).await?;
+        resources.sort_by(|a, b| a.node.cmp(&b.node).then(a.vmid.cmp(&b.vmid)).then(a.storage.cmp(&b.storage)));
+        let of_type = |kind: &'static str| resources.iter().filter(move |r| r.resource_type == kind);
+        let title = match &health.cluster {
+            Some(cluster) => format!("Cluster report: {}", cluster),
+            None => "Node report".to_string(),
+        };
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let mut md = String::new();
+        writeln!(md, "# {}", title)?;
+        writeln!(md)?;
+        writeln!(md, "Generated by pvenom {} on {}.", env!("CARGO_PKG_VERSION"), self.timestamp(now))?;
+        writeln!(md)?;
+        writeln!(md, "{}/{} node(s) online, {} guest(s) running, {} stopped.",
+                 health.nodes_online, health.nodes_total, health.guests_running, health.guests_stopped)?;
+
+        writeln!(md)?;
+        writeln!(md, "## Alerts")?;
+        writeln!(md)?;
+        if health.alerts.is_empty() {
+            writeln!(md, "None.")?;
+        }
+        for alert in &health.alerts {
+            writeln!(md, "- {}", alert)?;
+        }
+
+        writeln!(md)?;
+        writeln!(md, "## Nodes")?;
+        writeln!(md)?;
+        writeln!(md, "| Node | Status | CPU | RAM | Uptime |")?;
+        writeln!(md, "|---|---|---|---|---|")?;
+        for node in of_type("node") {
+            writeln!(md, "| {} | {} | {} | {} | {} |",
+                     node.node.as_deref().unwrap_or_default(),
+                     node.status.as_deref().unwrap_or("unknown"),
+                     node.cpu.map(|c| format!("{:.0}%", c * 100.0)).unwrap_or_default(),
+                     usage(node.mem, node.maxmem),
+                     node.uptime.filter(|u| *u > 0).map(|u| self.uptime(u)).unwrap_or_default())?;
+        }
+
+        writeln!(md)?;
+        writeln!(md, "## Storage")?;
+        writeln!(md)?;
+        writeln!(md, "| Storage | Node | Type | Used |")?;
+        writeln!(md, "|---|---|---|---|")?;
+        for storage in of_type("storage") {
+            writeln!(md, "| {} | {} | {} | {} |",
+                     storage.storage.as_deref().unwrap_or_default(),
+                     storage.node.as_deref().unwrap_or_default(),
+                     storage.plugintype.as_deref().unwrap_or_default(),
+                     usage(storage.disk, storage.maxdisk))?;
+        }
+
+        writeln!(md)?;
+        writeln!(md, "## Guests")?;
+        writeln!(md)?;
+        writeln!(md, "| VMID | Name | Type | Node | Status | Uptime |")?;
+        writeln!(md, "|---|---|---|---|---|---|")?;
+        for guest in resources.iter().filter(|r| r.is_guest()) {
+            writeln!(md, "| {} | {} | {} | {} | {} | {} |",
+                     guest.vmid.unwrap_or_default(),
+                     guest.name.as_deref().unwrap_or_default(),
+                     guest.resource_type,
+                     guest.node.as_deref().unwrap_or_default(),
+                     guest.status.as_deref().unwrap_or("unknown"),
+                     guest.uptime.filter(|u| *u > 0).map(|u| self.uptime(u)).unwrap_or_default())?;
+        }
+
+        if email.is_empty() {
+            print!("{}", md);
+            return Ok(());
+        }
+        let subject = match health.alerts.len() {
+            0 => title,
+            n => format!("{} ({} alert(s))", title, n),
+        };
+        mail::send(self.smtp.as_ref(), email, &subject, md).await?;
+        vlog_success!("Report sent to {}", email.join(", "));
+        Ok(())
+    }
+}
+
+/// `used/total GiB (percent)` of a node RAM or a storage
+fn usage(used: Option<u64>, total: Option<u64>) -> String {
+    const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+    match (used, total) {
+        (Some(u), Some(t)) if t > 0 => format!("{:.1}/{:.1} GiB ({:.0}%)", u as f64 / GIB, t as f64 / GIB, u as f64 * 100.0 / t as f64),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,227 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # restore.rs
+//!
+//! Restore drills, `pvenom backups test-restore <volid>`:
+//!
+//! pvenom backups test-restore pbs:backup/vm/100/2025-01-31T02:00:00Z --as-vmid 9999 --cleanup
+//!
+//! The backup is restored into a scratch VMID with new MAC addresses and
+//! every network link down, so the copy never answers on the network of
+//! the original. Then it is started and, for VMs, the guest agent pinged;
+//! `--no-start` stops after the restore. `--cleanup` destroys the scratch
+//! guest at the end, also when a step failed.
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+use super::Commands;
+use crate::models::{OutputFormat, TestRestoreOutput};
+use crate::{vlog_debug, vlog_info, vlog_success, vlog_warn};
+
+/// Seconds between two guest agent pings
+const AGENT_POLL_SECS: u64 = 5;
+
+/// Settings of `pvenom backups test-restore`
+pub struct TestRestoreOptions {
+    pub volid: String,
+    /// Scratch VMID, the next free one when None
+    pub vmid: Option<u32>,
+    /// Node to restore on, the first one with the backup storage when None
+    pub node: Option<String>,
+    /// Storage for the restored disks, the original ones when None
+    pub storage: Option<String>,
+    pub start: bool,
+    pub cleanup: bool,
+    /// Seconds allowed to each step
+    pub timeout: u64,
+}
+
+impl Commands {
+    pub async fn test_restore(&self, options: &TestRestoreOptions) -> Result<()> {
+        let guest_type = backup_guest_type(&options.volid)
+            .with_context(|| format!("Cannot tell VM from container backup: {}", options.volid))?;
+        let storage = options.volid.split_once(':').map(|(s, _)| s)
+            .with_context(|| format!("Malformed volume id: {}", options.volid))?;
+        let node = match &options.node {
+            Some(node) => node.clone(),
+            None => self.storage_nodes(storage).await?.remove(0),
+        };
+        let vmid = match options.vmid {
+            Some(vmid) => vmid,
+            None => self.client.get_next_vmid().await?,
+        };
+        if self.locate_guest(vmid).await.is_ok() {
+            bail!("VMID {} is in use, pick a free one with --as-vmid", vmid);
+        }
+
+        self.confirm("test-restore", &format!("{} into {} {} on '{}'", options.volid, guest_type, vmid, node))?;
+
+        let mut report = TestRestoreOutput {
+            volid: options.volid.clone(),
+            vmid,
+            node: node.clone(),
+            guest_type: guest_type.to_string(),
+            restore_seconds: None,
+            started: None,
+            agent: None,
+            cleaned_up: false,
+            error: None,
+        };
+
+        let outcome = self.run_drill(options, guest_type, &node, vmid, &mut report).await;
+        if let Err(e) = &outcome {
+            report.error = Some(e.to_string());
+        }
+        if options.cleanup && report.restore_seconds.is_some() {
+            match self.destroy_scratch(guest_type, &node, vmid, options.timeout).await {
+                Ok(()) => report.cleaned_up = true,
+                Err(e) => vlog_warn!("Scratch guest {} not destroyed, remove it by hand: {}", vmid, e),
+            }
+        }
+
+        if self.client.dry_run() {
+            return outcome;
+        }
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+            _ => {
+                let step = |v: Option<bool>| match v {
+                    Some(true) => "ok",
+                    Some(false) => "FAILED",
+                    None => "skipped",
+                };
+                println!("Backup:     {}", report.volid);
+                println!("Restored:   {} {} on '{}'{}", report.guest_type, report.vmid, report.node,
+                         report.restore_seconds.map(|s| format!(" in {}s", s)).unwrap_or_else(|| " FAILED".to_string()));
+                println!("Started:    {}", step(report.started));
+                println!("Agent:      {}", step(report.agent));
+                println!("Cleaned up: {}", if report.cleaned_up { "yes" } else { "no" });
+            }
+        }
+
+        outcome?;
+        vlog_success!("Restore drill of {} passed", options.volid);
+        Ok(())
+    }
+
+    async fn run_drill(&self, options: &TestRestoreOptions, guest_type: &str, node: &str, vmid: u32, report: &mut TestRestoreOutput) -> Result<()> {
+        let started = Instant::now();
+        let mut params = vec![
+            ("vmid".to_string(), vmid.to_string()),
+            ("unique".to_string(), "1".to_string()),
+        ];
+        match guest_type {
+            "qemu" => params.push(("archive".to_string(), options.volid.clone())),
+            _ => {
+                params.push(("ostemplate".to_string(), options.volid.clone()));
+                params.push(("restore".to_string(), "1".to_string()));
+            }
+        }
+        if let Some(storage) = &options.storage {
+            params.push(("storage".to_string(), storage.clone()));
+        }
+
+        vlog_info!("Restoring {} into {} {} on node '{}'...", options.volid, guest_type, vmid, node);
+        let upid = self.client.create_guest(node, guest_type, &params).await?;
+        self.wait_for_task(&upid, options.timeout).await.context("Restore failed")?;
+        report.restore_seconds = Some(started.elapsed().as_secs());
+        if self.client.dry_run() {
+            return Ok(());
+        }
+
+        self.isolate_network(guest_type, node, vmid).await?;
+        if !options.start {
+            return Ok(());
+        }
+
+        vlog_info!("Starting scratch guest {}...", vmid);
+        let upid = self.client.set_guest_status(node, guest_type, vmid, "start").await?;
+        let boot = self.wait_for_task(&upid, options.timeout).await;
+        report.started = Some(boot.is_ok());
+        boot.context("Start failed")?;
+
+        // Containers have no agent, running is as far as they go
+        if guest_type != "qemu" {
+            return Ok(());
+        }
+        let config = self.client.get_guest_config(node, guest_type, vmid).await?;
+        let agent_enabled = config.get("agent")
+            .and_then(Value::as_str)
+            .is_some_and(|a| a.split(',').any(|o| o == "1" || o == "enabled=1"));
+        if !agent_enabled {
+            vlog_warn!("Guest agent not enabled in the backup, not pinged");
+            return Ok(());
+        }
+
+        vlog_info!("Waiting for the guest agent of {}...", vmid);
+        let waiting = Instant::now();
+        // `agent/ping` is POST only, `info` answers a GET just as well
+        while self.client.get_agent_result(node, vmid, "info").await.is_none() {
+            if waiting.elapsed().as_secs() > options.timeout {
+                report.agent = Some(false);
+                bail!("Guest agent silent after {}s", options.timeout);
+            }
+            tokio::time::sleep(Duration::from_secs(AGENT_POLL_SECS)).await;
+        }
+        report.agent = Some(true);
+        Ok(())
+    }
+
+    /// Take every network link of the restored guest down
+    async fn isolate_network(&self, guest_type: &str, node: &str, vmid: u32) -> Result<()> {
+        let config = self.client.get_guest_config(node, guest_type, vmid).await?;
+        let params: Vec<(String, String)> = config.iter()
+            .filter(|(key, _)| key.starts_with("net") && key[3..].parse::<u32>().is_ok())
+            .filter_map(|(key, value)| value.as_str().map(|v| (key.clone(), v)))
+            .filter(|(_, value)| !value.contains("link_down=1"))
+            .map(|(key, value)| (key, format!("{},link_down=1", value)))
+            .collect();
+        if params.is_empty() {
+            return Ok(());
+        }
+        vlog_debug!("Taking {} link(s) of guest {} down", params.len(), vmid);
+        self.client.set_guest_config(node, guest_type, vmid, &params).await
+    }
+
+    async fn destroy_scratch(&self, guest_type: &str, node: &str, vmid: u32, timeout: u64) -> Result<()> {
+        if !self.client.dry_run() {
+            let status = self.client.get_guest_status(node, guest_type, vmid).await?;
+            if status.status == "running" {
+                let upid = self.client.set_guest_status(node, guest_type, vmid, "stop").await?;
+                self.wait_for_task(&upid, timeout).await?;
+            }
+        }
+        vlog_info!("Destroying scratch guest {}...", vmid);
+        let upid = self.client.destroy_guest(node, guest_type, vmid).await?;
+        self.wait_for_task(&upid, timeout).await
+    }
+}
+
+/// `qemu` or `lxc` from a vzdump file name or a PBS snapshot volume
+fn backup_guest_type(volid: &str) -> Option<&'static str> {
+    if volid.contains("vzdump-qemu-") || volid.contains(":backup/vm/") {
+        Some("qemu")
+    } else if volid.contains("vzdump-lxc-") || volid.contains(":backup/ct/") {
+        Some("lxc")
+    } else {
+        None
+    }
+}
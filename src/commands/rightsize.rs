@@ -0,0 +1,230 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # rightsize.rs
+//!
+//! Oversized guests, `pvenom rightsize --last 14d`.
+//!
+//! From the RRD history of the running guests, flags those whose 95th
+//! percentile CPU usage stays under `--cpu-threshold` percent of their
+//! vCPUs, or memory under `--ram-threshold` percent of their RAM, and
+//! suggests an allocation of twice that percentile: RRD samples are
+//! averages over up to 12 hours and hide short peaks. A second table sums
+//! what the suggestions give back on each node.
+//!
+//! VM memory is what the host sees in use, a guest without ballooning
+//! reports its page cache as used; the suggestion is then conservative.
+
+use anyhow::Result;
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::uptime::rrd_timeframe;
+use super::Commands;
+use crate::models::{OutputFormat, RightsizeGuest, RightsizeNode, RightsizeOutput};
+use crate::{csv, csv_row, pager, vlog_debug, vlog_info, vlog_success, vlog_warn};
+
+/// Suggested allocation over the 95th percentile usage
+const HEADROOM: f64 = 2.0;
+
+/// Memory suggestions are rounded up to this, MiB
+const MEMORY_STEP_MIB: u64 = 512;
+
+/// Fewer samples than this are not a history
+const MIN_SAMPLES: usize = 10;
+
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+pub struct RightsizeOptions {
+    /// History considered, seconds
+    pub last: u64,
+    /// Flag guests under this CPU usage, percent
+    pub cpu_threshold: u8,
+    /// Flag guests under this memory usage, percent
+    pub ram_threshold: u8,
+}
+
+impl Commands {
+    pub async fn rightsize(&self, options: &RightsizeOptions) -> Result<()> {
+        let until = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let since = until.saturating_sub(options.last);
+        let timeframe = rrd_timeframe(options.last);
+        vlog_info!("Looking for oversized guests over the last {} second(s)...", options.last);
+
+        let resources = self.client.get_cluster_resources(Some("vm")).await?;
+        let mut guests: Vec<_> = resources.into_iter()
+            .filter(|r| r.is_guest() && r.status.as_deref() == Some("running"))
+            .collect();
+        guests.sort_by(|a, b| a.node.cmp(&b.node).then(a.vmid.cmp(&b.vmid)));
+
+        let mut flagged = Vec::new();
+        let mut nodes: BTreeMap<String, RightsizeNode> = BTreeMap::new();
+        for guest in &guests {
+            let (Some(vmid), Some(node)) = (guest.vmid, guest.node.as_ref()) else {
+                continue;
+            };
+            let cores = guest.maxcpu.unwrap_or(0.0) as u32;
+            let memory = guest.maxmem.unwrap_or(0);
+            let totals = nodes.entry(node.clone()).or_insert_with(|| RightsizeNode { node: node.clone(), ..Default::default() });
+            totals.cores_allocated += cores;
+            totals.memory_allocated_gb += memory as f64 / GIB;
+
+            let points = match self.client.get_guest_rrddata(node, &guest.resource_type, vmid, timeframe).await {
+                Ok(points) => points,
+                Err(e) => {
+                    vlog_warn!("No RRD data for guest {}: {}", vmid, e);
+                    continue;
+                }
+            };
+            let points: Vec<_> = points.into_iter().filter(|p| p.time >= since).collect();
+            let cpu: Vec<f64> = points.iter().filter_map(|p| p.cpu).collect();
+            let mem: Vec<f64> = points.iter()
+                .filter_map(|p| match (p.mem, p.maxmem) {
+                    (Some(used), Some(total)) if total > 0.0 => Some(used / total),
+                    _ => None,
+                })
+                .collect();
+            if cpu.len() < MIN_SAMPLES || mem.len() < MIN_SAMPLES {
+                vlog_debug!("Guest {} has {} sample(s), not enough history", vmid, cpu.len().min(mem.len()));
+                continue;
+            }
+
+            let cpu_p95 = percentile(cpu, 0.95);
+            let mem_p95 = percentile(mem, 0.95);
+            let recommended_cores = (cpu_p95 * 100.0 < options.cpu_threshold as f64)
+                .then(|| ((cores as f64 * cpu_p95 * HEADROOM).ceil() as u32).max(1))
+                .filter(|c| *c < cores);
+            let recommended_memory = (mem_p95 * 100.0 < options.ram_threshold as f64)
+                .then(|| {
+                    let mib = (memory as f64 * mem_p95 * HEADROOM / 1024.0 / 1024.0).ceil() as u64;
+                    (mib.div_ceil(MEMORY_STEP_MIB).max(1) * MEMORY_STEP_MIB) as f64 / 1024.0
+                })
+                .filter(|m| *m < memory as f64 / GIB);
+            if recommended_cores.is_none() && recommended_memory.is_none() {
+                continue;
+            }
+
+            totals.cores_reclaimed += recommended_cores.map(|c| cores - c).unwrap_or(0);
+            totals.memory_reclaimed_gb += recommended_memory.map(|m| memory as f64 / GIB - m).unwrap_or(0.0);
+            flagged.push(RightsizeGuest {
+                node: node.clone(),
+                vmid,
+                name: guest.name.clone().unwrap_or_default(),
+                guest_type: guest.guest_type().to_string(),
+                cores,
+                cpu_p95_percent: round(cpu_p95 * 100.0),
+                recommended_cores,
+                memory_gb: round(memory as f64 / GIB),
+                memory_p95_percent: round(mem_p95 * 100.0),
+                recommended_memory_gb: recommended_memory.map(round),
+            });
+        }
+        let nodes: Vec<RightsizeNode> = nodes.into_values()
+            .map(|n| RightsizeNode { memory_allocated_gb: round(n.memory_allocated_gb), memory_reclaimed_gb: round(n.memory_reclaimed_gb), ..n })
+            .collect();
+
+        let suggestion = |current: String, recommended: Option<String>| match recommended {
+            Some(r) => format!("{} -> {}", current, r),
+            None => current,
+        };
+        match self.output_format {
+            OutputFormat::Json => {
+                let output = RightsizeOutput { since, until, guests: flagged.clone(), nodes: nodes.clone() };
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            }
+            OutputFormat::Csv => {
+                csv_row!("NODE", "VMID", "NAME", "TYPE", "CORES", "CPU_P95_PERCENT", "RECOMMENDED_CORES", "RAM_GB",
+                         "RAM_P95_PERCENT", "RECOMMENDED_RAM_GB");
+                for g in &flagged {
+                    csv_row!(g.node, g.vmid, g.name, g.guest_type, g.cores,
+                             csv::num(format!("{:.1}", g.cpu_p95_percent)),
+                             g.recommended_cores.map(|c| c.to_string()).unwrap_or_default(),
+                             csv::num(format!("{:.1}", g.memory_gb)),
+                             csv::num(format!("{:.1}", g.memory_p95_percent)),
+                             csv::num(g.recommended_memory_gb.map(|m| format!("{:.1}", m)).unwrap_or_default()));
+                }
+            }
+            OutputFormat::Table => {
+                if flagged.is_empty() {
+                    println!("No oversized guests among {} running.", guests.len());
+                } else {
+                    let mut table = Table::new();
+                    table.load_preset(UTF8_FULL)
+                         .set_content_arrangement(ContentArrangement::Dynamic);
+                    table.set_header(vec![
+                        Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("CPU p95 %").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("vCPUs").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("RAM p95 %").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("RAM (GB)").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    ]);
+                    for g in &flagged {
+                        let cores = suggestion(g.cores.to_string(), g.recommended_cores.map(|c| c.to_string()));
+                        let memory = suggestion(format!("{:.1}", g.memory_gb), g.recommended_memory_gb.map(|m| format!("{:.1}", m)));
+                        table.add_row(vec![
+                            Cell::new(&g.node),
+                            Cell::new(g.vmid),
+                            Cell::new(&g.name),
+                            Cell::new(format!("{:.1}", g.cpu_p95_percent)),
+                            if g.recommended_cores.is_some() { Cell::new(cores).fg(Color::Yellow) } else { Cell::new(cores) },
+                            Cell::new(format!("{:.1}", g.memory_p95_percent)),
+                            if g.recommended_memory_gb.is_some() { Cell::new(memory).fg(Color::Yellow) } else { Cell::new(memory) },
+                        ]);
+                    }
+                    pager::print_table(&mut table);
+
+                    println!("\n=== Reclaimed per node ===\n");
+                    let mut node_table = Table::new();
+                    node_table.load_preset(UTF8_FULL)
+                         .set_content_arrangement(ContentArrangement::Dynamic);
+                    node_table.set_header(vec![
+                        Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Allocated vCPUs").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Allocated RAM (GB)").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    ]);
+                    for n in &nodes {
+                        node_table.add_row(vec![
+                            Cell::new(&n.node),
+                            Cell::new(format!("{} -> {} (-{})", n.cores_allocated, n.cores_allocated - n.cores_reclaimed, n.cores_reclaimed)),
+                            Cell::new(format!("{:.1} -> {:.1} (-{:.1})", n.memory_allocated_gb,
+                                              n.memory_allocated_gb - n.memory_reclaimed_gb, n.memory_reclaimed_gb)),
+                        ]);
+                    }
+                    pager::print_table(&mut node_table);
+                }
+            }
+        }
+
+        vlog_success!("{} of {} running guest(s) oversized", flagged.len(), guests.len());
+        Ok(())
+    }
+}
+
+/// `share` percentile of the values, nearest rank
+fn percentile(mut values: Vec<f64>, share: f64) -> f64 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let rank = ((values.len() as f64 * share).ceil() as usize).clamp(1, values.len());
+    values[rank - 1]
+}
+
+fn round(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
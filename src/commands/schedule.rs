@@ -0,0 +1,227 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # schedule.rs
+//!
+//! `pvenom schedule` lists the power and snapshot schedules of the config
+//! file with their next run, `pvenom schedule run` stays in the foreground
+//! and carries them out, printing one line per action. Run it under
+//! systemd or in a container; see [`crate::schedule`] for the entries.
+//!
+//! Snapshot schedules wait for each snapshot before rotating, since PVE
+//! locks the guest while a snapshot is taken or removed.
+
+use anyhow::{bail, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::Commands;
+use crate::models::{OutputFormat, ScheduleInfo};
+use crate::schedule::{Schedule, ScheduleEntry};
+use crate::{csv_row, pager, timefmt, vlog_debug, vlog_error, vlog_info, vlog_success, vlog_warn};
+
+/// Seconds allowed to take or remove one snapshot
+const SNAPSHOT_TIMEOUT: u64 = 1800;
+
+/// Longest delay after which a due entry is still carried out
+const MAX_CATCH_UP_SECS: u64 = 3600;
+
+impl Commands {
+    pub async fn schedule_list(&self) -> Result<()> {
+        let parsed = self.parsed_schedules()?;
+        let resources = self.client.get_cluster_resources(Some("vm")).await?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+        let schedules: Vec<ScheduleInfo> = parsed.iter()
+            .map(|(entry, schedule)| ScheduleInfo {
+                name: entry.title(),
+                cron: entry.cron.clone(),
+                action: entry.action.clone(),
+                keep_last: entry.keep_last,
+                guests: entry.targets(&resources).iter().filter_map(|r| r.vmid).collect(),
+                next_run: schedule.next_after(now),
+            })
+            .collect();
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&schedules)?),
+            OutputFormat::Csv => {
+                csv_row!("NAME", "CRON", "ACTION", "KEEP_LAST", "GUESTS", "NEXT_RUN");
+                for s in &schedules {
+                    let guests: Vec<String> = s.guests.iter().map(|g| g.to_string()).collect();
+                    csv_row!(s.name, s.cron, s.action, s.keep_last.map(|k| k.to_string()).unwrap_or_default(),
+                             guests.join(" "), s.next_run.map(|t| t.to_string()).unwrap_or_default());
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Schedule").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Cron").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Action").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Guests").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Next Run").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for s in &schedules {
+                    let guests: Vec<String> = s.guests.iter().map(|g| g.to_string()).collect();
+                    let guests = if guests.is_empty() { Cell::new("none").fg(Color::Yellow) } else { Cell::new(guests.join(", ")) };
+                    let action = match s.keep_last {
+                        Some(keep) => format!("{}, keep {}", s.action, keep),
+                        None => s.action.clone(),
+                    };
+                    table.add_row(vec![
+                        Cell::new(&s.name),
+                        Cell::new(&s.cron),
+                        Cell::new(action),
+                        guests,
+                        Cell::new(s.next_run.map(|t| self.timestamp(t)).unwrap_or_else(|| "never".to_string())),
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        vlog_success!("Listed {} schedule(s)", schedules.len());
+        Ok(())
+    }
+
+    /// Carry out the schedules as they come due, until killed. Failed
+    /// actions are logged and the next ones still run. Every minute since
+    /// the previous pass is checked, so entries coming due while earlier
+    /// actions ran are carried out late instead of skipped.
+    pub async fn schedule_run(&self) -> Result<()> {
+        let parsed = self.parsed_schedules()?;
+        if self.client.read_only() {
+            bail!("Read-only mode, schedules cannot change guests");
+        }
+        vlog_success!("Running {} schedule(s)", parsed.len());
+
+        let started = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let mut last_minute = started - started % 60 - 60;
+        loop {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let minute = now - now % 60;
+            if minute == last_minute {
+                tokio::time::sleep(Duration::from_secs(60 - now % 60)).await;
+                continue;
+            }
+            let mut first = last_minute + 60;
+            if minute - first > MAX_CATCH_UP_SECS {
+                vlog_warn!("Schedules stalled for {}s, entries due before the last hour are skipped", minute - first);
+                first = minute - MAX_CATCH_UP_SECS;
+            }
+            last_minute = minute;
+
+            // Each entry once, even when due in several of the minutes
+            let due = parsed.iter().filter(|(_, schedule)| (first..=minute).step_by(60).any(|t| schedule.is_due(t)));
+            for (entry, schedule) in due {
+                if !schedule.is_due(minute) {
+                    vlog_warn!("Schedule '{}' came due while earlier actions ran, running it late", entry.title());
+                }
+                if let Err(e) = self.run_schedule(entry).await {
+                    vlog_error!("Schedule '{}' failed: {}", entry.title(), e);
+                }
+            }
+        }
+    }
+
+    async fn run_schedule(&self, entry: &ScheduleEntry) -> Result<()> {
+        vlog_info!("Schedule '{}' is due", entry.title());
+        let resources = self.client.get_cluster_resources(Some("vm")).await?;
+        for guest in entry.targets(&resources) {
+            let (Some(vmid), Some(node)) = (guest.vmid, guest.node.as_deref()) else {
+                continue;
+            };
+            if entry.action == "snapshot" {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+                let outcome = match self.rotate_snapshots(entry, node, &guest.resource_type, vmid, now).await {
+                    Ok(summary) => summary,
+                    Err(e) => format!("FAILED: {}", e),
+                };
+                println!("{} {}: snapshot guest {} ({}) on '{}': {}", self.timestamp(now), entry.title(),
+                         vmid, guest.name.as_deref().unwrap_or("-"), node, outcome);
+                continue;
+            }
+            let running = guest.status.as_deref() == Some("running");
+            let needed = match entry.action.as_str() {
+                "start" => !running,
+                _ => running,
+            };
+            if !needed {
+                vlog_debug!("Guest {} already {}, skipped", vmid, guest.status.as_deref().unwrap_or("unknown"));
+                continue;
+            }
+            let result = self.client.set_guest_status(node, &guest.resource_type, vmid, &entry.action).await;
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let outcome = match result {
+                Ok(upid) => upid,
+                Err(e) => format!("FAILED: {}", e),
+            };
+            println!("{} {}: {} guest {} ({}) on '{}': {}", self.timestamp(now), entry.title(), entry.action,
+                     vmid, guest.name.as_deref().unwrap_or("-"), node, outcome);
+        }
+        Ok(())
+    }
+
+    /// Take the snapshot of a `snapshot` schedule, then remove the oldest
+    /// ones of the schedule beyond `keep_last`
+    async fn rotate_snapshots(&self, entry: &ScheduleEntry, node: &str, guest_type: &str, vmid: u32, now: u64) -> Result<String> {
+        let (year, month, day, hour, minute, _) = timefmt::civil(now);
+        let name = format!("{}_{:04}{:02}{:02}_{:02}{:02}", entry.snapshot_prefix(), year, month, day, hour, minute);
+        let description = format!("Taken by pvenom schedule '{}'", entry.title());
+        let upid = self.client.create_snapshot(node, guest_type, vmid, &name, &description).await?;
+        self.wait_for_task(&upid, SNAPSHOT_TIMEOUT).await?;
+
+        let mut ours: Vec<_> = self.client.get_guest_snapshots(node, guest_type, vmid).await?
+            .into_iter()
+            .filter(|s| is_scheduled_snapshot(&s.name, entry.snapshot_prefix()))
+            .collect();
+        ours.sort_by_key(|s| (s.snaptime.unwrap_or(0), s.name.clone()));
+        let keep = entry.keep_last.unwrap_or(1) as usize;
+        let mut removed = Vec::new();
+        for old in &ours[..ours.len().saturating_sub(keep)] {
+            let upid = self.client.delete_snapshot(node, guest_type, vmid, &old.name).await?;
+            self.wait_for_task(&upid, SNAPSHOT_TIMEOUT).await?;
+            removed.push(old.name.as_str());
+        }
+        Ok(match removed.is_empty() {
+            true => name,
+            false => format!("{}, removed {}", name, removed.join(", ")),
+        })
+    }
+
+    fn parsed_schedules(&self) -> Result<Vec<(&ScheduleEntry, Schedule)>> {
+        if self.schedules.is_empty() {
+            bail!("No [[schedule]] entries in the config file");
+        }
+        self.schedules.iter().map(|entry| Ok((entry, entry.parse()?))).collect()
+    }
+}
+
+/// Name taken by a snapshot schedule with `prefix`, exactly
+/// `<prefix>_YYYYMMDD_HHMM`, so `auto_before_upgrade` or the snapshots of
+/// prefix `auto_hourly` are not mistaken for those of `auto`
+fn is_scheduled_snapshot(name: &str, prefix: &str) -> bool {
+    let digits = |part: &str, len: usize| part.len() == len && part.bytes().all(|b| b.is_ascii_digit());
+    name.strip_prefix(prefix)
+        .and_then(|rest| rest.strip_prefix('_'))
+        .and_then(|rest| rest.split_once('_'))
+        .is_some_and(|(date, time)| digits(date, 8) && digits(time, 4))
+}
@@ -0,0 +1,134 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # schema.rs
+//!
+//! JSON Schema of pvenom's own JSON output, `pvenom schema [NAME]`.
+//!
+//! Every structure printed with `-o json` derives its schema from the
+//! models, so the document always matches what the commands emit. Alone,
+//! `schema` prints one draft-07 document with all the definitions and a
+//! `commands` map from each command to the shape it prints; with a name,
+//! e.g. `schema NodeListOutput`, only that structure as a root schema.
+//! No cluster connection is needed.
+
+use anyhow::{bail, Result};
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::Schema;
+use serde_json::{json, Map};
+
+use super::serve::ServeSnapshot;
+use super::tasks::TaskDetail;
+use crate::labels::LabelStore;
+use crate::models::*;
+
+type SchemaFn = fn(&mut SchemaGenerator) -> Schema;
+
+/// JSON outputs as (structure, command printing it, schema). Commands
+/// printing a list are registered with the list type.
+const OUTPUTS: &[(&str, &str, SchemaFn)] = &[
+    ("NodeListOutput", "pvenom", |g| g.subschema_for::<NodeListOutput>()),
+    ("MultiClusterNodeListOutput", "pvenom --clusters", |g| g.subschema_for::<MultiClusterNodeListOutput>()),
+    ("NodeListOutputV2", "pvenom --output-version 2", |g| g.subschema_for::<NodeListOutputV2>()),
+    ("MultiClusterNodeListOutputV2", "pvenom --clusters --output-version 2", |g| g.subschema_for::<MultiClusterNodeListOutputV2>()),
+    ("NodeDetailOutput", "pvenom --node", |g| g.subschema_for::<NodeDetailOutput>()),
+    ("NodeDetailOutputV2", "pvenom --node --output-version 2", |g| g.subschema_for::<NodeDetailOutputV2>()),
+    ("GuestJsonInfo", "pvenom --node, each guest", |g| g.subschema_for::<GuestJsonInfo>()),
+    ("NodeIoOutput", "node <name> io", |g| g.subschema_for::<NodeIoOutput>()),
+    ("NodeNumaOutput", "node <name> numa", |g| g.subschema_for::<NodeNumaOutput>()),
+    ("NodeSensorsOutput", "node <name> sensors", |g| g.subschema_for::<NodeSensorsOutput>()),
+    ("DrainOutput", "node <name> drain", |g| g.subschema_for::<DrainOutput>()),
+    ("EvacuationOutput", "storage <id> evacuate", |g| g.subschema_for::<EvacuationOutput>()),
+    ("VolumeWhois", "storage whois <volid>", |g| g.subschema_for::<VolumeWhois>()),
+    ("StorageOvercommit", "storage overcommit", |g| g.subschema_for::<Vec<StorageOvercommit>>()),
+    ("GuestDetailOutput", "vm <guest>", |g| g.subschema_for::<GuestDetailOutput>()),
+    ("MigrationCheckOutput", "vm <guest> migrate --check", |g| g.subschema_for::<MigrationCheckOutput>()),
+    ("CompareOutput", "compare", |g| g.subschema_for::<CompareOutput>()),
+    ("MaintenanceInfo", "maintenance", |g| g.subschema_for::<Vec<MaintenanceInfo>>()),
+    ("ScheduleInfo", "schedule", |g| g.subschema_for::<Vec<ScheduleInfo>>()),
+    ("ClientDevicesInfo", "audit spice-usb", |g| g.subschema_for::<Vec<ClientDevicesInfo>>()),
+    ("MachineTypeInfo", "audit machine-types", |g| g.subschema_for::<Vec<MachineTypeInfo>>()),
+    ("FirmwareInfo", "audit firmware", |g| g.subschema_for::<Vec<FirmwareInfo>>()),
+    ("HookscriptInfo", "audit hookscripts", |g| g.subschema_for::<Vec<HookscriptInfo>>()),
+    ("GpusOutput", "gpus", |g| g.subschema_for::<GpusOutput>()),
+    ("SummaryOutput", "summary", |g| g.subschema_for::<SummaryOutput>()),
+    ("PingSweepOutput", "ping-sweep", |g| g.subschema_for::<PingSweepOutput>()),
+    ("RightsizeOutput", "rightsize", |g| g.subschema_for::<RightsizeOutput>()),
+    ("DriftOutput", "drift", |g| g.subschema_for::<DriftOutput>()),
+    ("LabelStore", "label", |g| g.subschema_for::<LabelStore>()),
+    ("UptimeReportOutput", "uptime-report", |g| g.subschema_for::<UptimeReportOutput>()),
+    ("ClusterStatusOutput", "cluster status", |g| g.subschema_for::<ClusterStatusOutput>()),
+    ("CpuMatrixOutput", "cluster cpu-matrix", |g| g.subschema_for::<CpuMatrixOutput>()),
+    ("CorosyncNode", "cluster links", |g| g.subschema_for::<Vec<CorosyncNode>>()),
+    ("CephPool", "ceph pools", |g| g.subschema_for::<Vec<CephPool>>()),
+    ("CephOsd", "ceph osd perf", |g| g.subschema_for::<Vec<CephOsd>>()),
+    ("HaGroupOutput", "ha groups", |g| g.subschema_for::<Vec<HaGroupOutput>>()),
+    ("BridgeInventory", "network bridges", |g| g.subschema_for::<Vec<BridgeInventory>>()),
+    ("IpamOutput", "ipam", |g| g.subschema_for::<IpamOutput>()),
+    ("TemplateAudit", "templates audit", |g| g.subschema_for::<Vec<TemplateAudit>>()),
+    ("GuestOs", "inventory os", |g| g.subschema_for::<Vec<GuestOs>>()),
+    ("BackupGrowth", "backups growth", |g| g.subschema_for::<Vec<BackupGrowth>>()),
+    ("BackupVerifyOutput", "backups verify", |g| g.subschema_for::<Vec<BackupVerifyOutput>>()),
+    ("TestRestoreOutput", "backups test-restore", |g| g.subschema_for::<TestRestoreOutput>()),
+    ("BackupConfigOutput", "backups config", |g| g.subschema_for::<BackupConfigOutput>()),
+    ("PrunePreviewOutput", "backups prune-preview", |g| g.subschema_for::<PrunePreviewOutput>()),
+    ("Task", "tasks", |g| g.subschema_for::<Vec<Task>>()),
+    ("TaskFailureGroup", "tasks failures", |g| g.subschema_for::<Vec<TaskFailureGroup>>()),
+    ("JobRetry", "jobs retry-failed", |g| g.subschema_for::<Vec<JobRetry>>()),
+    ("TaskDetail", "task <upid>", |g| g.subschema_for::<TaskDetail>()),
+    ("ApiChild", "api <path> --ls", |g| g.subschema_for::<Vec<ApiChild>>()),
+    ("NetboxExport", "export netbox", |g| g.subschema_for::<NetboxExport>()),
+    ("ZabbixDiscovery", "export zabbix-lld", |g| g.subschema_for::<ZabbixDiscovery>()),
+    ("ClusterState", "snapshot-state", |g| g.subschema_for::<ClusterState>()),
+    ("ServeSnapshot", "serve /api/state", |g| g.subschema_for::<ServeSnapshot>()),
+];
+
+/// Print the schema document, needs no cluster connection
+pub fn print_schema(name: Option<&str>) -> Result<()> {
+    let settings = SchemaSettings::draft07();
+    let Some(name) = name else {
+        let mut generator = settings.into_generator();
+        let mut commands = Map::new();
+        for (_, command, schema) in OUTPUTS {
+            commands.insert(command.to_string(), serde_json::to_value(schema(&mut generator))?);
+        }
+        let document = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "title": "pvenom JSON output",
+            "commands": commands,
+            "definitions": generator.take_definitions(),
+        });
+        println!("{}", serde_json::to_string_pretty(&document)?);
+        return Ok(());
+    };
+
+    let Some((name, _, schema)) = OUTPUTS.iter().find(|(n, _, _)| n.eq_ignore_ascii_case(name)) else {
+        let names: Vec<&str> = OUTPUTS.iter().map(|(n, _, _)| *n).collect();
+        bail!("Unknown output structure '{}', one of: {}", name, names.join(", "));
+    };
+    // The definitions hold the structure itself even for list outputs
+    let mut generator = settings.into_generator();
+    schema(&mut generator);
+    let document = json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "$ref": format!("#/definitions/{}", name),
+        "definitions": generator.take_definitions(),
+    });
+    println!("{}", serde_json::to_string_pretty(&document)?);
+    Ok(())
+}
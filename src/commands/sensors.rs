@@ -0,0 +1,156 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # sensors.rs
+//!
+//! Thermal state of a node, `pvenom node <name> sensors`.
+//!
+//! The Proxmox API has no lm-sensors endpoint, so CPU and board
+//! temperatures are out of reach: what it exposes are the SMART data of the
+//! physical disks, whose drive temperature is the best proxy for a hot
+//! closet. Each disk is listed hottest first with its health and wearout,
+//! next to the node CPU usage, and disks above the thresholds are warned
+//! about.
+
+use anyhow::Result;
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use serde_json::Value;
+
+use super::Commands;
+use crate::models::{DiskSensor, NodeSensorsOutput, OutputFormat};
+use crate::{csv_row, pager, vlog_debug, vlog_success, vlog_warn};
+
+/// SMART attributes carrying the drive temperature, preferred first
+const TEMPERATURE_ATTRIBUTES: [&str; 2] = ["Temperature_Celsius", "Airflow_Temperature_Cel"];
+
+impl Commands {
+    pub async fn show_node_sensors(&self, node: &str, warn: u8, critical: u8) -> Result<()> {
+        let disks = self.client.get_node_disks(node).await?;
+        let mut sensors = Vec::new();
+        for disk in disks {
+            let temperature = match self.client.get_disk_smart(node, &disk.devpath).await {
+                Ok(smart) => smart_temperature(&smart),
+                Err(e) => {
+                    vlog_debug!("No SMART data for {} on '{}': {}", disk.devpath, node, e);
+                    None
+                }
+            };
+            sensors.push(DiskSensor {
+                disk: disk.devpath,
+                model: disk.model,
+                disk_type: disk.disk_type,
+                health: disk.health,
+                wearout: disk.wearout.as_ref().and_then(|w| w.as_u64()),
+                temperature_c: temperature,
+            });
+        }
+        sensors.sort_by(|a, b| b.temperature_c.cmp(&a.temperature_c).then(a.disk.cmp(&b.disk)));
+
+        let cpu_percent = match self.client.get_node_status(node).await {
+            Ok(status) => status.cpu.map(|c| (c * 1000.0).round() / 10.0),
+            Err(e) => {
+                vlog_warn!("No status for node '{}': {}", node, e);
+                None
+            }
+        };
+
+        for sensor in &sensors {
+            match sensor.temperature_c {
+                Some(t) if t >= critical as i64 => vlog_warn!("{} is at {}°C, above the critical {}°C", sensor.disk, t, critical),
+                Some(t) if t >= warn as i64 => vlog_warn!("{} is at {}°C, above {}°C", sensor.disk, t, warn),
+                _ => {}
+            }
+        }
+
+        let output = NodeSensorsOutput { node: node.to_string(), cpu_percent, disks: sensors };
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&output)?),
+            OutputFormat::Csv => {
+                csv_row!("DISK", "MODEL", "TYPE", "HEALTH", "WEAROUT", "TEMPERATURE_C");
+                for d in &output.disks {
+                    csv_row!(d.disk, d.model.as_deref().unwrap_or(""), d.disk_type.as_deref().unwrap_or(""),
+                             d.health.as_deref().unwrap_or(""), d.wearout.map(|w| w.to_string()).unwrap_or_default(),
+                             d.temperature_c.map(|t| t.to_string()).unwrap_or_default());
+                }
+            }
+            OutputFormat::Table => {
+                match output.cpu_percent {
+                    Some(cpu) => println!("\n=== Sensors of {} (CPU {:.1}%) ===\n", node, cpu),
+                    None => println!("\n=== Sensors of {} ===\n", node),
+                }
+
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Disk").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Model").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Type").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Health").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Wearout").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Temperature").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for d in &output.disks {
+                    let health = match d.health.as_deref() {
+                        Some(h @ ("PASSED" | "OK")) => Cell::new(h).fg(Color::Green),
+                        Some(h) => Cell::new(h).fg(Color::Red),
+                        None => Cell::new("N/A"),
+                    };
+                    let temperature = match d.temperature_c {
+                        Some(t) if t >= critical as i64 => Cell::new(format!("{}°C", t)).fg(Color::Red),
+                        Some(t) if t >= warn as i64 => Cell::new(format!("{}°C", t)).fg(Color::Yellow),
+                        Some(t) => Cell::new(format!("{}°C", t)).fg(Color::Green),
+                        None => Cell::new("N/A"),
+                    };
+                    table.add_row(vec![
+                        Cell::new(&d.disk),
+                        Cell::new(d.model.as_deref().unwrap_or("N/A")),
+                        Cell::new(d.disk_type.as_deref().unwrap_or("N/A")),
+                        health,
+                        Cell::new(d.wearout.map(|w| format!("{}%", w)).unwrap_or_else(|| "N/A".to_string())),
+                        temperature,
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        vlog_success!("Read sensors of {} disk(s) on '{}'", output.disks.len(), node);
+        Ok(())
+    }
+}
+
+/// Drive temperature in °C from `/nodes/{node}/disks/smart`: ATA disks
+/// report it as an attribute whose raw value may be "35 (Min/Max 20/45)",
+/// NVMe ones only as smartctl text, "Temperature: 41 Celsius"
+fn smart_temperature(smart: &Value) -> Option<i64> {
+    let leading = |s: &str| s.split_whitespace().next().and_then(|n| n.parse::<i64>().ok());
+    if let Some(attributes) = smart["attributes"].as_array() {
+        for name in TEMPERATURE_ATTRIBUTES {
+            let raw = attributes.iter()
+                .find(|a| a["name"].as_str() == Some(name))
+                .and_then(|a| a["raw"].as_str());
+            if let Some(t) = raw.and_then(leading) {
+                return Some(t);
+            }
+        }
+    }
+    smart["text"].as_str()?.lines()
+        .find_map(|l| l.strip_prefix("Temperature:"))
+        .and_then(leading)
+}
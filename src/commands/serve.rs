@@ -0,0 +1,234 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # serve.rs
+//!
+//! Read-only JSON API over the cluster state, `pvenom serve --listen :8088`.
+//!
+//! pvenom logs in once and refreshes a cached snapshot every `--refresh`
+//! seconds, dashboards query the cache and never see Proxmox credentials.
+//!
+//! GET /health             liveness and age of the snapshot, no auth
+//! GET /api/v1/cluster     nodes, guests and storage in one document
+//! GET /api/v1/nodes       nodes only
+//! GET /api/v1/guests      VMs and LXC containers
+//! GET /api/v1/storage     storages, one entry per node
+//! *   /grafana/...          Grafana simple-json datasource, see grafana.rs
+//!
+//! Lists accept `?node=<name>` and `?status=<status>` filters.
+//! With `--token`, /api and /grafana requests need
+//! `Authorization: Bearer <token>`.
+//!
+//! The node, guest and cluster metrics of every snapshot are kept in memory
+//! for `--history`, that is the time range Grafana can chart. Only the
+//! latest snapshot is kept whole.
+
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::net::{TcpListener, TcpStream};
+
+use super::{grafana, Commands};
+use crate::http::{read_request, write_response, HttpRequest};
+use crate::models::ClusterResource;
+use crate::{vlog_debug, vlog_error, vlog_info, vlog_success};
+
+/// Settings of `pvenom serve`
+pub struct ServeOptions {
+    pub listen: String,
+    pub token: Option<String>,
+    /// Seconds between two refreshes of the cached snapshot
+    pub refresh: u64,
+    /// Seconds of snapshots kept in memory for Grafana time series
+    pub history: u64,
+}
+
+/// Normalized cluster state served to clients
+#[derive(Debug, Serialize, Clone, JsonSchema)]
+pub(super) struct ServeSnapshot {
+    pub updated: u64,
+    pub nodes: Vec<ClusterResource>,
+    pub guests: Vec<ClusterResource>,
+    pub storage: Vec<ClusterResource>,
+}
+
+/// The latest snapshot, and the metric series Grafana charts
+#[derive(Default)]
+pub(super) struct ServeState {
+    pub current: Option<ServeSnapshot>,
+    /// `(updated, value)` samples of every metric, oldest first
+    pub series: HashMap<String, VecDeque<(u64, f64)>>,
+}
+
+type SharedState = Arc<RwLock<ServeState>>;
+
+impl ServeState {
+    /// Make `snapshot` the current one and append its metrics, dropping
+    /// samples older than `history` seconds
+    fn record(&mut self, snapshot: ServeSnapshot, history: u64) {
+        let oldest_kept = snapshot.updated.saturating_sub(history);
+        for (metric, value) in grafana::metrics(&snapshot) {
+            self.series.entry(metric).or_default().push_back((snapshot.updated, value));
+        }
+        for samples in self.series.values_mut() {
+            while samples.front().is_some_and(|(updated, _)| *updated < oldest_kept) {
+                samples.pop_front();
+            }
+        }
+        self.series.retain(|_, samples| !samples.is_empty());
+        self.current = Some(snapshot);
+    }
+}
+
+impl Commands {
+    pub async fn serve(&self, options: &ServeOptions) -> Result<()> {
+        // ":8088" means every interface
+        let address = if options.listen.starts_with(':') {
+            format!("0.0.0.0{}", options.listen)
+        } else {
+            options.listen.clone()
+        };
+        let listener = TcpListener::bind(&address).await
+            .with_context(|| format!("Failed to listen on {}", address))?;
+        vlog_success!("Serving cluster state on http://{}", address);
+
+        let state: SharedState = Arc::new(RwLock::new(ServeState::default()));
+        let token: Arc<Option<String>> = Arc::new(options.token.clone());
+
+        // Refreshes and connections run side by side, a slow API call
+        // does not hold up requests
+        let refresh = async {
+            let mut ticker = tokio::time::interval(std::time::Duration::from_secs(options.refresh.max(1)));
+            loop {
+                ticker.tick().await;
+                match self.take_snapshot().await {
+                    Ok(fresh) => {
+                        vlog_debug!("Snapshot refreshed: {} node(s), {} guest(s), {} storage(s)",
+                                    fresh.nodes.len(), fresh.guests.len(), fresh.storage.len());
+                        state.write().unwrap_or_else(|e| e.into_inner()).record(fresh, options.history);
+                    }
+                    // Keep serving the previous snapshot, clients see its age
+                    Err(e) => vlog_error!("Snapshot refresh failed: {}", e),
+                }
+            }
+        };
+        let accept = async {
+            loop {
+                let (stream, peer) = match listener.accept().await {
+                    Ok(a) => a,
+                    Err(e) => {
+                        vlog_error!("Accept failed: {}", e);
+                        continue;
+                    }
+                };
+                vlog_debug!("Connection from {}", peer);
+                tokio::spawn(handle_connection(stream, state.clone(), token.clone()));
+            }
+        };
+        tokio::join!(refresh, accept);
+        Ok(())
+    }
+
+    pub(super) async fn take_snapshot(&self) -> Result<ServeSnapshot> {
+        vlog_info!("Refreshing cluster snapshot...");
+        let resources = self.client.get_cluster_resources(None).await?;
+        let updated = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+        let mut snapshot = ServeSnapshot { updated, nodes: Vec::new(), guests: Vec::new(), storage: Vec::new() };
+        for resource in resources {
+            match resource.resource_type.as_str() {
+                "node" => snapshot.nodes.push(resource),
+                "storage" => snapshot.storage.push(resource),
+                _ if resource.is_guest() => snapshot.guests.push(resource),
+                _ => {}
+            }
+        }
+        snapshot.nodes.sort_by(|a, b| a.node.cmp(&b.node));
+        snapshot.guests.sort_by_key(|g| g.vmid);
+        snapshot.storage.sort_by(|a, b| a.storage.cmp(&b.storage).then(a.node.cmp(&b.node)));
+        Ok(snapshot)
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, state: SharedState, token: Arc<Option<String>>) {
+    let request = match tokio::time::timeout(std::time::Duration::from_secs(10), read_request(&mut stream)).await {
+        Ok(Ok(r)) => r,
+        Ok(Err(e)) => {
+            vlog_debug!("Bad request: {}", e);
+            let _ = write_response(&mut stream, 400, "application/json", br#"{"error":"bad request"}"#).await;
+            return;
+        }
+        Err(_) => return,
+    };
+    vlog_debug!("{} {}", request.method, request.path);
+
+    let protected = request.path.starts_with("/api") || request.path.starts_with("/grafana");
+    let (status, body) = if protected && !authorized(&request, token.as_ref().as_deref()) {
+        (401, json!({ "error": "missing or invalid bearer token" }))
+    } else if request.path.starts_with("/grafana") {
+        grafana::route(&request, &state.read().unwrap_or_else(|e| e.into_inner()))
+    } else {
+        let current = state.read().unwrap_or_else(|e| e.into_inner()).current.clone();
+        route(&request, current)
+    };
+
+    let body = serde_json::to_vec(&body).unwrap_or_default();
+    if let Err(e) = write_response(&mut stream, status, "application/json", &body).await {
+        vlog_debug!("Failed to write response: {}", e);
+    }
+}
+
+fn authorized(request: &HttpRequest, token: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+    request.headers.get("authorization")
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .is_some_and(|t| t.trim() == token)
+}
+
+fn route(request: &HttpRequest, snapshot: Option<ServeSnapshot>) -> (u16, Value) {
+    if request.method != "GET" {
+        return (405, json!({ "error": "read-only API, only GET is allowed" }));
+    }
+
+    let Some(snapshot) = snapshot else {
+        return (503, json!({ "error": "cluster snapshot not available yet" }));
+    };
+
+    let list = |resources: Vec<ClusterResource>| {
+        let data: Vec<ClusterResource> = resources.into_iter()
+            .filter(|r| request.query.get("node").is_none_or(|n| r.node.as_ref() == Some(n)))
+            .filter(|r| request.query.get("status").is_none_or(|s| r.status.as_ref() == Some(s)))
+            .collect();
+        (200, json!({ "updated": snapshot.updated, "data": data }))
+    };
+
+    match request.path.trim_end_matches('/') {
+        "/health" => (200, json!({ "status": "ok", "updated": snapshot.updated })),
+        "/api/v1/cluster" => (200, json!(snapshot)),
+        "/api/v1/nodes" => list(snapshot.nodes.clone()),
+        "/api/v1/guests" => list(snapshot.guests.clone()),
+        "/api/v1/storage" => list(snapshot.storage.clone()),
+        _ => (404, json!({ "error": "not found" })),
+    }
+}
@@ -0,0 +1,103 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # state.rs
+//!
+//! Capture the whole cluster state in one document,
+//! `pvenom snapshot-state --output cluster-2025-01-01.json.gz`.
+//!
+//! Files ending in `.gz` are gzip-compressed, anything else is plain
+//! pretty JSON, and `-` writes to stdout.
+
+use anyhow::{Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::Commands;
+use crate::models::{ClusterState, GuestState, NodeState};
+use crate::{vlog_debug, vlog_info, vlog_success, vlog_warn};
+
+impl Commands {
+    pub(super) async fn capture_state(&self) -> Result<ClusterState> {
+        vlog_info!("Capturing cluster state...");
+        let captured_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let resources = self.client.get_cluster_resources(None).await?;
+
+        let mut nodes = Vec::new();
+        let mut storage = Vec::new();
+        for resource in resources {
+            match resource.resource_type.as_str() {
+                "node" => {
+                    let online = resource.status.as_deref() == Some("online");
+                    let version = match (online, resource.node.as_deref()) {
+                        (true, Some(node)) => match self.client.get_node_version(node).await {
+                            Ok(v) => Some(v),
+                            Err(e) => {
+                                vlog_warn!("No version for node '{}': {}", node, e);
+                                None
+                            }
+                        },
+                        _ => None,
+                    };
+                    nodes.push(NodeState { resource, version });
+                }
+                "storage" => storage.push(resource),
+                _ => {}
+            }
+        }
+        nodes.sort_by(|a, b| a.resource.node.cmp(&b.resource.node));
+        storage.sort_by(|a, b| a.storage.cmp(&b.storage).then(a.node.cmp(&b.node)));
+
+        let guests: Vec<GuestState> = self.guests_with_config().await?
+            .into_iter()
+            .map(|(resource, config)| GuestState { resource, config })
+            .collect();
+
+        vlog_debug!("Captured {} node(s), {} guest(s), {} storage(s)", nodes.len(), guests.len(), storage.len());
+        Ok(ClusterState {
+            pvenom_version: env!("CARGO_PKG_VERSION").to_string(),
+            captured_at,
+            cluster: self.cluster_name().await?,
+            nodes,
+            guests,
+            storage,
+        })
+    }
+
+    pub async fn snapshot_state(&self, output: &str) -> Result<()> {
+        let state = self.capture_state().await?;
+        let json = serde_json::to_vec_pretty(&state)?;
+
+        if output == "-" {
+            std::io::stdout().write_all(&json)?;
+            println!();
+        } else if output.ends_with(".gz") {
+            let file = std::fs::File::create(output).with_context(|| format!("Failed to create {}", output))?;
+            let mut encoder = GzEncoder::new(file, Compression::default());
+            encoder.write_all(&json)?;
+            encoder.finish().with_context(|| format!("Failed to write {}", output))?;
+        } else {
+            std::fs::write(output, &json).with_context(|| format!("Failed to write {}", output))?;
+        }
+
+        vlog_success!("Cluster state with {} guest(s) saved to {}", state.guests.len(), output);
+        Ok(())
+    }
+}
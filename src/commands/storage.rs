@@ -0,0 +1,574 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+
+//! # storage.rs
+//!
+//! Operations on a whole storage, such as emptying it before the
+//! datastore is decommissioned, and lookups of its volumes:
+//!
+//! pvenom storage old-nfs evacuate --target ceph-rbd --parallel 2
+//! pvenom storage whois local-lvm:vm-100-disk-1
+//! pvenom storage overcommit
+//!
+//! Every guest disk on the storage moves to the target through the same
+//! checks as `vm <vmid> disk move`. Moves run `--parallel` at a time, never
+//! two of the same guest since PVE locks the guest during a move. The
+//! plan is an operation journal (see journal.rs): `--resume <journal>`
+//! retries the disks not moved yet.
+
+use anyhow::{bail, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use std::future::{poll_fn, Future};
+use std::path::Path;
+use std::pin::Pin;
+use std::task::Poll;
+use std::time::Instant;
+
+use super::journal::{create_journal, journal_name, load_journal, save_journal};
+use super::vm::is_disk_key;
+use super::Commands;
+use crate::models::{DiskMoveResult, EvacuationOutput, Journal, JournalItem, JournalState, OutputFormat, StorageOvercommit, VolumeReference, VolumeWhois};
+use crate::progress::{self, Event};
+use crate::{csv, csv_row, pager, vlog_debug, vlog_error, vlog_info, vlog_success, vlog_warn};
+
+/// Storage types allocating the whole volume size upfront
+const THICK_TYPES: &[&str] = &["lvm", "iscsi", "iscsidirect"];
+
+/// Provisioned percentage of the capacity shown as a warning
+const OVERCOMMIT_WARN_PERCENT: f64 = 90.0;
+
+/// Settings of `pvenom storage <id> evacuate`
+pub struct EvacuateOptions {
+    pub target: String,
+    /// Disks moved at the same time
+    pub parallel: usize,
+    pub delete_source: bool,
+    /// Seconds allowed for each move
+    pub timeout: u64,
+    /// Journal of an earlier evacuation to retry instead of planning a new one
+    pub resume: Option<String>,
+}
+
+/// Completion of a move task, see `wait_for_task`
+type TaskWait<'a> = Pin<Box<dyn Future<Output = Result<()>> + 'a>>;
+
+/// Move of the evacuation in progress
+struct RunningMove<'a> {
+    index: usize,
+    started: Instant,
+    /// Waiting for the task of an earlier, interrupted run
+    resumed: bool,
+    wait: TaskWait<'a>,
+}
+
+impl Commands {
+    /// Move every guest disk off `storage` to `options.target`
+    pub async fn evacuate_storage(&self, storage: &str, options: &EvacuateOptions) -> Result<()> {
+        if storage == options.target {
+            bail!("Target storage is '{}' itself", storage);
+        }
+        let dry_run = self.client.dry_run();
+        let (mut journal, path) = match &options.resume {
+            Some(path) => load_journal(path, "evacuate", storage)?,
+            None => {
+                let items = self.plan_evacuation(storage, &options.target).await?;
+                if items.is_empty() {
+                    println!("No guest disks on storage '{}'.", storage);
+                    return Ok(());
+                }
+                self.confirm("evacuate", &format!("storage '{}' ({} disk(s) to '{}')", storage, items.len(), options.target))?;
+                create_journal("evacuate", storage, items, !dry_run)?
+            }
+        };
+
+        vlog_info!("Moving {} disk(s) from '{}' to '{}', journal {}", journal.items.len(), storage, options.target, journal_name(&path));
+        self.run_disk_moves(&mut journal, path.as_deref(), storage, options).await?;
+
+        let output = EvacuationOutput {
+            storage: storage.to_string(),
+            target: options.target.clone(),
+            disks: journal.items.iter()
+                .map(|item| DiskMoveResult {
+                    vmid: item.vmid,
+                    name: item.name.clone(),
+                    disk: item.disk.clone().unwrap_or_default(),
+                    target: item.target.clone(),
+                    ok: item.state == JournalState::Done,
+                    error: match item.state {
+                        JournalState::Done => None,
+                        JournalState::Failed => item.error.clone(),
+                        _ => Some("Not attempted".to_string()),
+                    },
+                    seconds: item.seconds,
+                })
+                .collect(),
+        };
+        self.print_disk_moves(&output)?;
+
+        let failed = output.disks.iter().filter(|d| !d.ok).count();
+        if failed > 0 {
+            bail!("{} of {} disk move(s) failed, storage '{}' is not empty, retry with --resume {}",
+                  failed, output.disks.len(), storage, journal_name(&path));
+        }
+        if !dry_run {
+            vlog_success!("Storage '{}' evacuated, {} disk(s) moved to '{}'", storage, output.disks.len(), options.target);
+            if !options.delete_source {
+                vlog_info!("The source volumes stay as unused disks, --delete-source removes them");
+            }
+        }
+        Ok(())
+    }
+
+    /// Guests and config keys referencing `volid`, or whether it is an
+    /// orphan left on its storage
+    pub async fn storage_whois(&self, volid: &str) -> Result<()> {
+        let Some((storage, volname)) = volid.split_once(':') else {
+            bail!("'{}' is not a volume ID, e.g. local-lvm:vm-100-disk-0", volid);
+        };
+        // Linked clones name the base volume before their own
+        let base_of = format!("{}/", volname);
+
+        let mut references = Vec::new();
+        for (guest, config) in self.guests_with_config().await? {
+            for (key, value) in &config {
+                let Some(value) = value.as_str() else { continue };
+                let mut options = value.split(',');
+                let first = options.next().unwrap_or_default();
+                let usage = match first.split_once(':') {
+                    Some((s, name)) if s == storage && name == volname => {
+                        if key.starts_with("unused") {
+                            "unused"
+                        } else if options.any(|o| o == "media=cdrom") {
+                            "cdrom"
+                        } else if is_disk_key(key) {
+                            "disk"
+                        } else {
+                            "option"
+                        }
+                    }
+                    Some((s, name)) if s == storage && name.starts_with(&base_of) => "linked clone",
+                    // Options holding volumes, e.g. `cicustom: user=local:snippets/user.yaml`
+                    _ if value.split(',').any(|o| o.split_once('=').is_some_and(|(_, v)| v == volid)) => "option",
+                    _ => continue,
+                };
+                references.push(VolumeReference {
+                    vmid: guest.vmid.unwrap_or_default(),
+                    name: guest.name.clone().unwrap_or_else(|| "N/A".to_string()),
+                    guest_type: guest.resource_type.clone(),
+                    node: guest.node.clone().unwrap_or_default(),
+                    key: key.clone(),
+                    usage: usage.to_string(),
+                });
+            }
+        }
+
+        // Look the volume up on the storage, once for shared ones
+        let mut exists = None;
+        let mut content = None;
+        let nodes: Vec<(String, bool)> = self.client.get_cluster_resources(Some("storage")).await?
+            .into_iter()
+            .filter(|s| s.storage.as_deref() == Some(storage) && s.status.as_deref() == Some("available"))
+            .filter_map(|s| s.node.map(|node| (node, s.shared == Some(1))))
+            .collect();
+        if nodes.is_empty() {
+            vlog_warn!("Storage '{}' is not available on any node, its content is not checked", storage);
+        }
+        for (node, shared) in &nodes {
+            match self.client.get_storage_content(node, storage, None, None).await {
+                Ok(volumes) => {
+                    let found = volumes.into_iter().find(|v| v.volid == volid);
+                    exists = Some(exists.unwrap_or(false) || found.is_some());
+                    if let Some(found) = found {
+                        content = found.content;
+                        break;
+                    }
+                }
+                Err(e) => vlog_warn!("No content list of storage '{}' on '{}': {}", storage, node, e),
+            }
+            if *shared && exists.is_some() {
+                break;
+            }
+        }
+
+        let owner = volname.rsplit('/').next()
+            .and_then(|name| ["vm-", "base-", "subvol-"].iter().find_map(|p| name.strip_prefix(p)))
+            .and_then(|rest| rest.split('-').next())
+            .and_then(|id| id.parse().ok());
+        let whois = VolumeWhois {
+            volid: volid.to_string(),
+            orphaned: references.is_empty() && exists == Some(true),
+            references,
+            exists,
+            content,
+            owner,
+        };
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&whois)?),
+            OutputFormat::Csv => {
+                csv_row!("VOLID", "VMID", "NAME", "TYPE", "NODE", "KEY", "USAGE");
+                for r in &whois.references {
+                    csv_row!(whois.volid, r.vmid, r.name, r.guest_type, r.node, r.key, r.usage);
+                }
+            }
+            OutputFormat::Table => {
+                if !whois.references.is_empty() {
+                    let mut table = Table::new();
+                    table.load_preset(UTF8_FULL)
+                         .set_content_arrangement(ContentArrangement::Dynamic);
+                    table.set_header(vec![
+                        Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Type").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Key").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                        Cell::new("Usage").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    ]);
+                    for r in &whois.references {
+                        table.add_row(vec![
+                            Cell::new(r.vmid),
+                            Cell::new(&r.name),
+                            Cell::new(if r.guest_type == "qemu" { "VM" } else { "LXC" }),
+                            Cell::new(&r.node),
+                            Cell::new(&r.key),
+                            Cell::new(&r.usage),
+                        ]);
+                    }
+                    pager::print_table(&mut table);
+                } else if whois.orphaned {
+                    let owner = match whois.owner {
+                        Some(vmid) => format!(", named after guest {}", vmid),
+                        None => String::new(),
+                    };
+                    println!("{} is orphaned: no guest config references it{}.", volid, owner);
+                } else if whois.exists == Some(false) {
+                    println!("{} does not exist and no guest config references it.", volid);
+                } else {
+                    println!("No guest config references {}.", volid);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Virtual size of the guest volumes of every storage against its
+    /// capacity, most provisioned first
+    pub async fn storage_overcommit(&self) -> Result<()> {
+        let gb = |bytes: u64| (bytes as f64 / 1024.0 / 1024.0 / 1024.0 * 10.0).round() / 10.0;
+        let mut rows: Vec<StorageOvercommit> = Vec::new();
+        for r in self.client.get_cluster_resources(Some("storage")).await? {
+            let (Some(storage), Some(node)) = (r.storage.as_deref(), r.node.as_deref()) else { continue };
+            let guest_content = r.content.as_deref().unwrap_or_default()
+                .split(',')
+                .any(|c| c == "images" || c == "rootdir");
+            let shared = r.shared == Some(1);
+            if !guest_content || r.status.as_deref() != Some("available")
+                || (shared && rows.iter().any(|row| row.storage == storage)) {
+                continue;
+            }
+
+            let volumes: Vec<u64> = match self.client.get_storage_content(node, storage, None, None).await {
+                Ok(content) => content.into_iter()
+                    .filter(|v| matches!(v.content.as_deref(), Some("images") | Some("rootdir")))
+                    .map(|v| v.size.unwrap_or(0))
+                    .collect(),
+                Err(e) => {
+                    vlog_warn!("No content list of storage '{}' on '{}': {}", storage, node, e);
+                    continue;
+                }
+            };
+            let storage_type = r.plugintype.clone().unwrap_or_else(|| "N/A".to_string());
+            let thin = !THICK_TYPES.contains(&storage_type.as_str());
+            let provisioned: u64 = volumes.iter().sum();
+            let total = r.maxdisk.unwrap_or(0);
+            rows.push(StorageOvercommit {
+                storage: storage.to_string(),
+                node: (!shared).then(|| node.to_string()),
+                storage_type,
+                thin,
+                volumes: volumes.len(),
+                provisioned_gb: gb(provisioned),
+                used_gb: gb(r.disk.unwrap_or(0)),
+                total_gb: gb(total),
+                provisioned_percent: (total > 0).then(|| (provisioned as f64 / total as f64 * 1000.0).round() / 10.0),
+                at_risk: thin && provisioned > total,
+            });
+        }
+        rows.sort_by(|a, b| b.provisioned_percent.unwrap_or(0.0).total_cmp(&a.provisioned_percent.unwrap_or(0.0)));
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+            OutputFormat::Csv => {
+                csv_row!("STORAGE", "NODE", "TYPE", "THIN", "VOLUMES", "PROVISIONED_GB", "USED_GB", "TOTAL_GB",
+                         "PROVISIONED_PERCENT", "AT_RISK");
+                for r in &rows {
+                    csv_row!(r.storage, r.node.as_deref().unwrap_or(""), r.storage_type, r.thin, r.volumes,
+                             csv::num(r.provisioned_gb), csv::num(r.used_gb), csv::num(r.total_gb),
+                             csv::num(r.provisioned_percent.map(|p| p.to_string()).unwrap_or_default()), r.at_risk);
+                }
+            }
+            OutputFormat::Table => {
+                if rows.is_empty() {
+                    println!("No storages holding guest disks.");
+                    return Ok(());
+                }
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Storage").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Type").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Thin").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Volumes").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Provisioned (GB)").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Used (GB)").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Total (GB)").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Provisioned").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for r in &rows {
+                    let percent = match r.provisioned_percent {
+                        Some(p) if r.at_risk => Cell::new(format!("{:.0}% overflow risk", p)).fg(Color::Red),
+                        Some(p) if r.thin && p >= OVERCOMMIT_WARN_PERCENT => Cell::new(format!("{:.0}%", p)).fg(Color::Yellow),
+                        Some(p) => Cell::new(format!("{:.0}%", p)),
+                        None => Cell::new("N/A"),
+                    };
+                    table.add_row(vec![
+                        Cell::new(&r.storage),
+                        Cell::new(r.node.as_deref().unwrap_or("shared")),
+                        Cell::new(&r.storage_type),
+                        Cell::new(if r.thin { "yes" } else { "no" }),
+                        Cell::new(r.volumes),
+                        Cell::new(format!("{:.1}", r.provisioned_gb)),
+                        Cell::new(format!("{:.1}", r.used_gb)),
+                        Cell::new(format!("{:.1}", r.total_gb)),
+                        percent,
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        let at_risk = rows.iter().filter(|r| r.at_risk).count();
+        if at_risk > 0 {
+            vlog_warn!("{} thin storage(s) overflow if the guests fill their disks", at_risk);
+        } else {
+            vlog_success!("No thin storage provisioned beyond its capacity");
+        }
+        Ok(())
+    }
+
+    /// One journal item per guest disk on `storage`
+    async fn plan_evacuation(&self, storage: &str, target: &str) -> Result<Vec<JournalItem>> {
+        let prefix = format!("{}:", storage);
+        let mut items = Vec::new();
+        for (guest, config) in self.guests_with_config().await? {
+            let vmid = guest.vmid.unwrap_or_default();
+            for (key, value) in config.iter().filter(|(k, _)| is_disk_key(k) || k.starts_with("unused")) {
+                let Some(value) = value.as_str().filter(|v| v.starts_with(&prefix)) else {
+                    continue;
+                };
+                if value.split(',').any(|o| o == "media=cdrom") {
+                    vlog_warn!("Guest {} has an ISO of '{}' in {}, eject it by hand", vmid, storage, key);
+                    continue;
+                }
+                items.push(JournalItem {
+                    vmid,
+                    name: guest.name.clone().unwrap_or_else(|| "N/A".to_string()),
+                    guest_type: guest.resource_type.clone(),
+                    action: "move".to_string(),
+                    disk: Some(key.clone()),
+                    target: target.to_string(),
+                    ha: false,
+                    upid: None,
+                    state: JournalState::Pending,
+                    error: None,
+                    seconds: 0,
+                });
+            }
+        }
+        items.sort_by_key(|i| i.vmid);
+        Ok(items)
+    }
+
+    /// Move every journal item not done yet, `options.parallel` at a time,
+    /// saving the journal after each step. Disks already off `storage`
+    /// count as done.
+    async fn run_disk_moves(&self, journal: &mut Journal, path: Option<&Path>, storage: &str, options: &EvacuateOptions) -> Result<()> {
+        let total = journal.items.len();
+        let mut attempted = vec![false; total];
+        let mut running: Vec<RunningMove> = Vec::new();
+        progress::emit(&Event::BatchStarted { operation: &journal.operation, total });
+        loop {
+            // Start moves up to the limit, one per guest
+            while running.len() < options.parallel.max(1) {
+                let next = (0..total).find(|&i| {
+                    !attempted[i]
+                        && journal.items[i].state != JournalState::Done
+                        && !running.iter().any(|r| journal.items[r.index].vmid == journal.items[i].vmid)
+                });
+                let Some(i) = next else { break };
+                attempted[i] = true;
+                let item = journal.items[i].clone();
+                progress::emit(&Event::ItemStarted { index: i + 1, total, vmid: item.vmid, target: &item.target });
+
+                // An interrupted run may have left its move task running,
+                // wait for it rather than starting a second one
+                match item.upid.filter(|_| item.state == JournalState::Running) {
+                    Some(upid) => {
+                        vlog_info!("Waiting for the earlier move of {} of guest {}: {}",
+                                   item.disk.as_deref().unwrap_or_default(), item.vmid, upid);
+                        let wait = Box::pin(async move { self.wait_for_task(&upid, options.timeout).await });
+                        running.push(RunningMove { index: i, started: Instant::now(), resumed: true, wait });
+                    }
+                    None => {
+                        if let Some(wait) = self.launch_disk_move(journal, path, i, storage, options).await? {
+                            running.push(RunningMove { index: i, started: Instant::now(), resumed: false, wait });
+                        }
+                    }
+                }
+            }
+            if running.is_empty() {
+                break;
+            }
+
+            // Whichever running move finishes first
+            let (k, outcome) = poll_fn(|cx| {
+                running.iter_mut()
+                    .enumerate()
+                    .find_map(|(k, job)| match job.wait.as_mut().poll(cx) {
+                        Poll::Ready(outcome) => Some((k, outcome)),
+                        Poll::Pending => None,
+                    })
+                    .map_or(Poll::Pending, Poll::Ready)
+            }).await;
+            let job = running.swap_remove(k);
+            match outcome {
+                Err(e) if job.resumed => {
+                    let item = &journal.items[job.index];
+                    vlog_warn!("Earlier move of {} of guest {} did not succeed ({}), moving again",
+                               item.disk.as_deref().unwrap_or_default(), item.vmid, e);
+                    if let Some(wait) = self.launch_disk_move(journal, path, job.index, storage, options).await? {
+                        running.push(RunningMove { index: job.index, started: Instant::now(), resumed: false, wait });
+                    }
+                }
+                outcome => self.finish_disk_move(journal, path, job.index, outcome, job.started.elapsed().as_secs())?,
+            }
+        }
+
+        let done = journal.items.iter().filter(|i| i.state == JournalState::Done).count();
+        progress::emit(&Event::BatchFinished { operation: &journal.operation, done, failed: total - done });
+        Ok(())
+    }
+
+    /// Start the move of journal item `index`, returns the wait for its task,
+    /// none when the item is already finished
+    async fn launch_disk_move<'a>(&'a self, journal: &mut Journal, path: Option<&Path>, index: usize, storage: &str,
+                                  options: &'a EvacuateOptions) -> Result<Option<TaskWait<'a>>> {
+        journal.items[index].state = JournalState::Running;
+        journal.items[index].upid = None;
+        journal.items[index].error = None;
+        save_journal(journal, path)?;
+
+        let item = journal.items[index].clone();
+        match self.start_disk_move(&item, storage, options.delete_source).await {
+            Ok(Some(upid)) if !self.client.dry_run() => {
+                journal.items[index].upid = Some(upid.clone());
+                save_journal(journal, path)?;
+                Ok(Some(Box::pin(async move { self.wait_for_task(&upid, options.timeout).await })))
+            }
+            Ok(_) => self.finish_disk_move(journal, path, index, Ok(()), 0).map(|_| None),
+            Err(e) => self.finish_disk_move(journal, path, index, Err(e), 0).map(|_| None),
+        }
+    }
+
+    /// Start the move of a journal item and return its UPID, none when the
+    /// disk already left `storage`
+    async fn start_disk_move(&self, item: &JournalItem, storage: &str, delete_source: bool) -> Result<Option<String>> {
+        let guest = self.locate_guest(item.vmid).await?;
+        let disk = item.disk.as_deref().unwrap_or_default();
+        let volume = self.check_disk_move(&guest, disk, &item.target).await?;
+        if !volume.starts_with(&format!("{}:", storage)) {
+            vlog_debug!("{} of guest {} is already on '{}'", disk, item.vmid, volume);
+            return Ok(None);
+        }
+
+        let node = guest.node.as_deref().unwrap_or_default();
+        vlog_info!("Moving {} of guest {} from '{}' to '{}'...", disk, item.vmid, storage, item.target);
+        let upid = self.client.move_disk(node, &guest.resource_type, item.vmid, disk, &item.target, delete_source).await?;
+        Ok(Some(upid))
+    }
+
+    /// Record the outcome of journal item `index`
+    fn finish_disk_move(&self, journal: &mut Journal, path: Option<&Path>, index: usize, outcome: Result<()>, seconds: u64) -> Result<()> {
+        let total = journal.items.len();
+        let entry = &mut journal.items[index];
+        let disk = entry.disk.as_deref().unwrap_or_default();
+        match &outcome {
+            Ok(()) => vlog_success!("{} of guest {} moved to '{}'", disk, entry.vmid, entry.target),
+            Err(e) => vlog_error!("Move of {} of guest {} failed: {}", disk, entry.vmid, e),
+        }
+        progress::emit(&Event::ItemFinished { index: index + 1, total, vmid: entry.vmid, ok: outcome.is_ok() });
+        entry.state = if outcome.is_ok() { JournalState::Done } else { JournalState::Failed };
+        entry.error = outcome.err().map(|e| e.to_string());
+        entry.seconds = seconds;
+        save_journal(journal, path)
+    }
+
+    fn print_disk_moves(&self, output: &EvacuationOutput) -> Result<()> {
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(output)?),
+            OutputFormat::Csv => {
+                csv_row!("VMID", "NAME", "DISK", "TARGET", "RESULT", "SECONDS");
+                for d in &output.disks {
+                    csv_row!(d.vmid, d.name, d.disk, d.target, d.error.as_deref().unwrap_or("OK"), d.seconds);
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Disk").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Target").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Result").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Time (s)").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for d in &output.disks {
+                    let result = match &d.error {
+                        None => Cell::new("OK").fg(Color::Green),
+                        Some(error) => Cell::new(error).fg(Color::Red),
+                    };
+                    table.add_row(vec![
+                        Cell::new(d.vmid),
+                        Cell::new(&d.name),
+                        Cell::new(&d.disk),
+                        Cell::new(&d.target),
+                        result,
+                        Cell::new(d.seconds),
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # summary.rs
+//!
+//! `pvenom summary`: cluster health in a few lines, nodes up, guests
+//! running and stopped, and what needs a look. With `--format slack` or
+//! `--format discord` it prints a webhook payload instead, for a morning
+//! post from cron:
+//!
+//! pvenom summary --format slack | curl -d @- -H 'Content-Type: application/json' $WEBHOOK
+
+use anyhow::Result;
+use clap::ValueEnum;
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::Commands;
+use crate::models::{OutputFormat, SummaryOutput};
+use crate::{csv_row, pager, vlog_debug};
+
+/// Usage share of CPU, RAM or storage raising an alert, percent
+const USAGE_ALERT: f64 = 90.0;
+
+/// Discord embed colors, green when nothing needs a look
+const DISCORD_OK: u32 = 0x2eb67d;
+const DISCORD_ALERT: u32 = 0xe01e5a;
+
+/// Chat webhook payload of `summary --format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SummaryStyle {
+    /// Slack mrkdwn blocks
+    Slack,
+    /// Discord embed
+    Discord,
+}
+
+impl Commands {
+    /// Cluster health as a table, CSV, JSON, or a chat payload in `style`
+    pub async fn summary(&self, style: Option<SummaryStyle>) -> Result<()> {
+        let output = self.health().await?;
+        if let Some(style) = style {
+            let payload = match style {
+                SummaryStyle::Slack => slack_payload(&output),
+                SummaryStyle::Discord => discord_payload(&output),
+            };
+            println!("{}", serde_json::to_string_pretty(&payload)?);
+            return Ok(());
+        }
+
+        let rows = [
+            ("Cluster", output.cluster.clone().unwrap_or_else(|| "standalone".to_string())),
+            ("Nodes", format!("{}/{} online", output.nodes_online, output.nodes_total)),
+            ("Guests", format!("{} running, {} stopped", output.guests_running, output.guests_stopped)),
+            ("Alerts", if output.alerts.is_empty() { "none".to_string() } else { output.alerts.join("\n") }),
+        ];
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&output)?),
+            OutputFormat::Csv => {
+                csv_row!("PROPERTY", "VALUE");
+                for (property, value) in &rows {
+                    csv_row!(property, value.replace('\n', " | "));
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Property").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Value").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for (property, value) in &rows {
+                    let cell = match *property {
+                        "Alerts" if output.alerts.is_empty() => Cell::new(value).fg(Color::Green),
+                        "Alerts" => Cell::new(value).fg(Color::Red),
+                        _ => Cell::new(value),
+                    };
+                    table.add_row(vec![Cell::new(property), cell]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+        Ok(())
+    }
+
+    /// Counts and alerts of `summary`, shared with `report`
+    pub(super) async fn health(&self) -> Result<SummaryOutput> {
+        let cluster = self.client.get_cluster_status().await?
+            .into_iter()
+            .find(|e| e.entry_type == "cluster");
+        let resources = self.client.get_cluster_resources(None).await?;
+        let windows = self.maintenance_windows();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let in_maintenance = |node: Option<&str>, tags: Option<&str>| {
+            windows.as_ref().is_some_and(|w| w.covering(node, tags, now).is_some())
+        };
+
+        let nodes: Vec<_> = resources.iter().filter(|r| r.resource_type == "node").collect();
+        let guests: Vec<_> = resources.iter().filter(|r| r.is_guest()).collect();
+        let mut alerts = Vec::new();
+        if cluster.as_ref().is_some_and(|c| c.quorate != Some(1)) {
+            alerts.push("Cluster not quorate".to_string());
+        }
+        for node in &nodes {
+            let name = node.node.as_deref().unwrap_or(&node.id);
+            if in_maintenance(Some(name), None) {
+                vlog_debug!("Node {} in maintenance, no alerts", name);
+                continue;
+            }
+            if node.status.as_deref() != Some("online") {
+                alerts.push(format!("Node {} is {}", name, node.status.as_deref().unwrap_or("unknown")));
+                continue;
+            }
+            if let Some(cpu) = node.cpu.map(|c| c * 100.0).filter(|c| *c >= USAGE_ALERT) {
+                alerts.push(format!("Node {} CPU at {:.0}%", name, cpu));
+            }
+            if let Some(ram) = percent(node.mem, node.maxmem).filter(|r| *r >= USAGE_ALERT) {
+                alerts.push(format!("Node {} RAM at {:.0}%", name, ram));
+            }
+        }
+        for storage in resources.iter().filter(|r| r.resource_type == "storage") {
+            if let Some(used) = percent(storage.disk, storage.maxdisk).filter(|u| *u >= USAGE_ALERT) {
+                alerts.push(format!("Storage {} on {} {:.0}% full",
+                    storage.storage.as_deref().unwrap_or(&storage.id), storage.node.as_deref().unwrap_or("?"), used));
+            }
+        }
+        for guest in guests.iter().filter(|g| g.hastate.as_deref() == Some("error")) {
+            if !in_maintenance(guest.node.as_deref(), guest.tags.as_deref()) {
+                alerts.push(format!("Guest {} ({}) in HA error state",
+                    guest.vmid.unwrap_or_default(), guest.name.as_deref().unwrap_or("")));
+            }
+        }
+
+        Ok(SummaryOutput {
+            cluster: cluster.map(|c| c.name),
+            nodes_online: nodes.iter().filter(|n| n.status.as_deref() == Some("online")).count(),
+            nodes_total: nodes.len(),
+            guests_running: guests.iter().filter(|g| g.status.as_deref() == Some("running")).count(),
+            guests_stopped: guests.iter().filter(|g| g.status.as_deref() != Some("running")).count(),
+            alerts,
+        })
+    }
+}
+
+fn percent(used: Option<u64>, total: Option<u64>) -> Option<f64> {
+    match (used, total) {
+        (Some(u), Some(t)) if t > 0 => Some(u as f64 * 100.0 / t as f64),
+        _ => None,
+    }
+}
+
+fn title(output: &SummaryOutput) -> String {
+    match &output.cluster {
+        Some(name) => format!("Proxmox cluster {}", name),
+        None => "Proxmox node".to_string(),
+    }
+}
+
+/// `text` is the notification fallback, `blocks` what the channel shows
+fn slack_payload(output: &SummaryOutput) -> Value {
+    let status = if output.alerts.is_empty() { ":large_green_circle: all good" } else { ":red_circle: needs a look" };
+    let mut text = format!("*{}*: {}\n*Nodes* {}/{} online   *Guests* {} running, {} stopped",
+        title(output), status, output.nodes_online, output.nodes_total, output.guests_running, output.guests_stopped);
+    for alert in &output.alerts {
+        text.push_str(&format!("\n• {}", alert));
+    }
+    json!({
+        "text": format!("{}: {}", title(output), status),
+        "blocks": [{ "type": "section", "text": { "type": "mrkdwn", "text": text } }],
+    })
+}
+
+fn discord_payload(output: &SummaryOutput) -> Value {
+    let alerts = if output.alerts.is_empty() {
+        "none".to_string()
+    } else {
+        output.alerts.iter().map(|a| format!("• {}", a)).collect::<Vec<_>>().join("\n")
+    };
+    json!({
+        "embeds": [{
+            "title": title(output),
+            "color": if output.alerts.is_empty() { DISCORD_OK } else { DISCORD_ALERT },
+            "fields": [
+                { "name": "Nodes", "value": format!("{}/{} online", output.nodes_online, output.nodes_total), "inline": true },
+                { "name": "Guests", "value": format!("{} running, {} stopped", output.guests_running, output.guests_stopped), "inline": true },
+                { "name": "Alerts", "value": alerts },
+            ],
+        }],
+    })
+}
@@ -0,0 +1,348 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # tasks.rs
+//!
+//! Proxmox tasks (UPIDs): follow the ones started by pvenom until they
+//! finish, reporting them as `--progress json` events, and find and cancel
+//! stuck ones:
+//!
+//! pvenom tasks --running --mine
+//! pvenom tasks failures --since 24h
+//! pvenom task UPID:pve1:0012A4B1:... cancel
+
+use anyhow::{bail, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use super::Commands;
+use crate::models::{OutputFormat, Task, TaskFailureGroup, TaskStatus};
+use crate::progress::{self, Event};
+use crate::{csv_row, pager, vlog_debug, vlog_success, vlog_warn};
+
+/// Log lines shown by `task <upid>`
+const TASK_LOG_TAIL: usize = 20;
+
+/// JSON output of `task <upid>`
+#[derive(Serialize, JsonSchema)]
+pub(super) struct TaskDetail<'a> {
+    upid: &'a str,
+    #[serde(flatten)]
+    status: TaskStatus,
+    log: Vec<String>,
+}
+
+/// Seconds between two task status polls
+const TASK_POLL_SECS: u64 = 2;
+
+/// Characters of the error shown by `tasks failures` as a table
+const FAILURE_EXCERPT_LEN: usize = 80;
+
+impl Commands {
+    /// Recent tasks of every online node, newest first
+    pub async fn list_tasks(&self, running: bool, mine: bool, limit: usize) -> Result<()> {
+        let user = mine.then(|| self.client.username().to_string());
+        let mut tasks: Vec<Task> = Vec::new();
+        for node in self.client.get_nodes().await? {
+            if node.status != "online" {
+                vlog_warn!("Node '{}' is {}, its tasks are not listed", node.node, node.status);
+                continue;
+            }
+            tasks.extend(self.client.get_node_task_list(&node.node, running, user.as_deref(), limit).await?);
+        }
+        tasks.sort_by_key(|t| std::cmp::Reverse(t.starttime));
+        tasks.truncate(limit);
+
+        let state = |t: &Task| match (&t.status, t.endtime) {
+            (Some(status), Some(_)) => status.clone(),
+            _ => "running".to_string(),
+        };
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&tasks)?),
+            OutputFormat::Csv => {
+                csv_row!("UPID", "NODE", "TYPE", "ID", "USER", "STARTED", "STATUS");
+                for t in &tasks {
+                    csv_row!(t.upid, t.node, t.task_type, t.id.as_deref().unwrap_or(""),
+                             t.user.as_deref().unwrap_or(""), self.timestamp(t.starttime), state(t));
+                }
+            }
+            OutputFormat::Table => {
+                if tasks.is_empty() {
+                    println!("No {}tasks.", if running { "running " } else { "" });
+                    return Ok(());
+                }
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Started").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Type").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("ID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("User").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Status").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("UPID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for t in &tasks {
+                    let status = state(t);
+                    let status_cell = match status.as_str() {
+                        "OK" => Cell::new(status).fg(Color::Green),
+                        "running" => Cell::new(status).fg(Color::Yellow),
+                        _ => Cell::new(status).fg(Color::Red),
+                    };
+                    table.add_row(vec![
+                        Cell::new(self.timestamp(t.starttime)),
+                        Cell::new(&t.node),
+                        Cell::new(&t.task_type),
+                        Cell::new(t.id.as_deref().unwrap_or("")),
+                        Cell::new(t.user.as_deref().unwrap_or("")),
+                        status_cell,
+                        Cell::new(&t.upid),
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        vlog_success!("Listed {} task(s)", tasks.len());
+        Ok(())
+    }
+
+    /// State and last log lines of a task
+    pub async fn show_task(&self, upid: &str) -> Result<()> {
+        let status = self.client.get_task_status(upid).await?;
+        let mut log = self.client.get_task_log(upid, 0).await?;
+        log.drain(..log.len().saturating_sub(TASK_LOG_TAIL));
+
+        match self.output_format {
+            OutputFormat::Json => {
+                let detail = TaskDetail { upid, status, log };
+                println!("{}", serde_json::to_string_pretty(&detail)?);
+            }
+            _ => {
+                println!("{}", upid);
+                println!("Status: {}", status.exitstatus.as_deref().unwrap_or(&status.status));
+                println!();
+                for line in &log {
+                    println!("{}", line);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Tasks of every online node that failed in the last `since` seconds,
+    /// grouped by task type and error signature, most frequent first
+    pub async fn list_task_failures(&self, since: u64) -> Result<()> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let mut groups: BTreeMap<(String, String), TaskFailureGroup> = BTreeMap::new();
+        for node in self.client.get_nodes().await? {
+            if node.status != "online" {
+                vlog_warn!("Node '{}' is {}, its tasks are not checked", node.node, node.status);
+                continue;
+            }
+            let mut tasks = self.client.get_node_tasks(&node.node, now.saturating_sub(since)).await?;
+            tasks.sort_by_key(|t| t.starttime);
+            for task in tasks {
+                let error = match (&task.status, task.endtime) {
+                    (Some(status), Some(_)) if status != "OK" && !status.starts_with("WARNINGS") => status.clone(),
+                    _ => continue,
+                };
+                let signature = error_signature(&error);
+                let group = groups.entry((task.task_type.clone(), signature.clone()))
+                    .or_insert_with(|| TaskFailureGroup {
+                        signature,
+                        task_type: task.task_type.clone(),
+                        count: 0,
+                        guests: Vec::new(),
+                        nodes: Vec::new(),
+                        first: task.starttime,
+                        last: task.starttime,
+                        excerpt: String::new(),
+                        upids: Vec::new(),
+                    });
+                group.count += 1;
+                if let Some(id) = task.id.filter(|id| !id.is_empty() && !group.guests.contains(id)) {
+                    group.guests.push(id);
+                }
+                if !group.nodes.contains(&task.node) {
+                    group.nodes.push(task.node.clone());
+                }
+                group.first = group.first.min(task.starttime);
+                if task.starttime >= group.last {
+                    group.last = task.starttime;
+                    group.excerpt = error;
+                }
+                group.upids.push(task.upid);
+            }
+        }
+        let mut failures: Vec<TaskFailureGroup> = groups.into_values().collect();
+        failures.sort_by(|a, b| b.count.cmp(&a.count).then(b.last.cmp(&a.last)));
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&failures)?),
+            OutputFormat::Csv => {
+                csv_row!("TYPE", "COUNT", "GUESTS", "NODES", "FIRST", "LAST", "SIGNATURE", "ERROR");
+                for f in &failures {
+                    csv_row!(f.task_type, f.count, f.guests.join(" "), f.nodes.join(" "), self.timestamp(f.first),
+                             self.timestamp(f.last), f.signature, f.excerpt);
+                }
+            }
+            OutputFormat::Table => {
+                if failures.is_empty() {
+                    println!("No failed tasks.");
+                    return Ok(());
+                }
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Type").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Count").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Guests").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Nodes").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Last").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Error").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for f in &failures {
+                    let excerpt: String = if f.excerpt.chars().count() > FAILURE_EXCERPT_LEN {
+                        format!("{}…", f.excerpt.chars().take(FAILURE_EXCERPT_LEN).collect::<String>())
+                    } else {
+                        f.excerpt.clone()
+                    };
+                    table.add_row(vec![
+                        Cell::new(&f.task_type),
+                        Cell::new(f.count),
+                        Cell::new(f.guests.join(", ")),
+                        Cell::new(f.nodes.join(", ")),
+                        Cell::new(self.timestamp(f.last)),
+                        Cell::new(excerpt).fg(Color::Red),
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        let total: usize = failures.iter().map(|f| f.count).sum();
+        if total > 0 {
+            vlog_warn!("{} failed task(s) in {} group(s)", total, failures.len());
+        } else {
+            vlog_success!("No failed tasks");
+        }
+        Ok(())
+    }
+
+    /// Stop a running task
+    pub async fn cancel_task(&self, upid: &str) -> Result<()> {
+        let status = self.client.get_task_status(upid).await?;
+        if status.status != "running" {
+            bail!("Task is not running: {}", status.exitstatus.as_deref().unwrap_or(&status.status));
+        }
+        self.confirm("cancel", &format!("task {}", upid))?;
+
+        self.client.stop_task(upid).await?;
+        vlog_success!("Task {} cancelled", upid);
+        Ok(())
+    }
+
+    /// Wait for a task to stop, failing when it ends with an error or runs
+    /// longer than `timeout` seconds
+    pub(super) async fn wait_for_task(&self, upid: &str, timeout: u64) -> Result<()> {
+        if self.client.dry_run() {
+            return Ok(());
+        }
+        progress::emit(&Event::TaskStarted { upid });
+        let outcome = self.poll_task(upid, timeout).await;
+        let error = outcome.as_ref().err().map(|e| e.to_string());
+        progress::emit(&Event::TaskFinished { upid, ok: outcome.is_ok(), error: error.as_deref() });
+        outcome
+    }
+
+    async fn poll_task(&self, upid: &str, timeout: u64) -> Result<()> {
+        let started = Instant::now();
+        let mut log_lines = 0;
+        let mut last_percent = None;
+        loop {
+            let status = self.client.get_task_status(upid).await?;
+            if status.status == "stopped" {
+                return match status.exitstatus.as_deref() {
+                    Some("OK") => Ok(()),
+                    // Finished, with warnings in the task log
+                    Some(warnings) if warnings.starts_with("WARNINGS") => {
+                        vlog_warn!("Task {} finished with {}", upid, warnings.to_lowercase());
+                        Ok(())
+                    }
+                    Some(error) => bail!("{}", error),
+                    None => bail!("Task stopped without exit status"),
+                };
+            }
+            if started.elapsed().as_secs() > timeout {
+                bail!("Task still running after {}s: {}", timeout, upid);
+            }
+            vlog_debug!("Task {} still {}", upid, status.status);
+
+            // Only read the task log when someone listens for percentages
+            if progress::enabled() {
+                if let Ok(lines) = self.client.get_task_log(upid, log_lines).await {
+                    log_lines += lines.len();
+                    let percent = lines.iter().rev().find_map(|l| progress::parse_percent(l));
+                    if percent.is_some() && percent != last_percent {
+                        progress::emit(&Event::TaskProgress { upid, percent: percent.unwrap_or_default() });
+                        last_percent = percent;
+                    }
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(TASK_POLL_SECS)).await;
+        }
+    }
+}
+
+/// Error text with the parts that change between occurrences masked, so
+/// the same failure on different guests, volumes or times groups together
+fn error_signature(error: &str) -> String {
+    let mut signature = String::with_capacity(error.len());
+    let mut quote: Option<char> = None;
+    let mut in_number = false;
+    for c in error.chars() {
+        if let Some(q) = quote {
+            if c == q {
+                signature.push(c);
+                quote = None;
+            }
+            continue;
+        }
+        if c.is_ascii_digit() {
+            if !in_number {
+                signature.push('N');
+            }
+            in_number = true;
+            continue;
+        }
+        in_number = false;
+        signature.push(c);
+        if c == '\'' || c == '"' {
+            signature.push('*');
+            quote = Some(c);
+        }
+    }
+    signature
+}
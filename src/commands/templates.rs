@@ -0,0 +1,124 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # templates.rs
+//!
+//! Container templates, `pvenom templates ...`.
+//!
+//! `templates audit` matches every downloaded template against the
+//! appliance index of `pveam` by package (`debian-12-standard`) and shows
+//! the newer version when there is one. Outdated Debian and Ubuntu
+//! templates are shown in red, since containers created from them start
+//! with a backlog of updates. Templates not in the index (custom or
+//! retired ones) are listed as unknown. Run `pveam update` on the nodes
+//! first when the index itself is stale.
+
+use anyhow::{bail, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use std::collections::HashMap;
+
+use super::Commands;
+use crate::models::{OutputFormat, TemplateAudit};
+use crate::{csv_row, pager, vlog_info, vlog_success};
+
+/// Distributions whose outdated templates are highlighted
+const WATCHED_OS: [&str; 2] = ["debian", "ubuntu"];
+
+impl Commands {
+    pub async fn audit_templates(&self) -> Result<()> {
+        let Some(node) = self.client.get_nodes().await?.into_iter().find(|n| n.status == "online") else {
+            bail!("No online node to read the appliance index from");
+        };
+        vlog_info!("Reading the appliance index of node '{}'...", node.node);
+        let latest: HashMap<String, String> = self.client.get_appliance_index(&node.node).await?
+            .into_iter()
+            .filter(|a| a.appliance_type == "lxc")
+            .map(|a| (a.package, a.template))
+            .collect();
+
+        let mut rows: Vec<TemplateAudit> = self.storage_volumes("vztmpl", None).await?
+            .into_iter()
+            .map(|(node, volume)| {
+                let template = volume.volid.rsplit('/').next().unwrap_or(&volume.volid).to_string();
+                let package = template.split('_').next().unwrap_or_default().to_string();
+                let latest = latest.get(&package).cloned();
+                let status = match &latest {
+                    Some(l) if *l == template => "current",
+                    Some(_) => "outdated",
+                    None => "unknown",
+                };
+                TemplateAudit {
+                    storage: volume.volid.split(':').next().unwrap_or_default().to_string(),
+                    node,
+                    template,
+                    package,
+                    latest,
+                    status: status.to_string(),
+                }
+            })
+            .collect();
+        rows.sort_by(|a, b| a.package.cmp(&b.package).then(a.storage.cmp(&b.storage)).then(a.node.cmp(&b.node)));
+
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+            OutputFormat::Csv => {
+                csv_row!("STORAGE", "NODE", "TEMPLATE", "PACKAGE", "LATEST", "STATUS");
+                for r in &rows {
+                    csv_row!(r.storage, r.node, r.template, r.package, r.latest.as_deref().unwrap_or(""), r.status);
+                }
+            }
+            OutputFormat::Table => {
+                if rows.is_empty() {
+                    println!("No container templates downloaded.");
+                    return Ok(());
+                }
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Storage").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Template").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Latest").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Status").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for r in &rows {
+                    let watched = WATCHED_OS.iter().any(|os| r.package.starts_with(os));
+                    let status = match r.status.as_str() {
+                        "current" => Cell::new(&r.status).fg(Color::Green),
+                        "outdated" if watched => Cell::new(&r.status).fg(Color::Red),
+                        "outdated" => Cell::new(&r.status).fg(Color::Yellow),
+                        _ => Cell::new(&r.status),
+                    };
+                    table.add_row(vec![
+                        Cell::new(&r.storage),
+                        Cell::new(&r.node),
+                        Cell::new(&r.template),
+                        Cell::new(r.latest.as_deref().filter(|l| *l != r.template).unwrap_or("-")),
+                        status,
+                    ]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        let outdated = rows.iter().filter(|r| r.status == "outdated").count();
+        vlog_success!("Audited {} template(s), {} outdated", rows.len(), outdated);
+        Ok(())
+    }
+}
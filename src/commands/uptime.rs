@@ -0,0 +1,259 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # uptime.rs
+//!
+//! Per-guest availability report, `pvenom uptime-report --last 30d`.
+//!
+//! Downtime is estimated from two sources:
+//! - the task history: a guest is down between a stop/shutdown task and
+//!   the next start task;
+//! - the node RRD history: samples with no CPU value mean the node was
+//!   unreachable, so all of its guests were down as well.
+//!
+//! The result is an estimate, good enough for SLA reporting, not a proof.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+
+use super::Commands;
+use crate::models::{GuestAvailabilityJson, OutputFormat, Task, UptimeReportOutput};
+use crate::{csv, csv_row, pager, vlog_debug, vlog_info, vlog_success, vlog_warn};
+
+const STOP_TASKS: [&str; 4] = ["qmstop", "qmshutdown", "vzstop", "vzshutdown"];
+const START_TASKS: [&str; 2] = ["qmstart", "vzstart"];
+
+impl Commands {
+    pub async fn uptime_report(&self, last: u64) -> Result<()> {
+        let until = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let since = until.saturating_sub(last);
+        vlog_info!("Computing guest availability for the last {} second(s)...", last);
+
+        let resources = self.client.get_cluster_resources(Some("vm")).await?;
+        let mut guests: Vec<_> = resources.into_iter().filter(|r| r.is_guest()).collect();
+        guests.sort_by(|a, b| a.node.cmp(&b.node).then(a.vmid.cmp(&b.vmid)));
+
+        // Node outages and task history, collected once per node
+        let mut node_outages: HashMap<String, Vec<(u64, u64)>> = HashMap::new();
+        let mut tasks_by_vmid: HashMap<u32, Vec<Task>> = HashMap::new();
+        for node in self.client.get_nodes().await? {
+            if node.status != "online" {
+                vlog_warn!("Node '{}' is {}, its history is not available", node.node, node.status);
+                continue;
+            }
+
+            let timeframe = rrd_timeframe(last);
+            match self.client.get_node_rrddata(&node.node, timeframe).await {
+                Ok(points) => {
+                    let step = rrd_step(timeframe);
+                    let outages = points.iter()
+                        .filter(|p| p.time + step > since && p.cpu.is_none())
+                        .map(|p| (p.time.max(since), (p.time + step).min(until)))
+                        .collect();
+                    node_outages.insert(node.node.clone(), merge_windows(outages));
+                }
+                Err(e) => vlog_warn!("No RRD data for node '{}': {}", node.node, e),
+            }
+
+            let tasks = match self.client.get_node_tasks(&node.node, since).await {
+                Ok(tasks) => tasks,
+                Err(e) => {
+                    vlog_warn!("No task history for node '{}': {}", node.node, e);
+                    continue;
+                }
+            };
+            for task in tasks {
+                let is_power_task = STOP_TASKS.contains(&task.task_type.as_str())
+                    || START_TASKS.contains(&task.task_type.as_str());
+                if !is_power_task || task.status.as_deref().unwrap_or("") != "OK" {
+                    continue;
+                }
+                if let Some(vmid) = task.id.as_deref().and_then(|id| id.parse::<u32>().ok()) {
+                    tasks_by_vmid.entry(vmid).or_default().push(task);
+                }
+            }
+        }
+
+        let window = until - since;
+        let mut report: Vec<GuestAvailabilityJson> = Vec::new();
+        for guest in &guests {
+            let (Some(vmid), Some(node)) = (guest.vmid, guest.node.as_ref()) else {
+                continue;
+            };
+            let currently_running = guest.status.as_deref() == Some("running");
+            let tasks = tasks_by_vmid.remove(&vmid).unwrap_or_default();
+
+            let mut windows = guest_down_windows(tasks, currently_running, since, until);
+            if let Some(outages) = node_outages.get(node) {
+                windows.extend(outages.iter().copied());
+            }
+            let windows = merge_windows(windows);
+            let downtime: u64 = windows.iter().map(|(start, end)| end - start).sum();
+            vlog_debug!("Guest {} down for {}s in {} window(s)", vmid, downtime, windows.len());
+
+            let availability = if window > 0 {
+                100.0 * (window - downtime.min(window)) as f64 / window as f64
+            } else {
+                100.0
+            };
+
+            report.push(GuestAvailabilityJson {
+                node: node.clone(),
+                vmid,
+                name: guest.name.clone().unwrap_or_default(),
+                guest_type: guest.guest_type().to_string(),
+                downtime_seconds: downtime,
+                downtime_windows: windows.len(),
+                availability_percent: (availability * 1000.0).round() / 1000.0,
+            });
+        }
+
+        let count = report.len();
+        match self.output_format {
+            OutputFormat::Json => {
+                let output = UptimeReportOutput { since, until, guests: report };
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            }
+            OutputFormat::Csv => {
+                csv_row!("NODE", "VMID", "NAME", "TYPE", "DOWNTIME_SECONDS", "DOWNTIME_WINDOWS",
+                         "AVAILABILITY_PERCENT");
+                for g in &report {
+                    csv_row!(g.node, g.vmid, g.name, g.guest_type, g.downtime_seconds, g.downtime_windows,
+                             csv::num(format!("{:.3}", g.availability_percent)));
+                }
+            }
+            OutputFormat::Table => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+
+                table.set_header(vec![
+                    Cell::new("Node").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("VMID").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Name").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Type").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Downtime").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Windows").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Availability %").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+
+                for g in &report {
+                    let availability_cell = if g.availability_percent >= 99.9 {
+                        Cell::new(format!("{:.3}", g.availability_percent)).fg(Color::Green)
+                    } else if g.availability_percent >= 99.0 {
+                        Cell::new(format!("{:.3}", g.availability_percent)).fg(Color::Yellow)
+                    } else {
+                        Cell::new(format!("{:.3}", g.availability_percent)).fg(Color::Red)
+                    };
+
+                    let downtime = format!("{}h {}m",
+                        g.downtime_seconds / 3600,
+                        (g.downtime_seconds % 3600) / 60);
+
+                    table.add_row(vec![
+                        Cell::new(&g.node),
+                        Cell::new(g.vmid),
+                        Cell::new(&g.name),
+                        Cell::new(&g.guest_type),
+                        Cell::new(&downtime),
+                        Cell::new(g.downtime_windows),
+                        availability_cell,
+                    ]);
+                }
+
+                pager::print_table(&mut table);
+            }
+        }
+
+        vlog_success!("Availability computed for {} guest(s)", count);
+        Ok(())
+    }
+}
+
+/// Smallest RRD timeframe covering `seconds`
+pub(super) fn rrd_timeframe(seconds: u64) -> &'static str {
+    match seconds {
+        0..=3_600 => "hour",
+        3_601..=86_400 => "day",
+        86_401..=604_800 => "week",
+        604_801..=2_678_400 => "month",
+        _ => "year",
+    }
+}
+
+/// Seconds between two RRD samples of a timeframe (PVE keeps 70 samples)
+fn rrd_step(timeframe: &str) -> u64 {
+    match timeframe {
+        "hour" => 60,
+        "day" => 1_800,
+        "week" => 10_800,
+        "month" => 43_200,
+        _ => 604_800,
+    }
+}
+
+/// Turn the power tasks of a guest into downtime windows within since..until
+fn guest_down_windows(mut tasks: Vec<Task>, running: bool, since: u64, until: u64) -> Vec<(u64, u64)> {
+    tasks.sort_by_key(|t| t.starttime);
+
+    let mut windows = Vec::new();
+    let mut down_since: Option<u64> = None;
+    let mut seen_event = false;
+
+    for task in &tasks {
+        if STOP_TASKS.contains(&task.task_type.as_str()) {
+            if down_since.is_none() {
+                down_since = Some(task.endtime.unwrap_or(task.starttime).max(since));
+            }
+        } else if START_TASKS.contains(&task.task_type.as_str()) {
+            // A start with no previous stop in the window: down since the beginning
+            let start = match down_since.take() {
+                Some(s) => s,
+                None if !seen_event => since,
+                None => continue,
+            };
+            windows.push((start, task.starttime.min(until)));
+        }
+        seen_event = true;
+    }
+
+    if let Some(start) = down_since {
+        windows.push((start, until));
+    } else if !seen_event && !running {
+        windows.push((since, until));
+    }
+
+    windows
+}
+
+/// Sort and merge overlapping windows so downtime is never counted twice
+fn merge_windows(mut windows: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    windows.retain(|(start, end)| end > start);
+    windows.sort();
+
+    let mut merged: Vec<(u64, u64)> = Vec::new();
+    for (start, end) in windows {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
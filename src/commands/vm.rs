@@ -0,0 +1,651 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # vm.rs
+//!
+//! Single guest commands, `pvenom vm <vmid> ...`. Guests can be given by
+//! name too, `pvenom vm web-frontend start`, as long as the name is unique.
+//!
+//! `vm <vmid>` alone shows everything about the guest in one card: status,
+//! resources, snapshot and backup counts, agent and addresses. Containers
+//! also list their root and mount points, bind mounts of host paths
+//! included, since those are neither backed up nor migrated.
+//!
+//! With ballooning, the RAM delivered to a VM can stay well below the one
+//! allocated: the card then adds the balloon size, the memory the guest
+//! sees and uses, swap traffic and memory pressure.
+//!
+//! `vm <vmid> --fs` adds the usage of every filesystem mounted inside a
+//! VM, read through the guest agent, since the disk size seen by the host
+//! says nothing about a full root partition. Filesystems used above
+//! `--fs-threshold` percent (90 by default) are shown in red.
+//!
+//! `vm <vmid> --probe-ports 22,80,443` tries a TCP connection to each
+//! port of the guest's first address and marks which services answer,
+//! handy after restarting many guests at once.
+//!
+//! VMs with a SPICE display, SPICE audio or USB devices, redirected
+//! through SPICE or passed through from the host, list them too: they need
+//! a SPICE client, and host USB devices rule out live migration.
+//!
+//! `vm <vmid> start|stop|shutdown|reboot` change the power state and print
+//! the UPID of the Proxmox task.
+//!
+//! Guest profiles are the TOML files written by `vm <vmid> export-config`:
+//!
+//! [guest]
+//! type = "qemu"
+//! node = "tatooine"
+//!
+//! [config]
+//! cores = 4
+//! memory = 8192
+//! name = "database-prod"
+//! net0 = "model=virtio,bridge=vmbr0"
+//! scsi0 = "local-lvm:32,iothread=1"
+//!
+//! Disks are written in the `STORAGE:SIZE_IN_GiB` allocation syntax and MAC
+//! addresses are dropped, so `vm create --from-config` builds a new guest
+//! instead of clashing with the original one.
+
+use anyhow::{bail, Context, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+use super::ping::probe;
+use super::Commands;
+use crate::models::{ClientDevice, ClusterResource, GuestAgentInfo, GuestDetailOutput, GuestInterface, GuestProfile, GuestProfileHeader, LxcMount, OutputFormat, PortProbe, StorageContent};
+use crate::{csv_row, pager, vlog_debug, vlog_info, vlog_success, vlog_warn};
+
+/// Time allowed to each `--probe-ports` connection
+const PORT_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Config keys bound to a single guest instance, never exported
+const VOLATILE_KEYS: [&str; 5] = ["digest", "vmgenid", "lock", "parent", "meta"];
+
+impl Commands {
+    /// Find node and type of a guest from its VMID
+    pub(super) async fn locate_guest(&self, vmid: u32) -> Result<ClusterResource> {
+        vlog_debug!("Locating guest {}...", vmid);
+        let resources = self.client.get_cluster_resources(Some("vm")).await?;
+
+        resources.into_iter()
+            .find(|r| r.vmid == Some(vmid))
+            .with_context(|| format!("Guest {} not found in the cluster", vmid))
+    }
+
+    /// VMID of a guest given as VMID or name. Names must match exactly one
+    /// guest, case-insensitively when there is no exact match.
+    pub(super) async fn resolve_guest(&self, guest: &str) -> Result<u32> {
+        if let Ok(vmid) = guest.parse::<u32>() {
+            return Ok(vmid);
+        }
+
+        vlog_debug!("Resolving guest name '{}'...", guest);
+        let resources = self.client.get_cluster_resources(Some("vm")).await?;
+        let mut matches: Vec<&ClusterResource> = resources.iter()
+            .filter(|r| r.name.as_deref() == Some(guest))
+            .collect();
+        if matches.is_empty() {
+            matches = resources.iter()
+                .filter(|r| r.name.as_deref().is_some_and(|n| n.eq_ignore_ascii_case(guest)))
+                .collect();
+        }
+
+        match matches.as_slice() {
+            [] => bail!("No guest named '{}' in the cluster", guest),
+            [only] => only.vmid.with_context(|| format!("Guest '{}' has no VMID", guest)),
+            many => {
+                let list: Vec<String> = many.iter()
+                    .map(|r| format!("  {} {} on {}",
+                                     r.vmid.unwrap_or_default(),
+                                     r.name.as_deref().unwrap_or_default(),
+                                     r.node.as_deref().unwrap_or("-")))
+                    .collect();
+                bail!("Guest name '{}' is ambiguous, use a VMID:\n{}", guest, list.join("\n"))
+            }
+        }
+    }
+
+    /// Detail card of one guest: runtime status, snapshots, backups, agent
+    /// and, in JSON, the full config. `fs_threshold` adds the filesystems
+    /// inside the VM, highlighting those used above that percentage.
+    pub async fn show_guest(&self, vmid: u32, fs_threshold: Option<u8>, probe_ports: &[u16]) -> Result<()> {
+        let guest = self.locate_guest(vmid).await?;
+        let node = guest.node.clone().context("Guest has no node")?;
+        let guest_type = guest.resource_type.as_str();
+
+        let status = self.client.get_guest_status(&node, guest_type, vmid).await?;
+        let config = self.client.get_guest_config(&node, guest_type, vmid).await?;
+        let snapshots = self.client.get_guest_snapshots(&node, guest_type, vmid).await?;
+        let backups = self.guest_backups(vmid, &node).await?;
+        let interfaces = self.client.get_guest_interfaces(&node, guest_type, vmid).await?;
+
+        // Only VMs have an agent, and only a running one answers
+        let agent = if guest_type == "qemu" && status.agent == Some(1) && status.status == "running" {
+            let info = self.client.get_agent_result(&node, vmid, "info").await;
+            let os = self.client.get_agent_result(&node, vmid, "get-osinfo").await;
+            Some(GuestAgentInfo {
+                version: info.and_then(|i| i["version"].as_str().map(str::to_string)),
+                os: os.and_then(|o| o["pretty-name"].as_str().or(o["name"].as_str()).map(str::to_string)),
+            })
+        } else {
+            None
+        };
+        let mounts = if guest_type == "lxc" { lxc_mounts(&config) } else { Vec::new() };
+        let client_devices = if guest_type == "qemu" { client_devices(&config) } else { Vec::new() };
+        let filesystems = match (fs_threshold, &agent) {
+            (Some(_), Some(_)) => self.client.get_guest_filesystems(&node, vmid).await.unwrap_or_default(),
+            (Some(_), None) => {
+                vlog_warn!("Filesystems need a running VM with the guest agent enabled");
+                Vec::new()
+            }
+            (None, _) => Vec::new(),
+        };
+        let ports = match guest_address(&interfaces) {
+            Some(address) => probe_ports_of(&address, probe_ports).await,
+            None if !probe_ports.is_empty() => {
+                vlog_warn!("No address known for guest {}, ports not probed", vmid);
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+
+        let output = GuestDetailOutput {
+            vmid,
+            name: status.name.clone().or(guest.name.clone()).unwrap_or_else(|| "N/A".to_string()),
+            guest_type: guest.guest_type().to_string(),
+            node,
+            snapshots: snapshots.len(),
+            backups: backups.len(),
+            last_backup: backups.iter().filter_map(|b| b.ctime).max(),
+            agent,
+            interfaces,
+            mounts,
+            client_devices,
+            filesystems,
+            ports,
+            config,
+            status,
+        };
+
+        if self.output_format == OutputFormat::Json {
+            println!("{}", serde_json::to_string_pretty(&output)?);
+            vlog_success!("Guest {} displayed", vmid);
+            return Ok(());
+        }
+
+        let gb = |bytes: u64| format!("{:.1}", bytes as f64 / 1024.0 / 1024.0 / 1024.0);
+        let pair = |used: Option<u64>, max: Option<u64>| match (used, max) {
+            (Some(u), Some(m)) => format!("{}/{} GB", gb(u), gb(m)),
+            (None, Some(m)) => format!("{} GB", gb(m)),
+            _ => "N/A".to_string(),
+        };
+
+        let status = &output.status;
+        let mut rows: Vec<(&str, String)> = vec![
+            ("VMID", vmid.to_string()),
+            ("Name", output.name.clone()),
+            ("Type", output.guest_type.clone()),
+            ("Node", output.node.clone()),
+            ("Status", status.qmpstatus.clone().unwrap_or_else(|| status.status.clone())),
+        ];
+        if let Some(uptime) = status.uptime.filter(|u| *u > 0) {
+            rows.push(("Uptime", self.uptime(uptime)));
+        }
+        rows.push(("CPUs", match (status.cpus, status.cpu) {
+            (Some(cpus), Some(cpu)) => format!("{} ({:.1}% used)", cpus, cpu * 100.0),
+            (Some(cpus), None) => cpus.to_string(),
+            _ => "N/A".to_string(),
+        }));
+        rows.push(("RAM", pair(status.mem, status.maxmem)));
+        if let Some(info) = &status.ballooninfo {
+            if let Some(actual) = info.actual {
+                let balloon = match (info.max_mem, status.balloon) {
+                    (Some(max), Some(target)) if target != actual => format!("{}/{} GB, target {} GB", gb(actual), gb(max), gb(target)),
+                    (Some(max), _) => format!("{}/{} GB", gb(actual), gb(max)),
+                    _ => format!("{} GB", gb(actual)),
+                };
+                rows.push(("Balloon", balloon));
+            }
+            if let (Some(total), Some(free)) = (info.total_mem, info.free_mem) {
+                rows.push(("Guest RAM", format!("{}/{} GB used", gb(total.saturating_sub(free)), gb(total))));
+            }
+            if let (Some(swap_in), Some(swap_out)) = (info.mem_swapped_in, info.mem_swapped_out) {
+                rows.push(("Guest swap in/out", format!("{}/{} GB", gb(swap_in), gb(swap_out))));
+            }
+        }
+        if let (Some(some), Some(full)) = (status.pressurememorysome, status.pressurememoryfull) {
+            rows.push(("Memory pressure", format!("{:.1}% some, {:.1}% full", some, full)));
+        }
+        rows.push(("Disk", pair(status.disk.filter(|d| *d > 0), status.maxdisk)));
+        if let (Some(netin), Some(netout)) = (status.netin, status.netout) {
+            rows.push(("Network in/out", format!("{}/{} GB", gb(netin), gb(netout))));
+        }
+        if let Some(tags) = &status.tags {
+            rows.push(("Tags", tags.replace(';', ", ")));
+        }
+        if let Some(lock) = &status.lock {
+            rows.push(("Lock", lock.clone()));
+        }
+        if let Some(ha) = status.ha.as_ref().filter(|ha| ha["managed"].as_u64() == Some(1)) {
+            rows.push(("HA", ha["state"].as_str().unwrap_or("managed").to_string()));
+        }
+        rows.push(("Snapshots", output.snapshots.to_string()));
+        rows.push(("Backups", match output.last_backup {
+            Some(last) => format!("{} (last {})", output.backups, self.timestamp(last)),
+            None => output.backups.to_string(),
+        }));
+        if let Some(agent) = &output.agent {
+            rows.push(("Agent", agent.version.clone().unwrap_or_else(|| "not responding".to_string())));
+            if let Some(os) = &agent.os {
+                rows.push(("OS", os.clone()));
+            }
+        }
+        for iface in &output.interfaces {
+            rows.push(("Interface", format!("{} {}", iface.name, iface.addresses.join(", "))));
+        }
+        for mount in &output.mounts {
+            let mut flags = Vec::new();
+            if mount.bind {
+                flags.push("bind".to_string());
+            }
+            if let Some(size) = &mount.size {
+                flags.push(size.clone());
+            }
+            if mount.read_only {
+                flags.push("read-only".to_string());
+            }
+            if mount.shared {
+                flags.push("shared".to_string());
+            }
+            if !mount.backup {
+                flags.push("no backup".to_string());
+            }
+            rows.push(("Mount", format!("{} {} ({})", mount.path, mount.volume, flags.join(", "))));
+        }
+        for device in &output.client_devices {
+            let label = if device.kind.starts_with("spice") { "SPICE" } else { "USB" };
+            let note = if device.blocks_migration { ", no live migration" } else { "" };
+            rows.push((label, format!("{} ({}){}", device.detail, device.key, note)));
+        }
+        let mut full = HashSet::new();
+        for fs in &output.filesystems {
+            let percent = fs.used_bytes as f64 / fs.total_bytes as f64 * 100.0;
+            if fs_threshold.is_some_and(|t| percent >= t as f64) {
+                full.insert(rows.len());
+            }
+            rows.push(("Filesystem", format!("{} {} {}/{} GB ({:.0}%)",
+                                             fs.mountpoint, fs.fs_type, gb(fs.used_bytes), gb(fs.total_bytes), percent)));
+        }
+
+        for port in &output.ports {
+            rows.push(("Port", match (port.open, &port.error) {
+                (true, _) => format!("{}/tcp on {} open", port.port, port.address),
+                (false, error) => format!("{}/tcp on {} closed: {}", port.port, port.address, error.as_deref().unwrap_or("no answer")),
+            }));
+        }
+
+        match self.output_format {
+            OutputFormat::Csv => {
+                csv_row!("PROPERTY", "VALUE");
+                for (property, value) in &rows {
+                    csv_row!(property, value);
+                }
+            }
+            _ => {
+                let mut table = Table::new();
+                table.load_preset(UTF8_FULL)
+                     .set_content_arrangement(ContentArrangement::Dynamic);
+                table.set_header(vec![
+                    Cell::new("Property").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                    Cell::new("Value").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                ]);
+                for (i, (property, value)) in rows.iter().enumerate() {
+                    let cell = match (*property, value.as_str()) {
+                        ("Status", "running") => Cell::new(value).fg(Color::Green),
+                        ("Status", _) => Cell::new(value).fg(Color::Red),
+                        ("Filesystem", _) if full.contains(&i) => Cell::new(value).fg(Color::Red),
+                        ("Mount", _) if value.contains("(bind") => Cell::new(value).fg(Color::Yellow),
+                        ("Port", _) if value.ends_with(" open") => Cell::new(value).fg(Color::Green),
+                        ("Port", _) => Cell::new(value).fg(Color::Red),
+                        _ => Cell::new(value),
+                    };
+                    table.add_row(vec![Cell::new(property), cell]);
+                }
+                pager::print_table(&mut table);
+            }
+        }
+
+        vlog_success!("Guest {} displayed", vmid);
+        Ok(())
+    }
+
+    /// Backups of a guest on every backup storage, shared storages once
+    async fn guest_backups(&self, vmid: u32, guest_node: &str) -> Result<Vec<StorageContent>> {
+        let storages = self.client.get_cluster_resources(Some("storage")).await?;
+        let mut seen = HashSet::new();
+        let mut backups = Vec::new();
+
+        for storage in storages {
+            let (Some(name), Some(node)) = (storage.storage.as_deref(), storage.node.as_deref()) else {
+                continue;
+            };
+            let holds_backups = storage.content.as_deref().is_some_and(|c| c.split(',').any(|c| c == "backup"));
+            if !holds_backups || storage.status.as_deref() != Some("available") {
+                continue;
+            }
+            // Query shared storages from the guest node, once
+            let shared = storage.shared == Some(1);
+            if shared && node != guest_node {
+                continue;
+            }
+            if !seen.insert((name.to_string(), if shared { String::new() } else { node.to_string() })) {
+                continue;
+            }
+            match self.client.get_storage_content(node, name, Some("backup"), Some(vmid)).await {
+                Ok(volumes) => backups.extend(volumes),
+                Err(e) => vlog_warn!("No backup list from storage '{}' on '{}': {}", name, node, e),
+            }
+        }
+        Ok(backups)
+    }
+
+    /// Start, stop, shut down or reboot a guest
+    pub async fn guest_power(&self, vmid: u32, action: &str) -> Result<()> {
+        let guest = self.locate_guest(vmid).await?;
+        let node = guest.node.clone().context("Guest has no node")?;
+
+        let what = format!("guest {} ({}) on '{}'", vmid, guest.name.as_deref().unwrap_or("-"), node);
+        self.confirm(action, &what)?;
+
+        vlog_info!("Sending {} to guest {} on node '{}'...", action, vmid, node);
+        let upid = self.client.set_guest_status(&node, &guest.resource_type, vmid, action).await?;
+        if self.client.dry_run() {
+            return Ok(());
+        }
+
+        println!("{}", upid);
+        vlog_success!("Task {} of guest {} started", action, vmid);
+        Ok(())
+    }
+
+    pub async fn export_guest_config(&self, vmid: u32, file: &str) -> Result<()> {
+        let guest = self.locate_guest(vmid).await?;
+        let node = guest.node.clone().context("Guest has no node")?;
+
+        let config = self.client.get_guest_config(&node, &guest.resource_type, vmid).await?;
+        let profile = GuestProfile {
+            guest: GuestProfileHeader {
+                guest_type: guest.resource_type.clone(),
+                node: Some(node),
+                ostemplate: None,
+            },
+            config: portable_config(config),
+        };
+
+        let toml = toml::to_string_pretty(&profile).context("Failed to serialize guest profile")?;
+        std::fs::write(file, toml).with_context(|| format!("Failed to write {}", file))?;
+
+        vlog_success!("Exported {} {} to {}", guest.guest_type(), vmid, file);
+        Ok(())
+    }
+
+    pub async fn create_guest_from_config(&self, file: &str, node: Option<&str>, vmid: Option<u32>) -> Result<()> {
+        let content = std::fs::read_to_string(file).with_context(|| format!("Failed to read {}", file))?;
+        let profile: GuestProfile = toml::from_str(&content).with_context(|| format!("Invalid guest profile {}", file))?;
+
+        let guest_type = profile.guest.guest_type.as_str();
+        if guest_type != "qemu" && guest_type != "lxc" {
+            bail!("Unsupported guest type '{}'. Expected 'qemu' or 'lxc'", guest_type);
+        }
+
+        let node = node.map(str::to_string)
+            .or(profile.guest.node.clone())
+            .context("No target node, use --node or set guest.node in the profile")?;
+        let vmid = match vmid {
+            Some(id) => id,
+            None => self.client.get_next_vmid().await?,
+        };
+
+        let mut params: Vec<(String, String)> = vec![("vmid".to_string(), vmid.to_string())];
+        if guest_type == "lxc" {
+            let ostemplate = profile.guest.ostemplate.as_deref()
+                .context("LXC profiles need guest.ostemplate, e.g. local:vztmpl/debian-12-standard_12.7-1_amd64.tar.zst")?;
+            params.push(("ostemplate".to_string(), ostemplate.to_string()));
+        }
+        for (key, value) in &profile.config {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                Value::Bool(b) => (*b as u8).to_string(),
+                other => other.to_string(),
+            };
+            params.push((key.clone(), value));
+        }
+
+        self.confirm("create", &format!("{} {} on '{}' from {}", guest_type, vmid, node, file))?;
+        vlog_info!("Creating {} {} on node '{}' from {}...", guest_type, vmid, node, file);
+        let upid = self.client.create_guest(&node, guest_type, &params).await?;
+        if self.client.dry_run() {
+            return Ok(());
+        }
+
+        println!("{}", upid);
+        vlog_success!("Creation of guest {} started", vmid);
+        Ok(())
+    }
+}
+
+/// Strip instance-bound keys, MAC addresses, the SMBIOS UUID and volume
+/// names from a config
+fn portable_config(config: Map<String, Value>) -> BTreeMap<String, Value> {
+    config.into_iter()
+        .filter(|(key, _)| !VOLATILE_KEYS.contains(&key.as_str()) && !key.starts_with("unused"))
+        .filter_map(|(key, value)| {
+            let value = match value {
+                Value::String(s) if is_disk_key(&key) => Value::String(portable_disk(&s)),
+                Value::String(s) if key.starts_with("net") => Value::String(portable_net(&s)),
+                Value::String(s) if key == "smbios1" => Value::String(portable_smbios(&s)?),
+                other => other,
+            };
+            Some((key, value))
+        })
+        .collect()
+}
+
+/// `smbios1` without its `uuid`, none when nothing else is set.
+/// Manufacturer, product, serial... may be what a license is bound to
+fn portable_smbios(value: &str) -> Option<String> {
+    let options: Vec<&str> = value.split(',').filter(|o| !o.starts_with("uuid=")).collect();
+    options.iter().any(|o| !o.starts_with("base64=")).then(|| options.join(","))
+}
+
+/// Root and mount points of a container config, in key order.
+///
+/// `local-lvm:vm-101-disk-1,mp=/srv,size=32G,backup=1` is a volume,
+/// `/mnt/share,mp=/srv` a bind mount, `/dev/sdb1,mp=/srv` a device mount.
+/// The root is always backed up, mount points only with `backup=1`.
+fn lxc_mounts(config: &Map<String, Value>) -> Vec<LxcMount> {
+    let mut mounts: Vec<LxcMount> = config.iter()
+        .filter(|(key, _)| *key == "rootfs" || (key.starts_with("mp") && is_disk_key(key)))
+        .filter_map(|(key, value)| {
+            let value = value.as_str()?;
+            let mut volume = None;
+            let mut options = BTreeMap::new();
+            for part in value.split(',') {
+                match part.split_once('=') {
+                    Some(("volume", v)) => volume = Some(v),
+                    Some((option, v)) => { options.insert(option, v); }
+                    None => volume = Some(part),
+                }
+            }
+            let volume = volume?.to_string();
+            let is_root = key == "rootfs";
+            let bind = volume.starts_with('/');
+            let flag = |name: &str, default: bool| options.get(name).map_or(default, |v| *v == "1");
+            Some(LxcMount {
+                key: key.clone(),
+                path: if is_root { "/".to_string() } else { options.get("mp").unwrap_or(&"?").to_string() },
+                storage: if bind { None } else { volume.split_once(':').map(|(s, _)| s.to_string()) },
+                size: options.get("size").map(|s| s.to_string()),
+                bind,
+                read_only: flag("ro", false),
+                backup: !bind && (is_root || flag("backup", false)),
+                shared: flag("shared", false),
+                volume,
+            })
+        })
+        .collect();
+
+    // rootfs first, then mp0, mp1, ... mp10 numerically
+    mounts.sort_by_key(|m| m.key.strip_prefix("mp").and_then(|n| n.parse::<u32>().ok()).map_or(0, |n| n + 1));
+    mounts
+}
+
+/// SPICE display and audio, SPICE USB redirection and host USB devices
+/// of a VM config, in key order
+pub(super) fn client_devices(config: &Map<String, Value>) -> Vec<ClientDevice> {
+    let mut devices = Vec::new();
+    for (key, value) in config {
+        let Some(value) = value.as_str() else { continue };
+        let options: BTreeMap<&str, &str> = value.split(',').filter_map(|p| p.split_once('=')).collect();
+        let first = value.split(',').next().unwrap_or_default();
+        let device = |kind: &str, detail: String, blocks_migration: bool| ClientDevice {
+            key: key.clone(),
+            kind: kind.to_string(),
+            detail,
+            blocks_migration,
+        };
+        if key == "vga" {
+            let vga = options.get("type").copied().unwrap_or(first);
+            if vga.starts_with("qxl") {
+                devices.push(device("spice-display", format!("display {}", vga), false));
+            }
+        } else if key == "spice_enhancements" {
+            devices.push(device("spice-enhancements", value.replace(',', ", "), false));
+        } else if key.starts_with("audio") && options.get("driver") == Some(&"spice") {
+            devices.push(device("spice-audio", format!("audio {}", options.get("device").unwrap_or(&"?")), false));
+        } else if key.starts_with("usb") && key[3..].chars().all(|c| c.is_ascii_digit()) {
+            match (first, options.get("host"), options.get("mapping")) {
+                ("spice", _, _) | (_, Some(&"spice"), _) => devices.push(device("spice-usb", "redirection".to_string(), false)),
+                (_, Some(host), _) => devices.push(device("usb-host", format!("host device {}", host), true)),
+                (_, _, Some(mapping)) => devices.push(device("usb-host", format!("mapped device {}", mapping), true)),
+                (host, None, None) => devices.push(device("usb-host", format!("host device {}", host), true)),
+            }
+        }
+    }
+    devices
+}
+
+/// First address of a guest outside loopback, IPv4 preferred
+pub(super) fn guest_address(interfaces: &[GuestInterface]) -> Option<String> {
+    let addresses: Vec<&str> = interfaces.iter()
+        .filter(|i| i.name != "lo")
+        .flat_map(|i| i.addresses.iter())
+        .map(|a| a.split('/').next().unwrap_or(a))
+        .filter(|a| !a.starts_with("127.") && *a != "::1" && !a.starts_with("fe80:"))
+        .collect();
+    addresses.iter().find(|a| a.contains('.')).or(addresses.first()).map(|a| a.to_string())
+}
+
+/// TCP connection to each port of `address`, all at once
+async fn probe_ports_of(address: &str, ports: &[u16]) -> Vec<PortProbe> {
+    let mut probes = JoinSet::new();
+    for &port in ports {
+        let address = address.to_string();
+        probes.spawn(async move {
+            let result = probe(&address, Some(port), PORT_PROBE_TIMEOUT).await;
+            PortProbe { port, address, open: result.is_ok(), error: result.err().map(|e| e.to_string()) }
+        });
+    }
+    let mut results = Vec::new();
+    while let Some(Ok(result)) = probes.join_next().await {
+        results.push(result);
+    }
+    results.sort_by_key(|p| p.port);
+    results
+}
+
+pub(super) fn is_disk_key(key: &str) -> bool {
+    const PREFIXES: [&str; 8] = ["ide", "sata", "scsi", "virtio", "efidisk", "tpmstate", "rootfs", "mp"];
+    PREFIXES.iter().any(|p| {
+        key.strip_prefix(p)
+            .is_some_and(|rest| rest.is_empty() || rest.chars().all(|c| c.is_ascii_digit()))
+    })
+}
+
+/// `local-lvm:vm-100-disk-0,size=32G,ssd=1` becomes `local-lvm:32,ssd=1`,
+/// cdroms, passthrough and bind mounts are left untouched
+fn portable_disk(value: &str) -> String {
+    let mut parts = value.split(',');
+    let volume = parts.next().unwrap_or_default();
+    let options: Vec<&str> = parts.collect();
+
+    let Some((storage, _)) = volume.split_once(':') else {
+        return value.to_string();
+    };
+    if options.contains(&"media=cdrom") {
+        return value.to_string();
+    }
+    let Some(size) = options.iter().find_map(|o| o.strip_prefix("size=")) else {
+        return value.to_string();
+    };
+
+    let mut result = format!("{}:{}", storage, size_in_gib(size));
+    for option in options.iter().filter(|o| !o.starts_with("size=")) {
+        result.push(',');
+        result.push_str(option);
+    }
+    result
+}
+
+/// Round a PVE size (`32G`, `512M`, `4T`) up to whole GiB, at least 1
+pub(super) fn size_in_gib(size: &str) -> u64 {
+    let (number, unit) = size.split_at(size.len().saturating_sub(1));
+    let (number, unit) = match unit {
+        "K" | "M" | "G" | "T" => (number, unit),
+        _ => (size, "B"),
+    };
+    let value: f64 = number.parse().unwrap_or(0.0);
+    let gib = match unit {
+        "K" => value / 1024.0 / 1024.0,
+        "M" => value / 1024.0,
+        "G" => value,
+        "T" => value * 1024.0,
+        _ => value / 1024.0 / 1024.0 / 1024.0,
+    };
+    (gib.ceil() as u64).max(1)
+}
+
+/// `virtio=BC:24:11:AA:BB:CC,bridge=vmbr0` becomes `model=virtio,bridge=vmbr0`
+/// (LXC `hwaddr=` options are dropped as well)
+fn portable_net(value: &str) -> String {
+    const MODELS: [&str; 6] = ["virtio", "e1000", "e1000e", "rtl8139", "vmxnet3", "i82551"];
+
+    value.split(',')
+        .filter(|o| !o.starts_with("hwaddr=") && !o.starts_with("macaddr="))
+        .map(|o| match o.split_once('=') {
+            Some((model, _)) if MODELS.contains(&model) => format!("model={}", model),
+            _ => o.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
@@ -0,0 +1,321 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # config.rs
+//!
+//! The optional pvenom config file, one profile per cluster.
+//!
+//! Looked up in `--config`, `$PVENOM_CONFIG`,
+//! `$XDG_CONFIG_HOME/pvenom/config.toml` and `~/.config/pvenom/config.toml`,
+//! in this order, then the system wide `/etc/pvenom/config.toml`. A missing file is not an error, pvenom keeps working from
+//! command line options alone.
+//!
+//! default_profile = "prod"
+//! format = "table"
+//!
+//! [profiles.prod]
+//! controller = "pve.example.com:8006"
+//! username = "monitor@pve"
+//! password_env = "PVENOM_PROD_PASSWORD"
+//! secure = true
+//! production = true
+//!
+//! [profiles.monitoring]
+//! controller = "pve.example.com:8006"
+//! username = "monitor@pve"
+//! password_env = "PVENOM_MONITOR_PASSWORD"
+//! read_only = true
+//!
+//! [profiles.backup]
+//! controller = "pve.example.com:8006"
+//! token_id = "monitor@pve!pvenom"
+//! token_secret_env = "PVENOM_BACKUP_TOKEN"
+//!
+//! [profiles.lab]
+//! controller = "192.168.54.10:8006"
+//! password = "tatooine"
+//! secure = false
+//!
+//! `format` may also be a table by command name, e.g. `format.nodes =
+//! "table"` and `format.guests = "json"`, with `default` for the others.
+//! `nodes` is the node listing, `guests` the node detail of `--node`, any
+//! other key a subcommand such as `tasks` or `uptime-report`.
+//!
+//! An API token, `token_id` with `token_secret` or `token_secret_env`,
+//! replaces username and password. Command line options always win over
+//! profile values. `pvenom init` writes a first profile, `pvenom config
+//! print-default` prints every setting with its default.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::audit::AuditConfig;
+use crate::mail::SmtpConfig;
+use crate::maintenance::MaintenanceEntry;
+use crate::models::OutputFormat;
+use crate::schedule::ScheduleEntry;
+use crate::vlog_debug;
+
+/// Every setting commented out with its default, `config print-default`,
+/// installing it as is changes nothing
+pub const DEFAULT_CONFIG: &str = r#"# pvenom configuration
+#
+# Looked up in --config, $PVENOM_CONFIG, $XDG_CONFIG_HOME/pvenom/config.toml,
+# ~/.config/pvenom/config.toml and /etc/pvenom/config.toml. Command line
+# options win over it.
+
+# Profile used when --profile is not given
+# default_profile = "prod"
+
+# Output format when --format is not given: table, json or csv
+# format = "table"
+# Or per command: nodes (node listing), guests (--node), any subcommand,
+# and default for the others
+# [format]
+# default = "table"
+# nodes = "table"
+# guests = "json"
+
+# Operations asking for confirmation before running, any of start, stop,
+# shutdown, reboot, create, migrate, drain, restore-placement, cancel,
+# prune, test-restore, destroy, rollback
+# confirm = ["destroy", "rollback", "stop", "prune"]
+
+# Programs to pipe results through, by the keys of [format]; they read the
+# JSON output as NDJSON on stdin and their stdout is printed
+# [hooks]
+# tasks = "/usr/local/bin/enrich-tasks"
+
+# Power and snapshot schedules carried out by `pvenom schedule run`, cron
+# fields minute hour day month weekday in UTC, shifted by utc_offset;
+# action is start, stop, shutdown, reboot or snapshot, for the guests with
+# tag and the listed ones. Snapshots rotate, keeping the keep_last newest.
+# [[schedule]]
+# name = "dev off for the night"
+# cron = "0 20 * * 1-5"
+# action = "shutdown"
+# tag = "dev"
+# guests = [100, 101]
+# utc_offset = "+02:00"
+#
+# [[schedule]]
+# cron = "0 * * * *"
+# action = "snapshot"
+# tag = "db"
+# keep_last = 24
+
+# Recurring maintenance windows of a node, or of the guests with a tag:
+# ping-sweep and node --exit-code do not fail for what falls in them
+# [[maintenance]]
+# node = "pve2"
+# cron = "0 2 * * 0"
+# duration = "3h"
+
+# Mail server of `pvenom report --email`; tls is starttls, tls (port 465)
+# or none, the port defaults to 587, 465 and 25 respectively
+# [smtp]
+# host = "mail.example.com"
+# port = 587
+# username = "pvenom@example.com"
+# password_env = "PVENOM_SMTP_PASSWORD"
+# from = "pvenom <pvenom@example.com>"
+# tls = "starttls"
+
+[audit]
+# Record every change pvenom makes to a cluster
+# enabled = true
+# Log file, one JSON line per change, default $XDG_STATE_HOME/pvenom/audit.log
+# path = "/var/log/pvenom/audit.log"
+# Also send every entry to the local syslog
+# syslog = false
+
+# One section per cluster, [profiles.<name>]
+# [profiles.prod]
+# Controller address, host or host:port, optionally with the scheme
+# controller = "pve.example.com:8006"
+# Login with username and password...
+# username = "root@pam"
+# password = "secret"
+# Or the name of an environment variable holding the password
+# password_env = "PVENOM_PROD_PASSWORD"
+# ...or with an API token instead
+# token_id = "monitor@pve!pvenom"
+# token_secret = "00000000-0000-0000-0000-000000000000"
+# token_secret_env = "PVENOM_PROD_TOKEN"
+# Verify the TLS certificate, false for self-signed clusters
+# secure = true
+# Refuse destructive operations without --i-know-what-i-am-doing
+# production = false
+# Never send changes, for monitoring accounts
+# read_only = false
+"#;
+
+#[derive(Debug, Deserialize, Default)]
+pub struct Config {
+    #[serde(default)]
+    pub default_profile: Option<String>,
+    /// Output format when --format is not given, see `format_for`
+    #[serde(default)]
+    pub format: Option<FormatSetting>,
+    /// Operations asking for confirmation, see confirm.rs
+    #[serde(default)]
+    pub confirm: Option<Vec<String>>,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    /// Programs to pipe results through by command, see hooks.rs
+    #[serde(default)]
+    pub hooks: BTreeMap<String, String>,
+    /// Power and snapshot schedules run by `schedule run`, see schedule.rs
+    #[serde(default)]
+    pub schedule: Vec<ScheduleEntry>,
+    /// Recurring maintenance windows, see maintenance.rs
+    #[serde(default)]
+    pub maintenance: Vec<MaintenanceEntry>,
+    /// Mail server of `report --email`, see mail.rs
+    #[serde(default)]
+    pub smtp: Option<SmtpConfig>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Profile {
+    #[serde(default)]
+    pub controller: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Name of an environment variable holding the password
+    #[serde(default)]
+    pub password_env: Option<String>,
+    /// API token, `user@realm!tokenid`
+    #[serde(default)]
+    pub token_id: Option<String>,
+    #[serde(default)]
+    pub token_secret: Option<String>,
+    /// Name of an environment variable holding the token secret
+    #[serde(default)]
+    pub token_secret_env: Option<String>,
+    #[serde(default)]
+    pub secure: Option<bool>,
+    /// Refuse destructive operations without --i-know-what-i-am-doing
+    #[serde(default)]
+    pub production: bool,
+    /// Never send mutating requests, for monitoring accounts
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+impl Profile {
+    /// Inline password first, then the variable named by `password_env`
+    pub fn resolve_password(&self) -> Option<String> {
+        self.password.clone()
+            .or_else(|| self.password_env.as_ref().and_then(|var| std::env::var(var).ok()))
+    }
+
+    /// API token as the API wants it, `user@realm!tokenid=secret`
+    pub fn resolve_token(&self) -> Option<String> {
+        let secret = self.token_secret.clone()
+            .or_else(|| self.token_secret_env.as_ref().and_then(|var| std::env::var(var).ok()))?;
+        Some(format!("{}={}", self.token_id.as_ref()?, secret))
+    }
+}
+
+/// `format` of the config file, one for all commands or one per command
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged, expecting = "a format (table, json or csv) or a table of formats by command")]
+pub enum FormatSetting {
+    All(OutputFormat),
+    PerCommand(BTreeMap<String, OutputFormat>),
+}
+
+impl Config {
+    /// Output format of `command` when --format is not given
+    pub fn format_for(&self, command: &str) -> Option<OutputFormat> {
+        match self.format.as_ref()? {
+            FormatSetting::All(format) => Some(*format),
+            FormatSetting::PerCommand(formats) => formats.get(command).or_else(|| formats.get("default")).copied(),
+        }
+    }
+
+    pub fn profile(&self, name: &str) -> Result<&Profile> {
+        self.profiles.get(name).with_context(|| {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            format!("Unknown profile '{}'. Known profiles: {}", name, known.join(", "))
+        })
+    }
+}
+
+/// Config file installed by packages, used when the user has none
+const SYSTEM_PATH: &str = "/etc/pvenom/config.toml";
+
+/// Config file read without --config: the user one, or the system wide
+/// one when the user has none
+pub fn lookup_path() -> Option<PathBuf> {
+    match default_path() {
+        Some(path) if !path.exists() && std::env::var("PVENOM_CONFIG").is_err() && Path::new(SYSTEM_PATH).exists() => {
+            Some(PathBuf::from(SYSTEM_PATH))
+        }
+        path => path,
+    }
+}
+
+/// Default config location following the XDG base directory spec
+pub fn default_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("PVENOM_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let base = std::env::var("XDG_CONFIG_HOME").map(PathBuf::from)
+        .or_else(|_| std::env::var("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .ok()?;
+    Some(base.join("pvenom").join("config.toml"))
+}
+
+/// Directory of files pvenom keeps between runs (shell history, drain
+/// placement records): `$XDG_STATE_HOME/pvenom` or `~/.local/state/pvenom`
+pub fn state_dir() -> Option<PathBuf> {
+    let base = std::env::var("XDG_STATE_HOME").map(PathBuf::from)
+        .or_else(|_| std::env::var("HOME").map(|home| PathBuf::from(home).join(".local").join("state")))
+        .ok()?;
+    Some(base.join("pvenom"))
+}
+
+/// Load the config file, an explicit path must exist while the default one
+/// may be missing
+pub fn load(path: Option<&str>) -> Result<Config> {
+    let (path, explicit) = match path {
+        Some(p) => (PathBuf::from(p), true),
+        None => match lookup_path() {
+            Some(p) => (p, false),
+            None => return Ok(Config::default()),
+        },
+    };
+
+    if !explicit && !path.exists() {
+        vlog_debug!("No config file at {}", path.display());
+        return Ok(Config::default());
+    }
+
+    vlog_debug!("Loading config file {}", path.display());
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read config file {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Invalid config file {}", path.display()))
+}
@@ -0,0 +1,110 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # confirm.rs
+//!
+//! Confirmation policy of mutating commands.
+//!
+//! The operations listed in the config file `confirm` setting ask before
+//! running, `destroy`, `rollback`, `stop` and `prune` by default:
+//!
+//! confirm = ["destroy", "rollback", "stop", "drain"]
+//!
+//! Operation names are the command names: start, stop, shutdown, reboot,
+//! create, migrate, drain, restore-placement, cancel, prune, test-restore,
+//! destroy, rollback. `--yes` (or `--force`) answers for the user, and
+//! without a terminal the command fails instead of waiting for an answer
+//! nobody will give.
+//!
+//! Profiles marked `production = true` are stricter: destructive operations
+//! are refused outright unless `--i-know-what-i-am-doing` is given, `--yes`
+//! is not enough.
+
+use anyhow::{bail, Result};
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::Confirm;
+use std::io::IsTerminal;
+
+use crate::vlog_debug;
+
+/// Operations that lose state or data, refused on production profiles
+const DESTRUCTIVE: [&str; 4] = ["destroy", "rollback", "stop", "prune"];
+
+/// Every operation name the `confirm` setting accepts
+pub const OPERATIONS: [&str; 13] = ["start", "stop", "shutdown", "reboot", "create", "migrate", "drain",
+                                    "restore-placement", "cancel", "prune", "test-restore", "destroy", "rollback"];
+
+/// Operations asking for confirmation when the config does not say
+pub const DEFAULT_CONFIRM: [&str; 4] = ["destroy", "rollback", "stop", "prune"];
+
+#[derive(Debug, Clone)]
+pub struct ConfirmPolicy {
+    /// Operations asking before running
+    pub confirm: Vec<String>,
+    /// The profile is marked `production = true`
+    pub production: bool,
+    /// `--yes`: confirm everything without asking
+    pub assume_yes: bool,
+    /// `--i-know-what-i-am-doing`: allow destructive operations in production
+    pub override_production: bool,
+}
+
+impl Default for ConfirmPolicy {
+    fn default() -> Self {
+        Self {
+            confirm: DEFAULT_CONFIRM.iter().map(|o| o.to_string()).collect(),
+            production: false,
+            assume_yes: false,
+            override_production: false,
+        }
+    }
+}
+
+impl ConfirmPolicy {
+    /// Fail unless `operation` may run, asking the user when the policy
+    /// wants it. `what` describes the target, e.g. "guest 100 (web)".
+    pub fn check(&self, operation: &str, what: &str) -> Result<()> {
+        if self.production && DESTRUCTIVE.contains(&operation) && !self.override_production {
+            bail!("Refusing to {} {} on a production profile without --i-know-what-i-am-doing", operation, what);
+        }
+        if self.assume_yes || !self.confirm.iter().any(|o| o == operation) {
+            return Ok(());
+        }
+        if !(std::io::stdin().is_terminal() && std::io::stderr().is_terminal()) {
+            bail!("{} {} needs confirmation, pass --yes to run it without a terminal", capitalize(operation), what);
+        }
+
+        let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("{} {}?", capitalize(operation), what))
+            .default(false)
+            .interact()?;
+        if !confirmed {
+            bail!("{} of {} cancelled", capitalize(operation), what);
+        }
+        vlog_debug!("{} of {} confirmed", operation, what);
+        Ok(())
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,101 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # csv.rs
+//!
+//! Print CSV rows with the delimiter and decimal mark of `--csv-delimiter`
+//! and `--decimal-comma`.
+//!
+//! Commands pass each row as a list of fields to `csv_row!`, fields that
+//! contain the delimiter, quotes or line breaks are quoted per RFC 4180.
+//! European Excel setups read `12.5` in a comma separated file as a date
+//! or text, `--decimal-comma` writes `12,5` instead for fields marked with
+//! `num` and switches the delimiter to `;` unless one is given.
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+static DELIMITER: AtomicU32 = AtomicU32::new(',' as u32);
+static DECIMAL_COMMA: AtomicBool = AtomicBool::new(false);
+
+pub fn set_delimiter(delimiter: char) {
+    DELIMITER.store(delimiter as u32, Ordering::Relaxed);
+}
+
+pub fn set_decimal_comma(enabled: bool) {
+    DECIMAL_COMMA.store(enabled, Ordering::Relaxed);
+}
+
+/// A CSV field, numeric fields get the configured decimal mark
+pub struct Field {
+    value: String,
+    numeric: bool,
+}
+
+/// Mark a value as numeric so `--decimal-comma` applies to it
+pub fn num(value: impl Display) -> Field {
+    Field { value: value.to_string(), numeric: true }
+}
+
+/// Anything `csv_row!` accepts as a field
+pub trait ToField {
+    fn to_field(&self) -> Field;
+}
+
+impl<T: Display + ?Sized> ToField for T {
+    fn to_field(&self) -> Field {
+        Field { value: self.to_string(), numeric: false }
+    }
+}
+
+impl ToField for Field {
+    fn to_field(&self) -> Field {
+        Field { value: self.value.clone(), numeric: self.numeric }
+    }
+}
+
+/// Print a row with the configured delimiter and decimal mark
+pub fn print_row(fields: &[Field]) {
+    let delimiter = char::from_u32(DELIMITER.load(Ordering::Relaxed)).unwrap_or(',');
+    let decimal_comma = DECIMAL_COMMA.load(Ordering::Relaxed);
+
+    let fields: Vec<String> = fields.iter()
+        .map(|field| {
+            let value = if decimal_comma && field.numeric { field.value.replace('.', ",") } else { field.value.clone() };
+            quote(value, delimiter)
+        })
+        .collect();
+    println!("{}", fields.join(&delimiter.to_string()));
+}
+
+/// Quote a field holding the delimiter, quotes or line breaks, doubling inner quotes
+fn quote(value: String, delimiter: char) -> String {
+    if value.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// `println!` for CSV rows: `csv_row!(name, status, csv::num(cpu))`, see `print_row`
+#[macro_export]
+macro_rules! csv_row {
+    ($($field:expr),* $(,)?) => {
+        $crate::csv::print_row(&[$($crate::csv::ToField::to_field(&$field)),*])
+    };
+}
@@ -0,0 +1,51 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # docs.rs
+//!
+//! Documentation generated from the command line definitions, `pvenom docs`.
+//!
+//! `docs man` prints the pvenom(1) man page, or with `--out-dir` writes one
+//! page per command and subcommand, `pvenom-node-drain.1` and so on, for
+//! packagers to install under `/usr/share/man/man1`:
+//!
+//! pvenom docs man | man -l -
+//! pvenom docs man --out-dir debian/man
+
+use anyhow::{Context, Result};
+use clap::CommandFactory;
+use std::path::Path;
+
+use crate::{vlog_debug, vlog_success, Cli};
+
+/// Print the main man page, or write every page to `out_dir`
+pub fn man(out_dir: Option<&Path>) -> Result<()> {
+    let command = Cli::command();
+    let Some(out_dir) = out_dir else {
+        clap_mangen::Man::new(command).render(&mut std::io::stdout())?;
+        return Ok(());
+    };
+
+    std::fs::create_dir_all(out_dir)
+        .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+    vlog_debug!("Writing man pages to {}", out_dir.display());
+    clap_mangen::generate_to(command, out_dir)
+        .with_context(|| format!("Failed to write man pages to {}", out_dir.display()))?;
+    vlog_success!("Man pages written to {}", out_dir.display());
+    Ok(())
+}
@@ -0,0 +1,261 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # doctor.rs
+//!
+//! Self-check of a pvenom setup, `pvenom doctor`, the first thing to run
+//! when something does not work.
+//!
+//! The config file is parsed and validated, then every profile (only
+//! `--profile` when given, the command line options without a config) is
+//! connected to: reachability and credentials, the privileges pvenom
+//! needs to see everything, and the clock skew between this machine and
+//! each online node, which breaks ticket validation and makes task times
+//! lie. Each check ends up pass, warn or fail in one checklist, and the
+//! command fails when any check does.
+
+use anyhow::{bail, Result};
+use comfy_table::{Table, Cell, Color, Attribute, ContentArrangement, presets::UTF8_FULL};
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::{self, Config};
+use crate::confirm;
+use crate::models::OutputFormat;
+use crate::{connect, csv_row, pager, Cli, Connection};
+
+/// Clock skew tolerated without a warning, seconds
+const SKEW_WARN_SECS: i64 = 5;
+
+/// Clock skew past which tickets and task times are not to be trusted
+const SKEW_FAIL_SECS: i64 = 60;
+
+/// Privileges on `/` pvenom needs to list nodes, guests and storages
+const AUDIT_PRIVILEGES: [&str; 3] = ["Sys.Audit", "VM.Audit", "Datastore.Audit"];
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl Status {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Status::Pass => "pass",
+            Status::Warn => "warn",
+            Status::Fail => "fail",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Check {
+    /// `config` or the profile the check is about
+    scope: String,
+    check: String,
+    status: Status,
+    detail: String,
+}
+
+#[derive(Default)]
+struct Checklist(Vec<Check>);
+
+impl Checklist {
+    fn add(&mut self, scope: &str, check: &str, status: Status, detail: impl Into<String>) {
+        self.0.push(Check { scope: scope.to_string(), check: check.to_string(), status, detail: detail.into() });
+    }
+}
+
+/// Run every check and print the checklist, failing when a check failed
+pub async fn run(cli: &Cli) -> Result<()> {
+    let mut checks = Checklist::default();
+
+    let config = check_config(cli, &mut checks);
+    let profiles: Vec<(String, Option<config::Profile>)> = match (&config, cli.profile.as_deref()) {
+        (Some(config), Some(name)) => vec![(name.to_string(), config.profiles.get(name).cloned())],
+        (Some(config), None) if !config.profiles.is_empty() => config.profiles.iter()
+            .map(|(name, profile)| (name.clone(), Some(profile.clone())))
+            .collect(),
+        _ => vec![("command line".to_string(), None)],
+    };
+    for (name, profile) in &profiles {
+        check_profile(cli, name, profile.as_ref(), &mut checks).await;
+    }
+
+    let format = cli.output_format().or(config.as_ref().and_then(|c| c.format_for("doctor"))).unwrap_or(OutputFormat::Table);
+    print(&checks.0, format)?;
+    let failed = checks.0.iter().filter(|c| c.status == Status::Fail).count();
+    if failed > 0 {
+        bail!("{} check(s) failed", failed);
+    }
+    Ok(())
+}
+
+/// Parse the config file and check its references, None when unusable
+fn check_config(cli: &Cli, checks: &mut Checklist) -> Option<Config> {
+    let path = cli.config.clone()
+        .or_else(|| config::lookup_path().map(|p| p.display().to_string()));
+    let config = match config::load(cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            checks.add("config", "file", Status::Fail, format!("{:#}", e));
+            return None;
+        }
+    };
+    match path {
+        Some(path) if std::path::Path::new(&path).exists() => checks.add("config", "file", Status::Pass, path),
+        Some(path) => checks.add("config", "file", Status::Warn, format!("{} not found, command line options only", path)),
+        None => checks.add("config", "file", Status::Warn, "No config location, neither $XDG_CONFIG_HOME nor $HOME is set"),
+    }
+
+    if let Some(name) = &config.default_profile {
+        if config.profiles.contains_key(name) {
+            checks.add("config", "default profile", Status::Pass, name.clone());
+        } else {
+            checks.add("config", "default profile", Status::Fail, format!("'{}' is not a profile", name));
+        }
+    }
+    if let Some(confirm) = &config.confirm {
+        let unknown: Vec<&str> = confirm.iter()
+            .map(String::as_str)
+            .filter(|o| !confirm::OPERATIONS.contains(o))
+            .collect();
+        if unknown.is_empty() {
+            checks.add("config", "confirm", Status::Pass, confirm.join(", "));
+        } else {
+            checks.add("config", "confirm", Status::Fail, format!("Unknown operation(s): {}", unknown.join(", ")));
+        }
+    }
+    Some(config)
+}
+
+/// Connect with a profile and check privileges and clocks
+async fn check_profile(cli: &Cli, name: &str, profile: Option<&config::Profile>, checks: &mut Checklist) {
+    if let Some(var) = profile.and_then(|p| p.password_env.as_deref()).filter(|_| cli.password.is_none()) {
+        if std::env::var(var).is_err() && profile.is_some_and(|p| p.password.is_none()) {
+            checks.add(name, "password", Status::Fail, format!("${} is not set", var));
+            return;
+        }
+    }
+    if let Some(var) = profile.and_then(|p| p.token_secret_env.as_deref()).filter(|_| cli.api_token.is_none()) {
+        if std::env::var(var).is_err() && profile.is_some_and(|p| p.token_secret.is_none()) {
+            checks.add(name, "api token", Status::Fail, format!("${} is not set", var));
+            return;
+        }
+    }
+    let conn = match Connection::resolve(cli, profile) {
+        Ok(conn) => conn,
+        Err(e) => {
+            checks.add(name, "settings", Status::Fail, e.to_string());
+            return;
+        }
+    };
+    if !conn.secure {
+        checks.add(name, "tls", Status::Warn, "Certificate verification is off");
+    }
+
+    let client = match connect(&conn).await {
+        Ok(client) => {
+            checks.add(name, "login", Status::Pass, format!("{} on {}", client.username(), conn.controller));
+            client
+        }
+        Err(e) => {
+            checks.add(name, "login", Status::Fail, e.to_string());
+            return;
+        }
+    };
+
+    match client.get_permissions().await {
+        Ok(permissions) => {
+            let missing: Vec<&str> = AUDIT_PRIVILEGES.iter()
+                .copied()
+                .filter(|p| permissions.get("/").and_then(|root| root.get(*p)).is_none())
+                .collect();
+            if missing.is_empty() {
+                checks.add(name, "privileges", Status::Pass, AUDIT_PRIVILEGES.join(", "));
+            } else {
+                checks.add(name, "privileges", Status::Warn,
+                           format!("Missing {} on /, some data will not show", missing.join(", ")));
+            }
+        }
+        Err(e) => checks.add(name, "privileges", Status::Warn, format!("Not readable: {}", e)),
+    }
+
+    let nodes = match client.get_nodes().await {
+        Ok(nodes) => nodes,
+        Err(e) => {
+            checks.add(name, "nodes", Status::Fail, e.to_string());
+            return;
+        }
+    };
+    for node in nodes.iter().filter(|n| n.status == "online") {
+        let check = format!("clock {}", node.node);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+        match client.get_node_time(&node.node).await {
+            Ok(time) => {
+                let skew = time - now;
+                let detail = format!("{:+}s from this machine", skew);
+                match skew.abs() {
+                    s if s >= SKEW_FAIL_SECS => checks.add(name, &check, Status::Fail, detail),
+                    s if s > SKEW_WARN_SECS => checks.add(name, &check, Status::Warn, detail),
+                    _ => checks.add(name, &check, Status::Pass, detail),
+                }
+            }
+            Err(e) => checks.add(name, &check, Status::Warn, format!("Not readable: {}", e)),
+        }
+    }
+    for node in nodes.iter().filter(|n| n.status != "online") {
+        checks.add(name, &format!("node {}", node.node), Status::Warn, format!("Node is {}", node.status));
+    }
+}
+
+fn print(checks: &[Check], format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(checks)?),
+        OutputFormat::Csv => {
+            csv_row!("SCOPE", "CHECK", "STATUS", "DETAIL");
+            for c in checks {
+                csv_row!(c.scope, c.check, c.status.as_str(), c.detail);
+            }
+        }
+        OutputFormat::Table => {
+            let mut table = Table::new();
+            table.load_preset(UTF8_FULL)
+                 .set_content_arrangement(ContentArrangement::Dynamic);
+            table.set_header(vec![
+                Cell::new("Scope").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                Cell::new("Check").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                Cell::new("Result").add_attribute(Attribute::Bold).fg(Color::Cyan),
+                Cell::new("Detail").add_attribute(Attribute::Bold).fg(Color::Cyan),
+            ]);
+            for c in checks {
+                let result = match c.status {
+                    Status::Pass => Cell::new("PASS").fg(Color::Green),
+                    Status::Warn => Cell::new("WARN").fg(Color::Yellow),
+                    Status::Fail => Cell::new("FAIL").fg(Color::Red),
+                };
+                table.add_row(vec![Cell::new(&c.scope), Cell::new(&c.check), result, Cell::new(&c.detail)]);
+            }
+            pager::print_table(&mut table);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,103 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # envfmt.rs
+//!
+//! `--format env`, results as shell variables for scripts without a JSON
+//! parser: `eval "$(pvenom -n pve1 --format env)"`.
+//!
+//! The JSON output is flattened into `PVENOM_` variables, object keys and
+//! list indices joined by `_` and uppercased, plus a `_COUNT` per list.
+//! Values are single quoted, missing ones are empty.
+//!
+//! PVENOM_NAME='pve1'
+//! PVENOM_GUESTS_COUNT='2'
+//! PVENOM_GUESTS_0_NAME='web01'
+//! PVENOM_GUESTS_0_IPV4='10.0.0.21'
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::ffi::OsString;
+
+use crate::hooks;
+
+/// Run the current command line again with JSON output and print the
+/// result as variable assignments
+pub fn run() -> Result<()> {
+    // The JSON run of `hooks::json_output` must never come back here
+    if std::env::var("PVENOM_FORMAT").is_ok_and(|f| f == "json") && std::env::var_os("PVENOM_NO_HOOKS").is_some() {
+        bail!("--format env survived the switch to JSON, not running pvenom again");
+    }
+    let output = hooks::json_output(json_args())?;
+    let value: Value = serde_json::from_slice(&output).context("Command output is not JSON")?;
+    let mut vars = Vec::new();
+    flatten("PVENOM".to_string(), &value, &mut vars);
+    for (name, value) in vars {
+        println!("{}={}", name, quote(&value));
+    }
+    Ok(())
+}
+
+/// The command line arguments with `--format env` as `--format json`, in
+/// any case and in the `--format=env`, `-f=env` and `-fenv` forms too
+fn json_args() -> Vec<OsString> {
+    let is_env = |value: &str| value.eq_ignore_ascii_case("env");
+    let mut args: Vec<OsString> = std::env::args_os().skip(1).collect();
+    for i in 0..args.len() {
+        let arg = args[i].to_string_lossy().to_string();
+        let inline = arg.strip_prefix("--format=")
+            .or_else(|| arg.strip_prefix("-f=").or_else(|| arg.strip_prefix("-f").filter(|v| !v.is_empty())));
+        if inline.is_some_and(is_env) {
+            args[i] = "--format=json".into();
+        } else if (arg == "--format" || arg == "-f") && args.get(i + 1).is_some_and(|v| is_env(&v.to_string_lossy())) {
+            args[i + 1] = "json".into();
+        }
+    }
+    args
+}
+
+fn flatten(prefix: String, value: &Value, vars: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields {
+                flatten(format!("{}_{}", prefix, name(key)), field, vars);
+            }
+        }
+        Value::Array(items) => {
+            vars.push((format!("{}_COUNT", prefix), items.len().to_string()));
+            for (i, item) in items.iter().enumerate() {
+                flatten(format!("{}_{}", prefix, i), item, vars);
+            }
+        }
+        Value::Null => vars.push((prefix, String::new())),
+        Value::String(s) => vars.push((prefix, s.clone())),
+        other => vars.push((prefix, other.to_string())),
+    }
+}
+
+/// JSON key as part of a variable name, `memory_gb` is `MEMORY_GB`
+fn name(key: &str) -> String {
+    key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect()
+}
+
+/// Single quoted for the shell, `it's` is `'it'\''s'`
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
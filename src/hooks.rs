@@ -0,0 +1,98 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # hooks.rs
+//!
+//! Pipe the results of a command through an external program, e.g. a
+//! company-internal enrichment script.
+//!
+//! [hooks]
+//! tasks = "/usr/local/bin/enrich-tasks"
+//! nodes = "jq -c 'select(.status == \"online\")'"
+//!
+//! Keys are the ones of the per-command `format`. pvenom runs itself again
+//! with JSON output and without hooks, writes the result to the hook as
+//! NDJSON, one line per element of a list or a single line otherwise, and
+//! the hook output is printed as is. An explicit `--format` or `--no-hooks`
+//! skips the hook.
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::ffi::OsString;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::vlog_debug;
+
+/// Run the current command line again with JSON output and pipe it through
+/// `hook`, a shell command
+pub fn run(hook: &str) -> Result<()> {
+    vlog_debug!("Running the command for hook '{}'", hook);
+    let output = json_output(std::env::args_os().skip(1))
+        .with_context(|| format!("Hook '{}' not run", hook))?;
+
+    let ndjson = to_ndjson(&output)?;
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(hook)
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to start hook '{}'", hook))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook may stop reading early, like `head`
+        let _ = stdin.write_all(ndjson.as_bytes());
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("Hook '{}' failed with {}", hook, status);
+    }
+    Ok(())
+}
+
+/// Run pvenom again with `args`, JSON output and no hooks, and return what
+/// it printed. Shared with `--format env`.
+pub fn json_output(args: impl IntoIterator<Item = OsString>) -> Result<Vec<u8>> {
+    let exe = std::env::current_exe().context("Cannot locate the pvenom executable")?;
+    let output = Command::new(exe)
+        .args(args)
+        .env("PVENOM_FORMAT", "json")
+        .env("PVENOM_NO_HOOKS", "1")
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .context("Failed to run pvenom")?;
+    if !output.status.success() {
+        bail!("Command failed");
+    }
+    Ok(output.stdout)
+}
+
+/// One compact JSON line per list element, or the whole value on one line
+fn to_ndjson(json: &[u8]) -> Result<String> {
+    let value: Value = serde_json::from_slice(json).context("Command output is not JSON")?;
+    let values = match value {
+        Value::Array(items) => items,
+        other => vec![other],
+    };
+    let mut ndjson = String::new();
+    for value in values {
+        ndjson.push_str(&serde_json::to_string(&value)?);
+        ndjson.push('\n');
+    }
+    Ok(ndjson)
+}
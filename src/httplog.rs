@@ -0,0 +1,201 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # httplog.rs
+//!
+//! `--debug-http`, one line on stderr per API request with method, path,
+//! status, latency and response size, to diagnose API incompatibilities.
+//!
+//! http: GET /api2/json/nodes -> 200 OK, 14 ms, 1532 bytes
+//!
+//! `--debug-http-bodies` adds the request and response bodies. Passwords,
+//! tickets, tokens and secrets are redacted from both; the Cookie and
+//! Authorization headers are never printed.
+//!
+//! With `-v` the latencies are also collected by endpoint, node names and
+//! VMIDs replaced by placeholders, and `print_summary` logs the call count
+//! and percentiles of each at the end of the run. Slow `agent` endpoints
+//! point at guest agents, uniformly slow ones at the network or pveproxy.
+
+use reqwest::{Client, Request, Response};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::vlog::{self, LogLevel};
+use crate::vlog_debug;
+
+const OFF: u8 = 0;
+const METADATA: u8 = 1;
+const BODIES: u8 = 2;
+
+static MODE: AtomicU8 = AtomicU8::new(OFF);
+
+/// Latencies by `METHOD /endpoint`, collected with `-v`
+static LATENCIES: Mutex<BTreeMap<String, Vec<Duration>>> = Mutex::new(BTreeMap::new());
+
+pub fn set_mode(debug_http: bool, bodies: bool) {
+    let mode = if bodies { BODIES } else if debug_http { METADATA } else { OFF };
+    MODE.store(mode, Ordering::Relaxed);
+}
+
+/// Send `request`, traced on stderr when `--debug-http` is on
+pub async fn execute(client: &Client, request: Request) -> reqwest::Result<Response> {
+    let mode = MODE.load(Ordering::Relaxed);
+    let collect = vlog::should_log(LogLevel::Debug);
+    if mode == OFF && !collect {
+        return client.execute(request).await;
+    }
+
+    let method = request.method().clone();
+    let path = match request.url().query() {
+        Some(query) => format!("{}?{}", request.url().path(), query),
+        None => request.url().path().to_string(),
+    };
+    if mode == BODIES {
+        if let Some(body) = request.body().and_then(|b| b.as_bytes()) {
+            eprintln!("http: {} {} body: {}", method, path, redact_form(&String::from_utf8_lossy(body)));
+        }
+    }
+
+    let started = Instant::now();
+    let result = client.execute(request).await;
+    if collect {
+        LATENCIES.lock().unwrap_or_else(|e| e.into_inner())
+            .entry(format!("{} {}", method, endpoint(&path)))
+            .or_default()
+            .push(started.elapsed());
+    }
+    if mode == OFF {
+        return result;
+    }
+    let response = match result {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!("http: {} {} -> {}, {} ms", method, path, e, started.elapsed().as_millis());
+            return Err(e);
+        }
+    };
+
+    // The body is read here to count it, callers get it back unchanged
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.bytes().await?;
+    eprintln!("http: {} {} -> {}, {} ms, {} bytes", method, path, status, started.elapsed().as_millis(), body.len());
+    if mode == BODIES {
+        eprintln!("http: {} {} response: {}", method, path, redact_json(&String::from_utf8_lossy(&body)));
+    }
+
+    let mut rebuilt = http::Response::new(body);
+    *rebuilt.status_mut() = status;
+    *rebuilt.headers_mut() = headers;
+    Ok(Response::from(rebuilt))
+}
+
+/// Log call count and latency percentiles by endpoint, slowest in total
+/// first, when `-v` collected any
+pub fn print_summary() {
+    let latencies = LATENCIES.lock().unwrap_or_else(|e| e.into_inner());
+    if latencies.is_empty() {
+        return;
+    }
+    let mut endpoints: Vec<(&String, Vec<Duration>)> = latencies.iter()
+        .map(|(endpoint, times)| {
+            let mut times = times.clone();
+            times.sort();
+            (endpoint, times)
+        })
+        .collect();
+    endpoints.sort_by_key(|(_, times)| std::cmp::Reverse(times.iter().sum::<Duration>()));
+
+    let calls: usize = endpoints.iter().map(|(_, times)| times.len()).sum();
+    vlog_debug!("API calls: {} to {} endpoint(s)", calls, endpoints.len());
+    for (endpoint, times) in &endpoints {
+        let ms = |p: f64| times[((times.len() - 1) as f64 * p).round() as usize].as_millis();
+        vlog_debug!("  {}: {} call(s), p50 {} ms, p90 {} ms, p99 {} ms, max {} ms",
+                    endpoint, times.len(), ms(0.5), ms(0.9), ms(0.99), ms(1.0));
+    }
+}
+
+/// Path without query and API prefix, names and IDs as placeholders:
+/// `/nodes/{node}/qemu/{vmid}/agent/get-osinfo`
+fn endpoint(path: &str) -> String {
+    let path = path.split('?').next().unwrap_or_default();
+    let mut previous = "";
+    path.trim_start_matches("/api2/json")
+        .split('/')
+        .map(|segment| {
+            let placeholder = match previous {
+                "nodes" => "{node}",
+                "storage" => "{storage}",
+                "tasks" => "{upid}",
+                _ if segment.parse::<u64>().is_ok() => "{vmid}",
+                _ => segment,
+            };
+            previous = segment;
+            placeholder.to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Names of values never printed
+pub(crate) fn is_secret(key: &str) -> bool {
+    let key = key.to_lowercase();
+    ["password", "ticket", "token", "secret"].iter().any(|s| key.contains(s))
+}
+
+/// `username=root%40pam&password=<redacted>`
+fn redact_form(body: &str) -> String {
+    body.split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((key, _)) if is_secret(key) => format!("{}=<redacted>", key),
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// JSON bodies with secret values redacted, other bodies as they are
+fn redact_json(body: &str) -> String {
+    fn redact(value: &mut Value) {
+        match value {
+            Value::Object(fields) => {
+                for (key, field) in fields.iter_mut() {
+                    if is_secret(key) && !field.is_null() {
+                        *field = Value::String("<redacted>".to_string());
+                    } else {
+                        redact(field);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(redact),
+            _ => {}
+        }
+    }
+
+    match serde_json::from_str::<Value>(body) {
+        Ok(mut value) => {
+            redact(&mut value);
+            value.to_string()
+        }
+        Err(_) => body.to_string(),
+    }
+}
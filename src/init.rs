@@ -0,0 +1,184 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # init.rs
+//!
+//! First-run setup, `pvenom init`.
+//!
+//! Asks for the controller, how to authenticate (password or API token,
+//! the secret either read from an environment variable or stored in the
+//! file), whether to verify the TLS certificate and the default output
+//! format, then writes a profile to the config file and tests it. An
+//! existing config file keeps its other settings and profiles, but loses
+//! its comments. Files holding a secret are made readable by the owner
+//! only.
+
+use anyhow::{bail, Context, Result};
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Confirm, Input, Password, Select};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use crate::config::{self, Profile};
+use crate::{connect, Cli, Connection};
+use crate::{vlog_info, vlog_success};
+
+/// Output formats offered, the first is the default
+const FORMATS: [&str; 3] = ["table", "json", "csv"];
+
+/// Ask for a profile, write it and test the connection
+pub async fn run(cli: &Cli) -> Result<()> {
+    if !std::io::stdin().is_terminal() {
+        bail!("`pvenom init` is interactive, write the config file by hand instead, see `pvenom doctor`");
+    }
+    let path = cli.config.as_ref().map(PathBuf::from)
+        .or_else(config::default_path)
+        .context("No config location, set --config or $HOME")?;
+    let mut document: toml::Table = match std::fs::read_to_string(&path) {
+        Ok(content) => toml::from_str(&content).with_context(|| format!("Invalid config file {}", path.display()))?,
+        Err(_) => toml::Table::new(),
+    };
+    vlog_info!("Writing to {}", path.display());
+
+    let theme = ColorfulTheme::default();
+    let name: String = Input::with_theme(&theme)
+        .with_prompt("Profile name")
+        .default("default".to_string())
+        .interact_text()?;
+    let exists = document.get("profiles").and_then(|p| p.get(&name)).is_some();
+    if exists && !Confirm::with_theme(&theme)
+        .with_prompt(format!("Profile '{}' exists, replace it?", name))
+        .default(false)
+        .interact()? {
+        bail!("Profile '{}' left untouched", name);
+    }
+
+    let controller: String = Input::with_theme(&theme)
+        .with_prompt("Controller, host or host:port")
+        .interact_text()?;
+    let mut profile = Profile { controller: Some(controller), ..Profile::default() };
+    let mut entry = toml::Table::new();
+    entry.insert("controller".into(), profile.controller.clone().unwrap_or_default().into());
+
+    let use_token = Select::with_theme(&theme)
+        .with_prompt("Authentication")
+        .items(["password", "API token"])
+        .default(0)
+        .interact()? == 1;
+    let (secret_key, env_key) = if use_token {
+        let token_id: String = Input::with_theme(&theme)
+            .with_prompt("Token ID, user@realm!tokenid")
+            .validate_with(|id: &String| if id.contains('!') { Ok(()) } else { Err("Expected user@realm!tokenid") })
+            .interact_text()?;
+        entry.insert("token_id".into(), token_id.clone().into());
+        profile.token_id = Some(token_id);
+        ("token_secret", "token_secret_env")
+    } else {
+        let username: String = Input::with_theme(&theme)
+            .with_prompt("Username")
+            .default("root@pam".to_string())
+            .interact_text()?;
+        entry.insert("username".into(), username.clone().into());
+        profile.username = Some(username);
+        ("password", "password_env")
+    };
+
+    let in_file = Select::with_theme(&theme)
+        .with_prompt("Keep the secret")
+        .items(["in an environment variable (recommended)", "in the config file"])
+        .default(0)
+        .interact()? == 1;
+    let secret = if in_file {
+        let value = Password::with_theme(&theme).with_prompt("Secret").interact()?;
+        entry.insert(secret_key.into(), value.clone().into());
+        value
+    } else {
+        let default_var = format!("PVENOM_{}_{}", name.to_uppercase().replace(|c: char| !c.is_ascii_alphanumeric(), "_"),
+                                  if use_token { "TOKEN" } else { "PASSWORD" });
+        let var: String = Input::with_theme(&theme)
+            .with_prompt("Environment variable")
+            .default(default_var)
+            .interact_text()?;
+        entry.insert(env_key.into(), var.clone().into());
+        // The test connection needs the secret even when it is not saved
+        match std::env::var(&var) {
+            Ok(value) => value,
+            Err(_) => Password::with_theme(&theme)
+                .with_prompt(format!("Secret, for the test only (${} is not set)", var))
+                .interact()?,
+        }
+    };
+    let secret = Some(secret);
+    if use_token {
+        profile.token_secret = secret;
+    } else {
+        profile.password = secret;
+    }
+
+    let secure = Select::with_theme(&theme)
+        .with_prompt("TLS certificate")
+        .items(["verify it", "accept self-signed certificates (insecure)"])
+        .default(0)
+        .interact()? == 0;
+    entry.insert("secure".into(), secure.into());
+    profile.secure = Some(secure);
+
+    let format = Select::with_theme(&theme)
+        .with_prompt("Default output format")
+        .items(FORMATS)
+        .default(0)
+        .interact()?;
+
+    document.entry("profiles").or_insert_with(|| toml::Table::new().into())
+        .as_table_mut()
+        .context("`profiles` in the config file is not a table")?
+        .insert(name.clone(), entry.into());
+    document.entry("default_profile").or_insert_with(|| name.clone().into());
+    document.insert("format".into(), FORMATS[format].into());
+    write(&path, &document, in_file)?;
+    vlog_success!("Profile '{}' written to {}", name, path.display());
+
+    // Test with the answers alone, not whatever the command line says
+    let conn = Connection {
+        controller: profile.controller.clone().unwrap_or_default(),
+        username: profile.username.clone().unwrap_or_else(|| "root@pam".to_string()),
+        password: profile.password.clone(),
+        token: profile.resolve_token(),
+        secure,
+        max_rps: 0.0,
+    };
+    let client = connect(&conn).await?;
+    vlog_success!("Connected to {} as {}", conn.controller, client.username());
+    Ok(())
+}
+
+fn write(path: &Path, document: &toml::Table, has_secret: bool) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let content = toml::to_string_pretty(document).context("Failed to serialize config")?;
+    std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    #[cfg(unix)]
+    if has_secret {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("Failed to restrict {}", path.display()))?;
+    }
+    Ok(())
+}
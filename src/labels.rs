@@ -0,0 +1,183 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # labels.rs
+//!
+//! Local labels of nodes and guests, for what Proxmox has no field for:
+//! owner, ticket, cost center. Kept by pvenom alone, one file per cluster
+//! in `$XDG_STATE_HOME/pvenom/labels/<cluster>.json`, so every user of
+//! the workstation sees their own.
+//!
+//! pvenom label vm 100 owner=alice ticket=OPS-12
+//! pvenom label vm 100 ticket-
+//! pvenom --label owner=alice -n pve1
+//!
+//! Labels show in the node and guest listings, `--label` keeps the rows
+//! matching `KEY=VALUE`, `KEY!=VALUE` or just `KEY`.
+
+use anyhow::{bail, Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::config;
+use crate::vlog_debug;
+
+pub type Labels = BTreeMap<String, String>;
+
+/// Labels of one cluster
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+pub struct LabelStore {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub nodes: BTreeMap<String, Labels>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub guests: BTreeMap<u32, Labels>,
+}
+
+impl LabelStore {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.guests.is_empty()
+    }
+
+    pub fn node(&self, node: &str) -> Option<&Labels> {
+        self.nodes.get(node)
+    }
+
+    pub fn guest(&self, vmid: u32) -> Option<&Labels> {
+        self.guests.get(&vmid)
+    }
+}
+
+/// What `label` attaches labels to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LabelKind {
+    Node,
+    #[value(alias = "guest")]
+    Vm,
+}
+
+/// One `--label` condition
+#[derive(Debug, Clone)]
+pub struct Selector {
+    key: String,
+    value: Option<String>,
+    negate: bool,
+}
+
+impl Selector {
+    fn matches(&self, labels: Option<&Labels>) -> bool {
+        let value = labels.and_then(|l| l.get(&self.key));
+        match (&self.value, self.negate) {
+            (None, _) => value.is_some(),
+            (Some(expected), false) => value == Some(expected),
+            (Some(expected), true) => value != Some(expected),
+        }
+    }
+}
+
+/// `KEY=VALUE`, `KEY!=VALUE` or `KEY`, for clap
+pub fn parse_selector(s: &str) -> Result<Selector, String> {
+    let (key, value, negate) = match s.split_once("!=") {
+        Some((key, value)) => (key, Some(value), true),
+        None => match s.split_once('=') {
+            Some((key, value)) => (key, Some(value), false),
+            None => (s, None, false),
+        },
+    };
+    check_key(key).map_err(|e| e.to_string())?;
+    Ok(Selector { key: key.to_string(), value: value.map(str::to_string), negate })
+}
+
+/// All selectors hold, true without selectors
+pub fn matches(labels: Option<&Labels>, selectors: &[Selector]) -> bool {
+    selectors.iter().all(|s| s.matches(labels))
+}
+
+/// Apply `KEY=VALUE` and `KEY-` changes
+pub fn apply(labels: &mut Labels, changes: &[String]) -> Result<()> {
+    for change in changes {
+        match change.split_once('=') {
+            Some((key, value)) => {
+                check_key(key)?;
+                labels.insert(key.to_string(), value.to_string());
+            }
+            None => match change.strip_suffix('-') {
+                Some(key) => {
+                    check_key(key)?;
+                    labels.remove(key);
+                }
+                None => bail!("Invalid label '{}', expected KEY=VALUE or KEY- to remove it", change),
+            },
+        }
+    }
+    Ok(())
+}
+
+fn check_key(key: &str) -> Result<()> {
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || "_-./".contains(c)) {
+        bail!("Invalid label key '{}', use letters, digits and _-./", key);
+    }
+    Ok(())
+}
+
+/// `owner=alice ticket=OPS-12`
+pub fn join(labels: Option<&Labels>) -> String {
+    labels.map(|l| l.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join(" "))
+        .unwrap_or_default()
+}
+
+fn labels_dir() -> Result<PathBuf> {
+    let dir = config::state_dir().context("Cannot locate the state directory, HOME is not set")?;
+    Ok(dir.join("labels"))
+}
+
+/// Whether any cluster has labels, listings skip looking up the cluster
+/// name otherwise
+pub fn any() -> bool {
+    labels_dir().ok()
+        .and_then(|dir| std::fs::read_dir(dir).ok())
+        .is_some_and(|mut entries| entries.next().is_some())
+}
+
+pub fn load(cluster: &str) -> Result<LabelStore> {
+    let path = labels_dir()?.join(format!("{}.json", cluster));
+    if !path.exists() {
+        return Ok(LabelStore::default());
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid labels file {}", path.display()))
+}
+
+/// Write the labels of a cluster, removing the file once empty
+pub fn save(cluster: &str, store: &LabelStore) -> Result<()> {
+    let dir = labels_dir()?;
+    let path = dir.join(format!("{}.json", cluster));
+    if store.is_empty() {
+        if path.exists() {
+            std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+        return Ok(());
+    }
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    std::fs::write(&path, serde_json::to_vec_pretty(store)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    vlog_debug!("Labels saved to {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,114 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # mail.rs
+//!
+//! Sending pvenom output by email over SMTP, `report --email`. The server
+//! comes from the config file:
+//!
+//! [smtp]
+//! host = "mail.example.com"
+//! port = 587
+//! username = "pvenom@example.com"
+//! password_env = "PVENOM_SMTP_PASSWORD"
+//! from = "pvenom <pvenom@example.com>"
+//! tls = "starttls"
+//!
+//! `tls` is `starttls` (the default), `tls` for implicit TLS on port 465,
+//! or `none` for a local relay.
+
+use anyhow::{bail, Context, Result};
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Deserialize;
+
+use crate::vlog_debug;
+
+/// `[smtp]` section of the config file
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SmtpConfig {
+    pub host: String,
+    /// Default 587, 465 with `tls = "tls"`, 25 with `tls = "none"`
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Name of an environment variable holding the password
+    #[serde(default)]
+    pub password_env: Option<String>,
+    /// Sender, `name <address>` or a bare address
+    pub from: String,
+    #[serde(default)]
+    pub tls: SmtpTls,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    #[default]
+    Starttls,
+    Tls,
+    None,
+}
+
+impl SmtpConfig {
+    /// Inline password first, then the variable named by `password_env`
+    fn resolve_password(&self) -> Option<String> {
+        self.password.clone()
+            .or_else(|| self.password_env.as_ref().and_then(|var| std::env::var(var).ok()))
+    }
+}
+
+/// Send `body`, plain text, to every address of `to`
+pub async fn send(config: Option<&SmtpConfig>, to: &[String], subject: &str, body: String) -> Result<()> {
+    let Some(config) = config else {
+        bail!("No [smtp] section in the config file, see `pvenom config print-default`");
+    };
+    let mut message = Message::builder()
+        .from(config.from.parse().with_context(|| format!("Invalid sender address '{}'", config.from))?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN);
+    for address in to {
+        message = message.to(address.parse().with_context(|| format!("Invalid recipient address '{}'", address))?);
+    }
+    let message = message.body(body)?;
+
+    let mut transport = match config.tls {
+        SmtpTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?,
+        SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+        SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host).port(25),
+    };
+    if let Some(port) = config.port {
+        transport = transport.port(port);
+    }
+    if let Some(username) = &config.username {
+        let Some(password) = config.resolve_password() else {
+            bail!("SMTP user '{}' configured without password or password_env", username);
+        };
+        transport = transport.credentials(Credentials::new(username.clone(), password));
+    }
+
+    vlog_debug!("Sending '{}' to {} through {}", subject, to.join(", "), config.host);
+    transport.build().send(message).await
+        .with_context(|| format!("Cannot send the email through {}", config.host))?;
+    Ok(())
+}
@@ -21,49 +21,1050 @@
 //! Copyright (C) 2025 Francesco Garbin
 //!
 
-use clap::Parser;
-use anyhow::{bail, Result};
+use clap::builder::BoolishValueParser;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use anyhow::{anyhow, bail, Result};
 use std::env;
+mod audit;
 mod client;
 use client::ProxmoxClient;
 mod models;
 mod commands;
+mod config;
+mod confirm;
+mod csv;
+mod docs;
+mod doctor;
+mod envfmt;
+mod hooks;
+mod http;
+mod httplog;
+mod init;
+mod labels;
+mod mail;
+mod maintenance;
+mod mqtt;
+mod netbox;
+mod pager;
+mod pbs;
+mod progress;
+mod schedule;
+mod shell;
+mod syslog;
+mod timefmt;
 mod vlog;
 
-/// Proxmox Virtual Environment Node Observability Monitor
+/// Proxmox Virtual Environment Node Observability Monitor. Every global
+/// option falls back to a `PVENOM_*` environment variable, flags take
+/// true/false, yes/no or 1/0.
 #[derive(Parser)]
 #[command(name = "pvenom")]
 #[command(author = "Francesco - GameVision Italia CTO")]
 #[command(version = "0.1.0")]
 #[command(about = "Monitor and observe Proxmox VE cluster nodes, VMs and LXC containers", long_about = None)]
 struct Cli {
-    /// Proxmox cluster controller IP or hostname
-    #[arg(short = 'c', long = "controller", required = true)]
-    controller: String,
+    /// Proxmox cluster controller IP or hostname (required without a profile)
+    #[arg(short = 'c', long = "controller", env = "PVENOM_CONTROLLER")]
+    controller: Option<String>,
 
-    /// Username for authentication (e.g., root@pam)
-    #[arg(short = 'u', long = "username", default_value = "root@pam")]
-    username: String,
+    /// Username for authentication (default root@pam)
+    #[arg(short = 'u', long = "username", env = "PVENOM_USERNAME")]
+    username: Option<String>,
 
     /// Password for authentication
-    #[arg(short = 'p', long = "password", env = "PVENOM_PASSWORD")]
-    password: String,
+    #[arg(short = 'p', long = "password", env = "PVENOM_PASSWORD", hide_env_values = true)]
+    password: Option<String>,
 
-    /// Use SSL certificate verification (yes or no)
-    #[arg(short = 's', long = "secure", default_value = "yes", value_parser = parse_yes_no, num_args = 1)]
-    secure: bool,
+    /// API token instead of username and password, `user@realm!tokenid=secret`
+    #[arg(long = "api-token", env = "PVENOM_API_TOKEN", hide_env_values = true)]
+    api_token: Option<String>,
+
+    /// Use SSL certificate verification (yes or no, default yes)
+    #[arg(short = 's', long = "secure", env = "PVENOM_SECURE", value_parser = parse_yes_no, num_args = 1)]
+    secure: Option<bool>,
+
+    /// Config file with cluster profiles
+    #[arg(long = "config", env = "PVENOM_CONFIG")]
+    config: Option<String>,
+
+    /// Cluster profile from the config file, `all` for every profile
+    /// (nodes listing only)
+    #[arg(long = "profile", env = "PVENOM_PROFILE")]
+    profile: Option<String>,
 
-    /// Specify node name for operations (optional - lists all nodes if omitted)
-    #[arg(short = 'n', long = "node")]
+    /// Comma separated profiles to query concurrently, e.g. prod,lab
+    /// (nodes listing only)
+    #[arg(long = "clusters", env = "PVENOM_CLUSTERS", value_delimiter = ',', conflicts_with = "profile")]
+    clusters: Option<Vec<String>>,
+
+    /// Specify node name for operations (optional - lists all nodes if omitted,
+    /// pick one interactively when given without a name)
+    #[arg(short = 'n', long = "node", env = "PVENOM_NODE", num_args = 0..=1, default_missing_value = "")]
     node: Option<String>,
 
-    /// Output format: json, csv, or table
-    #[arg(short = 'f', long = "format", default_value = "table", value_parser = parse_format)]
-    format: models::OutputFormat,
+    /// Output format: json, csv, table, or env for shell variables (default table, or the config `format`)
+    #[arg(short = 'f', long = "format", env = "PVENOM_FORMAT", value_parser = parse_format)]
+    format: Option<FormatArg>,
+
+    /// JSON contract version: 1, or 2 with raw numbers instead of formatted strings
+    #[arg(long = "output-version", env = "PVENOM_OUTPUT_VERSION", default_value = "1", value_parser = parse_output_version)]
+    output_version: models::OutputVersion,
+
+    /// Uptimes and timestamps as relative (15d 4h, 3h ago), iso (RFC 3339) or unix (epoch seconds)
+    #[arg(long = "time-format", env = "PVENOM_TIME_FORMAT", value_parser = parse_time_format)]
+    time_format: Option<models::TimeFormat>,
+
+    /// Field delimiter of CSV output (default ',', or ';' with --decimal-comma)
+    #[arg(long = "csv-delimiter", env = "PVENOM_CSV_DELIMITER")]
+    csv_delimiter: Option<char>,
+
+    /// Write decimals in CSV output as 12,5 for European spreadsheets
+    #[arg(long = "decimal-comma", env = "PVENOM_DECIMAL_COMMA", value_parser = BoolishValueParser::new())]
+    decimal_comma: bool,
+
+    /// Append last hour CPU and RAM sparklines to the nodes table, in red
+    /// when the latest usage is far off the hour's baseline
+    #[arg(long = "trends", env = "PVENOM_TRENDS", value_parser = BoolishValueParser::new())]
+    trends: bool,
+
+    /// Add extra columns to tables: IP, uptime, tags, pool and HA state
+    #[arg(long = "wide", env = "PVENOM_WIDE", value_parser = BoolishValueParser::new())]
+    wide: bool,
+
+    /// Only nodes and guests with this local label, KEY=VALUE, KEY!=VALUE
+    /// or KEY, repeatable
+    #[arg(long = "label", env = "PVENOM_LABEL", value_delimiter = ',', value_parser = labels::parse_selector)]
+    label: Vec<labels::Selector>,
+
+    /// Add current network in/out rates to the guests of --node
+    #[arg(long = "net", env = "PVENOM_NET", value_parser = BoolishValueParser::new())]
+    net: bool,
+
+    /// Run operations needing confirmation without asking
+    #[arg(short = 'y', long = "yes", env = "PVENOM_YES", value_parser = BoolishValueParser::new(), visible_alias = "force")]
+    yes: bool,
+
+    /// Allow destructive operations on profiles marked production
+    #[arg(long = "i-know-what-i-am-doing", env = "PVENOM_I_KNOW_WHAT_I_AM_DOING", value_parser = BoolishValueParser::new())]
+    i_know_what_i_am_doing: bool,
+
+    /// Refuse every request that could change the cluster
+    #[arg(long = "read-only", env = "PVENOM_READ_ONLY", value_parser = BoolishValueParser::new())]
+    read_only: bool,
+
+    /// Print the POST/PUT/DELETE requests a command would send, send none
+    #[arg(long = "dry-run", env = "PVENOM_DRY_RUN", value_parser = BoolishValueParser::new())]
+    dry_run: bool,
+
+    /// Print results directly instead of through the config [hooks]
+    #[arg(long = "no-hooks", env = "PVENOM_NO_HOOKS", value_parser = BoolishValueParser::new())]
+    no_hooks: bool,
+
+    /// Print long tables directly instead of through $PAGER
+    #[arg(long = "no-pager", env = "PVENOM_NO_PAGER", value_parser = BoolishValueParser::new())]
+    no_pager: bool,
 
     /// Enable verbose debug logging
-    #[arg(short = 'v', long = "verbose")]
+    #[arg(short = 'v', long = "verbose", env = "PVENOM_VERBOSE", value_parser = BoolishValueParser::new())]
     verbose: bool,
+
+    /// Log method, path, status, latency and size of every API request on stderr
+    #[arg(long = "debug-http", env = "PVENOM_DEBUG_HTTP", value_parser = BoolishValueParser::new())]
+    debug_http: bool,
+
+    /// Like --debug-http, with request and response bodies, secrets redacted
+    #[arg(long = "debug-http-bodies", env = "PVENOM_DEBUG_HTTP_BODIES", value_parser = BoolishValueParser::new())]
+    debug_http_bodies: bool,
+
+    /// Requests per second at most to a cluster, 0 for no limit
+    #[arg(long = "max-rps", env = "PVENOM_MAX_RPS", default_value = "20", value_parser = parse_max_rps)]
+    max_rps: f64,
+
+    /// Progress events of long operations on stderr: none or json
+    #[arg(long = "progress", env = "PVENOM_PROGRESS", value_enum, default_value = "none")]
+    progress: progress::ProgressMode,
+
+    /// Where log messages go: console, or syslog/journald for services
+    #[arg(long = "log-target", env = "PVENOM_LOG_TARGET", value_enum, default_value = "console")]
+    log_target: vlog::LogTarget,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Subcommands, when omitted pvenom lists nodes or inspects --node
+#[derive(Subcommand)]
+enum Command {
+    /// Estimate per-guest availability from task and RRD history
+    UptimeReport {
+        /// Reporting window, e.g. 24h, 7d, 30d
+        #[arg(long = "last", default_value = "30d", value_parser = parse_duration)]
+        last: u64,
+    },
+
+    /// Guests using a fraction of their vCPUs or RAM, with smaller sizes
+    /// and the capacity they give back per node
+    Rightsize {
+        /// History considered, e.g. 7d, 30d
+        #[arg(long = "last", default_value = "14d", value_parser = parse_duration)]
+        last: u64,
+
+        /// Flag guests using less CPU than this, percent
+        #[arg(long = "cpu-threshold", default_value_t = 10, value_parser = clap::value_parser!(u8).range(1..=100))]
+        cpu_threshold: u8,
+
+        /// Flag guests using less RAM than this, percent
+        #[arg(long = "ram-threshold", default_value_t = 30, value_parser = clap::value_parser!(u8).range(1..=100))]
+        ram_threshold: u8,
+    },
+
+    /// Export the cluster inventory for third-party tools
+    Export {
+        #[command(subcommand)]
+        target: ExportTarget,
+    },
+
+    /// Publish node and guest state to external systems
+    Publish {
+        #[command(subcommand)]
+        target: PublishTarget,
+    },
+
+    /// Serve the cluster state as a read-only JSON API
+    Serve {
+        /// Address to listen on, e.g. :8088 or 127.0.0.1:8088
+        #[arg(long = "listen", default_value = ":8088")]
+        listen: String,
+
+        /// Require this bearer token on /api requests
+        #[arg(long = "token", env = "PVENOM_SERVE_TOKEN", hide_env_values = true)]
+        token: Option<String>,
+
+        /// Interval between two refreshes of the cached state, e.g. 30s
+        #[arg(long = "refresh", default_value = "30s", value_parser = parse_duration)]
+        refresh: u64,
+
+        /// Snapshot history kept in memory for Grafana, e.g. 24h
+        #[arg(long = "history", default_value = "24h", value_parser = parse_duration)]
+        history: u64,
+    },
+
+    /// Save nodes, guests, configs, storage and versions to a file
+    SnapshotState {
+        /// Destination file, `.gz` for gzip compression, `-` for stdout
+        #[arg(short = 'o', long = "output")]
+        output: String,
+    },
+
+    /// Log in once and run commands interactively
+    Shell,
+
+    /// Check the config file, connections, privileges and clocks
+    Doctor,
+
+    /// Probe for containers: exit 0 when the API answers to a fresh login,
+    /// 1 otherwise, silent without -v
+    Healthcheck {
+        /// Time allowed for the whole check, e.g. 10s
+        #[arg(long = "timeout", default_value = "10s", value_parser = parse_duration)]
+        timeout: u64,
+    },
+
+    /// Write a first config profile interactively and test it
+    Init,
+
+    /// The pvenom config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Generate documentation from the command line definitions
+    Docs {
+        #[command(subcommand)]
+        action: DocsAction,
+    },
+
+    /// JSON Schema of pvenom's JSON output, all of it or one structure
+    Schema {
+        /// Output structure, e.g. NodeListOutput
+        name: Option<String>,
+    },
+
+    /// Local labels of nodes and guests (owner, ticket, cost center),
+    /// `label` alone lists them all
+    Label {
+        /// `node` or `vm`
+        #[arg(requires = "target")]
+        kind: Option<labels::LabelKind>,
+
+        /// Node name, guest VMID or name
+        target: Option<String>,
+
+        /// KEY=VALUE to set, KEY- to remove, none to show the labels
+        labels: Vec<String>,
+    },
+
+    /// Compare the guests with an expected inventory (YAML, JSON or CSV),
+    /// failing on drift
+    Drift {
+        /// Inventory file, a `guests` list of name, vmid, node, type, cores,
+        /// memory_gb and disk_gb, each optional but name or vmid
+        #[arg(long = "expected")]
+        expected: String,
+
+        /// Ignore guests the inventory does not list
+        #[arg(long = "ignore-unmanaged")]
+        ignore_unmanaged: bool,
+    },
+
+    /// Power and snapshot schedules of the config file and their next run
+    Schedule {
+        #[command(subcommand)]
+        action: Option<ScheduleAction>,
+    },
+
+    /// Maintenance windows silencing the checks, `maintenance` alone lists them
+    Maintenance {
+        #[command(subcommand)]
+        action: Option<MaintenanceAction>,
+    },
+
+    /// Markdown disaster recovery runbook: which guests fail over where
+    DrPlan,
+
+    /// Diff the guests of two clusters, e.g. `compare --profile prod --profile dr`
+    Compare {
+        /// Primary and replica cluster profiles, in this order
+        #[arg(long = "profile", required = true)]
+        profile: Vec<String>,
+    },
+
+    /// Print captured JSON output or a state snapshot again, no cluster needed
+    Render {
+        /// JSON file, `.gz` compressed or `-` for stdin
+        #[arg(short = 'i', long = "input")]
+        input: String,
+
+        /// Keep rows where FIELD=VALUE or FIELD!=VALUE, dotted for nested fields
+        #[arg(long = "filter")]
+        filter: Vec<String>,
+
+        /// Columns of the tables, e.g. vmid,name,config.memory
+        #[arg(long = "columns", value_delimiter = ',')]
+        columns: Vec<String>,
+    },
+
+    /// GET any API path and print its data, e.g. `api /nodes/pve1/disks/list`
+    Api {
+        /// API path, with or without the /api2/json prefix
+        #[arg(default_value = "/")]
+        path: String,
+
+        /// List the child paths and their methods instead
+        #[arg(long = "ls")]
+        ls: bool,
+    },
+
+    /// Cluster-wide checks
+    Cluster {
+        #[command(subcommand)]
+        action: ClusterAction,
+    },
+
+    /// Ceph pools and OSDs
+    Ceph {
+        #[command(subcommand)]
+        action: CephAction,
+    },
+
+    /// High availability groups and resources
+    Ha {
+        #[command(subcommand)]
+        action: HaAction,
+    },
+
+    /// Bridges and VLANs across the nodes
+    Network {
+        #[command(subcommand)]
+        action: NetworkAction,
+    },
+
+    /// Cluster health in a few lines: nodes, guests and what needs a look
+    Summary {
+        /// Webhook payload for a chat instead: slack or discord
+        #[arg(long = "format", value_enum)]
+        style: Option<commands::SummaryStyle>,
+    },
+
+    /// Markdown report of the cluster: health, nodes, storage and guests
+    Report {
+        /// Send it to these addresses through the config [smtp] server
+        #[arg(long = "email", value_delimiter = ',')]
+        email: Vec<String>,
+    },
+
+    /// Scheduled replication and backup jobs
+    Jobs {
+        #[command(subcommand)]
+        action: JobsAction,
+    },
+
+    /// Configuration audits of the guests
+    Audit {
+        #[command(subcommand)]
+        action: AuditAction,
+    },
+
+    /// GPUs and mdev types per node with the guests claiming them,
+    /// flagging devices passed through twice
+    Gpus,
+
+    /// Probe the running guests from this machine, failing when one does
+    /// not answer
+    PingSweep {
+        /// TCP connect to this port instead of ICMP ping
+        #[arg(long = "port")]
+        port: Option<u16>,
+
+        /// Time allowed to each guest, e.g. 2s
+        #[arg(long = "timeout", default_value = "2s", value_parser = parse_duration)]
+        timeout: u64,
+    },
+
+    /// Addresses of the running guests, with free ranges of a network
+    Ipam {
+        /// Only addresses in this network, listing its free ranges too
+        #[arg(long = "cidr")]
+        cidr: Option<String>,
+    },
+
+    /// Container templates
+    Templates {
+        #[command(subcommand)]
+        action: TemplatesAction,
+    },
+
+    /// What runs inside the guests
+    Inventory {
+        #[command(subcommand)]
+        action: InventoryAction,
+    },
+
+    /// Backup housekeeping
+    Backups {
+        #[command(subcommand)]
+        action: BackupsAction,
+    },
+
+    /// List recent cluster tasks, newest first
+    Tasks {
+        #[command(subcommand)]
+        action: Option<TasksAction>,
+
+        /// Only tasks still running
+        #[arg(long = "running")]
+        running: bool,
+
+        /// Only tasks started by the logged in user
+        #[arg(long = "mine")]
+        mine: bool,
+
+        /// Maximum number of tasks
+        #[arg(long = "limit", default_value = "50")]
+        limit: usize,
+    },
+
+    /// Show or cancel a single task, `task <upid>` alone shows its log
+    #[command(subcommand_precedence_over_arg = true)]
+    Task {
+        /// Task UPID, as listed by `tasks`
+        upid: String,
+
+        #[command(subcommand)]
+        action: Option<TaskAction>,
+    },
+
+    /// Show or operate a single node, `node <name>` alone shows its details
+    #[command(subcommand_precedence_over_arg = true)]
+    Node {
+        /// Node name
+        name: Option<String>,
+
+        /// Fail when the node needs a reboot to load a newer kernel
+        #[arg(long = "exit-code")]
+        exit_code: bool,
+
+        /// Add current network in/out rates to the guests table
+        #[arg(long = "net")]
+        net: bool,
+
+        #[command(subcommand)]
+        action: Option<NodeAction>,
+    },
+
+    /// Storage capacity, volume lookups and evacuation
+    #[command(subcommand_precedence_over_arg = true)]
+    Storage {
+        /// Storage ID
+        storage: Option<String>,
+
+        #[command(subcommand)]
+        action: StorageAction,
+    },
+
+    /// Show or operate a single guest, `vm <vmid>` alone shows its details
+    #[command(subcommand_precedence_over_arg = true, visible_alias = "guest")]
+    Vm {
+        /// Guest VMID or name
+        guest: Option<String>,
+
+        /// Add filesystem usage inside the VM, from the guest agent
+        #[arg(long = "fs")]
+        fs: bool,
+
+        /// Highlight filesystems used above this percentage
+        #[arg(long = "fs-threshold", default_value_t = 90, value_parser = clap::value_parser!(u8).range(1..=100))]
+        fs_threshold: u8,
+
+        /// Check which of these TCP ports answer on the guest, e.g. 22,80,443
+        #[arg(long = "probe-ports", value_delimiter = ',')]
+        probe_ports: Vec<u16>,
+
+        #[command(subcommand)]
+        action: Option<VmAction>,
+    },
+}
+
+#[derive(Subcommand)]
+enum MaintenanceAction {
+    /// Open a window for a node, or for the guests with --tag
+    Start {
+        /// Node name
+        #[arg(required_unless_present = "tag")]
+        node: Option<String>,
+
+        /// Guests with this tag instead of a node
+        #[arg(long = "tag", conflicts_with = "node")]
+        tag: Option<String>,
+
+        /// Length of the window, e.g. 2h
+        #[arg(long = "for", default_value = "1h", value_parser = parse_duration)]
+        duration: u64,
+
+        /// Shown in the list of windows
+        #[arg(long = "reason")]
+        reason: Option<String>,
+    },
+
+    /// Close the ad hoc window of a node or tag early
+    Stop {
+        /// Node name
+        #[arg(required_unless_present = "tag")]
+        node: Option<String>,
+
+        /// Tag instead of a node
+        #[arg(long = "tag", conflicts_with = "node")]
+        tag: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ScheduleAction {
+    /// Carry out the schedules as they come due, until stopped
+    Run,
+}
+
+#[derive(Subcommand)]
+enum ExportTarget {
+    /// Terraform/OpenTofu import blocks and resource skeletons (bpg/proxmox)
+    Terraform,
+
+    /// NetBox virtualization JSON (clusters, VMs, interfaces, IPs)
+    Netbox {
+        /// Push directly to this NetBox instance instead of printing
+        #[arg(long = "netbox-url", requires = "netbox_token")]
+        netbox_url: Option<String>,
+
+        /// NetBox API token used with --netbox-url
+        #[arg(long = "netbox-token", env = "PVENOM_NETBOX_TOKEN", hide_env_values = true)]
+        netbox_token: Option<String>,
+    },
+
+    /// A/AAAA records of the running guests, BIND zone snippet or dnsmasq lines
+    Dns {
+        /// Zone the guest names live in, e.g. lab.example.com
+        #[arg(long = "zone")]
+        zone: String,
+
+        /// Output style: bind or dnsmasq
+        #[arg(long = "style", default_value = "bind", value_parser = ["bind", "dnsmasq"])]
+        style: String,
+    },
+
+    /// known_hosts lines with the SSH host keys of the running VMs
+    KnownHosts,
+
+    /// Zabbix low-level discovery JSON for nodes and guests
+    ZabbixLld {
+        /// Entities to discover: nodes, guests or all
+        #[arg(long = "kind", default_value = "all", value_parser = ["nodes", "guests", "all"])]
+        kind: String,
+    },
+
+    /// iCalendar feed of the backup jobs and maintenance windows
+    Ical {
+        /// Days ahead the feed covers
+        #[arg(long = "days", default_value_t = 30)]
+        days: u64,
+
+        /// Length of the backup events, e.g. 1h
+        #[arg(long = "duration", default_value = "1h", value_parser = parse_duration)]
+        duration: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum PublishTarget {
+    /// MQTT state topics with Home Assistant discovery
+    Mqtt {
+        /// MQTT broker as host or host:port (default port 1883)
+        #[arg(long = "broker")]
+        broker: String,
+
+        /// MQTT username
+        #[arg(long = "mqtt-username")]
+        mqtt_username: Option<String>,
+
+        /// MQTT password
+        #[arg(long = "mqtt-password", env = "PVENOM_MQTT_PASSWORD", hide_env_values = true)]
+        mqtt_password: Option<String>,
+
+        /// Prefix of the state topics
+        #[arg(long = "topic-prefix", default_value = "pvenom")]
+        topic_prefix: String,
+
+        /// Home Assistant discovery prefix
+        #[arg(long = "discovery-prefix", default_value = "homeassistant")]
+        discovery_prefix: String,
+
+        /// Do not publish Home Assistant discovery payloads
+        #[arg(long = "no-discovery")]
+        no_discovery: bool,
+
+        /// Keep running and publish every interval, e.g. 30s, 5m
+        #[arg(long = "interval", value_parser = parse_duration)]
+        interval: Option<u64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum NodeAction {
+    /// Migrate all running guests to other nodes before maintenance
+    Drain {
+        /// Destination node, or `auto` for the one with most free memory
+        #[arg(long = "target", default_value = "auto")]
+        target: String,
+
+        /// Also migrate VMs with disks on local storage
+        #[arg(long = "with-local-disks")]
+        with_local_disks: bool,
+
+        /// Time allowed for each migration, e.g. 30m
+        #[arg(long = "timeout", default_value = "30m", value_parser = parse_duration)]
+        timeout: u64,
+
+        /// Retry the unfinished guests of an earlier run's journal
+        #[arg(long = "resume", value_name = "JOURNAL")]
+        resume: Option<String>,
+    },
+
+    /// Guest disk throughput and IOPS, busiest first, and storage status
+    Io,
+
+    /// vCPU pinning, NUMA and hugepages of the VMs, with their conflicts
+    Numa,
+
+    /// Disk temperatures from SMART, warning above thresholds
+    Sensors {
+        /// Warn above this temperature, in °C
+        #[arg(long = "warn", default_value_t = 50)]
+        warn: u8,
+
+        /// Flag as critical above this temperature, in °C
+        #[arg(long = "critical", default_value_t = 60)]
+        critical: u8,
+    },
+
+    /// Migrate guests moved away by drain back to this node
+    RestorePlacement {
+        /// Also migrate VMs with disks on local storage
+        #[arg(long = "with-local-disks")]
+        with_local_disks: bool,
+
+        /// Time allowed for each migration, e.g. 30m
+        #[arg(long = "timeout", default_value = "30m", value_parser = parse_duration)]
+        timeout: u64,
+
+        /// Retry the unfinished guests of an earlier run's journal
+        #[arg(long = "resume", value_name = "JOURNAL")]
+        resume: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print a config file with every setting commented out at its default
+    PrintDefault,
+}
+
+#[derive(Subcommand)]
+enum DocsAction {
+    /// Man pages: pvenom(1) on stdout, or one page per command in a directory
+    Man {
+        /// Write every page into this directory instead
+        #[arg(long = "out-dir")]
+        out_dir: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ClusterAction {
+    /// Quorum, votes and QDevice of the cluster
+    Status,
+
+    /// Compare node CPU models and flags, list host CPU guests they block
+    CpuMatrix,
+
+    /// Corosync links of every node, flagging nodes with a single link
+    Links,
+}
+
+#[derive(Subcommand)]
+enum CephAction {
+    /// Pools with usage, replication and placement groups
+    Pools,
+
+    /// Object storage daemons
+    Osd {
+        #[command(subcommand)]
+        action: CephOsdAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum CephOsdAction {
+    /// OSDs slowest first, with commit/apply latency and fill level
+    Perf,
+}
+
+#[derive(Subcommand)]
+enum HaAction {
+    /// HA groups with their nodes by priority and the resources using them
+    Groups,
+}
+
+#[derive(Subcommand)]
+enum NetworkAction {
+    /// Bridges of every node, their VLAN settings and attached guest NICs
+    Bridges,
+}
+
+#[derive(Subcommand)]
+enum TemplatesAction {
+    /// Compare downloaded container templates with the appliance index
+    Audit,
+}
+
+#[derive(Subcommand)]
+enum AuditAction {
+    /// Hookscripts of the guests and whether their snippet exists
+    Hookscripts,
+
+    /// OVMF, EFI disks, secure boot and TPM of the VMs, Windows 11 readiness
+    Firmware,
+
+    /// QEMU machine versions of the VMs, flagging outdated pins
+    MachineTypes,
+
+    /// SPICE displays and USB redirection or passthrough of the VMs
+    SpiceUsb,
+}
+
+#[derive(Subcommand)]
+enum InventoryAction {
+    /// OS, version, kernel and hostname of the running VMs, from the guest agent
+    Os,
+}
+
+#[derive(Subcommand)]
+enum BackupsAction {
+    /// Backup jobs and the vzdump defaults of every node, for review and
+    /// diffs between clusters
+    Config,
+
+    /// Backup chain size and week-over-week growth per guest
+    Growth {
+        /// Only backups on this storage
+        #[arg(long = "storage")]
+        storage: Option<String>,
+    },
+
+    /// Start the verification of PBS backups and report their verification state
+    Verify {
+        /// Backup volume, e.g. pbs:backup/vm/100/2025-01-31T02:00:00Z
+        #[arg(required_unless_present = "all")]
+        volid: Option<String>,
+
+        /// Every backup of the PBS storages
+        #[arg(long = "all", conflicts_with = "volid")]
+        all: bool,
+
+        /// Only backups on this PBS storage
+        #[arg(long = "storage")]
+        storage: Option<String>,
+
+        /// Password of the PBS user configured on the storage
+        #[arg(long = "pbs-password", env = "PVENOM_PBS_PASSWORD", hide_env_values = true)]
+        pbs_password: Option<String>,
+    },
+
+    /// Restore a backup into a scratch guest, boot it and optionally destroy it
+    TestRestore {
+        /// Backup volume to restore
+        volid: String,
+
+        /// Scratch VMID, the next free one by default
+        #[arg(long = "as-vmid")]
+        as_vmid: Option<u32>,
+
+        /// Node to restore on, by default the first one with the backup storage
+        #[arg(long = "target-node")]
+        target_node: Option<String>,
+
+        /// Storage for the restored disks, by default the original ones
+        #[arg(long = "target-storage")]
+        target_storage: Option<String>,
+
+        /// Only restore, do not boot the guest
+        #[arg(long = "no-start")]
+        no_start: bool,
+
+        /// Destroy the scratch guest at the end
+        #[arg(long = "cleanup")]
+        cleanup: bool,
+
+        /// Time allowed to each step, e.g. 30m
+        #[arg(long = "timeout", default_value = "30m", value_parser = parse_duration)]
+        timeout: u64,
+    },
+
+    /// Show which backups a retention policy removes, remove them with --apply
+    PrunePreview {
+        /// Backup storage
+        #[arg(long = "storage")]
+        storage: String,
+
+        /// Only backups of this guest (VMID or name)
+        #[arg(long = "vmid")]
+        vmid: Option<String>,
+
+        #[arg(long = "keep-last")]
+        keep_last: Option<u32>,
+
+        #[arg(long = "keep-hourly")]
+        keep_hourly: Option<u32>,
+
+        #[arg(long = "keep-daily")]
+        keep_daily: Option<u32>,
+
+        #[arg(long = "keep-weekly")]
+        keep_weekly: Option<u32>,
+
+        #[arg(long = "keep-monthly")]
+        keep_monthly: Option<u32>,
+
+        #[arg(long = "keep-yearly")]
+        keep_yearly: Option<u32>,
+
+        /// Remove the backups marked for removal
+        #[arg(long = "apply")]
+        apply: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TaskAction {
+    /// Stop the running task
+    Cancel,
+}
+
+#[derive(Subcommand)]
+enum JobsAction {
+    /// Run again the jobs whose last run failed, one at a time
+    RetryFailed {
+        /// Only jobs of this type: replication or backup
+        #[arg(long = "type", value_enum)]
+        kind: Option<commands::JobKind>,
+
+        /// Time allowed to each retry, e.g. 30m or 2h
+        #[arg(long = "timeout", default_value = "2h", value_parser = parse_duration)]
+        timeout: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum TasksAction {
+    /// Failed tasks cluster-wide, grouped by error signature
+    Failures {
+        /// How far back to look, e.g. 24h or 7d
+        #[arg(long = "since", default_value = "24h", value_parser = parse_duration)]
+        since: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum VmAction {
+    /// Save the guest hardware profile to a TOML file
+    ExportConfig {
+        /// Destination TOML file
+        file: String,
+    },
+
+    /// Start the guest
+    Start,
+
+    /// Stop the guest immediately, like pulling the plug
+    Stop,
+
+    /// Ask the guest OS to shut down
+    Shutdown,
+
+    /// Ask the guest OS to reboot
+    Reboot,
+
+    /// Migrate the guest to another node, or only check that it can
+    Migrate {
+        /// Target node
+        #[arg(long = "target")]
+        target: String,
+
+        /// Only report what blocks the migration, do not start it
+        #[arg(long = "check")]
+        check: bool,
+
+        /// Copy disks on local storages too
+        #[arg(long = "with-local-disks")]
+        with_local_disks: bool,
+
+        /// Time allowed to the migration, e.g. 1h
+        #[arg(long = "timeout", default_value = "1h", value_parser = parse_duration)]
+        timeout: u64,
+    },
+
+    /// Start the guest, wait for its address and SSH, then hand over to a
+    /// local command such as an Ansible playbook
+    Provision {
+        /// Wait for an address from the guest agent
+        #[arg(long = "wait-ip")]
+        wait_ip: bool,
+
+        /// Also wait for the SSH port to accept connections
+        #[arg(long = "wait-ssh")]
+        wait_ssh: bool,
+
+        /// Port waited for by --wait-ssh
+        #[arg(long = "ssh-port", default_value_t = 22)]
+        ssh_port: u16,
+
+        /// Local command run once the address is known (and SSH is up with
+        /// --wait-ssh), `{ip}` replaced by the address
+        #[arg(long = "then")]
+        then: Option<String>,
+
+        /// Time allowed to the whole provisioning, e.g. 10m
+        #[arg(long = "timeout", default_value = "10m", value_parser = parse_duration)]
+        timeout: u64,
+    },
+
+    /// Guest disks
+    Disk {
+        #[command(subcommand)]
+        action: DiskAction,
+    },
+
+    /// Create a new guest from a TOML hardware profile
+    Create {
+        /// Source TOML file written by export-config
+        #[arg(long = "from-config")]
+        from_config: String,
+
+        /// Target node, defaults to the node stored in the profile
+        #[arg(long = "node")]
+        node: Option<String>,
+
+        /// VMID of the new guest, defaults to the next free one
+        #[arg(long = "vmid")]
+        vmid: Option<u32>,
+    },
+}
+
+#[derive(Subcommand)]
+enum StorageAction {
+    /// Provisioned guest disk sizes against the capacity of each storage,
+    /// flagging thin storages that overflow if the guests fill their disks
+    Overcommit,
+
+    /// Which guest and config key reference a volume, or whether it is
+    /// an orphan
+    Whois {
+        /// Volume ID, e.g. local-lvm:vm-100-disk-1
+        volid: String,
+    },
+
+    /// Move every guest disk to another storage, before decommissioning
+    Evacuate {
+        /// Destination storage
+        #[arg(long = "target")]
+        target: String,
+
+        /// Disks moved at the same time
+        #[arg(long = "parallel", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..=16))]
+        parallel: u16,
+
+        /// Remove the source volumes instead of keeping them as unused disks
+        #[arg(long = "delete-source")]
+        delete_source: bool,
+
+        /// Time allowed for each move, e.g. 30m or 2h
+        #[arg(long = "timeout", default_value = "1h", value_parser = parse_duration)]
+        timeout: u64,
+
+        /// Retry the unfinished disks of an earlier run's journal
+        #[arg(long = "resume", value_name = "JOURNAL")]
+        resume: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum DiskAction {
+    /// Move a disk to another storage, VMs can keep running
+    Move {
+        /// Disk config key, e.g. scsi0, virtio1, rootfs or mp0
+        disk: String,
+
+        /// Target storage
+        #[arg(long = "storage")]
+        storage: String,
+
+        /// Remove the source volume instead of keeping it as unused disk
+        #[arg(long = "delete-source")]
+        delete_source: bool,
+
+        /// Time allowed to the move, e.g. 30m or 2h
+        #[arg(long = "timeout", default_value = "1h", value_parser = parse_duration)]
+        timeout: u64,
+    },
 }
 
 /// Parse yes/no values for --secure flag
@@ -76,18 +1077,138 @@ fn parse_yes_no(s: &str) -> Result<bool, String> {
 }
 
 /// Parse format values for --format flag
-fn parse_format(s: &str) -> Result<models::OutputFormat, String> {
+fn parse_format(s: &str) -> Result<FormatArg, String> {
+    match s.to_lowercase().as_str() {
+        "json" => Ok(FormatArg::Output(models::OutputFormat::Json)),
+        "csv" => Ok(FormatArg::Output(models::OutputFormat::Csv)),
+        "table" => Ok(FormatArg::Output(models::OutputFormat::Table)),
+        "env" => Ok(FormatArg::Env),
+        _ => Err(format!("Invalid format '{}'. Expected 'json', 'csv', 'table', or 'env'", s)),
+    }
+}
+
+/// Value of --format: an output format of the commands, or `env`, the JSON
+/// output turned into shell variables by envfmt.rs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FormatArg {
+    Output(models::OutputFormat),
+    Env,
+}
+
+impl Cli {
+    /// Output format forced on the command line, `env` runs the commands
+    /// with JSON output
+    fn output_format(&self) -> Option<models::OutputFormat> {
+        match self.format? {
+            FormatArg::Output(format) => Some(format),
+            FormatArg::Env => Some(models::OutputFormat::Json),
+        }
+    }
+}
+
+/// Parse versions for --output-version flag
+fn parse_output_version(s: &str) -> Result<models::OutputVersion, String> {
+    match s {
+        "1" => Ok(models::OutputVersion::V1),
+        "2" => Ok(models::OutputVersion::V2),
+        _ => Err(format!("Invalid output version '{}'. Expected 1 or 2", s)),
+    }
+}
+
+/// Parse non-negative rates for --max-rps flag
+fn parse_max_rps(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(rps) if rps >= 0.0 && rps.is_finite() => Ok(rps),
+        _ => Err(format!("Invalid rate '{}'. Expected requests per second, 0 for no limit", s)),
+    }
+}
+
+/// Parse notations for --time-format flag
+fn parse_time_format(s: &str) -> Result<models::TimeFormat, String> {
     match s.to_lowercase().as_str() {
-        "json" => Ok(models::OutputFormat::Json),
-        "csv" => Ok(models::OutputFormat::Csv),
-        "table" => Ok(models::OutputFormat::Table),
-        _ => Err(format!("Invalid format '{}'. Expected 'json', 'csv', or 'table'", s)),
+        "relative" => Ok(models::TimeFormat::Relative),
+        "iso" => Ok(models::TimeFormat::Iso),
+        "unix" => Ok(models::TimeFormat::Unix),
+        _ => Err(format!("Invalid time format '{}'. Expected 'relative', 'iso', or 'unix'", s)),
     }
 }
 
+/// Parse durations like 90s, 30m, 24h, 30d or 2w into seconds
+fn parse_duration(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let value: u64 = value.parse()
+        .map_err(|_| format!("Invalid duration '{}'. Expected e.g. 30m, 24h, 30d", s))?;
+    let multiplier = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => return Err(format!("Invalid duration unit '{}'. Expected s, m, h, d or w", unit)),
+    };
+    value.checked_mul(multiplier).ok_or_else(|| format!("Duration '{}' is too long", s))
+}
+
+/// Connection settings of one cluster, from the command line and a profile
+struct Connection {
+    controller: String,
+    username: String,
+    password: Option<String>,
+    /// API token, used instead of username and password when given
+    token: Option<String>,
+    secure: bool,
+    /// Requests per second at most, 0 for no limit
+    max_rps: f64,
+}
+
+impl Connection {
+    /// Command line options win over profile values
+    fn resolve(cli: &Cli, profile: Option<&config::Profile>) -> Result<Self> {
+        let profile = profile.cloned().unwrap_or_default();
+        let controller = cli.controller.clone().or(profile.controller.clone())
+            .ok_or_else(|| anyhow!("No controller given, use --controller or a --profile"))?;
+        let token = cli.api_token.clone().or_else(|| profile.resolve_token());
+        let password = cli.password.clone().or_else(|| profile.resolve_password());
+        if token.is_none() && password.is_none() {
+            bail!("No password for {}, use --password, PVENOM_PASSWORD, --api-token or the profile", controller);
+        }
+
+        Ok(Self {
+            username: cli.username.clone().or(profile.username).unwrap_or_else(|| "root@pam".to_string()),
+            secure: cli.secure.or(profile.secure).unwrap_or(true),
+            controller,
+            password,
+            token,
+            max_rps: cli.max_rps,
+        })
+    }
+}
+
+/// Resolve the base URL and authenticate
+async fn connect(conn: &Connection) -> Result<ProxmoxClient> {
+    // Resolve base URL with auto-detection (hidden ugliness under Persian carpets!)
+    vlog_info!("Connecting to Proxmox cluster at {}...", conn.controller);
+    let base_url = resolve_base_url(conn).await
+        .map_err(|e| anyhow!("Connection failed: {}", e))?;
+
+    // Create Proxmox client and authenticate
+    vlog_info!("Authenticating to Proxmox API...");
+    let mut client = match (&conn.token, &conn.password) {
+        (Some(token), _) => ProxmoxClient::with_token(&base_url, token, conn.secure).await,
+        (None, Some(password)) => ProxmoxClient::new(&base_url, &conn.username, password, conn.secure).await,
+        (None, None) => Err(anyhow!("No password or API token")),
+    }.map_err(|e| anyhow!("Authentication failed: {}", e))?;
+    vlog_success!("Authentication successful!");
+    client.set_max_rps(conn.max_rps);
+    Ok(client)
+}
+
 /// Try to build a working base URL with protocol auto-detection
 /// Tries HTTPS first, falls back to HTTP if needed
-async fn resolve_base_url(controller: &str, username: &str, password: &str, secure: bool) -> Result<String> {
+async fn resolve_base_url(conn: &Connection) -> Result<String> {
+    let controller = &conn.controller;
     // If user already specified protocol, use it as-is
     if controller.starts_with("http://") || controller.starts_with("https://") {
         vlog_debug!("Protocol already specified in controller address: {}", controller);
@@ -98,7 +1219,7 @@ async fn resolve_base_url(controller: &str, username: &str, password: &str, secu
     let https_url = format!("https://{}", controller);
     vlog_info!("Attempting HTTPS connection to {}...", controller);
 
-    if try_connection(&https_url, username, password, secure).await.is_ok() {
+    if try_connection(&https_url, conn).await.is_ok() {
         vlog_success!("HTTPS connection established to {}", controller);
         return Ok(https_url);
     }
@@ -107,7 +1228,7 @@ async fn resolve_base_url(controller: &str, username: &str, password: &str, secu
     vlog_warn!("HTTPS connection failed, attempting HTTP fallback...");
     let http_url = format!("http://{}", controller);
 
-    if try_connection(&http_url, username, password, secure).await.is_ok() {
+    if try_connection(&http_url, conn).await.is_ok() {
         vlog_warn!("HTTP connection successful - consider using HTTPS in production!");
         return Ok(http_url);
     }
@@ -117,25 +1238,30 @@ async fn resolve_base_url(controller: &str, username: &str, password: &str, secu
 }
 
 /// Quick connection test to check if the endpoint is reachable
-async fn try_connection(base_url: &str, username: &str, password: &str, secure: bool) -> Result<()> {
+async fn try_connection(base_url: &str, conn: &Connection) -> Result<()> {
     vlog_debug!("Testing connection to {}", base_url);
 
     // Build a minimal reqwest client just for testing
     // When secure=true, verify certs; when secure=false, skip verification (danger!)
     let client = reqwest::Client::builder()
-        .danger_accept_invalid_certs(!secure)
+        .danger_accept_invalid_certs(!conn.secure)
         .timeout(std::time::Duration::from_secs(5))
         .build()?;
 
-    // Try to hit the ticket endpoint
-    let url = format!("{}/api2/json/access/ticket", base_url);
-    let response = client.post(&url)
-        .form(&[
-            ("username", username),
-            ("password", password),
-        ])
-        .send()
-        .await?;
+    // Try to hit the ticket endpoint, or the version one with a token
+    let response = match &conn.token {
+        Some(token) => client.get(format!("{}/api2/json/version", base_url))
+            .header("Authorization", format!("PVEAPIToken={}", token))
+            .send()
+            .await?,
+        None => client.post(format!("{}/api2/json/access/ticket", base_url))
+            .form(&[
+                ("username", conn.username.as_str()),
+                ("password", conn.password.as_deref().unwrap_or_default()),
+            ])
+            .send()
+            .await?,
+    };
 
     if response.status().is_success() {
         vlog_debug!("Connection test successful");
@@ -148,54 +1274,207 @@ async fn try_connection(base_url: &str, username: &str, password: &str, secure:
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // Fan-out merges the nodes listing only, other commands need one cluster
+    if (cli.clusters.is_some() || cli.profile.as_deref() == Some("all")) && (cli.command.is_some() || cli.node.is_some()) {
+        Cli::command()
+            .error(clap::error::ErrorKind::ArgumentConflict,
+                   "--profile all and --clusters only list nodes, pick a single --profile for other commands")
+            .exit();
+    }
+    // Key of the per-command `format` in the config file
+    let format_key = matches.subcommand_name()
+        .unwrap_or(if cli.node.is_some() { "guests" } else { "nodes" });
 
-    // Set log level based on verbose flag
+    // Set log level based on verbose flag, syslog gets info and up anyway
+    vlog::set_target(cli.log_target);
     if cli.verbose {
         vlog::set_level(vlog::LogLevel::Debug);
         vlog_debug!("Verbose logging enabled");
+    } else if cli.log_target == vlog::LogTarget::Syslog {
+        vlog::set_level(vlog::LogLevel::Info);
+    }
+    pager::set_enabled(!cli.no_pager);
+    if cli.decimal_comma && cli.csv_delimiter == Some(',') {
+        vlog_error!("--decimal-comma needs a CSV delimiter other than ','");
+        std::process::exit(1);
     }
-    vlog_debug!("--controller: {}", &cli.controller);
-    vlog_debug!("--username: {}", &cli.username);
-    vlog_debug!("--password: {}", &cli.password);
+    csv::set_delimiter(cli.csv_delimiter.unwrap_or(if cli.decimal_comma { ';' } else { ',' }));
+    csv::set_decimal_comma(cli.decimal_comma);
+    progress::set_mode(cli.progress);
+    httplog::set_mode(cli.debug_http, cli.debug_http_bodies);
+    vlog_debug!("--controller: {:?}", &cli.controller);
+    vlog_debug!("--username: {:?}", &cli.username);
+    vlog_debug!("--password: {}", if cli.password.is_some() { "<set>" } else { "<unset>" });
 
     vlog_info!("Proxmox VE Node Observability Monitor v{}", env!("CARGO_PKG_VERSION"));
 
-    // Resolve base URL with auto-detection (hidden ugliness under Persian carpets!)
-    vlog_info!("Connecting to Proxmox cluster at {}...", cli.controller);
-    let base_url = match resolve_base_url(&cli.controller, &cli.username, &cli.password, cli.secure).await {
-        Ok(url) => url,
+    if cli.format == Some(FormatArg::Env) {
+        if let Err(e) = envfmt::run() {
+            vlog_error!("{:#}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Init may create or repair the config, it is not loaded before
+    if let Some(Command::Init) = &cli.command {
+        if let Err(e) = init::run(&cli).await {
+            vlog_error!("{:#}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Doctor reports a broken config instead of stopping at it
+    if let Some(Command::Doctor) = &cli.command {
+        if let Err(e) = doctor::run(&cli).await {
+            vlog_error!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let config = match config::load(cli.config.as_deref()) {
+        Ok(c) => c,
         Err(e) => {
-            vlog_error!("Connection failed: {}", e);
+            vlog_error!("{:#}", e);
             std::process::exit(1);
         }
     };
 
-    // Create Proxmox client and authenticate
-    vlog_info!("Authenticating to Proxmox API...");
-    let client = match ProxmoxClient::new(&base_url, &cli.username, &cli.password, cli.secure).await {
-        Ok(c) => {
-            vlog_success!("Authentication successful!");
-            c
-        },
+    // Commands about pvenom itself, no cluster involved
+    let offline = match &cli.command {
+        Some(Command::Schema { name }) => Some(commands::print_schema(name.as_deref())),
+        Some(Command::Render { input, filter, columns }) => {
+            let options = commands::RenderOptions { input: input.clone(), filters: filter.clone(), columns: columns.clone() };
+            Some(commands::render(&options, cli.output_format().or(config.format_for("render")).unwrap_or(models::OutputFormat::Table)))
+        }
+        Some(Command::Docs { action: DocsAction::Man { out_dir } }) => Some(docs::man(out_dir.as_deref())),
+        Some(Command::Maintenance { action }) => {
+            let format = cli.output_format().or(config.format_for("maintenance")).unwrap_or(models::OutputFormat::Table);
+            Some(commands::maintenance(maintenance_change(action), &config.maintenance, format))
+        }
+        Some(Command::Config { action: ConfigAction::PrintDefault }) => {
+            print!("{}", config::DEFAULT_CONFIG);
+            Some(Ok(()))
+        }
+        _ => None,
+    };
+    if let Some(result) = offline {
+        if let Err(e) = result {
+            vlog_error!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Healthcheck { timeout }) = &cli.command {
+        let check = tokio::time::timeout(std::time::Duration::from_secs(*timeout), healthcheck(&cli, &config));
+        match check.await {
+            Ok(Ok(())) => return Ok(()),
+            Ok(Err(e)) => vlog_error!("Healthcheck failed: {}", e),
+            Err(_) => vlog_error!("Healthcheck timed out after {}s", timeout),
+        }
+        std::process::exit(1);
+    }
+
+    // Results through the hook of the command, unless a format is forced
+    if let Some(hook) = config.hooks.get(format_key).filter(|_| cli.format.is_none() && !cli.no_hooks) {
+        if let Err(e) = hooks::run(hook) {
+            vlog_error!("{:#}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Two clusters of their own, not the one of --profile
+    if let Some(Command::Compare { profile }) = &cli.command {
+        let result = run_compare(&cli, &config, profile).await;
+        httplog::print_summary();
+        if let Err(e) = result {
+            vlog_error!("Command execution failed: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Multi-cluster fan-out of the nodes listing
+    let fanout = match (&cli.clusters, cli.profile.as_deref()) {
+        (Some(names), _) => Some(names.clone()),
+        (None, Some("all")) => Some(config.profiles.keys().cloned().collect()),
+        _ => None,
+    };
+    if let Some(names) = fanout {
+        let result = run_fanout(&cli, &config, names).await;
+        httplog::print_summary();
+        if let Err(e) = result {
+            vlog_error!("Command execution failed: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let profile_name = cli.profile.clone().or(config.default_profile.clone());
+    let conn = match profile_name.as_deref().map(|name| config.profile(name)).transpose()
+        .and_then(|profile| Connection::resolve(&cli, profile)) {
+        Ok(c) => c,
+        Err(e) => {
+            vlog_error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut client = match connect(&conn).await {
+        Ok(c) => c,
         Err(e) => {
-            vlog_error!("Authentication failed: {}", e);
+            vlog_error!("{}", e);
             std::process::exit(1);
         }
     };
+    client.set_dry_run(cli.dry_run);
+    let profile = profile_name.as_deref().and_then(|name| config.profiles.get(name));
+    client.set_read_only(cli.read_only || profile.is_some_and(|p| p.read_only));
+    client.set_audit(audit::AuditLog::new(&config.audit));
 
     // Execute the requested command
-    let commands = commands::Commands::new(client, cli.format);
+    let mut commands = commands::Commands::new(client, cli.output_format().or(config.format_for(format_key)).unwrap_or(models::OutputFormat::Table));
+    commands.set_output_version(cli.output_version);
+    commands.set_wide(cli.wide);
+    commands.set_time_format(cli.time_format);
+    commands.set_label_filter(cli.label.clone());
+    commands.set_schedules(config.schedule.clone());
+    commands.set_maintenance(config.maintenance.clone());
+    commands.set_smtp(config.smtp.clone());
+    commands.set_confirm_policy(confirm::ConfirmPolicy {
+        confirm: config.confirm.clone()
+            .unwrap_or_else(|| confirm::DEFAULT_CONFIRM.iter().map(|o| o.to_string()).collect()),
+        production: profile.is_some_and(|p| p.production),
+        assume_yes: cli.yes,
+        override_production: cli.i_know_what_i_am_doing,
+    });
 
-    let result = if let Some(node_name) = cli.node {
-        // Inspect specific node and list its guests
-        vlog_info!("Executing: show info for node '{}' with guests", node_name);
-        commands.show_node_info(&node_name).await
+    let result = if let Some(Command::Shell) = cli.command {
+        vlog_debug!("Executing: interactive shell");
+        shell::run(&commands, conn.secure).await
+    } else if let Some(command) = cli.command {
+        run_command(&commands, command, conn.secure).await
+    } else if let Some(node_name) = cli.node {
+        // Inspect specific node and list its guests, `-n` alone picks one
+        match commands.node_or_pick(Some(node_name.as_str()).filter(|n| !n.is_empty())).await {
+            Ok(node_name) => {
+                vlog_info!("Executing: show info for node '{}' with guests", node_name);
+                commands.show_node_info(&node_name, false, cli.net).await
+            }
+            Err(e) => Err(e),
+        }
     } else {
         // Default behavior: list all nodes
         vlog_debug!("Executing: list all nodes");
-        commands.list_nodes().await
+        commands.list_nodes(cli.trends).await
     };
+    httplog::print_summary();
 
     // Handle command execution result
     if let Err(e) = result {
@@ -204,4 +1483,440 @@ async fn main() -> Result<()> {
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// The change a `maintenance` subcommand asks for, none to list
+fn maintenance_change(action: &Option<MaintenanceAction>) -> Option<commands::MaintenanceChange> {
+    let target = |node: &Option<String>, tag: &Option<String>| match (node, tag) {
+        (Some(node), _) => maintenance::Target::Node(node.clone()),
+        (None, tag) => maintenance::Target::Tag(tag.clone().unwrap_or_default()),
+    };
+    match action {
+        Some(MaintenanceAction::Start { node, tag, duration, reason }) => Some(commands::MaintenanceChange::Start {
+            target: target(node, tag),
+            duration: *duration,
+            reason: reason.clone(),
+        }),
+        Some(MaintenanceAction::Stop { node, tag }) => Some(commands::MaintenanceChange::Stop(target(node, tag))),
+        None => None,
+    }
+}
+
+/// Log in with the profile or command line settings and read the
+/// version, which proves the API answers and accepts the ticket
+async fn healthcheck(cli: &Cli, config: &config::Config) -> Result<()> {
+    let profile_name = cli.profile.clone().or(config.default_profile.clone());
+    let profile = profile_name.as_deref().map(|name| config.profile(name)).transpose()?;
+    let client = connect(&Connection::resolve(cli, profile)?).await?;
+    let version = client.get_version().await?;
+    vlog_success!("Proxmox VE {} answers as {}", version.version, client.username());
+    Ok(())
+}
+
+/// Connect to every profile concurrently and merge the nodes listing
+async fn run_fanout(cli: &Cli, config: &config::Config, names: Vec<String>) -> Result<()> {
+    if cli.controller.is_some() {
+        bail!("--controller cannot be combined with --profile all or --clusters");
+    }
+    if names.is_empty() {
+        bail!("No cluster profiles configured");
+    }
+
+    vlog_debug!("Executing: list nodes of clusters {}", names.join(", "));
+    let mut pending = Vec::new();
+    for name in names {
+        let conn = config.profile(&name).and_then(|profile| Connection::resolve(cli, Some(profile)));
+        pending.push((name, conn.map(|conn| tokio::spawn(async move { connect(&conn).await }))));
+    }
+
+    // A profile without usable settings counts as a failed cluster
+    let mut clusters = Vec::new();
+    for (name, handle) in pending {
+        let client = match handle {
+            Ok(handle) => handle.await?,
+            Err(e) => Err(e),
+        };
+        clusters.push((name, client));
+    }
+    let format = cli.output_format().or(config.format_for("nodes")).unwrap_or(models::OutputFormat::Table);
+    commands::list_nodes_fanout(clusters, format, cli.output_version, cli.time_format).await
+}
+
+/// Connect to a primary and a replica profile and compare their guests
+async fn run_compare(cli: &Cli, config: &config::Config, profiles: &[String]) -> Result<()> {
+    let [primary, replica] = profiles else {
+        bail!("compare needs two profiles, e.g. --profile prod --profile dr");
+    };
+    if cli.controller.is_some() {
+        bail!("--controller cannot be combined with compare, both sides come from profiles");
+    }
+
+    let format = cli.output_format().or(config.format_for("compare")).unwrap_or(models::OutputFormat::Table);
+    let mut sides = Vec::new();
+    for name in [primary, replica] {
+        let conn = Connection::resolve(cli, Some(config.profile(name)?))?;
+        let client = connect(&conn).await.map_err(|e| anyhow!("Cluster '{}': {}", name, e))?;
+        sides.push(commands::Commands::new(client, format));
+    }
+    sides[0].compare(&sides[1], primary, replica).await
+}
+
+/// Run one subcommand, shared by the command line and `pvenom shell`.
+/// `secure` is the certificate verification setting of the cluster
+/// connection, reused by clients of third-party services.
+async fn run_command(commands: &commands::Commands, command: Command, secure: bool) -> Result<()> {
+    match command {
+        Command::UptimeReport { last } => {
+            vlog_debug!("Executing: uptime report for the last {}s", last);
+            commands.uptime_report(last).await
+        }
+        Command::Rightsize { last, cpu_threshold, ram_threshold } => {
+            vlog_debug!("Executing: rightsize over the last {}s", last);
+            commands.rightsize(&commands::RightsizeOptions { last, cpu_threshold, ram_threshold }).await
+        }
+        Command::Export { target } => match target {
+            ExportTarget::Terraform => {
+                vlog_debug!("Executing: export terraform");
+                commands.export_terraform().await
+            }
+            ExportTarget::Netbox { netbox_url, netbox_token } => {
+                vlog_debug!("Executing: export netbox");
+                let push = match (netbox_url, netbox_token) {
+                    (Some(url), Some(token)) => Some(netbox::NetboxClient::new(&url, &token, secure)),
+                    _ => None,
+                };
+                match push.transpose() {
+                    Ok(push) => commands.export_netbox(push).await,
+                    Err(e) => Err(e),
+                }
+            }
+            ExportTarget::Dns { zone, style } => {
+                vlog_debug!("Executing: export dns ({})", style);
+                commands.export_dns(&zone, &style).await
+            }
+            ExportTarget::KnownHosts => {
+                vlog_debug!("Executing: export known-hosts");
+                commands.export_known_hosts().await
+            }
+            ExportTarget::ZabbixLld { kind } => {
+                vlog_debug!("Executing: export zabbix-lld ({})", kind);
+                commands.export_zabbix_lld(kind != "guests", kind != "nodes").await
+            }
+            ExportTarget::Ical { days, duration } => {
+                vlog_debug!("Executing: export ical for {} days", days);
+                commands.export_ical(days, duration).await
+            }
+        },
+        Command::Publish { target } => match target {
+            PublishTarget::Mqtt { broker, mqtt_username, mqtt_password, topic_prefix,
+                                  discovery_prefix, no_discovery, interval } => {
+                vlog_debug!("Executing: publish mqtt to {}", broker);
+                let options = commands::MqttOptions {
+                    broker,
+                    username: mqtt_username,
+                    password: mqtt_password,
+                    topic_prefix,
+                    discovery_prefix,
+                    discovery: !no_discovery,
+                    interval,
+                };
+                commands.publish_mqtt(&options).await
+            }
+        },
+        Command::Serve { listen, token, refresh, history } => {
+            vlog_debug!("Executing: serve on {}", listen);
+            let options = commands::ServeOptions { listen, token, refresh, history };
+            commands.serve(&options).await
+        }
+        Command::Cluster { action } => match action {
+            ClusterAction::Status => {
+                vlog_debug!("Executing: cluster status");
+                commands.cluster_status().await
+            }
+            ClusterAction::CpuMatrix => {
+                vlog_debug!("Executing: cluster cpu-matrix");
+                commands.cpu_matrix().await
+            }
+            ClusterAction::Links => {
+                vlog_debug!("Executing: cluster links");
+                commands.cluster_links().await
+            }
+        },
+        Command::Ceph { action } => match action {
+            CephAction::Pools => {
+                vlog_debug!("Executing: ceph pools");
+                commands.ceph_pools().await
+            }
+            CephAction::Osd { action: CephOsdAction::Perf } => {
+                vlog_debug!("Executing: ceph osd perf");
+                commands.ceph_osd_perf().await
+            }
+        },
+        Command::Ha { action } => match action {
+            HaAction::Groups => {
+                vlog_debug!("Executing: ha groups");
+                commands.ha_groups().await
+            }
+        },
+        Command::Network { action } => match action {
+            NetworkAction::Bridges => {
+                vlog_debug!("Executing: network bridges");
+                commands.list_bridges().await
+            }
+        },
+        Command::Schema { name } => {
+            vlog_debug!("Executing: schema");
+            commands::print_schema(name.as_deref())
+        }
+        Command::Render { input, filter, columns } => {
+            vlog_debug!("Executing: render {}", input);
+            commands::render(&commands::RenderOptions { input, filters: filter, columns }, commands.output_format())
+        }
+        Command::Api { path, ls } => {
+            vlog_debug!("Executing: api {}", path);
+            commands.api(&path, ls).await
+        }
+        Command::Ipam { cidr } => {
+            vlog_debug!("Executing: ipam");
+            commands.ipam(cidr.as_deref()).await
+        }
+        Command::Templates { action } => match action {
+            TemplatesAction::Audit => {
+                vlog_debug!("Executing: templates audit");
+                commands.audit_templates().await
+            }
+        },
+        Command::Inventory { action } => match action {
+            InventoryAction::Os => {
+                vlog_debug!("Executing: inventory os");
+                commands.inventory_os().await
+            }
+        },
+        Command::Backups { action } => match action {
+            BackupsAction::Config => {
+                vlog_debug!("Executing: backups config");
+                commands.backup_config().await
+            }
+            BackupsAction::Growth { storage } => {
+                vlog_debug!("Executing: backup growth");
+                commands.backup_growth(storage.as_deref()).await
+            }
+            BackupsAction::Verify { volid, all: _, storage, pbs_password } => {
+                vlog_debug!("Executing: verify backups");
+                commands.verify_backups(&commands::VerifyOptions { volid, storage, pbs_password, secure }).await
+            }
+            BackupsAction::TestRestore { volid, as_vmid, target_node, target_storage, no_start, cleanup, timeout } => {
+                vlog_debug!("Executing: test restore of {}", volid);
+                commands.test_restore(&commands::TestRestoreOptions {
+                    volid,
+                    vmid: as_vmid,
+                    node: target_node,
+                    storage: target_storage,
+                    start: !no_start,
+                    cleanup,
+                    timeout,
+                }).await
+            }
+            BackupsAction::PrunePreview { storage, vmid, keep_last, keep_hourly, keep_daily, keep_weekly, keep_monthly, keep_yearly, apply } => {
+                vlog_debug!("Executing: prune preview of storage '{}'", storage);
+                let vmid = match vmid {
+                    Some(guest) => Some(commands.guest_or_pick(Some(&guest)).await?),
+                    None => None,
+                };
+                let keep = [("keep-last", keep_last), ("keep-hourly", keep_hourly), ("keep-daily", keep_daily),
+                            ("keep-weekly", keep_weekly), ("keep-monthly", keep_monthly), ("keep-yearly", keep_yearly)]
+                    .into_iter()
+                    .filter_map(|(option, value)| value.map(|v| (option, v)))
+                    .collect();
+                commands.prune_preview(&commands::PruneOptions { storage, vmid, keep, apply }).await
+            }
+        },
+        Command::Tasks { action: None, running, mine, limit } => {
+            vlog_debug!("Executing: list tasks");
+            commands.list_tasks(running, mine, limit).await
+        }
+        Command::Tasks { action: Some(TasksAction::Failures { since }), .. } => {
+            vlog_debug!("Executing: task failures");
+            commands.list_task_failures(since).await
+        }
+        Command::Task { upid, action: None } => {
+            vlog_debug!("Executing: show task {}", upid);
+            commands.show_task(&upid).await
+        }
+        Command::Task { upid, action: Some(TaskAction::Cancel) } => {
+            vlog_debug!("Executing: cancel task {}", upid);
+            commands.cancel_task(&upid).await
+        }
+        Command::Label { kind, target, labels } => {
+            vlog_debug!("Executing: label");
+            commands.label(kind.zip(target), &labels).await
+        }
+        Command::Drift { expected, ignore_unmanaged } => {
+            vlog_debug!("Executing: drift against {}", expected);
+            commands.drift(&expected, ignore_unmanaged).await
+        }
+        Command::Summary { style } => {
+            vlog_debug!("Executing: summary");
+            commands.summary(style).await
+        }
+        Command::Report { email } => {
+            vlog_debug!("Executing: report");
+            commands.report(&email).await
+        }
+        Command::Jobs { action: JobsAction::RetryFailed { kind, timeout } } => {
+            vlog_debug!("Executing: jobs retry-failed");
+            commands.retry_failed_jobs(kind, timeout).await
+        }
+        Command::Audit { action } => match action {
+            AuditAction::Hookscripts => {
+                vlog_debug!("Executing: audit hookscripts");
+                commands.audit_hookscripts().await
+            }
+            AuditAction::Firmware => {
+                vlog_debug!("Executing: audit firmware");
+                commands.audit_firmware().await
+            }
+            AuditAction::MachineTypes => {
+                vlog_debug!("Executing: audit machine-types");
+                commands.audit_machine_types().await
+            }
+            AuditAction::SpiceUsb => {
+                vlog_debug!("Executing: audit spice-usb");
+                commands.audit_spice_usb().await
+            }
+        },
+        Command::Gpus => {
+            vlog_debug!("Executing: gpus");
+            commands.gpus().await
+        }
+        Command::PingSweep { port, timeout } => {
+            vlog_debug!("Executing: ping-sweep");
+            commands.ping_sweep(port, timeout).await
+        }
+        Command::Schedule { action: None } => {
+            vlog_debug!("Executing: list schedules");
+            commands.schedule_list().await
+        }
+        Command::Schedule { action: Some(ScheduleAction::Run) } => {
+            vlog_debug!("Executing: run schedules");
+            commands.schedule_run().await
+        }
+        Command::Maintenance { action } => {
+            vlog_debug!("Executing: maintenance");
+            commands::maintenance(maintenance_change(&action), commands.maintenance_entries(), commands.output_format())
+        }
+        Command::DrPlan => {
+            vlog_debug!("Executing: dr-plan");
+            commands.dr_plan().await
+        }
+        Command::SnapshotState { output } => {
+            vlog_debug!("Executing: snapshot state to {}", output);
+            commands.snapshot_state(&output).await
+        }
+        Command::Node { name, exit_code, net, action } => match commands.node_or_pick(name.as_deref()).await {
+            Ok(name) => match action {
+                None => {
+                    vlog_debug!("Executing: show info for node '{}' with guests", name);
+                    commands.show_node_info(&name, exit_code, net).await
+                }
+                Some(NodeAction::Drain { target, with_local_disks, timeout, resume }) => {
+                    vlog_debug!("Executing: drain node '{}' to {}", name, target);
+                    let options = commands::DrainOptions { target, with_local_disks, timeout, resume };
+                    commands.drain_node(&name, &options).await
+                }
+                Some(NodeAction::Io) => {
+                    vlog_debug!("Executing: I/O pressure of node '{}'", name);
+                    commands.show_node_io(&name).await
+                }
+                Some(NodeAction::Numa) => {
+                    vlog_debug!("Executing: NUMA of node '{}'", name);
+                    commands.show_node_numa(&name).await
+                }
+                Some(NodeAction::Sensors { warn, critical }) => {
+                    vlog_debug!("Executing: sensors of node '{}'", name);
+                    commands.show_node_sensors(&name, warn, critical).await
+                }
+                Some(NodeAction::RestorePlacement { with_local_disks, timeout, resume }) => {
+                    vlog_debug!("Executing: restore placement of node '{}'", name);
+                    commands.restore_placement(&name, with_local_disks, timeout, resume.as_deref()).await
+                }
+            },
+            Err(e) => Err(e),
+        },
+        Command::Vm { guest, fs, fs_threshold, probe_ports, action } => match (guest, action) {
+            (guest, None) => match commands.guest_or_pick(guest.as_deref()).await {
+                Ok(vmid) => {
+                    vlog_debug!("Executing: show guest {}", vmid);
+                    commands.show_guest(vmid, fs.then_some(fs_threshold), &probe_ports).await
+                }
+                Err(e) => Err(e),
+            },
+            (guest, Some(VmAction::ExportConfig { file })) => match commands.guest_or_pick(guest.as_deref()).await {
+                Ok(vmid) => {
+                    vlog_debug!("Executing: export config of guest {} to {}", vmid, file);
+                    commands.export_guest_config(vmid, &file).await
+                }
+                Err(e) => Err(e),
+            },
+            (guest, Some(VmAction::Migrate { target, check, with_local_disks, timeout })) => {
+                let vmid = commands.guest_or_pick(guest.as_deref()).await?;
+                vlog_debug!("Executing: migrate guest {} to '{}'", vmid, target);
+                commands.migrate_guest(vmid, &commands::MigrateOptions { target, check, with_local_disks, timeout }).await
+            }
+            (guest, Some(VmAction::Provision { wait_ip, wait_ssh, ssh_port, then, timeout })) => {
+                let vmid = commands.guest_or_pick(guest.as_deref()).await?;
+                vlog_debug!("Executing: provision guest {}", vmid);
+                let options = commands::ProvisionOptions { wait_ip, wait_ssh, ssh_port, then, timeout };
+                commands.provision_guest(vmid, &options).await
+            }
+            (guest, Some(VmAction::Disk { action: DiskAction::Move { disk, storage, delete_source, timeout } })) => {
+                let vmid = commands.guest_or_pick(guest.as_deref()).await?;
+                vlog_debug!("Executing: move disk {} of guest {} to '{}'", disk, vmid, storage);
+                commands.move_guest_disk(vmid, &commands::DiskMoveOptions { disk, storage, delete_source, timeout }).await
+            }
+            (None, Some(VmAction::Create { from_config, node, vmid })) => {
+                vlog_debug!("Executing: create guest from {}", from_config);
+                commands.create_guest_from_config(&from_config, node.as_deref(), vmid).await
+            }
+            (guest, Some(VmAction::Start)) => guest_power(commands, guest.as_deref(), "start").await,
+            (guest, Some(VmAction::Stop)) => guest_power(commands, guest.as_deref(), "stop").await,
+            (guest, Some(VmAction::Shutdown)) => guest_power(commands, guest.as_deref(), "shutdown").await,
+            (guest, Some(VmAction::Reboot)) => guest_power(commands, guest.as_deref(), "reboot").await,
+            (Some(_), _) => Err(anyhow::anyhow!("This action does not take a guest: pvenom vm create ...")),
+        },
+        Command::Storage { storage, action } => match (storage, action) {
+            (Some(storage), StorageAction::Evacuate { target, parallel, delete_source, timeout, resume }) => {
+                vlog_debug!("Executing: evacuate storage '{}' to '{}'", storage, target);
+                let options = commands::EvacuateOptions { target, parallel: parallel as usize, delete_source, timeout, resume };
+                commands.evacuate_storage(&storage, &options).await
+            }
+            (None, StorageAction::Evacuate { .. }) => bail!("Name the storage: pvenom storage <id> evacuate --target <storage>"),
+            (None, StorageAction::Whois { volid }) => {
+                vlog_debug!("Executing: storage whois {}", volid);
+                commands.storage_whois(&volid).await
+            }
+            (Some(_), StorageAction::Whois { .. }) => bail!("whois takes no storage: pvenom storage whois <volid>"),
+            (None, StorageAction::Overcommit) => {
+                vlog_debug!("Executing: storage overcommit");
+                commands.storage_overcommit().await
+            }
+            (Some(_), StorageAction::Overcommit) => bail!("overcommit covers every storage: pvenom storage overcommit"),
+        },
+        Command::Shell => bail!("Already in the pvenom shell"),
+        Command::Init => bail!("Run `pvenom init` outside the shell"),
+        Command::Compare { .. } => bail!("Run `pvenom compare` outside the shell, it connects to both clusters"),
+        Command::Healthcheck { .. } => bail!("Run `pvenom healthcheck` outside the shell, it logs in anew"),
+        Command::Docs { action: DocsAction::Man { out_dir } } => docs::man(out_dir.as_deref()),
+        Command::Config { action: ConfigAction::PrintDefault } => {
+            print!("{}", config::DEFAULT_CONFIG);
+            Ok(())
+        }
+        Command::Doctor => bail!("Run `pvenom doctor` outside the shell, it checks every profile"),
+    }
+}
+
+async fn guest_power(commands: &commands::Commands, guest: Option<&str>, action: &str) -> Result<()> {
+    let vmid = commands.guest_or_pick(guest).await?;
+    vlog_debug!("Executing: {} guest {}", action, vmid);
+    commands.guest_power(vmid, action).await
+}
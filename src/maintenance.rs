@@ -0,0 +1,171 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # maintenance.rs
+//!
+//! Maintenance windows of nodes and tagged guests. The checks failing on
+//! problems, `ping-sweep` and `node <name> --exit-code`, report what falls
+//! in a window as maintenance and do not fail for it, so planned work
+//! pages nobody.
+//!
+//! Recurring windows come from the config file:
+//!
+//! [[maintenance]]
+//! node = "pve2"
+//! cron = "0 2 * * 0"
+//! duration = "3h"
+//!
+//! with `tag` instead of `node` for guests, and `utc_offset` as for
+//! schedules. Ad hoc ones from `pvenom maintenance start pve2 --for 2h`
+//! are kept in `$XDG_STATE_HOME/pvenom/maintenance.json` until they end.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::config;
+use crate::schedule::Schedule;
+use crate::vlog_debug;
+
+/// `[[maintenance]]` entry of the config file
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MaintenanceEntry {
+    #[serde(default)]
+    pub node: Option<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    pub cron: String,
+    /// Length of each window, e.g. `3h`
+    pub duration: String,
+    #[serde(default)]
+    pub utc_offset: Option<String>,
+}
+
+/// Window started by `maintenance start`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdHocWindow {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    pub until: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// What a window applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    Node(String),
+    Tag(String),
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Target::Node(node) => write!(f, "node {}", node),
+            Target::Tag(tag) => write!(f, "tag {}", tag),
+        }
+    }
+}
+
+/// Every window, configured and ad hoc, checked once
+pub struct Windows {
+    recurring: Vec<(Target, Schedule, u64)>,
+    ad_hoc: Vec<AdHocWindow>,
+}
+
+impl Windows {
+    pub fn load(entries: &[MaintenanceEntry]) -> Result<Self> {
+        let mut recurring = Vec::new();
+        for entry in entries {
+            let target = match (&entry.node, &entry.tag) {
+                (Some(node), None) => Target::Node(node.clone()),
+                (None, Some(tag)) => Target::Tag(tag.clone()),
+                _ => bail!("Maintenance '{}' needs either node or tag", entry.cron),
+            };
+            let schedule = Schedule::parse(&entry.cron, entry.utc_offset.as_deref())
+                .with_context(|| format!("Maintenance of {}", target))?;
+            let duration = crate::parse_duration(&entry.duration).map_err(anyhow::Error::msg)
+                .with_context(|| format!("Maintenance of {}", target))?;
+            recurring.push((target, schedule, duration));
+        }
+        Ok(Self { recurring, ad_hoc: load_ad_hoc()? })
+    }
+
+    /// End of the window covering the node or one of the `;` separated
+    /// tags at `now`, if any
+    pub fn covering(&self, node: Option<&str>, tags: Option<&str>, now: u64) -> Option<u64> {
+        let applies = |target: &Target| match target {
+            Target::Node(n) => node == Some(n.as_str()),
+            Target::Tag(t) => tags.is_some_and(|tags| tags.split(';').any(|tag| tag == t)),
+        };
+        let ad_hoc = self.ad_hoc.iter()
+            .filter(|w| w.until > now)
+            .filter(|w| w.node.clone().map(Target::Node).or(w.tag.clone().map(Target::Tag)).is_some_and(|t| applies(&t)))
+            .map(|w| w.until);
+        let recurring = self.recurring.iter()
+            .filter(|(target, _, _)| applies(target))
+            .filter_map(|(_, schedule, duration)| {
+                // Latest start within the duration before now
+                let minute = now - now % 60;
+                (0..=duration / 60).map(|i| minute.saturating_sub(i * 60))
+                    .find(|start| schedule.is_due(*start))
+                    .map(|start| start + duration)
+                    .filter(|end| *end > now)
+            });
+        ad_hoc.chain(recurring).max()
+    }
+
+    pub fn ad_hoc(&self) -> &[AdHocWindow] {
+        &self.ad_hoc
+    }
+
+    pub fn recurring(&self) -> impl Iterator<Item = (&Target, &Schedule, u64)> {
+        self.recurring.iter().map(|(target, schedule, duration)| (target, schedule, *duration))
+    }
+}
+
+fn ad_hoc_path() -> Result<PathBuf> {
+    let dir = config::state_dir().context("Cannot locate the state directory, HOME is not set")?;
+    Ok(dir.join("maintenance.json"))
+}
+
+pub fn load_ad_hoc() -> Result<Vec<AdHocWindow>> {
+    let path = ad_hoc_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid maintenance file {}", path.display()))
+}
+
+/// Write the ad hoc windows, dropping the ended ones
+pub fn save_ad_hoc(windows: &[AdHocWindow], now: u64) -> Result<()> {
+    let path = ad_hoc_path()?;
+    let open: Vec<&AdHocWindow> = windows.iter().filter(|w| w.until > now).collect();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    std::fs::write(&path, serde_json::to_vec_pretty(&open)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    vlog_debug!("Maintenance windows saved to {}", path.display());
+    Ok(())
+}
@@ -20,21 +20,43 @@
 //!
 //! Models uses throughout the project.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use crate::labels::Labels;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum OutputFormat {
     Json,
     Csv,
     Table,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+/// JSON output contract, `--output-version`: version 2 has raw numbers
+/// (bytes, seconds, fractions) where version 1 has preformatted strings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputVersion {
+    #[default]
+    V1,
+    V2,
+}
+
+/// Notation of uptimes and timestamps, `--time-format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeFormat {
+    Relative,
+    Iso,
+    Unix,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
 pub struct ProxmoxResponse<T> {
     pub data: T,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
 pub struct AuthTicket {
     pub ticket: String,
     #[serde(rename = "CSRFPreventionToken")]
@@ -42,7 +64,7 @@ pub struct AuthTicket {
     pub username: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
 pub struct Node {
     pub node: String,
     pub status: String,
@@ -64,7 +86,7 @@ pub struct Node {
     pub uptime: Option<u64>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
 pub struct VM {
     pub vmid: u32,
     pub name: String,
@@ -82,7 +104,8 @@ pub struct VM {
     pub uptime: Option<u64>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+#[allow(clippy::upper_case_acronyms)]
 pub struct LXC {
     pub vmid: u32,
     pub name: String,
@@ -101,6 +124,7 @@ pub struct LXC {
 }
 
 #[derive(Debug, Clone)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Guest {
     VM(VM),
     LXC(LXC),
@@ -136,51 +160,1737 @@ impl Guest {
     }
 }
 
-// ============================================================================
-// Custom JSON output structures (for --format json)
-// ============================================================================
+/// Entry of `/cluster/resources`, covering nodes, guests and storages
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct ClusterResource {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub resource_type: String,
+    #[serde(default)]
+    pub node: Option<String>,
+    #[serde(default)]
+    pub vmid: Option<u32>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub storage: Option<String>,
+    #[serde(default)]
+    pub template: Option<u8>,
+    #[serde(default)]
+    pub plugintype: Option<String>,
+    #[serde(default)]
+    pub shared: Option<u8>,
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub cpu: Option<f64>,
+    #[serde(default)]
+    pub maxcpu: Option<f64>,
+    #[serde(default)]
+    pub mem: Option<u64>,
+    #[serde(default)]
+    pub maxmem: Option<u64>,
+    #[serde(default)]
+    pub disk: Option<u64>,
+    #[serde(default)]
+    pub maxdisk: Option<u64>,
+    #[serde(default)]
+    pub uptime: Option<u64>,
+    /// HA manager state of guests under HA, e.g. `started`
+    #[serde(default)]
+    pub hastate: Option<String>,
+    /// Guest tags separated by `;`
+    #[serde(default)]
+    pub tags: Option<String>,
+    #[serde(default)]
+    pub pool: Option<String>,
+}
 
-/// JSON output structure for listing all nodes
-#[derive(Debug, Serialize)]
-pub struct NodeListOutput {
-    pub root_controller: String,
-    pub proxmox_version: String,
-    pub nodes: Vec<NodeJsonInfo>,
+impl ClusterResource {
+    /// True for QEMU and LXC guests, templates excluded
+    pub fn is_guest(&self) -> bool {
+        (self.resource_type == "qemu" || self.resource_type == "lxc")
+            && self.template.unwrap_or(0) == 0
+    }
+
+    pub fn guest_type(&self) -> &str {
+        match self.resource_type.as_str() {
+            "qemu" => "VM",
+            "lxc" => "LXC",
+            other => other,
+        }
+    }
 }
 
-/// Node information in JSON format
-#[derive(Debug, Serialize)]
-pub struct NodeJsonInfo {
+/// Entry of `/cluster/status`, either the cluster itself or one of its nodes
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct ClusterStatusEntry {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub entry_type: String,
     pub name: String,
-    pub cpu: String,
-    pub memory_gb: String,
-    pub storage_gb: String,
-    pub ipv4: String,
+    #[serde(default)]
+    pub ip: Option<String>,
+    #[serde(default)]
+    pub nodeid: Option<u32>,
+    #[serde(default)]
+    pub local: Option<u8>,
+    #[serde(default)]
+    pub online: Option<u8>,
+    #[serde(default)]
+    pub quorate: Option<u8>,
+    #[serde(default)]
+    pub nodes: Option<u32>,
+    #[serde(default)]
+    pub version: Option<u32>,
+}
+
+/// Child path of `api <path> --ls`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ApiChild {
+    pub path: String,
+    /// HTTP methods of the path, GET, POST, PUT, DELETE
+    pub methods: Vec<String>,
+    pub description: Option<String>,
+}
+
+/// Ceph pool (`/nodes/{node}/ceph/pool`)
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct CephPool {
+    pub pool_name: String,
+    /// `replicated` or `erasure`
+    #[serde(rename = "type", default)]
+    pub pool_type: Option<String>,
+    #[serde(default)]
+    pub size: Option<u32>,
+    #[serde(default)]
+    pub min_size: Option<u32>,
+    #[serde(default)]
+    pub pg_num: Option<u32>,
+    #[serde(default)]
+    pub pg_autoscale_mode: Option<String>,
+    #[serde(default)]
+    pub bytes_used: Option<u64>,
+    /// Share of the pool capacity used, 0.0 to 1.0
+    #[serde(default)]
+    pub percent_used: Option<f64>,
+    #[serde(default)]
+    pub crush_rule_name: Option<String>,
+}
+
+/// Row of `ceph osd perf`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CephOsd {
+    pub id: i64,
+    pub name: String,
+    pub host: Option<String>,
+    pub device_class: Option<String>,
+    /// `up` or `down`
     pub status: String,
+    pub in_cluster: bool,
+    pub commit_latency_ms: Option<u64>,
+    pub apply_latency_ms: Option<u64>,
+    pub percent_used: Option<f64>,
 }
 
-/// JSON output structure for inspecting a single node with guests
-#[derive(Debug, Serialize)]
-pub struct NodeDetailOutput {
+/// Output of `cluster status`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ClusterStatusOutput {
     pub name: String,
-    pub cpu: String,
-    pub memory_gb: String,
-    pub storage_gb: String,
-    pub ipv4: String,
+    pub quorate: bool,
+    pub nodes: usize,
+    pub online: usize,
+    /// Votes of all nodes plus the QDevice one
+    pub expected_votes: u32,
+    pub qdevice: Option<QDeviceStatus>,
+    pub warnings: Vec<String>,
+}
+
+/// QDevice as reported by `/cluster/config/qdevice`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct QDeviceStatus {
+    pub state: Option<String>,
+    /// e.g. `ACK`, `NACK`, `No change (ACK)`
+    pub vote: Option<String>,
+    /// QNetd server
+    pub host: Option<String>,
+}
+
+/// Row of `cluster links`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CorosyncNode {
+    pub node: String,
+    pub nodeid: Option<u32>,
+    pub online: bool,
+    pub links: Vec<CorosyncLink>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CorosyncLink {
+    pub link: u8,
+    pub address: String,
+}
+
+/// HA group (`/cluster/ha/groups`)
+#[derive(Debug, Deserialize)]
+pub struct HaGroup {
+    pub group: String,
+    /// Member nodes with optional priority, e.g. `pve1:2,pve2:1,pve3`
+    #[serde(default)]
+    pub nodes: String,
+    #[serde(default)]
+    pub restricted: Option<u8>,
+    #[serde(default)]
+    pub nofailback: Option<u8>,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// Storage replication job (`/cluster/replication`)
+#[derive(Debug, Deserialize)]
+pub struct ReplicationJob {
+    /// `<vmid>-<n>`
+    pub id: String,
+    pub guest: u32,
+    pub target: String,
+    /// Calendar event, PVE runs `*/15` when unset
+    #[serde(default)]
+    pub schedule: Option<String>,
+    #[serde(default)]
+    pub disable: Option<u8>,
+}
+
+/// Scheduled backup job (`/cluster/backup`)
+#[derive(Debug, Deserialize)]
+pub struct BackupJob {
+    pub id: String,
+    /// Calendar event, e.g. `sun 01:00`
+    #[serde(default)]
+    pub schedule: Option<String>,
+    /// PVE treats a missing flag as enabled
+    #[serde(default)]
+    pub enabled: Option<u8>,
+    #[serde(default)]
+    pub storage: Option<String>,
+    /// Node the job is limited to
+    #[serde(default)]
+    pub node: Option<String>,
+    /// Comma separated VMIDs, excluded ones with `all`
+    #[serde(default)]
+    pub vmid: Option<String>,
+    #[serde(default)]
+    pub all: Option<u8>,
+    /// Comma separated VMIDs skipped by `all`
+    #[serde(default)]
+    pub exclude: Option<String>,
+    #[serde(default)]
+    pub pool: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// Last run of a replication job (`/nodes/{node}/replication`)
+#[derive(Debug, Deserialize)]
+pub struct ReplicationState {
+    pub id: String,
+    #[serde(default)]
+    pub last_sync: Option<u64>,
+    #[serde(default)]
+    pub fail_count: Option<u32>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// HA resource (`/cluster/ha/resources`)
+#[derive(Debug, Deserialize)]
+pub struct HaResource {
+    /// e.g. `vm:100`, `ct:101`
+    pub sid: String,
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+/// Row of `ha groups`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct HaGroupOutput {
+    pub group: String,
+    /// Highest priority first
+    pub nodes: Vec<HaGroupMember>,
+    pub restricted: bool,
+    pub nofailback: bool,
+    /// SIDs of the resources bound to the group
+    pub resources: Vec<String>,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct HaGroupMember {
+    pub node: String,
+    pub priority: u32,
+}
+
+/// Network interface seen from inside a guest (agent or LXC runtime)
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct GuestInterface {
+    pub name: String,
+    #[serde(default)]
+    pub hwaddr: Option<String>,
+    /// Addresses in CIDR notation, e.g. 192.168.1.10/24
+    #[serde(default)]
+    pub addresses: Vec<String>,
+}
+
+/// Proxmox VE version (`/version`, `/nodes/{node}/version`)
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct PveVersion {
+    pub version: String,
+    #[serde(default)]
+    pub release: Option<String>,
+    #[serde(default)]
+    pub repoid: Option<String>,
+}
+
+/// Runtime state of a guest (`/nodes/{node}/{type}/{vmid}/status/current`)
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct GuestStatus {
     pub status: String,
-    pub is_root_controller: String,
-    pub guests: Vec<GuestJsonInfo>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub qmpstatus: Option<String>,
+    #[serde(default)]
+    pub uptime: Option<u64>,
+    #[serde(default)]
+    pub cpu: Option<f64>,
+    #[serde(default)]
+    pub cpus: Option<f64>,
+    #[serde(default)]
+    pub mem: Option<u64>,
+    #[serde(default)]
+    pub maxmem: Option<u64>,
+    #[serde(default)]
+    pub disk: Option<u64>,
+    #[serde(default)]
+    pub maxdisk: Option<u64>,
+    #[serde(default)]
+    pub netin: Option<u64>,
+    #[serde(default)]
+    pub netout: Option<u64>,
+    #[serde(default)]
+    pub lock: Option<String>,
+    #[serde(default)]
+    pub tags: Option<String>,
+    /// HA manager state, `{"managed": 0}` when not under HA
+    #[serde(default)]
+    pub ha: Option<serde_json::Value>,
+    /// 1 when the QEMU guest agent is enabled in the config
+    #[serde(default)]
+    pub agent: Option<u8>,
+    /// Memory the balloon driver targets, VMs with ballooning only
+    #[serde(default)]
+    pub balloon: Option<u64>,
+    #[serde(default)]
+    pub ballooninfo: Option<BalloonInfo>,
+    /// Share of time some or all tasks stalled on memory, percent
+    #[serde(default)]
+    pub pressurememorysome: Option<f64>,
+    #[serde(default)]
+    pub pressurememoryfull: Option<f64>,
 }
 
-/// Guest information in JSON format
-#[derive(Debug, Serialize)]
-pub struct GuestJsonInfo {
+/// Memory statistics of the balloon driver, as seen inside the VM
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct BalloonInfo {
+    /// Memory currently given to the VM
+    #[serde(default)]
+    pub actual: Option<u64>,
+    #[serde(default)]
+    pub max_mem: Option<u64>,
+    /// Memory the guest OS sees, used and free
+    #[serde(default)]
+    pub total_mem: Option<u64>,
+    #[serde(default)]
+    pub free_mem: Option<u64>,
+    #[serde(default)]
+    pub mem_swapped_in: Option<u64>,
+    #[serde(default)]
+    pub mem_swapped_out: Option<u64>,
+    #[serde(default)]
+    pub major_page_faults: Option<u64>,
+}
+
+/// Guest snapshot (`/nodes/{node}/{type}/{vmid}/snapshot`), the list always
+/// holds a `current` pseudo snapshot for the running state
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct GuestSnapshot {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub snaptime: Option<u64>,
+    #[serde(default)]
+    pub parent: Option<String>,
+}
+
+/// Volume of a storage (`/nodes/{node}/storage/{storage}/content`)
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct StorageContent {
+    pub volid: String,
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default)]
+    pub format: Option<String>,
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default)]
+    pub ctime: Option<u64>,
+    #[serde(default)]
+    pub vmid: Option<u32>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Last verification of a PBS backup
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<BackupVerification>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct BackupVerification {
+    /// `ok` or `failed`
+    pub state: String,
+    #[serde(default)]
+    pub upid: Option<String>,
+}
+
+/// Outcome of one `vm migrate --check` test
+#[derive(Debug, Serialize, JsonSchema, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckLevel {
+    Ok,
+    Warning,
+    Blocker,
+}
+
+impl CheckLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckLevel::Ok => "ok",
+            CheckLevel::Warning => "warning",
+            CheckLevel::Blocker => "blocker",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MigrationCheck {
+    pub check: String,
+    pub level: CheckLevel,
+    pub detail: String,
+}
+
+/// Readiness report of `vm <vmid> migrate --check`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MigrationCheckOutput {
+    pub vmid: u32,
+    pub source: String,
+    pub target: String,
+    /// No blocker found
+    pub ready: bool,
+    pub checks: Vec<MigrationCheck>,
+}
+
+/// Guests of two clusters side by side, `compare`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CompareOutput {
+    pub primary: String,
+    pub replica: String,
+    /// Guests found alike on both sides, not listed
+    pub identical: usize,
+    pub guests: Vec<GuestComparison>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GuestComparison {
+    pub name: String,
+    pub primary_vmid: Option<u32>,
+    pub replica_vmid: Option<u32>,
+    /// missing (not on the replica), extra (only on the replica) or divergent
+    pub state: String,
+    /// Differing settings as `key: primary -> replica`
+    pub differences: Vec<String>,
+}
+
+/// Guests differing from the expected inventory, `drift`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DriftOutput {
+    /// Guests in the inventory
+    pub expected: usize,
+    /// Inventory guests matching the cluster, not listed
+    pub in_sync: usize,
+    pub guests: Vec<GuestDrift>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GuestDrift {
     pub name: String,
+    pub vmid: Option<u32>,
+    /// missing (not in the cluster), drifted or unmanaged (not in the inventory)
+    pub state: String,
+    /// Differing fields as `field: expected X, found Y`
+    pub differences: Vec<String>,
+}
+
+/// `cpuinfo` of `/nodes/{node}/status`
+#[derive(Debug, Deserialize)]
+pub struct NodeCpuInfo {
+    #[serde(default)]
+    pub model: String,
+    #[serde(default)]
+    pub cpus: Option<u32>,
+    #[serde(default)]
+    pub sockets: Option<u32>,
+    /// Cores per socket
+    #[serde(default)]
+    pub cores: Option<u32>,
+    /// Space separated CPU flags
+    #[serde(default)]
+    pub flags: Option<String>,
+}
+
+/// Bridge of `/nodes/{node}/network`
+#[derive(Debug, Deserialize)]
+pub struct NodeBridge {
+    pub iface: String,
+    /// `bridge` or `OVSBridge`
     #[serde(rename = "type")]
+    pub bridge_type: String,
+    #[serde(default)]
+    pub bridge_vlan_aware: Option<u8>,
+}
+
+/// Row of `network bridges`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BridgeInventory {
+    pub bridge: String,
+    /// Nodes having the bridge
+    pub nodes: Vec<String>,
+    /// Online nodes without it
+    pub missing_on: Vec<String>,
+    pub vlan_aware_on: Vec<String>,
+    /// VLAN tags of the attached NICs
+    pub vlans: Vec<u32>,
+    pub guests: Vec<BridgeNic>,
+}
+
+impl BridgeInventory {
+    pub fn new(bridge: &str) -> Self {
+        Self {
+            bridge: bridge.to_string(),
+            nodes: Vec::new(),
+            missing_on: Vec::new(),
+            vlan_aware_on: Vec::new(),
+            vlans: Vec::new(),
+            guests: Vec::new(),
+        }
+    }
+}
+
+/// Guest NIC attached to a bridge
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BridgeNic {
+    pub vmid: u32,
+    pub name: String,
+    pub node: String,
+    /// Config key, e.g. net0
+    pub nic: String,
+    pub tag: Option<u32>,
+}
+
+/// Output of `ipam`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct IpamOutput {
+    pub cidr: Option<String>,
+    pub addresses: Vec<IpamEntry>,
+    /// Unused ranges of an IPv4 `--cidr`
+    pub free: Vec<FreeRange>,
+}
+
+/// Guest address seen by `ipam`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct IpamEntry {
+    pub address: std::net::IpAddr,
+    pub prefix: u8,
+    pub vmid: u32,
+    pub name: String,
+    pub node: String,
+    pub interface: String,
+    pub mac: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FreeRange {
+    pub first: std::net::Ipv4Addr,
+    pub last: std::net::Ipv4Addr,
+    pub count: u64,
+}
+
+/// Output of `cluster cpu-matrix`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CpuMatrixOutput {
+    /// Every node has the same CPU flags
+    pub homogeneous: bool,
+    pub nodes: Vec<NodeCpu>,
+    pub host_guests: Vec<HostCpuGuest>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NodeCpu {
+    pub node: String,
+    pub model: String,
+    pub cpus: Option<u32>,
+    pub flags: usize,
+    /// Flags of other nodes this one lacks
+    pub missing_flags: Vec<String>,
+}
+
+/// VM with CPU type `host`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct HostCpuGuest {
+    pub vmid: u32,
+    pub name: String,
+    pub node: String,
+    /// Nodes refusing its live migration
+    pub blocked_targets: Vec<String>,
+}
+
+/// Result of `backups test-restore`, steps not run are None
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TestRestoreOutput {
+    pub volid: String,
+    pub vmid: u32,
+    pub node: String,
     pub guest_type: String,
-    pub cpu: String,
-    pub memory_gb: String,
+    pub restore_seconds: Option<u64>,
+    pub started: Option<bool>,
+    /// Guest agent answered a ping, VMs only
+    pub agent: Option<bool>,
+    pub cleaned_up: bool,
+    pub error: Option<String>,
+}
+
+/// Row of `backups verify`, one per PBS storage
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BackupVerifyOutput {
+    pub storage: String,
+    pub backups: usize,
+    pub verified_ok: usize,
+    pub verify_failed: usize,
+    pub never_verified: usize,
+    /// UPIDs of the verification tasks started on the PBS
+    pub started: Vec<String>,
+}
+
+/// Backup volume judged by a retention policy (`.../prunebackups`)
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct PruneEntry {
+    pub volid: String,
+    #[serde(default)]
+    pub vmid: Option<u32>,
+    #[serde(default)]
+    pub ctime: Option<u64>,
+    /// `keep`, `remove`, `protected` or `renamed`
+    pub mark: String,
+}
+
+/// JSON output of `backups prune-preview`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PrunePreviewOutput {
+    pub storage: String,
+    /// Retention options, none for the storage's own policy
+    pub retention: Option<String>,
+    pub applied: bool,
+    pub volumes: Vec<PruneEntry>,
+}
+
+/// Row of `backups growth`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BackupGrowth {
+    pub vmid: u32,
+    pub name: String,
+    pub backups: usize,
+    /// All backups of the guest together
+    pub chain_gb: f64,
+    pub latest_gb: f64,
+    /// Newest backup at least a week older than the latest one
+    pub week_ago_gb: Option<f64>,
+    pub growth_percent: Option<f64>,
+    /// Creation time of the latest backup, epoch seconds
+    pub latest: u64,
+}
+
+/// Entry of the appliance index (`/nodes/{node}/aplinfo`)
+#[derive(Debug, Deserialize)]
+pub struct Appliance {
+    /// File name, e.g. debian-12-standard_12.7-1_amd64.tar.zst
+    pub template: String,
+    /// Name without version, e.g. debian-12-standard
+    pub package: String,
+    /// `lxc` for container templates
+    #[serde(rename = "type", default)]
+    pub appliance_type: String,
+}
+
+/// Row of `templates audit`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TemplateAudit {
+    pub storage: String,
+    /// Node the template was listed from
+    pub node: String,
+    pub template: String,
+    pub package: String,
+    /// Newest template of the package in the appliance index
+    pub latest: Option<String>,
+    /// `current`, `outdated` or `unknown`
+    pub status: String,
+}
+
+/// Package of `/nodes/{node}/apt/versions`
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct AptPackage {
+    #[serde(rename = "Package")]
+    pub package: String,
+    #[serde(rename = "Version", default)]
+    pub version: Option<String>,
+    #[serde(rename = "CurrentState", default)]
+    pub current_state: Option<String>,
+}
+
+/// Entry of the node task history (`/nodes/{node}/tasks`)
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct Task {
+    pub upid: String,
+    pub node: String,
+    #[serde(rename = "type")]
+    pub task_type: String,
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub user: Option<String>,
+    pub starttime: u64,
+    #[serde(default)]
+    pub endtime: Option<u64>,
+    #[serde(default)]
+    pub status: Option<String>,
+}
+
+/// State of a single task (`/nodes/{node}/tasks/{upid}/status`)
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct TaskStatus {
+    pub status: String,
+    /// `OK` on success, the error message otherwise, set once stopped
+    #[serde(default)]
+    pub exitstatus: Option<String>,
+}
+
+/// JSON output of `backups config`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BackupConfigOutput {
+    /// Backup jobs as stored in `/etc/pve/jobs.cfg`
+    pub jobs: Vec<BTreeMap<String, serde_json::Value>>,
+    pub nodes: Vec<NodeVzdumpDefaults>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NodeVzdumpDefaults {
+    pub node: String,
+    /// Options of the node's `/etc/vzdump.conf`
+    pub defaults: BTreeMap<String, serde_json::Value>,
+}
+
+/// Row of `jobs retry-failed`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct JobRetry {
+    /// `replication` or `backup`
+    pub job_type: String,
+    pub job: String,
+    pub node: String,
+    pub guests: Vec<u32>,
+    /// Error of the failed run
+    pub previous_error: Option<String>,
+    /// Backup task of the retry, replication runs have none
+    pub upid: Option<String>,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+/// Failed tasks sharing an error signature, row of `tasks failures`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TaskFailureGroup {
+    /// Error with numbers, quoted values and volumes masked
+    pub signature: String,
+    pub task_type: String,
+    pub count: usize,
+    /// Task IDs, usually the guest VMIDs
+    pub guests: Vec<String>,
+    pub nodes: Vec<String>,
+    pub first: u64,
+    pub last: u64,
+    /// Error of the most recent failure
+    pub excerpt: String,
+    pub upids: Vec<String>,
+}
+
+/// Reproducible guest definition, the TOML file of `vm export-config`
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct GuestProfile {
+    pub guest: GuestProfileHeader,
+    pub config: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct GuestProfileHeader {
+    /// qemu or lxc
+    #[serde(rename = "type")]
+    pub guest_type: String,
+    /// Node the guest was exported from, default target of `vm create`
+    #[serde(default)]
+    pub node: Option<String>,
+    /// Container template, required to create LXC guests
+    #[serde(default)]
+    pub ostemplate: Option<String>,
+}
+
+/// One sample of the node RRD history (`/nodes/{node}/rrddata`)
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct NodeRrdPoint {
+    pub time: u64,
+    #[serde(default)]
+    pub cpu: Option<f64>,
+    #[serde(default)]
+    pub memused: Option<f64>,
+    #[serde(default)]
+    pub memtotal: Option<f64>,
+    /// Share of CPU time waiting for I/O, 0..1
+    #[serde(default)]
+    pub iowait: Option<f64>,
+}
+
+/// Sample of the guest RRD history, network rates in bytes per second
+#[derive(Debug, Deserialize)]
+pub struct GuestRrdPoint {
+    #[serde(default)]
+    pub time: u64,
+    /// Share of the vCPUs in use, 0..1
+    #[serde(default)]
+    pub cpu: Option<f64>,
+    #[serde(default)]
+    pub mem: Option<f64>,
+    #[serde(default)]
+    pub maxmem: Option<f64>,
+    #[serde(default)]
+    pub netin: Option<f64>,
+    #[serde(default)]
+    pub netout: Option<f64>,
+    #[serde(default)]
+    pub diskread: Option<f64>,
+    #[serde(default)]
+    pub diskwrite: Option<f64>,
+}
+
+// ============================================================================
+// Custom JSON output structures (for --format json)
+// ============================================================================
+
+/// JSON output structure for listing all nodes
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NodeListOutput {
+    pub root_controller: String,
+    pub proxmox_version: String,
+    pub nodes: Vec<NodeJsonInfo>,
+    pub totals: NodeTotals,
+}
+
+/// Aggregate size of the listed nodes, GB values rounded up like the
+/// per-node ones
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NodeTotals {
+    pub nodes_online: usize,
+    pub nodes_offline: usize,
+    pub cpu_cores: u32,
+    /// Usage of all cores together, weighted by core count
+    pub cpu_percent: f64,
+    pub memory_used_gb: u64,
+    pub memory_total_gb: u64,
+    pub storage_used_gb: u64,
+    pub storage_total_gb: u64,
+}
+
+/// Node information in JSON format
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NodeJsonInfo {
+    pub name: String,
+    pub cpu: String,
+    pub memory_gb: String,
+    pub storage_gb: String,
+    pub ipv4: String,
+    pub status: String,
+    /// Local labels, see `pvenom label`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: Labels,
+}
+
+/// JSON output structure for inspecting a single node with guests
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NodeDetailOutput {
+    pub name: String,
+    pub cpu: String,
+    pub memory_gb: String,
     pub storage_gb: String,
     pub ipv4: String,
     pub status: String,
-}
\ No newline at end of file
+    pub is_root_controller: String,
+    pub boot: Option<NodeBootInfo>,
+    /// Local labels, see `pvenom label`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: Labels,
+    pub guests: Vec<GuestJsonInfo>,
+}
+
+/// Running and installed kernels of a node
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct NodeBootInfo {
+    pub running_kernel: Option<String>,
+    /// Newest installed Proxmox kernel
+    pub latest_kernel: Option<String>,
+    /// A newer kernel is installed than the running one
+    pub reboot_required: bool,
+    /// `efi` or `legacy-bios`
+    pub boot_mode: Option<String>,
+    pub secure_boot: Option<bool>,
+}
+
+/// Guest information in JSON format
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GuestJsonInfo {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub guest_type: String,
+    pub cpu: String,
+    pub memory_gb: String,
+    pub storage_gb: String,
+    pub ipv4: String,
+    pub status: String,
+    /// Network receive rate with --net, bytes per second
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub netin_bps: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub netout_bps: Option<f64>,
+    /// Local labels, see `pvenom label`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: Labels,
+}
+
+/// JSON output structure for the guest availability report
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UptimeReportOutput {
+    pub since: u64,
+    pub until: u64,
+    pub guests: Vec<GuestAvailabilityJson>,
+}
+
+/// Maintenance window, `maintenance`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MaintenanceInfo {
+    /// `node <name>` or `tag <tag>`
+    pub target: String,
+    /// Recurring windows only
+    pub cron: Option<String>,
+    pub duration_secs: Option<u64>,
+    /// End of the window open now, epoch seconds
+    pub active_until: Option<u64>,
+    pub next_start: Option<u64>,
+    pub reason: Option<String>,
+}
+
+/// Power or snapshot schedule of the config file, `schedule`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ScheduleInfo {
+    pub name: String,
+    pub cron: String,
+    pub action: String,
+    /// Snapshots kept per guest by a `snapshot` schedule
+    pub keep_last: Option<u32>,
+    /// VMIDs the schedule applies to right now
+    pub guests: Vec<u32>,
+    /// Next run, epoch seconds
+    pub next_run: Option<u64>,
+}
+
+/// Cluster health, `summary`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SummaryOutput {
+    /// None on a standalone node
+    pub cluster: Option<String>,
+    pub nodes_online: usize,
+    pub nodes_total: usize,
+    pub guests_running: usize,
+    pub guests_stopped: usize,
+    /// Offline or saturated nodes, full storages, HA errors, quorum loss;
+    /// nodes and guests in a maintenance window left out
+    pub alerts: Vec<String>,
+}
+
+/// Reachability of the running guests, `ping-sweep`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PingSweepOutput {
+    /// `icmp` or `tcp/<port>`
+    pub method: String,
+    pub guests: Vec<GuestReachability>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GuestReachability {
+    pub node: String,
+    pub vmid: u32,
+    pub name: String,
+    pub ip: String,
+    pub reachable: bool,
+    /// Unreachable within a maintenance window, not a failure
+    pub maintenance: bool,
+    pub latency_ms: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// Oversized guests and what resizing them gives back, `rightsize`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RightsizeOutput {
+    pub since: u64,
+    pub until: u64,
+    pub guests: Vec<RightsizeGuest>,
+    pub nodes: Vec<RightsizeNode>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct RightsizeGuest {
+    pub node: String,
+    pub vmid: u32,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub guest_type: String,
+    pub cores: u32,
+    /// 95th percentile of the CPU usage, percent of the vCPUs
+    pub cpu_p95_percent: f64,
+    /// Only when fewer vCPUs are enough
+    pub recommended_cores: Option<u32>,
+    pub memory_gb: f64,
+    pub memory_p95_percent: f64,
+    pub recommended_memory_gb: Option<f64>,
+}
+
+/// Allocation of the running guests of a node and the share the
+/// suggestions reclaim
+#[derive(Debug, Serialize, JsonSchema, Clone, Default)]
+pub struct RightsizeNode {
+    pub node: String,
+    pub cores_allocated: u32,
+    pub cores_reclaimed: u32,
+    pub memory_allocated_gb: f64,
+    pub memory_reclaimed_gb: f64,
+}
+
+/// Availability of a single guest in JSON format
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GuestAvailabilityJson {
+    pub node: String,
+    pub vmid: u32,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub guest_type: String,
+    pub downtime_seconds: u64,
+    pub downtime_windows: usize,
+    pub availability_percent: f64,
+}
+
+/// NetBox virtualization payload of `export netbox`, objects reference each
+/// other by name as accepted by the NetBox REST API for nested objects
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NetboxExport {
+    pub clusters: Vec<NetboxCluster>,
+    pub virtual_machines: Vec<NetboxVirtualMachine>,
+    pub interfaces: Vec<NetboxInterface>,
+    pub ip_addresses: Vec<NetboxIpAddress>,
+}
+
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct NetboxRef {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NetboxCluster {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub cluster_type: NetboxRef,
+    pub status: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NetboxVirtualMachine {
+    pub name: String,
+    pub cluster: NetboxRef,
+    pub status: String,
+    pub vcpus: Option<f64>,
+    /// MB, as NetBox stores it
+    pub memory: Option<u64>,
+    /// MB, as NetBox 4.x stores it
+    pub disk: Option<u64>,
+    pub comments: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NetboxInterface {
+    pub virtual_machine: NetboxRef,
+    pub name: String,
+    pub mac_address: Option<String>,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NetboxIpAddress {
+    pub address: String,
+    pub status: String,
+    pub assigned_object_type: String,
+    pub assigned_object: NetboxInterfaceRef,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NetboxInterfaceRef {
+    pub virtual_machine: NetboxRef,
+    pub name: String,
+}
+
+/// Zabbix low-level discovery document, `{"data":[{"{#MACRO}":"value"}]}`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ZabbixDiscovery {
+    pub data: Vec<BTreeMap<String, String>>,
+}
+
+/// Full normalized cluster state, the document of `snapshot-state`
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ClusterState {
+    pub pvenom_version: String,
+    pub captured_at: u64,
+    pub cluster: String,
+    pub nodes: Vec<NodeState>,
+    pub guests: Vec<GuestState>,
+    pub storage: Vec<ClusterResource>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct NodeState {
+    #[serde(flatten)]
+    pub resource: ClusterResource,
+    pub version: Option<PveVersion>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct GuestState {
+    #[serde(flatten)]
+    pub resource: ClusterResource,
+    pub config: serde_json::Map<String, serde_json::Value>,
+}
+
+/// JSON output of the nodes listing across several clusters
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MultiClusterNodeListOutput {
+    pub clusters: Vec<String>,
+    /// Clusters that could not be reached, with the reason
+    pub failed: BTreeMap<String, String>,
+    pub nodes: Vec<ClusterNodeJsonInfo>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ClusterNodeJsonInfo {
+    pub cluster: String,
+    #[serde(flatten)]
+    pub node: NodeJsonInfo,
+}
+
+/// Node in the version 2 JSON output, raw numbers and nulls for the
+/// unknown values
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NodeJsonInfoV2 {
+    pub name: String,
+    pub status: String,
+    pub ipv4: Option<String>,
+    pub cpu_cores: Option<u32>,
+    /// Usage of all cores, 0.0 to 1.0
+    pub cpu_usage: Option<f64>,
+    pub memory_used_bytes: Option<u64>,
+    pub memory_total_bytes: Option<u64>,
+    pub storage_used_bytes: Option<u64>,
+    pub storage_total_bytes: Option<u64>,
+    pub uptime_secs: Option<u64>,
+    /// Local labels, see `pvenom label`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: Labels,
+}
+
+/// Version 2 of [`NodeListOutput`]
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NodeListOutputV2 {
+    pub output_version: u8,
+    pub root_controller: Option<String>,
+    pub proxmox_version: Option<String>,
+    pub nodes: Vec<NodeJsonInfoV2>,
+    pub totals: NodeTotals,
+}
+
+/// Version 2 of [`NodeDetailOutput`]
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NodeDetailOutputV2 {
+    pub output_version: u8,
+    #[serde(flatten)]
+    pub node: NodeJsonInfoV2,
+    pub is_root_controller: bool,
+    pub boot: Option<NodeBootInfo>,
+    pub guests: Vec<GuestJsonInfoV2>,
+}
+
+/// Version 2 of [`GuestJsonInfo`]
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GuestJsonInfoV2 {
+    pub vmid: u32,
+    pub name: String,
+    /// `qemu` or `lxc`, like the API
+    #[serde(rename = "type")]
+    pub guest_type: String,
+    pub status: String,
+    pub ipv4: Option<String>,
+    pub cpu_cores: Option<u32>,
+    pub memory_total_bytes: Option<u64>,
+    pub storage_total_bytes: Option<u64>,
+    pub uptime_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub netin_bps: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub netout_bps: Option<f64>,
+    /// Local labels, see `pvenom label`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: Labels,
+}
+
+/// Version 2 of [`MultiClusterNodeListOutput`]
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MultiClusterNodeListOutputV2 {
+    pub output_version: u8,
+    pub clusters: Vec<String>,
+    pub failed: BTreeMap<String, String>,
+    pub nodes: Vec<ClusterNodeJsonInfoV2>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ClusterNodeJsonInfoV2 {
+    pub cluster: String,
+    #[serde(flatten)]
+    pub node: NodeJsonInfoV2,
+}
+
+/// JSON output of `vm <vmid>`, everything about one guest
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GuestDetailOutput {
+    pub vmid: u32,
+    pub name: String,
+    pub guest_type: String,
+    pub node: String,
+    pub status: GuestStatus,
+    pub snapshots: usize,
+    pub backups: usize,
+    /// Creation time of the newest backup, epoch seconds
+    pub last_backup: Option<u64>,
+    pub agent: Option<GuestAgentInfo>,
+    pub interfaces: Vec<GuestInterface>,
+    /// Container root and mount points, empty for VMs
+    pub mounts: Vec<LxcMount>,
+    /// SPICE and USB devices needing a client or pinning the VM to its node
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub client_devices: Vec<ClientDevice>,
+    /// Filesystems inside the VM, with `--fs` only
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub filesystems: Vec<GuestFilesystem>,
+    /// Services probed with `--probe-ports` only
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ports: Vec<PortProbe>,
+    pub config: serde_json::Map<String, serde_json::Value>,
+}
+
+/// SPICE or USB device of a VM config
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ClientDevice {
+    /// Config key, e.g. `vga`, `usb0`, `audio0`
+    pub key: String,
+    /// `spice-display`, `spice-audio`, `spice-enhancements`, `spice-usb` or `usb-host`
+    pub kind: String,
+    pub detail: String,
+    /// Host USB devices cannot follow a live migration
+    pub blocks_migration: bool,
+}
+
+/// Row of `audit spice-usb`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ClientDevicesInfo {
+    pub vmid: u32,
+    pub name: String,
+    pub node: String,
+    pub status: String,
+    pub devices: Vec<ClientDevice>,
+}
+
+/// TCP port of a guest tried with `--probe-ports`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PortProbe {
+    pub port: u16,
+    pub address: String,
+    pub open: bool,
+    pub error: Option<String>,
+}
+
+/// `rootfs` or `mpX` entry of a container config
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct LxcMount {
+    /// Config key, `rootfs` or `mp0`..`mp255`
+    pub key: String,
+    /// Path inside the container
+    pub path: String,
+    /// Storage volume, or host path for bind and device mounts
+    pub volume: String,
+    pub storage: Option<String>,
+    pub size: Option<String>,
+    /// Host directory or device mounted as is, never backed up or migrated
+    pub bind: bool,
+    pub read_only: bool,
+    /// Included in vzdump backups
+    pub backup: bool,
+    /// Marked as available on every node
+    pub shared: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GuestAgentInfo {
+    pub version: Option<String>,
+    pub os: Option<String>,
+}
+
+/// Filesystem mounted inside a VM (`agent/get-fsinfo`)
+#[derive(Debug, Serialize, JsonSchema, Clone)]
+pub struct GuestFilesystem {
+    pub mountpoint: String,
+    #[serde(rename = "type")]
+    pub fs_type: String,
+    pub used_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Row of `inventory os`, OS fields come from `agent/get-osinfo`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GuestOs {
+    pub vmid: u32,
+    pub name: String,
+    pub node: String,
+    /// The guest agent answered
+    pub agent: bool,
+    pub hostname: Option<String>,
+    /// e.g. debian, ubuntu, mswindows
+    pub os_id: Option<String>,
+    pub os: Option<String>,
+    pub version: Option<String>,
+    pub kernel: Option<String>,
+    pub machine: Option<String>,
+    pub filesystems: Vec<GuestFilesystem>,
+}
+
+/// PCI device of a node (`/nodes/{node}/hardware/pci`)
+#[derive(Debug, Deserialize)]
+pub struct PciDevice {
+    /// e.g. `0000:01:00.0`
+    pub id: String,
+    /// e.g. `0x030000`, display controllers are `0x03....`
+    #[serde(default)]
+    pub class: String,
+    #[serde(default)]
+    pub vendor_name: Option<String>,
+    #[serde(default)]
+    pub device_name: Option<String>,
+    /// Supports mediated devices
+    #[serde(default)]
+    pub mdev: Option<u8>,
+}
+
+/// Mediated device type of a PCI device (`/nodes/{node}/hardware/pci/{id}/mdev`)
+#[derive(Debug, Deserialize)]
+pub struct MdevType {
+    #[serde(rename = "type")]
+    pub mdev_type: String,
+    #[serde(default)]
+    pub available: Option<u32>,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// PCI resource mapping (`/cluster/mapping/pci`)
+#[derive(Debug, Deserialize)]
+pub struct PciMapping {
+    pub id: String,
+    /// One `node=pve1,path=0000:01:00.0,...` entry per node
+    #[serde(default)]
+    pub map: Vec<String>,
+}
+
+/// Row of `audit hookscripts`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct HookscriptInfo {
+    pub vmid: u32,
+    pub name: String,
+    /// `qemu` or `lxc`
+    pub guest_type: String,
+    pub node: String,
+    /// Snippet volume, e.g. `local:snippets/hook.pl`
+    pub hookscript: String,
+    /// Storage of the snippet shared between the nodes
+    pub shared: Option<bool>,
+    /// Volume found on the guest's node
+    pub exists: Option<bool>,
+    pub error: Option<String>,
+}
+
+/// Row of `audit firmware`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FirmwareInfo {
+    pub vmid: u32,
+    pub name: String,
+    pub node: String,
+    /// e.g. `win11`, `l26`
+    pub ostype: Option<String>,
+    /// `ovmf` or `seabios`
+    pub firmware: String,
+    /// Volume of `efidisk0`
+    pub efi_disk: Option<String>,
+    /// `2m` or `4m`
+    pub efitype: Option<String>,
+    /// OVMF with the Microsoft keys pre-enrolled
+    pub secure_boot: bool,
+    /// Version of the `tpmstate0` device, `v1.2` or `v2.0`
+    pub tpm: Option<String>,
+    /// OVMF, secure boot and a v2.0 TPM
+    pub win11_ready: bool,
+}
+
+/// Row of `audit machine-types`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MachineTypeInfo {
+    pub vmid: u32,
+    pub name: String,
+    pub node: String,
+    pub ostype: Option<String>,
+    /// `machine` of the config, `default` when unset
+    pub machine: String,
+    /// `q35` or `i440fx`
+    pub machine_type: String,
+    /// None for machines following the newest version
+    pub pinned_version: Option<String>,
+    /// Newest version of the type the node offers
+    pub latest_version: Option<String>,
+    /// Pinned below the newest major version
+    pub outdated: bool,
+    pub suggestion: Option<String>,
+}
+
+/// Row of `gpus`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct HostGpu {
+    pub node: String,
+    /// PCI address, e.g. `0000:01:00.0`
+    pub id: String,
+    pub vendor: Option<String>,
+    pub device: Option<String>,
+    pub mdev: bool,
+    /// `type (name), N available`
+    pub mdev_types: Vec<String>,
+    /// VMIDs of the guests with a `hostpciN` on the device
+    pub guests: Vec<u32>,
+}
+
+/// JSON output of `gpus`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GpusOutput {
+    pub gpus: Vec<HostGpu>,
+    /// Devices passed through whole to more than one guest
+    pub conflicts: Vec<String>,
+}
+
+/// Physical disk of a node (`/nodes/{node}/disks/list`)
+#[derive(Debug, Deserialize)]
+pub struct NodeDisk {
+    pub devpath: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// `hdd`, `ssd`, `nvme` or `usb`
+    #[serde(rename = "type", default)]
+    pub disk_type: Option<String>,
+    #[serde(default)]
+    pub health: Option<String>,
+    /// Percent of life used, a number or "N/A"
+    #[serde(default)]
+    pub wearout: Option<serde_json::Value>,
+}
+
+/// LVM thin pool of a node (`/nodes/{node}/disks/lvmthin`)
+#[derive(Debug, Deserialize)]
+pub struct LvmThinPool {
+    pub lv: String,
+    /// Volume group, missing on older PVE
+    #[serde(default)]
+    pub vg: Option<String>,
+    pub lv_size: u64,
+    pub used: u64,
+    pub metadata_size: u64,
+    pub metadata_used: u64,
+}
+
+/// JSON output of `node <name> numa`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NodeNumaOutput {
+    pub node: String,
+    /// Threads
+    pub host_cpus: u32,
+    /// Taken as the host NUMA nodes
+    pub host_sockets: u32,
+    pub guests: Vec<GuestNuma>,
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GuestNuma {
+    pub vmid: u32,
+    pub name: String,
+    pub status: String,
+    pub vcpus: u32,
+    pub sockets: u32,
+    /// `affinity` of the config, e.g. `0-3,8`
+    pub affinity: Option<String>,
+    pub pinned_cpus: Vec<u32>,
+    pub numa: bool,
+    /// `numaN` entries, e.g. `numa0: cpus=0-3,hostnodes=0,memory=4096`
+    pub numa_nodes: Vec<String>,
+    /// `any`, `2` or `1024` (MiB pages)
+    pub hugepages: Option<String>,
+    pub memory_mb: u64,
+}
+
+/// JSON output of `node <name> sensors`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NodeSensorsOutput {
+    pub node: String,
+    pub cpu_percent: Option<f64>,
+    /// Hottest first
+    pub disks: Vec<DiskSensor>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DiskSensor {
+    pub disk: String,
+    pub model: Option<String>,
+    pub disk_type: Option<String>,
+    pub health: Option<String>,
+    pub wearout: Option<u64>,
+    pub temperature_c: Option<i64>,
+}
+
+/// JSON output of `node <name> io`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct NodeIoOutput {
+    pub node: String,
+    /// Share of CPU time waiting for I/O in the last minute, percent
+    pub iowait_percent: Option<f64>,
+    /// Running guests, busiest first
+    pub guests: Vec<GuestIo>,
+    pub storages: Vec<StorageIo>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct GuestIo {
+    pub vmid: u32,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub guest_type: String,
+    /// Bytes per second, one minute average
+    pub read_bps: Option<f64>,
+    pub write_bps: Option<f64>,
+    /// Operations per second over a one second sample, VMs only
+    pub read_iops: Option<f64>,
+    pub write_iops: Option<f64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StorageIo {
+    pub storage: String,
+    #[serde(rename = "type")]
+    pub storage_type: String,
+    pub status: String,
+    pub shared: bool,
+    pub used_gb: f64,
+    pub total_gb: f64,
+    /// Data space used of an LVM thin pool
+    pub thin_data_percent: Option<f64>,
+    /// Metadata space used of an LVM thin pool, the pool goes read-only
+    /// when it runs out
+    pub thin_metadata_percent: Option<f64>,
+}
+
+/// JSON output of `node <name> drain`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DrainOutput {
+    pub node: String,
+    pub guests: Vec<MigrationResult>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MigrationResult {
+    pub vmid: u32,
+    pub name: String,
+    pub guest_type: String,
+    pub target: String,
+    /// Migrated through the HA manager
+    pub ha: bool,
+    pub ok: bool,
+    pub error: Option<String>,
+    pub seconds: u64,
+}
+
+/// JSON output of `storage <id> evacuate`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct EvacuationOutput {
+    pub storage: String,
+    pub target: String,
+    pub disks: Vec<DiskMoveResult>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DiskMoveResult {
+    pub vmid: u32,
+    pub name: String,
+    pub disk: String,
+    pub target: String,
+    pub ok: bool,
+    pub error: Option<String>,
+    pub seconds: u64,
+}
+
+/// JSON output of `storage whois <volid>`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct VolumeWhois {
+    pub volid: String,
+    pub references: Vec<VolumeReference>,
+    /// Found in the storage content, none when the storage was not listed
+    pub exists: Option<bool>,
+    /// Content type of the volume, e.g. `images` or `iso`
+    pub content: Option<String>,
+    /// VMID in the volume name, e.g. 100 for `vm-100-disk-0`
+    pub owner: Option<u32>,
+    /// Present on the storage but referenced by no guest config
+    pub orphaned: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct VolumeReference {
+    pub vmid: u32,
+    pub name: String,
+    /// `qemu` or `lxc`
+    pub guest_type: String,
+    pub node: String,
+    /// Config key referencing the volume, e.g. `scsi0`
+    pub key: String,
+    /// `disk`, `unused`, `cdrom`, `linked clone` or `option`
+    pub usage: String,
+}
+
+/// Row of `storage overcommit`
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct StorageOvercommit {
+    pub storage: String,
+    /// Node of a local storage, none for shared ones
+    pub node: Option<String>,
+    pub storage_type: String,
+    /// Volumes allocate space as they are written
+    pub thin: bool,
+    pub volumes: usize,
+    /// Sum of the virtual sizes of the guest volumes
+    pub provisioned_gb: f64,
+    pub used_gb: f64,
+    pub total_gb: f64,
+    /// Provisioned size against the capacity
+    pub provisioned_percent: Option<f64>,
+    /// Thin storage that overflows if the guests fill their disks
+    pub at_risk: bool,
+}
+
+/// Where drained guests came from, saved by `node <name> drain` and
+/// consumed by `node <name> restore-placement`
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct PlacementRecord {
+    pub node: String,
+    pub drained_at: u64,
+    pub guests: Vec<PlacementEntry>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct PlacementEntry {
+    pub vmid: u32,
+    pub name: String,
+    /// API guest type, `qemu` or `lxc`
+    pub guest_type: String,
+    /// Node the guest was migrated to
+    pub moved_to: String,
+}
+
+/// Operation journal of a batch command, rewritten after every step so an
+/// interrupted or partly failed run can be resumed with `--resume`
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct Journal {
+    /// Command that wrote it, e.g. `drain`
+    pub operation: String,
+    /// Node the operation works on, the storage for `evacuate`
+    pub node: String,
+    pub started_at: u64,
+    pub items: Vec<JournalItem>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+pub struct JournalItem {
+    pub vmid: u32,
+    pub name: String,
+    /// API guest type, `qemu` or `lxc`
+    pub guest_type: String,
+    /// What is done to the guest, e.g. `migrate`
+    pub action: String,
+    /// Config key of the disk of a `move`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk: Option<String>,
+    pub target: String,
+    pub ha: bool,
+    /// Proxmox task of the last attempt, none for HA migrations
+    pub upid: Option<String>,
+    pub state: JournalState,
+    pub error: Option<String>,
+    pub seconds: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalState {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
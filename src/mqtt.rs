@@ -0,0 +1,122 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # mqtt.rs
+//!
+//! A publish-only MQTT 3.1.1 client, QoS 0 over plain TCP.
+//!
+//! pvenom only needs to push retained state messages, so instead of pulling
+//! a full MQTT stack this speaks the four packets it needs: CONNECT,
+//! CONNACK, PUBLISH and DISCONNECT.
+
+use anyhow::{bail, Context, Result};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::vlog_debug;
+
+const KEEP_ALIVE_SECS: u16 = 60;
+
+pub struct MqttPublisher {
+    stream: TcpStream,
+}
+
+impl MqttPublisher {
+    /// Connect to `host[:port]` (default port 1883) and wait for CONNACK
+    pub async fn connect(broker: &str, client_id: &str, username: Option<&str>, password: Option<&str>) -> Result<Self> {
+        let address = if broker.contains(':') { broker.to_string() } else { format!("{}:1883", broker) };
+        vlog_debug!("Connecting to MQTT broker {}...", address);
+
+        let mut stream = tokio::time::timeout(std::time::Duration::from_secs(10), TcpStream::connect(&address))
+            .await
+            .context("MQTT broker connection timed out")?
+            .with_context(|| format!("Failed to connect to MQTT broker {}", address))?;
+
+        let mut flags = 0x02; // clean session
+        let mut payload = encode_string(client_id);
+        if let Some(username) = username {
+            flags |= 0x80;
+            payload.extend(encode_string(username));
+            if let Some(password) = password {
+                flags |= 0x40;
+                payload.extend(encode_string(password));
+            }
+        }
+
+        let mut body = encode_string("MQTT");
+        body.push(4); // protocol level 3.1.1
+        body.push(flags);
+        body.extend(KEEP_ALIVE_SECS.to_be_bytes());
+        body.extend(payload);
+        stream.write_all(&packet(0x10, &body)).await.context("Failed to send MQTT CONNECT")?;
+
+        let mut connack = [0u8; 4];
+        stream.read_exact(&mut connack).await.context("Failed to read MQTT CONNACK")?;
+        if connack[0] != 0x20 {
+            bail!("Unexpected MQTT packet 0x{:02x} instead of CONNACK", connack[0]);
+        }
+        if connack[3] != 0 {
+            bail!("MQTT broker refused the connection (return code {})", connack[3]);
+        }
+
+        vlog_debug!("Connected to MQTT broker {}", address);
+        Ok(Self { stream })
+    }
+
+    pub async fn publish(&mut self, topic: &str, payload: &str, retain: bool) -> Result<()> {
+        vlog_debug!("MQTT publish {} ({} bytes)", topic, payload.len());
+        let mut body = encode_string(topic);
+        body.extend(payload.as_bytes());
+
+        let header = if retain { 0x31 } else { 0x30 };
+        self.stream.write_all(&packet(header, &body)).await
+            .with_context(|| format!("Failed to publish to {}", topic))
+    }
+
+    pub async fn disconnect(mut self) -> Result<()> {
+        self.stream.write_all(&[0xE0, 0x00]).await.context("Failed to send MQTT DISCONNECT")?;
+        self.stream.shutdown().await.ok();
+        Ok(())
+    }
+}
+
+/// Fixed header with the variable-length "remaining length" encoding
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut result = vec![header];
+    let mut length = body.len();
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+        if length > 0 {
+            byte |= 0x80;
+        }
+        result.push(byte);
+        if length == 0 {
+            break;
+        }
+    }
+    result.extend_from_slice(body);
+    result
+}
+
+/// Length-prefixed UTF-8 string
+fn encode_string(s: &str) -> Vec<u8> {
+    let mut result = (s.len() as u16).to_be_bytes().to_vec();
+    result.extend(s.as_bytes());
+    result
+}
@@ -0,0 +1,194 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # netbox.rs
+//!
+//! A minimal NetBox REST client, just enough to push `export netbox`.
+//!
+//! Every object is looked up first and then updated or created, so running
+//! the sync twice leaves NetBox unchanged.
+
+use anyhow::{bail, Context, Result};
+use reqwest::{Client, ClientBuilder, Method};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::NetboxExport;
+use crate::{vlog_debug, vlog_error, vlog_info, vlog_warn};
+
+const CLUSTER_TYPE_NAME: &str = "Proxmox VE";
+const CLUSTER_TYPE_SLUG: &str = "proxmox-ve";
+
+pub struct NetboxClient {
+    base_url: String,
+    token: String,
+    client: Client,
+}
+
+impl NetboxClient {
+    pub fn new(base_url: &str, token: &str, secure: bool) -> Result<Self> {
+        let client = ClientBuilder::new()
+            .danger_accept_invalid_certs(!secure)
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            client,
+        })
+    }
+
+    async fn request(&self, method: Method, path: &str, body: Option<&Value>) -> Result<Value> {
+        let url = format!("{}{}", self.base_url, path);
+        vlog_debug!("NetBox {} {}", method, url);
+
+        let mut request = self.client
+            .request(method.clone(), &url)
+            .header("Authorization", format!("Token {}", self.token))
+            .header("Accept", "application/json");
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        let response = request.send().await
+            .with_context(|| format!("Failed to send NetBox {} request", method))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            vlog_error!("NetBox {} {} failed with status: {}", method, path, status);
+            bail!("NetBox request failed: HTTP {} {}", status, body.trim());
+        }
+
+        let json: Value = response.json().await.context("Failed to parse NetBox response")?;
+        Ok(json)
+    }
+
+    /// Update the object matching `query` or create it, returns its id
+    async fn upsert(&self, endpoint: &str, query: &[(&str, String)], body: Value) -> Result<u64> {
+        let query: Vec<String> = query.iter()
+            .map(|(k, v)| format!("{}={}", k, encode(v)))
+            .collect();
+        let found = self.request(Method::GET, &format!("{}?{}", endpoint, query.join("&")), None).await?;
+
+        let result = match found["results"].as_array().and_then(|r| r.first()) {
+            Some(existing) => {
+                let id = existing["id"].as_u64().context("NetBox object without id")?;
+                self.request(Method::PATCH, &format!("{}{}/", endpoint, id), Some(&body)).await?
+            }
+            None => self.request(Method::POST, endpoint, Some(&body)).await?,
+        };
+
+        result["id"].as_u64().context("NetBox object without id")
+    }
+
+    pub async fn sync(&self, export: &NetboxExport) -> Result<()> {
+        vlog_info!("Pushing inventory to NetBox at {}...", self.base_url);
+
+        let type_id = self.upsert(
+            "/api/virtualization/cluster-types/",
+            &[("slug", CLUSTER_TYPE_SLUG.to_string())],
+            json!({ "name": CLUSTER_TYPE_NAME, "slug": CLUSTER_TYPE_SLUG }),
+        ).await?;
+
+        let mut cluster_ids: HashMap<&str, u64> = HashMap::new();
+        for cluster in &export.clusters {
+            let id = self.upsert(
+                "/api/virtualization/clusters/",
+                &[("name", cluster.name.clone())],
+                json!({ "name": cluster.name, "type": type_id, "status": cluster.status }),
+            ).await?;
+            cluster_ids.insert(&cluster.name, id);
+        }
+
+        // Virtual machines are matched by name, guests sharing one would
+        // overwrite each other
+        let mut names: BTreeMap<&str, usize> = BTreeMap::new();
+        for vm in &export.virtual_machines {
+            *names.entry(&vm.name).or_default() += 1;
+        }
+        names.retain(|_, count| *count > 1);
+        for (name, count) in &names {
+            vlog_warn!("{} guests are named '{}', skipping them, rename them to sync to NetBox", count, name);
+        }
+
+        let mut vm_ids: HashMap<&str, u64> = HashMap::new();
+        for vm in export.virtual_machines.iter().filter(|vm| !names.contains_key(vm.name.as_str())) {
+            let cluster_id = *cluster_ids.get(vm.cluster.name.as_str()).context("Unknown cluster")?;
+            let id = self.upsert(
+                "/api/virtualization/virtual-machines/",
+                &[("name", vm.name.clone()), ("cluster_id", cluster_id.to_string())],
+                json!({
+                    "name": vm.name,
+                    "cluster": cluster_id,
+                    "status": vm.status,
+                    "vcpus": vm.vcpus,
+                    "memory": vm.memory,
+                    "disk": vm.disk,
+                    "comments": vm.comments,
+                }),
+            ).await?;
+            vm_ids.insert(&vm.name, id);
+        }
+
+        let mut interface_ids: HashMap<(&str, &str), u64> = HashMap::new();
+        for iface in export.interfaces.iter().filter(|i| !names.contains_key(i.virtual_machine.name.as_str())) {
+            let vm_id = *vm_ids.get(iface.virtual_machine.name.as_str()).context("Unknown virtual machine")?;
+            let id = self.upsert(
+                "/api/virtualization/interfaces/",
+                &[("virtual_machine_id", vm_id.to_string()), ("name", iface.name.clone())],
+                json!({
+                    "virtual_machine": vm_id,
+                    "name": iface.name,
+                    "mac_address": iface.mac_address,
+                    "enabled": iface.enabled,
+                }),
+            ).await?;
+            interface_ids.insert((&iface.virtual_machine.name, &iface.name), id);
+        }
+
+        for ip in export.ip_addresses.iter().filter(|ip| !names.contains_key(ip.assigned_object.virtual_machine.name.as_str())) {
+            let key = (ip.assigned_object.virtual_machine.name.as_str(), ip.assigned_object.name.as_str());
+            let interface_id = *interface_ids.get(&key).context("Unknown interface")?;
+            self.upsert(
+                "/api/ipam/ip-addresses/",
+                &[("address", ip.address.clone())],
+                json!({
+                    "address": ip.address,
+                    "status": ip.status,
+                    "assigned_object_type": ip.assigned_object_type,
+                    "assigned_object_id": interface_id,
+                }),
+            ).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Percent-encode a query string value
+pub(crate) fn encode(value: &str) -> String {
+    value.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
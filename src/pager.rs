@@ -0,0 +1,127 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # pager.rs
+//!
+//! Print tables that fit the terminal.
+//!
+//! Columns get at least the width of their longest word, so comfy-table
+//! wraps between words instead of in the middle of a guest name. When even
+//! that does not fit, comfy-table is left free to split words.
+//!
+//! Tables taller than the terminal go through `$PAGER`, or `less` with
+//! `LESS=FRX` (quit on one screen, keep colors, no screen clearing) like git
+//! does. `--no-pager`, or stdout not being a terminal, prints directly.
+
+use comfy_table::{ColumnConstraint, Table, Width};
+use crossterm::terminal;
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::vlog_debug;
+
+/// Words longer than this may still be split
+const MAX_WORD_WIDTH: usize = 32;
+
+/// Columns of border and padding around each cell of the UTF8_FULL preset
+const CELL_OVERHEAD: usize = 3;
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Fit the table to the terminal width and print it, paging when too tall
+pub fn print_table(table: &mut Table) {
+    keep_words_whole(table);
+    page(&table.to_string());
+}
+
+/// Print text, through the pager when it does not fit the terminal height
+pub fn page(text: &str) {
+    let fits = match terminal::size() {
+        Ok((_, rows)) => text.lines().count() < rows as usize,
+        Err(_) => true,
+    };
+    if fits || !ENABLED.load(Ordering::Relaxed) || !std::io::stdout().is_terminal() {
+        println!("{}", text);
+        return;
+    }
+
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+    vlog_debug!("Paging {} lines through '{}'", text.lines().count(), pager);
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(&pager)
+        .env("LESS", std::env::var("LESS").unwrap_or_else(|_| "FRX".to_string()))
+        .stdin(Stdio::piped())
+        .spawn();
+
+    match child {
+        Ok(mut child) => {
+            if let Some(mut stdin) = child.stdin.take() {
+                // The user may quit the pager early, a broken pipe is fine
+                let _ = writeln!(stdin, "{}", text);
+            }
+            let _ = child.wait();
+        }
+        Err(e) => {
+            vlog_debug!("Pager '{}' unavailable: {}", pager, e);
+            println!("{}", text);
+        }
+    }
+}
+
+/// Give every column at least the width of its longest word, if the sum
+/// still fits the terminal
+fn keep_words_whole(table: &mut Table) {
+    let Some(width) = table.width() else {
+        return;
+    };
+
+    let mut longest: Vec<usize> = Vec::new();
+    let rows = table.header().into_iter().chain(table.row_iter());
+    for row in rows {
+        for (i, cell) in row.cell_iter().enumerate() {
+            let word = cell.content()
+                .split_whitespace()
+                .map(|w| w.chars().count())
+                .max()
+                .unwrap_or(0)
+                .min(MAX_WORD_WIDTH);
+            if longest.len() <= i {
+                longest.resize(i + 1, 0);
+            }
+            longest[i] = longest[i].max(word);
+        }
+    }
+
+    let needed: usize = longest.iter().map(|w| w + CELL_OVERHEAD).sum::<usize>() + 1;
+    if needed > width as usize {
+        vlog_debug!("Table needs {} columns, terminal has {}", needed, width);
+        return;
+    }
+
+    for (i, word) in longest.into_iter().enumerate() {
+        if let Some(column) = table.column_mut(i) {
+            column.set_constraint(ColumnConstraint::LowerBoundary(Width::Fixed(word as u16)));
+        }
+    }
+}
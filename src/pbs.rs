@@ -0,0 +1,120 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # pbs.rs
+//!
+//! A minimal Proxmox Backup Server client, just enough to start the
+//! verification of backups for `backups verify`.
+//!
+//! PVE shows the verification state of PBS backups but cannot start one,
+//! so pvenom logs in to the PBS of the storage with the storage user and
+//! a password of its own (`--pbs-password`), since PVE never reveals the
+//! stored one.
+
+use anyhow::{bail, Context, Result};
+use reqwest::{Client, ClientBuilder};
+use serde_json::Value;
+
+use crate::models::{AuthTicket, ProxmoxResponse};
+use crate::{vlog_debug, vlog_error};
+
+/// PBS API port when the storage does not set one
+pub const DEFAULT_PORT: u16 = 8007;
+
+pub struct PbsClient {
+    base_url: String,
+    client: Client,
+    ticket: String,
+    csrf_token: String,
+}
+
+impl PbsClient {
+    pub async fn new(server: &str, port: u16, username: &str, password: &str, secure: bool) -> Result<Self> {
+        let client = ClientBuilder::new()
+            .danger_accept_invalid_certs(!secure)
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        let base_url = format!("https://{}:{}", server, port);
+        vlog_debug!("Logging in to PBS at {} as {}", base_url, username);
+        let response = client
+            .post(format!("{}/api2/json/access/ticket", base_url))
+            .form(&[("username", username), ("password", password)])
+            .send()
+            .await
+            .context("Failed to send PBS authentication request")?;
+
+        if !response.status().is_success() {
+            vlog_error!("PBS authentication failed with status: {}", response.status());
+            bail!("PBS authentication failed: HTTP {}", response.status());
+        }
+
+        let auth: ProxmoxResponse<AuthTicket> = response
+            .json()
+            .await
+            .context("Failed to parse PBS authentication response")?;
+
+        Ok(Self {
+            base_url,
+            client,
+            ticket: auth.data.ticket,
+            csrf_token: auth.data.csrf_token,
+        })
+    }
+
+    /// Start the verification of one backup snapshot, `(type, id, time)`,
+    /// or of the whole datastore or namespace, returns the task UPID
+    pub async fn verify(&self, datastore: &str, namespace: Option<&str>, snapshot: Option<(&str, &str, u64)>) -> Result<String> {
+        let path = format!("/api2/json/admin/datastore/{}/verify", datastore);
+        let mut params = match snapshot {
+            Some((backup_type, backup_id, backup_time)) => vec![
+                ("backup-type", backup_type.to_string()),
+                ("backup-id", backup_id.to_string()),
+                ("backup-time", backup_time.to_string()),
+            ],
+            // Like verifying each snapshot, not only the unverified ones
+            None => vec![("ignore-verified", "false".to_string())],
+        };
+        if let Some(namespace) = namespace {
+            params.push(("ns", namespace.to_string()));
+        }
+        vlog_debug!("PBS POST {}{}", self.base_url, path);
+
+        let response = self.client
+            .post(format!("{}{}", self.base_url, path))
+            .header("Cookie", format!("PBSAuthCookie={}", self.ticket))
+            .header("CSRFPreventionToken", &self.csrf_token)
+            .form(&params)
+            .send()
+            .await
+            .context("Failed to send PBS verify request")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            vlog_error!("PBS POST {} failed with status: {}", path, status);
+            bail!("PBS request failed: HTTP {} {}", status, body.trim());
+        }
+
+        let json: Value = response.json().await.context("Failed to parse PBS response")?;
+        json["data"].as_str()
+            .map(str::to_string)
+            .context("PBS verify returned no task")
+    }
+}
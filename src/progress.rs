@@ -0,0 +1,102 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # progress.rs
+//!
+//! Machine-readable progress of long operations, `--progress json`.
+//!
+//! One JSON object per line on stderr, stdout keeps the command output:
+//!
+//! {"event":"batch_started","operation":"drain","total":2}
+//! {"event":"item_started","index":1,"total":2,"vmid":100,"target":"pve2"}
+//! {"event":"task_started","upid":"UPID:pve1:..."}
+//! {"event":"task_progress","upid":"UPID:pve1:...","percent":42.0}
+//! {"event":"task_finished","upid":"UPID:pve1:...","ok":true,"error":null}
+//! {"event":"item_finished","index":1,"total":2,"vmid":100,"ok":true}
+//! {"event":"batch_finished","operation":"drain","done":2,"failed":0}
+//!
+//! Percentages come from the task log, for the tasks that report one
+//! (migrations, backups, disk moves); others only start and finish.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static JSON: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ProgressMode {
+    None,
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    BatchStarted { operation: &'a str, total: usize },
+    ItemStarted { index: usize, total: usize, vmid: u32, target: &'a str },
+    TaskStarted { upid: &'a str },
+    TaskProgress { upid: &'a str, percent: f64 },
+    TaskFinished { upid: &'a str, ok: bool, error: Option<&'a str> },
+    ItemFinished { index: usize, total: usize, vmid: u32, ok: bool },
+    BatchFinished { operation: &'a str, done: usize, failed: usize },
+}
+
+pub fn set_mode(mode: ProgressMode) {
+    JSON.store(mode == ProgressMode::Json, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+pub fn emit(event: &Event) {
+    if !enabled() {
+        return;
+    }
+    if let Ok(line) = serde_json::to_string(event) {
+        eprintln!("{}", line);
+    }
+}
+
+/// Completion of a task log line, e.g. `INFO:  42% (3.4 GiB of 8.0 GiB)`
+/// from vzdump or `migration active, transferred 1.2 GiB of 4.0 GiB`
+pub fn parse_percent(line: &str) -> Option<f64> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    if let Some(percent) = words.iter().find_map(|w| w.strip_suffix('%').and_then(|n| n.parse::<f64>().ok())) {
+        return Some(percent.clamp(0.0, 100.0));
+    }
+
+    // "<size> <unit> of <size> <unit>"
+    let of = words.iter().position(|w| *w == "of")?;
+    let done = size_in_bytes(words.get(of.checked_sub(2)?)?, words.get(of - 1)?)?;
+    let total = size_in_bytes(words.get(of + 1)?, words.get(of + 2)?)?;
+    (total > 0.0).then(|| (done / total * 100.0).clamp(0.0, 100.0))
+}
+
+fn size_in_bytes(value: &str, unit: &str) -> Option<f64> {
+    let value: f64 = value.trim_start_matches('(').parse().ok()?;
+    let multiplier = match unit.trim_end_matches([',', ')']) {
+        "B" => 1.0,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "TiB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some(value * multiplier)
+}
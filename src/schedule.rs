@@ -0,0 +1,259 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # schedule.rs
+//!
+//! Power and snapshot schedules of the config file, carried out by
+//! `pvenom schedule run` and listed by `pvenom schedule`:
+//!
+//! [[schedule]]
+//! name = "dev off for the night"
+//! cron = "0 20 * * 1-5"
+//! action = "shutdown"
+//! tag = "dev"
+//!
+//! [[schedule]]
+//! cron = "0 8 * * 1-5"
+//! action = "start"
+//! guests = [100, 101]
+//! utc_offset = "+02:00"
+//!
+//! [[schedule]]
+//! name = "hourly rollback points"
+//! cron = "0 * * * *"
+//! action = "snapshot"
+//! tag = "db"
+//! keep_last = 24
+//!
+//! `cron` has the five usual fields, minute, hour, day of month, month and
+//! day of week (0 or 7 is Sunday), with `*`, lists, ranges and `/` steps.
+//! Times are UTC like everything pvenom prints, `utc_offset` shifts them.
+//! `action` is start, stop, shutdown, reboot or snapshot, applied to the
+//! guests with `tag` and the ones in `guests`.
+//!
+//! `snapshot` takes a snapshot named `<snapshot_prefix>_<YYYYMMDD>_<HHMM>`
+//! (prefix `auto` by default), then removes the oldest snapshots with that
+//! prefix beyond `keep_last`. Snapshots taken by hand or by schedules with
+//! another prefix are never removed, so give each snapshot schedule of
+//! the same guests its own prefix.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::models::ClusterResource;
+use crate::timefmt;
+
+pub const ACTIONS: [&str; 5] = ["start", "stop", "shutdown", "reboot", "snapshot"];
+
+/// Prefix of the snapshots of a schedule without `snapshot_prefix`
+const DEFAULT_SNAPSHOT_PREFIX: &str = "auto";
+
+/// `[[schedule]]` entry of the config file
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleEntry {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub cron: String,
+    pub action: String,
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub guests: Vec<u32>,
+    /// `+02:00` or `-05:30`, UTC when missing
+    #[serde(default)]
+    pub utc_offset: Option<String>,
+    /// Snapshots of the schedule kept per guest, `snapshot` only
+    #[serde(default)]
+    pub keep_last: Option<u32>,
+    /// Start of the snapshot names, `snapshot` only
+    #[serde(default)]
+    pub snapshot_prefix: Option<String>,
+}
+
+impl ScheduleEntry {
+    /// Name, or cron and action when unnamed
+    pub fn title(&self) -> String {
+        self.name.clone().unwrap_or_else(|| format!("{} at '{}'", self.action, self.cron))
+    }
+
+    /// Check everything, so `schedule run` fails at start and not at 20:00
+    pub fn parse(&self) -> Result<Schedule> {
+        if !ACTIONS.contains(&self.action.as_str()) {
+            bail!("Schedule '{}': unknown action '{}', one of {}", self.title(), self.action, ACTIONS.join(", "));
+        }
+        if self.tag.is_none() && self.guests.is_empty() {
+            bail!("Schedule '{}': no tag and no guests, it would do nothing", self.title());
+        }
+        if self.action == "snapshot" {
+            if self.keep_last.is_none_or(|keep| keep == 0) {
+                bail!("Schedule '{}': snapshot needs keep_last of at least 1", self.title());
+            }
+            let prefix = self.snapshot_prefix();
+            let valid = prefix.starts_with(|c: char| c.is_ascii_alphabetic())
+                && prefix.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                && prefix.len() <= 24;
+            if !valid {
+                bail!("Schedule '{}': snapshot_prefix '{}' must start with a letter and hold up to 24 letters, digits, - or _",
+                      self.title(), prefix);
+            }
+        } else if self.keep_last.is_some() || self.snapshot_prefix.is_some() {
+            bail!("Schedule '{}': keep_last and snapshot_prefix only apply to snapshot", self.title());
+        }
+        Schedule::parse(&self.cron, self.utc_offset.as_deref()).with_context(|| format!("Schedule '{}'", self.title()))
+    }
+
+    pub fn snapshot_prefix(&self) -> &str {
+        self.snapshot_prefix.as_deref().unwrap_or(DEFAULT_SNAPSHOT_PREFIX)
+    }
+
+    /// Guests the entry applies to, templates excluded
+    pub fn targets<'a>(&self, resources: &'a [ClusterResource]) -> Vec<&'a ClusterResource> {
+        resources.iter()
+            .filter(|r| r.is_guest())
+            .filter(|r| {
+                let tagged = self.tag.as_ref().is_some_and(|tag| {
+                    r.tags.as_deref().is_some_and(|tags| tags.split(';').any(|t| t == tag))
+                });
+                tagged || r.vmid.is_some_and(|vmid| self.guests.contains(&vmid))
+            })
+            .collect()
+    }
+}
+
+/// A checked entry, ready to be asked whether it is due
+pub struct Schedule {
+    expression: String,
+    cron: Cron,
+    /// Seconds east of UTC
+    offset: i64,
+}
+
+impl Schedule {
+    /// Cron expression with an optional `+02:00` offset from UTC
+    pub fn parse(cron: &str, utc_offset: Option<&str>) -> Result<Self> {
+        let expression = cron.to_string();
+        let cron = Cron::parse(cron)?;
+        let offset = utc_offset.map(parse_offset).transpose()?.unwrap_or(0);
+        Ok(Self { expression, cron, offset })
+    }
+
+    pub fn cron(&self) -> &str {
+        &self.expression
+    }
+
+    /// Due in the minute of `epoch`
+    pub fn is_due(&self, epoch: u64) -> bool {
+        self.cron.matches((epoch as i64 + self.offset).max(0) as u64)
+    }
+
+    /// First minute after `epoch` the entry is due, within a year
+    pub fn next_after(&self, epoch: u64) -> Option<u64> {
+        let start = epoch - epoch % 60 + 60;
+        (0..366 * 24 * 60).map(|i| start + i * 60).find(|t| self.is_due(*t))
+    }
+}
+
+/// Five cron fields as the allowed values of each
+struct Cron {
+    minutes: Vec<u32>,
+    hours: Vec<u32>,
+    days: Vec<u32>,
+    months: Vec<u32>,
+    weekdays: Vec<u32>,
+    /// Day of month and day of week both restricted, either matches
+    either_day: bool,
+}
+
+impl Cron {
+    fn parse(expression: &str) -> Result<Self> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            bail!("Cron '{}' needs 5 fields: minute hour day month weekday", expression);
+        };
+        let mut weekdays = field(weekday, 0, 7)?;
+        if weekdays.contains(&7) {
+            weekdays.push(0);
+        }
+        Ok(Self {
+            minutes: field(minute, 0, 59)?,
+            hours: field(hour, 0, 23)?,
+            days: field(day, 1, 31)?,
+            months: field(month, 1, 12)?,
+            weekdays,
+            either_day: day != "*" && weekday != "*",
+        })
+    }
+
+    fn matches(&self, epoch: u64) -> bool {
+        let (_, month, day, hour, minute, _) = timefmt::civil(epoch);
+        // 1970-01-01 was a Thursday
+        let weekday = ((epoch / 86_400 + 4) % 7) as u32;
+        let day_ok = self.days.contains(&(day as u32));
+        let weekday_ok = self.weekdays.contains(&weekday);
+        let date_ok = if self.either_day { day_ok || weekday_ok } else { day_ok && weekday_ok };
+        date_ok
+            && self.minutes.contains(&(minute as u32))
+            && self.hours.contains(&(hour as u32))
+            && self.months.contains(&(month as u32))
+    }
+}
+
+/// Values of one cron field: `*`, `5`, `1-5`, `*/15`, `0-30/10`, `1,15`
+fn field(text: &str, min: u32, max: u32) -> Result<Vec<u32>> {
+    let mut values = Vec::new();
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|s| *s > 0)
+                .with_context(|| format!("Invalid step in '{}'", part))?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (a.parse()?, b.parse()?),
+                None => {
+                    let value: u32 = range.parse().with_context(|| format!("Invalid cron value '{}'", part))?;
+                    (value, if part.contains('/') { max } else { value })
+                }
+            },
+        };
+        if start < min || end > max || start > end {
+            bail!("Cron value '{}' outside {}-{}", part, min, max);
+        }
+        values.extend((start..=end).step_by(step as usize));
+    }
+    Ok(values)
+}
+
+/// `+02:00` as seconds
+fn parse_offset(text: &str) -> Result<i64> {
+    let (sign, rest) = match text.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+    let (hours, minutes): (i64, i64) = (
+        hours.parse().with_context(|| format!("Invalid UTC offset '{}'", text))?,
+        minutes.parse().with_context(|| format!("Invalid UTC offset '{}'", text))?,
+    );
+    if hours > 14 || minutes > 59 {
+        bail!("Invalid UTC offset '{}'", text);
+    }
+    Ok(sign * (hours * 3_600 + minutes * 60))
+}
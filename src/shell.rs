@@ -0,0 +1,188 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # shell.rs
+//!
+//! `pvenom shell`: log in once, then type commands at the prompt.
+//!
+//! pvenom> nodes
+//! pvenom> node tatooine
+//! pvenom> vm 100 start
+//! pvenom> export zabbix-lld --kind guests
+//!
+//! Every subcommand of the command line works, plus `nodes` and `exit`.
+//! Words are split on whitespace, there is no quoting.
+//! Tab completes command names, flags, nodes and guests, and the history
+//! is kept in `$XDG_STATE_HOME/pvenom/history`.
+//!
+//! Errors are always printed here, whatever the log level, because a
+//! failed command must not look like an empty result.
+
+use anyhow::Result;
+use clap::{CommandFactory, Parser, Subcommand};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Editor, Helper};
+
+use crate::commands::Commands;
+use crate::config;
+use crate::{run_command, vlog_debug, vlog_warn, Command};
+
+/// One line typed at the shell prompt
+#[derive(Parser)]
+#[command(name = "pvenom", no_binary_name = true, disable_version_flag = true)]
+struct ShellLine {
+    #[command(subcommand)]
+    command: ShellCommand,
+}
+
+#[derive(Subcommand)]
+enum ShellCommand {
+    /// List all cluster nodes
+    Nodes {
+        /// Append last hour CPU and RAM sparklines
+        #[arg(long = "trends")]
+        trends: bool,
+    },
+
+    /// Leave the shell
+    #[command(alias = "quit")]
+    Exit,
+
+    #[command(flatten)]
+    Cluster(Command),
+}
+
+/// Tab completion over command names and cluster objects
+struct ShellHelper {
+    /// Words valid as first word of a line
+    commands: Vec<String>,
+    /// Words valid anywhere else: subcommands, flags, nodes and VMIDs
+    words: Vec<String>,
+}
+
+impl ShellHelper {
+    fn new(names: Vec<String>) -> Self {
+        let root = ShellLine::command();
+        let commands: Vec<String> = root.get_subcommands().map(|c| c.get_name().to_string()).collect();
+
+        let mut words = names;
+        let mut pending: Vec<&clap::Command> = root.get_subcommands().collect();
+        while let Some(cmd) = pending.pop() {
+            words.extend(cmd.get_arguments().filter_map(|a| a.get_long()).map(|l| format!("--{}", l)));
+            for sub in cmd.get_subcommands() {
+                words.push(sub.get_name().to_string());
+                pending.push(sub);
+            }
+        }
+        words.sort();
+        words.dedup();
+
+        Self { commands, words }
+    }
+}
+
+impl Completer for ShellHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &rustyline::Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let head = &line[..pos];
+        let start = head.rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+        let partial = &head[start..];
+        let pool = if head[..start].trim().is_empty() { &self.commands } else { &self.words };
+
+        Ok((start, pool.iter().filter(|w| w.starts_with(partial)).cloned().collect()))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
+
+pub async fn run(commands: &Commands, secure: bool) -> Result<()> {
+    let names = commands.node_and_guest_names().await.unwrap_or_else(|e| {
+        vlog_warn!("No node and guest names for completion: {}", e);
+        Vec::new()
+    });
+
+    let mut editor: Editor<ShellHelper, DefaultHistory> = Editor::new()?;
+    editor.set_helper(Some(ShellHelper::new(names)));
+
+    let history = config::state_dir().map(|dir| dir.join("history"));
+    if let Some(path) = &history {
+        if editor.load_history(path).is_err() {
+            vlog_debug!("No shell history at {}", path.display());
+        }
+    }
+
+    println!("pvenom shell, `help` lists the commands, `exit` or Ctrl-D leaves.");
+
+    loop {
+        let line = match editor.readline("pvenom> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line.as_str())?;
+
+        // Parse errors include `help`, clap prints them with usage
+        let parsed = match ShellLine::try_parse_from(&words) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                let _ = e.print();
+                continue;
+            }
+        };
+
+        let result = match parsed.command {
+            ShellCommand::Exit => break,
+            ShellCommand::Nodes { trends } => commands.list_nodes(trends).await,
+            ShellCommand::Cluster(command) => run_command(commands, command, secure).await,
+        };
+
+        if let Err(e) = result {
+            eprintln!("error: {}", e);
+        }
+    }
+
+    if let Some(path) = &history {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).ok();
+        }
+        if let Err(e) = editor.save_history(path) {
+            vlog_warn!("Failed to save shell history to {}: {}", path.display(), e);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,57 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # syslog.rs
+//!
+//! Minimal syslog sender over the local `/dev/log` socket, RFC 3164
+//! format, facility user. No socket (containers, macOS without syslogd)
+//! means the message is dropped silently: syslog is always a second copy.
+//! Elsewhere than on Unix there is no socket and nothing is sent.
+
+/// Syslog socket of Linux and the BSDs
+#[cfg(unix)]
+const SOCKET: &str = "/dev/log";
+
+/// Facility `user` (1), shifted as the priority value wants it
+#[cfg(unix)]
+const FACILITY_USER: u8 = 1 << 3;
+
+#[derive(Debug, Clone, Copy)]
+#[repr(u8)]
+pub enum Severity {
+    Error = 3,
+    Warning = 4,
+    Notice = 5,
+    Info = 6,
+    Debug = 7,
+}
+
+/// Send one message tagged `pvenom[pid]`
+#[cfg(unix)]
+pub fn send(severity: Severity, message: &str) {
+    use std::os::unix::net::UnixDatagram;
+
+    let Ok(socket) = UnixDatagram::unbound() else {
+        return;
+    };
+    let line = format!("<{}>pvenom[{}]: {}", FACILITY_USER | severity as u8, std::process::id(), message);
+    let _ = socket.send_to(line.as_bytes(), SOCKET);
+}
+
+#[cfg(not(unix))]
+pub fn send(_severity: Severity, _message: &str) {}
@@ -0,0 +1,97 @@
+// proxmox-pvenom: inspect and operate your ProxMox clusters from
+// the CLI with no API keys.
+// Copyright (C) 2025 Francesco Garbin
+//
+// This library is free software; you can redistribute it and/or
+// modify it under the terms of the GNU Lesser General Public
+// License as published by the Free Software Foundation; either
+// version 2.1 of the License, or (at your option) any later version.
+//
+// This library is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
+// Lesser General Public License for more details.
+// You should have received a copy of the GNU Lesser General Public
+// License along with this library; if not, write to the Free Software
+// Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA 02110-1301
+// USA
+
+//! # timefmt.rs
+//!
+//! Uptimes and timestamps as `--time-format` asks, shared by all commands.
+//!
+//! Without the option durations read `15d 4h` and timestamps
+//! `YYYY-MM-DD HH:MM` UTC. `relative` shows timestamps as an age, `3d 2h
+//! ago`; `iso` and `unix` show uptimes as the boot time, RFC 3339 or epoch
+//! seconds, so every value of a table is a moment in the same notation.
+
+use crate::models::TimeFormat;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds since boot, `None` is the default `15d 4h`
+pub fn uptime(secs: u64, format: Option<TimeFormat>) -> String {
+    match format {
+        None | Some(TimeFormat::Relative) => duration(secs),
+        Some(_) => timestamp(now().saturating_sub(secs), format),
+    }
+}
+
+/// Epoch seconds, `None` is the default `YYYY-MM-DD HH:MM` UTC
+pub fn timestamp(epoch: u64, format: Option<TimeFormat>) -> String {
+    match format {
+        None => {
+            let (year, month, day, hour, minute, _) = civil(epoch);
+            format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, hour, minute)
+        }
+        Some(TimeFormat::Relative) => {
+            let now = now();
+            if epoch > now {
+                format!("in {}", duration(epoch - now))
+            } else {
+                format!("{} ago", duration(now - epoch))
+            }
+        }
+        Some(TimeFormat::Iso) => {
+            let (year, month, day, hour, minute, second) = civil(epoch);
+            format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, hour, minute, second)
+        }
+        Some(TimeFormat::Unix) => epoch.to_string(),
+    }
+}
+
+/// Two largest units of a duration, `15d 4h`, `3h 12m`, `42s`
+fn duration(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86_400, (secs % 86_400) / 3_600, (secs % 3_600) / 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+/// Year, month, day, hour, minute and second of epoch seconds, UTC
+pub fn civil(secs: u64) -> (i64, i64, i64, u64, u64, u64) {
+    let days = (secs / 86_400) as i64;
+    let (hour, minute, second) = ((secs % 86_400) / 3_600, (secs % 3_600) / 60, secs % 60);
+
+    // Civil date from days, proleptic Gregorian calendar
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day, hour, minute, second)
+}
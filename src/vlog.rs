@@ -25,8 +25,14 @@
 //! pub use vlog_warn as warn;
 //! pub use vlog_error as error;
 //! pub use vlog_success as success;
+//!
+//! Messages go to the console, or to syslog (journald under systemd) with
+//! `--log-target syslog`, where the level becomes the syslog priority and
+//! the emoji markers are left out.
+
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 
-use std::sync::atomic::{AtomicU8, Ordering};
+use crate::syslog::{self, Severity};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[repr(u8)]
@@ -40,6 +46,19 @@ pub enum LogLevel {
 
 static CURRENT_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Silent as u8);
 
+static TO_SYSLOG: AtomicBool = AtomicBool::new(false);
+
+/// Where log messages are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogTarget {
+    Console,
+    Syslog,
+}
+
+pub fn set_target(target: LogTarget) {
+    TO_SYSLOG.store(target == LogTarget::Syslog, Ordering::Relaxed);
+}
+
 pub fn set_level(level: LogLevel) {
     CURRENT_LEVEL.store(level as u8, Ordering::Relaxed);
 }
@@ -49,6 +68,24 @@ pub fn should_log(level: LogLevel) -> bool {
     (level as u8) >= current
 }
 
+/// Write one message, `marker` is the console prefix
+pub fn emit(level: LogLevel, marker: &str, message: &str) {
+    if TO_SYSLOG.load(Ordering::Relaxed) {
+        let severity = match level {
+            LogLevel::Debug => Severity::Debug,
+            LogLevel::Info => Severity::Info,
+            LogLevel::Warn => Severity::Warning,
+            LogLevel::Error | LogLevel::Silent => Severity::Error,
+        };
+        syslog::send(severity, message);
+        return;
+    }
+    match level {
+        LogLevel::Warn | LogLevel::Error => eprintln!("{} {}", marker, message),
+        _ => println!("{} {}", marker, message),
+    }
+}
+
 #[macro_export]
 macro_rules! vlog_set_level {
     ($($arg:tt)*) => {
@@ -60,7 +97,7 @@ macro_rules! vlog_set_level {
 macro_rules! vlog_debug {
     ($($arg:tt)*) => {
         if $crate::vlog::should_log($crate::vlog::LogLevel::Debug) {
-            println!("🔍 [DEBUG] {}", format!($($arg)*));
+            $crate::vlog::emit($crate::vlog::LogLevel::Debug, "🔍 [DEBUG]", &format!($($arg)*));
         }
     };
 }
@@ -69,7 +106,7 @@ macro_rules! vlog_debug {
 macro_rules! vlog_info {
     ($($arg:tt)*) => {
         if $crate::vlog::should_log($crate::vlog::LogLevel::Info) {
-            println!("ℹ️  [INFO]  {}", format!($($arg)*));
+            $crate::vlog::emit($crate::vlog::LogLevel::Info, "ℹ️  [INFO] ", &format!($($arg)*));
         }
     };
 }
@@ -78,7 +115,7 @@ macro_rules! vlog_info {
 macro_rules! vlog_warn {
     ($($arg:tt)*) => {
         if $crate::vlog::should_log($crate::vlog::LogLevel::Warn) {
-            eprintln!("⚠️  [WARN]  {}", format!($($arg)*));
+            $crate::vlog::emit($crate::vlog::LogLevel::Warn, "⚠️  [WARN] ", &format!($($arg)*));
         }
     };
 }
@@ -87,7 +124,7 @@ macro_rules! vlog_warn {
 macro_rules! vlog_error {
     ($($arg:tt)*) => {
         if $crate::vlog::should_log($crate::vlog::LogLevel::Error) {
-            eprintln!("❌ [ERROR] {}", format!($($arg)*));
+            $crate::vlog::emit($crate::vlog::LogLevel::Error, "❌ [ERROR]", &format!($($arg)*));
         }
     };
 }
@@ -96,7 +133,7 @@ macro_rules! vlog_error {
 macro_rules! vlog_success {
     ($($arg:tt)*) => {
         if $crate::vlog::should_log($crate::vlog::LogLevel::Info) {
-            println!("✅ [OK]    {}", format!($($arg)*));
+            $crate::vlog::emit($crate::vlog::LogLevel::Info, "✅ [OK]   ", &format!($($arg)*));
         }
     };
 }
\ No newline at end of file